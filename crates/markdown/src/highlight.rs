@@ -0,0 +1,94 @@
+//! Code Highlighting (feature `highlight`)
+//!
+//! syntect output uses `hl-*` classes instead of inline colours, so one
+//! stylesheet (`highlight_css`) switches between a light and a dark theme with
+//! the app. The syntax set is only built on first use and extends syntect's
+//! defaults with the definitions bundled in `syntaxes/` (TOML, Nix, Leptos).
+
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Theme used when the app is light
+pub const LIGHT_THEME: &str = "InspiredGitHub";
+/// Theme used under `prefers-color-scheme: dark`
+pub const DARK_THEME: &str = "base16-ocean.dark";
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Extra `.sublime-syntax` definitions compiled into the crate
+const BUNDLED_SYNTAXES: &[(&str, &str)] = &[
+    ("TOML", include_str!("../syntaxes/TOML.sublime-syntax")),
+    ("Nix", include_str!("../syntaxes/Nix.sublime-syntax")),
+    ("Leptos", include_str!("../syntaxes/Leptos.sublime-syntax")),
+];
+
+static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+
+fn get_syntax_set() -> &'static SyntaxSet {
+    SYNTAX_SET.get_or_init(|| build_syntax_set(None).expect("bundled syntaxes are valid"))
+}
+
+fn build_syntax_set(extra_dir: Option<&Path>) -> Result<SyntaxSet, String> {
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    for (name, source) in BUNDLED_SYNTAXES {
+        let syntax = SyntaxDefinition::load_from_str(source, true, None)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        builder.add(syntax);
+    }
+    if let Some(dir) = extra_dir {
+        builder.add_from_folder(dir, true).map_err(|e| e.to_string())?;
+    }
+    Ok(builder.build())
+}
+
+/// Load additional `.sublime-syntax` files from `dir` (e.g. the app data dir).
+/// Has to run before the first highlight; returns `false` if the syntax set
+/// was already built.
+pub fn load_extra_syntaxes(dir: &Path) -> Result<bool, String> {
+    if SYNTAX_SET.get().is_some() {
+        return Ok(false);
+    }
+    let set = build_syntax_set(Some(dir))?;
+    Ok(SYNTAX_SET.set(set).is_ok())
+}
+
+/// Names of the available themes (for `highlight_css`)
+pub fn theme_names() -> Vec<String> {
+    THEME_SET.get_or_init(ThemeSet::load_defaults).themes.keys().cloned().collect()
+}
+
+/// Stylesheet for highlighted code: `light` by default, `dark` under
+/// `prefers-color-scheme: dark`
+pub fn highlight_css(light: &str, dark: &str) -> Result<String, String> {
+    let themes = &THEME_SET.get_or_init(ThemeSet::load_defaults).themes;
+    let css_for = |name: &str| {
+        let theme = themes.get(name).ok_or_else(|| format!("Unknown theme: {}", name))?;
+        css_for_theme_with_class_style(theme, CLASS_STYLE).map_err(|e| e.to_string())
+    };
+    Ok(format!(
+        "{}\n@media (prefers-color-scheme: dark) {{\n{}}}\n",
+        css_for(light)?,
+        css_for(dark)?
+    ))
+}
+
+/// Highlight a code block; unknown languages render as plain text
+pub fn highlight_code(code: &str, lang: Option<&str>) -> String {
+    let ss = get_syntax_set();
+    let syntax = lang
+        .and_then(|l| ss.find_syntax_by_token(l))
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, ss, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+            return crate::plain_code_block(code, lang);
+        }
+    }
+    format!(r#"<pre class="hl-code">{}</pre>"#, generator.finalize())
+}
@@ -0,0 +1,1074 @@
+//! Tag-All Markdown
+//!
+//! Markdown rendering shared by the frontend preview (WASM) and the backend's
+//! static site export. Extends pulldown-cmark with:
+//! - Custom color syntax: %r%red%r%
+//! - Wiki links between items: [[Item title]], [[#42]], [[Item title|label]]
+//! - Inline tags: #tag, #parent/child (rendered as coloured chips)
+//! - Math support: $E=mc^2$ (Katex)
+//! - Syntax highlighting (syntect, feature `highlight`), ```mermaid diagrams (rendered client-side)
+//! - Footnotes, heading anchors and a generated table of contents: [TOC]
+//! - Callouts: > [!NOTE], > [!TIP], > [!IMPORTANT], > [!WARNING], > [!CAUTION]
+//! - Clickable task lists: `- [ ]` checkboxes carry their index for write-back (see `toggle_task`)
+//! - Enhanced media:
+//!   - Videos (<video> tag for mp4/webm/mov/mkv), audio (<audio> for mp3/wav/ogg/m4a/flac)
+//!   - Local file access (asset:// protocol), including synced `assets/{hash}.{ext}`
+//!     references resolved against this device's asset directory (`set_assets_dir`)
+//!
+//! The output goes into `inner_html`, and memos arrive from synced databases and
+//! the web clipper, so raw HTML is passed through an allow-list sanitiser and
+//! every URL we emit is checked against a scheme allow-list.
+
+#[cfg(feature = "highlight")]
+mod highlight;
+mod sanitize;
+mod tasks;
+
+use pulldown_cmark::{Parser, Options, Event, CowStr, Tag, TagEnd, CodeBlockKind, HeadingLevel, html::push_html};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use percent_encoding::{utf8_percent_encode, CONTROLS, AsciiSet};
+
+use sanitize::{clean_heading, HtmlSanitizer};
+
+pub use sanitize::is_safe_url;
+
+pub use tasks::{memo_tasks, remove_task_lines, toggle_task, MemoTask};
+
+#[cfg(feature = "highlight")]
+pub use highlight::{highlight_code, highlight_css, load_extra_syntaxes, theme_names, DARK_THEME, LIGHT_THEME};
+
+thread_local! {
+    /// Lower-cased tag name -> display colour, for inline #tag chips
+    static TAG_COLORS: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    /// This device's directory of content-addressed assets
+    static ASSETS_DIR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Update the default colours used for inline #tag chips (call when tags are loaded)
+pub fn set_tag_colors<'a>(colors: impl IntoIterator<Item = (&'a str, String)>) {
+    TAG_COLORS.with(|current| {
+        let mut current = current.borrow_mut();
+        current.clear();
+        for (name, color) in colors {
+            current.insert(name.to_lowercase(), color);
+        }
+    });
+}
+
+/// Set the local directory that `assets/{hash}.{ext}` references resolve to
+pub fn set_assets_dir(dir: &str) {
+    ASSETS_DIR.with(|current| *current.borrow_mut() = Some(dir.trim_end_matches(['/', '\\']).to_string()));
+}
+
+/// File name of a device-independent asset reference (`assets/{blake3 hex}.{ext}`)
+pub fn asset_reference_name(url: &str) -> Option<&str> {
+    let name = url.strip_prefix("assets/")?;
+    let (hash, ext) = name.split_once('.')?;
+    let valid = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+        && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(name)
+}
+
+/// Maps a link target or URL to its rendered form (see `RenderOptions`)
+pub type UrlHook<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Per-call rendering hooks. The defaults render for the app's preview; the
+/// site export uses them to point links and media at the exported files.
+#[derive(Default)]
+pub struct RenderOptions<'a> {
+    /// Lower-cased tag name -> colour for #tag chips (default: `set_tag_colors`)
+    pub tag_colors: Option<&'a HashMap<String, String>>,
+    /// Href for a `[[target]]` link. `None` from the hook renders the link as
+    /// unresolved; without a hook, links keep `data-wiki-link` for the app's click handler.
+    pub wiki_link_href: Option<UrlHook<'a>>,
+    /// Rewrite link/image/video destinations; returning `None` keeps the default
+    /// (local paths go through the asset protocol)
+    pub rewrite_url: Option<UrlHook<'a>>,
+    /// Render task checkboxes disabled (static pages have nothing to write back to)
+    pub readonly_tasks: bool,
+}
+
+/// Color codes and their hex values
+const COLORS: &[(&str, &str)] = &[
+    ("r", "#e74c3c"), // red
+    ("g", "#27ae60"), // green
+    ("b", "#3498db"), // blue
+    ("y", "#f1c40f"), // yellow
+    ("o", "#e67e22"), // orange
+    ("p", "#9b59b6"), // purple
+];
+
+/// Parse markdown with all extensions enabled
+pub fn parse_markdown(text: &str) -> String {
+    parse_markdown_with(text, &RenderOptions::default())
+}
+
+/// Parse markdown with custom link, media and tag colour hooks
+pub fn parse_markdown_with(text: &str, opts: &RenderOptions<'_>) -> String {
+    let parser = Parser::new_ext(text, get_options());
+    let events = transform_events(parser, opts);
+    let mut html_output = String::new();
+    push_html(&mut html_output, events.into_iter());
+    html_output
+}
+
+/// Parse markdown for inline use (strips outer <p> tags)
+pub fn parse_markdown_inline(text: &str) -> String {
+    parse_markdown_inline_with(text, &RenderOptions::default())
+}
+
+/// Inline variant of `parse_markdown_with`
+pub fn parse_markdown_inline_with(text: &str, opts: &RenderOptions<'_>) -> String {
+    let html = parse_markdown_with(text, opts);
+    
+    html.trim()
+        .strip_prefix("<p>")
+        .and_then(|s| s.strip_suffix("</p>"))
+        .map(|s| s.to_string())
+        .unwrap_or(html)
+}
+
+/// Sanitise a standalone HTML fragment (an archived web page) with the same
+/// allow-list as raw HTML in memos. With `resolve_assets`, `assets/{hash}.{ext}`
+/// sources and links point at this device's asset directory (`set_assets_dir`).
+pub fn sanitize_html(html: &str, resolve_assets: bool) -> String {
+    let mut sanitizer = if resolve_assets { HtmlSanitizer::resolving_assets() } else { HtmlSanitizer::default() };
+    sanitizer.clean(html)
+}
+
+pub(crate) fn get_options() -> Options {
+    Options::ENABLE_STRIKETHROUGH 
+        | Options::ENABLE_TABLES 
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
+/// Callout kinds for `> [!KIND]` block quotes: (marker, css class, title)
+const ADMONITIONS: &[(&str, &str, &str)] = &[
+    ("NOTE", "note", "备注"),
+    ("TIP", "tip", "提示"),
+    ("IMPORTANT", "important", "重要"),
+    ("WARNING", "warning", "警告"),
+    ("CAUTION", "caution", "注意"),
+];
+
+/// A heading collected for the [TOC] block
+struct TocEntry {
+    level: HeadingLevel,
+    text: String,
+    id: String,
+}
+
+// State for the event transformer
+enum State {
+    Normal,
+    InCodeBlock { lang: Option<String>, content: String },
+    InVideo { dropped_depth: usize },
+}
+
+/// Transform parser events to handle all custom features
+fn transform_events<'a>(parser: Parser<'a>, opts: &RenderOptions<'_>) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut state = State::Normal;
+    // pulldown-cmark splits text at brackets ("[[a]]" -> "[", "[a", "]", "]"), so
+    // adjacent text events are merged before custom syntax is matched
+    let mut pending_text = String::new();
+    // Heading being collected: (index of its Start event, plain text)
+    let mut heading: Option<(usize, String)> = None;
+    let mut headings: Vec<TocEntry> = Vec::new();
+    let mut used_ids: HashMap<String, usize> = HashMap::new();
+    // Paragraphs consisting only of [TOC], filled in once all headings are known
+    let mut toc_slots: Vec<usize> = Vec::new();
+    // Start indices of the open block quotes (candidates for callouts)
+    let mut quotes: Vec<usize> = Vec::new();
+    // Task checkboxes are numbered in source order, matching `memo_tasks`
+    let mut task_index = 0;
+    // Raw HTML is sanitised; comments and <script> bodies may span several events
+    let mut sanitizer = HtmlSanitizer::default();
+    
+    for event in parser {
+        if let State::Normal = state {
+            match &event {
+                Event::Text(text) => {
+                    if let Some((_, heading_text)) = heading.as_mut() {
+                        heading_text.push_str(text);
+                    }
+                    pending_text.push_str(text);
+                    continue;
+                }
+                Event::Code(code) => {
+                    if let Some((_, heading_text)) = heading.as_mut() {
+                        heading_text.push_str(code);
+                    }
+                }
+                Event::End(TagEnd::Paragraph) if pending_text.trim() == "[TOC]"
+                    && matches!(events.last(), Some(Event::Start(Tag::Paragraph))) => {
+                    events.pop();
+                    pending_text.clear();
+                    toc_slots.push(events.len());
+                    events.push(Event::Html(CowStr::from("")));
+                    continue;
+                }
+                _ => {}
+            }
+            flush_text(&mut events, &mut pending_text, opts);
+        }
+        
+        match state {
+            State::Normal => {
+                match event {
+                    // --- Code Blocks (Highlighting) ---
+                    Event::Start(Tag::CodeBlock(kind)) => {
+                        // Only the first word of the info string names the language ("rust,ignore")
+                        let lang = match kind {
+                            CodeBlockKind::Fenced(info) => info
+                                .split(|c: char| c == ',' || c.is_whitespace())
+                                .next()
+                                .filter(|l| !l.is_empty())
+                                .map(str::to_string),
+                            CodeBlockKind::Indented => None,
+                        };
+                        state = State::InCodeBlock { lang, content: String::new() };
+                    }
+                    
+                    // --- Headings (anchors for [TOC]) ---
+                    Event::Start(tag @ Tag::Heading { .. }) => {
+                        heading = Some((events.len(), String::new()));
+                        events.push(Event::Start(clean_heading(tag)));
+                    }
+                    Event::End(TagEnd::Heading(_)) => {
+                        if let Some((start, text)) = heading.take() {
+                            let text = strip_color_markers(&text);
+                            let start_event = std::mem::replace(&mut events[start], Event::SoftBreak);
+                            if let Event::Start(Tag::Heading { level, id, classes, attrs }) = start_event {
+                                let id = unique_heading_id(id.as_deref().unwrap_or(&text), id.is_some(), &mut used_ids);
+                                headings.push(TocEntry { level, text: text.trim().to_string(), id: id.clone() });
+                                events[start] = Event::Start(Tag::Heading { level, id: Some(CowStr::from(id)), classes, attrs });
+                            }
+                        }
+                        events.push(event);
+                    }
+                    
+                    // --- Callouts (> [!NOTE]) ---
+                    Event::Start(Tag::BlockQuote) => {
+                        quotes.push(events.len());
+                        events.push(event);
+                    }
+                    Event::End(TagEnd::BlockQuote) => {
+                        match quotes.pop().and_then(|start| take_admonition_marker(&mut events, start).map(|kind| (start, kind))) {
+                            Some((start, (class, title))) => {
+                                events[start] = Event::Html(CowStr::from(format!(
+                                    r#"<div class="admonition admonition-{}"><p class="admonition-title">{}</p>"#,
+                                    class, title
+                                )));
+                                events.push(Event::Html(CowStr::from("</div>")));
+                            }
+                            None => events.push(event),
+                        }
+                    }
+                    
+                    // --- Raw HTML (allow-listed) ---
+                    Event::Html(html) => {
+                        events.push(Event::Html(CowStr::from(sanitizer.clean(&html))));
+                    }
+                    Event::InlineHtml(html) => {
+                        events.push(Event::InlineHtml(CowStr::from(sanitizer.clean(&html))));
+                    }
+                    
+                    // --- Links: drop javascript: and other unsafe targets ---
+                    Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                        let dest_url = match opts.rewrite_url.and_then(|rewrite| rewrite(&dest_url)) {
+                            Some(url) => CowStr::from(url),
+                            // Linked files (PDFs, ...) in the asset store open through the asset protocol
+                            None if asset_reference_name(&dest_url).is_some() => CowStr::from(convert_local_url(&dest_url)),
+                            None => dest_url,
+                        };
+                        let dest_url = if is_safe_url(&dest_url, false) { dest_url } else { CowStr::from("") };
+                        events.push(Event::Start(Tag::Link { link_type, dest_url, title, id }));
+                    }
+                    
+                    // --- Task list checkboxes (clickable in the preview) ---
+                    Event::TaskListMarker(checked) => {
+                        events.push(Event::Html(CowStr::from(format!(
+                            "<input type=\"checkbox\" class=\"task-checkbox\" data-task-index=\"{}\"{}{}/>\n",
+                            task_index,
+                            if checked { " checked=\"\"" } else { "" },
+                            if opts.readonly_tasks { " disabled=\"\"" } else { "" }
+                        ))));
+                        task_index += 1;
+                    }
+                    
+                    // --- Media (Images & Videos) ---
+                    Event::Start(Tag::Image { dest_url, .. }) => {
+                        let url = opts.rewrite_url.and_then(|rewrite| rewrite(&dest_url))
+                            .unwrap_or_else(|| convert_local_url(&dest_url));
+                        let url = if is_safe_url(&url, true) { escape_html(&url) } else { String::new() };
+                        
+                        if is_video_url(&url) {
+                            // Render <video> tag
+                            let html = format!(
+                                r#"<video controls src="{}" style="max-width: 100%; max-height: 400px; display: block; border-radius: 4px;"></video>"#, 
+                                url
+                            );
+                            events.push(Event::Html(CowStr::from(html)));
+                            state = State::InVideo { dropped_depth: 0 };
+                        } else if is_audio_url(&url) {
+                            let html = format!(
+                                r#"<audio controls src="{}" style="max-width: 100%; display: block;"></audio>"#,
+                                url
+                            );
+                            events.push(Event::Html(CowStr::from(html)));
+                            state = State::InVideo { dropped_depth: 0 };
+                        } else {
+                            // Render image with max-width constraint
+                            let html = format!(
+                                r#"<img src="{}" style="max-width: 100%; max-height: 400px; display: block; border-radius: 4px; cursor: pointer;" />"#,
+                                url
+                            );
+                            events.push(Event::Html(CowStr::from(html)));
+                            state = State::InVideo { dropped_depth: 0 }; // Drop the alt text events
+                        }
+                    }
+                    
+                    other => events.push(other),
+                }
+            }
+            
+            State::InCodeBlock { ref mut lang, ref mut content } => {
+                match event {
+                    Event::Text(t) => content.push_str(&t),
+                    Event::End(TagEnd::CodeBlock) => {
+                        let html = if lang.as_deref() == Some("mermaid") {
+                            // Rendered client-side by trigger_diagram_render
+                            format!(r#"<pre class="mermaid">{}</pre>"#, escape_html(content))
+                        } else {
+                            render_code_block(content, lang.as_deref())
+                        };
+                        events.push(Event::Html(CowStr::from(html)));
+                        state = State::Normal;
+                    }
+                    _ => {} // Ignore likely
+                }
+            }
+            
+            State::InVideo { ref mut dropped_depth } => {
+                match event {
+                    Event::Start(_) => *dropped_depth += 1,
+                    Event::End(_) => {
+                        if *dropped_depth == 0 {
+                            state = State::Normal;
+                        } else {
+                            *dropped_depth -= 1;
+                        }
+                    }
+                    _ => {} 
+                }
+            }
+        }
+    }
+    flush_text(&mut events, &mut pending_text, opts);
+    
+    if !toc_slots.is_empty() {
+        let toc = toc_html(&headings);
+        for slot in toc_slots {
+            events[slot] = Event::Html(CowStr::from(toc.clone()));
+        }
+    }
+    
+    events
+}
+
+/// Strip a leading `[!KIND]` from the first paragraph of the block quote starting at `start`.
+/// Returns the callout's (css class, title) when the quote is a callout.
+fn take_admonition_marker(events: &mut [Event<'_>], start: usize) -> Option<(&'static str, &'static str)> {
+    if !matches!(events.get(start + 1), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+    let text = match events.get(start + 2) {
+        Some(Event::Text(text)) => text.trim_start(),
+        _ => return None,
+    };
+    let marker = text.strip_prefix("[!")?;
+    let end = marker.find(']')?;
+    let (_, class, title) = ADMONITIONS.iter()
+        .find(|(kind, _, _)| kind.eq_ignore_ascii_case(&marker[..end]))?;
+    
+    let rest = marker[end + 1..].trim_start().to_string();
+    if rest.is_empty() {
+        events[start + 2] = Event::Html(CowStr::from(""));
+        if matches!(events.get(start + 3), Some(Event::SoftBreak | Event::HardBreak)) {
+            events[start + 3] = Event::Html(CowStr::from(""));
+        }
+    } else {
+        events[start + 2] = Event::Text(CowStr::from(rest));
+    }
+    Some((*class, *title))
+}
+
+/// Turn heading text into an anchor id ("Hello World" -> "hello-world"); explicit
+/// `{#id}` attributes are kept as written. Repeated ids get a -1, -2... suffix.
+fn unique_heading_id(text: &str, explicit: bool, used: &mut HashMap<String, usize>) -> String {
+    let base = if explicit {
+        text.to_string()
+    } else {
+        let mut slug = String::new();
+        for c in text.trim().chars() {
+            if c.is_alphanumeric() || c == '_' {
+                slug.extend(c.to_lowercase());
+            } else if (c.is_whitespace() || c == '-') && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_matches('-');
+        if slug.is_empty() { "section".to_string() } else { slug.to_string() }
+    };
+    
+    let count = used.entry(base.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 { base } else { format!("{}-{}", base, *count - 1) }
+}
+
+/// Plain heading text without %r% colour markers (for ids and the TOC)
+pub(crate) fn strip_color_markers(text: &str) -> String {
+    COLORS.iter().fold(text.to_string(), |acc, (code, _)| acc.replace(&format!("%{}%", code), ""))
+}
+
+/// Render the collected headings as a flat, level-indented table of contents
+fn toc_html(headings: &[TocEntry]) -> String {
+    let mut html = String::from(r#"<nav class="toc"><ul>"#);
+    for entry in headings {
+        html.push_str(&format!(
+            r##"<li class="toc-level-{}"><a href="#{}">{}</a></li>"##,
+            entry.level as usize,
+            escape_html(&entry.id),
+            escape_html(&entry.text)
+        ));
+    }
+    html.push_str("</ul></nav>");
+    html
+}
+
+/// Emit merged text, handling custom colors (%r%), math ($$) and wiki links ([[...]])
+fn flush_text(events: &mut Vec<Event<'_>>, pending_text: &mut String, opts: &RenderOptions<'_>) {
+    if pending_text.is_empty() {
+        return;
+    }
+    let text = std::mem::take(pending_text);
+    if contains_special_syntax(&text) {
+        events.extend(process_text_events(&text, opts));
+    } else {
+        events.push(Event::Text(CowStr::from(text)));
+    }
+}
+
+#[cfg(feature = "highlight")]
+fn render_code_block(code: &str, lang: Option<&str>) -> String {
+    highlight::highlight_code(code, lang)
+}
+
+/// Without syntect, blocks keep their language in `data-lang` so the app can
+/// have them highlighted by the backend (`highlight_code` command)
+#[cfg(not(feature = "highlight"))]
+fn render_code_block(code: &str, lang: Option<&str>) -> String {
+    plain_code_block(code, lang)
+}
+
+/// Unhighlighted code block
+pub(crate) fn plain_code_block(code: &str, lang: Option<&str>) -> String {
+    match lang {
+        Some(lang) => {
+            let lang = escape_html(lang);
+            format!(r#"<pre><code class="language-{}" data-lang="{}">{}</code></pre>"#, lang, lang, escape_html(code))
+        }
+        None => format!("<pre><code>{}</code></pre>", escape_html(code)),
+    }
+}
+
+// function convert_local_url
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'?')
+    .add(b'{')
+    .add(b'}');
+
+/// Whether a link/image destination is a path on the local disk (C:/..., /home/...)
+pub fn is_local_path(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    lower.starts_with("c:") || lower.starts_with("d:") || lower.starts_with("e:") 
+        || lower.starts_with("f:") || lower.starts_with("g:") || lower.starts_with("z:") 
+        || (url.starts_with('/') && !url.starts_with("//"))
+}
+
+fn convert_local_url(url: &str) -> String {
+    if let Some(name) = asset_reference_name(url) {
+        if let Some(dir) = ASSETS_DIR.with(|dir| dir.borrow().clone()) {
+            return convert_local_url(&format!("{}/{}", dir, name));
+        }
+    }
+    if is_local_path(url) {
+        // Use https scheme for Windows compatibility now that assetProtocol is enabled
+        // Handles C:/Users... -> https://asset.localhost/C:/Users...
+        let normalized = url.replace('\\', "/");
+        
+        let encoded = utf8_percent_encode(&normalized, PATH_ENCODE_SET).to_string();
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        url.to_string()
+    }
+}
+
+fn is_video_url(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    lower.ends_with(".mp4") || lower.ends_with(".webm") || lower.ends_with(".mov") || lower.ends_with(".mkv")
+}
+
+fn is_audio_url(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    [".mp3", ".wav", ".ogg", ".m4a", ".flac"].iter().any(|ext| lower.ends_with(ext))
+}
+
+fn contains_special_syntax(text: &str) -> bool {
+    text.contains('$') || text.contains("[[") || find_inline_tag(text, 0).is_some() || COLORS.iter().any(|(code, _)| {
+        text.contains(&format!("%{}%", code))
+    })
+}
+
+// Process text for colors and math
+fn process_text_events(text: &str, opts: &RenderOptions<'_>) -> Vec<Event<'static>> {
+    let mut events = Vec::new();
+    let mut remaining = text.to_string();
+    
+    while !remaining.is_empty() {
+        let mut earliest_match: Option<(usize, String, MatchType)> = None; // pos, pattern, type
+        
+        // 1. Check for Display Math $$
+        if let Some(pos) = remaining.find("$$") {
+             if earliest_match.as_ref().is_none_or(|m| pos < m.0) {
+                earliest_match = Some((pos, "$$".to_string(), MatchType::DisplayMath));
+            }
+        }
+        
+        // 2. Check for Inline Math $ (if not overridden by $$)
+        if let Some(pos) = remaining.find('$') {
+            let is_start_of_display = remaining[pos..].starts_with("$$");
+            if !is_start_of_display && earliest_match.as_ref().is_none_or(|m| pos < m.0) {
+                earliest_match = Some((pos, "$".to_string(), MatchType::InlineMath));
+            }
+        }
+        
+        // 3. Check for Colors
+        for (code, color) in COLORS {
+            let pattern = format!("%{}%", code);
+            if let Some(pos) = remaining.find(&pattern) {
+                if earliest_match.as_ref().is_none_or(|m| pos < m.0) {
+                    earliest_match = Some((pos, pattern.clone(), MatchType::Color(color.to_string(), pattern)));
+                }
+            }
+        }
+        
+        // 4. Check for Wiki links [[...]]
+        if let Some(pos) = remaining.find("[[") {
+            if earliest_match.as_ref().is_none_or(|m| pos < m.0) {
+                earliest_match = Some((pos, "[[".to_string(), MatchType::WikiLink));
+            }
+        }
+        
+        // 5. Check for inline #tags
+        if let Some((pos, end)) = find_inline_tag(&remaining, 0) {
+            if earliest_match.as_ref().is_none_or(|m| pos < m.0) {
+                earliest_match = Some((pos, remaining[pos..end].to_string(), MatchType::InlineTag));
+            }
+        }
+        
+        match earliest_match {
+            Some((pos, pattern, match_type)) => {
+                // Add text before marker
+                if pos > 0 {
+                    events.push(Event::Text(CowStr::from(remaining[..pos].to_string())));
+                }
+                
+                match match_type {
+                    MatchType::DisplayMath => {
+                         remaining = remaining[pos + 2..].to_string();
+                         if let Some(end_pos) = remaining.find("$$") {
+                             // Emit as HTML to prevent escaping
+                             let content = &remaining[..end_pos];
+                             events.push(Event::Html(CowStr::from(format!("$${}$$", escape_html(content)))));
+                             remaining = remaining[end_pos + 2..].to_string();
+                         } else {
+                             events.push(Event::Text(CowStr::from("$$")));
+                         }
+                    }
+                    MatchType::InlineMath => {
+                         remaining = remaining[pos + 1..].to_string();
+                         if let Some(end_pos) = remaining.find('$') {
+                             let content = &remaining[..end_pos];
+                             events.push(Event::Html(CowStr::from(format!("${}$", escape_html(content)))));
+                             remaining = remaining[end_pos + 1..].to_string();
+                         } else {
+                             events.push(Event::Text(CowStr::from("$")));
+                         }
+                    }
+                    MatchType::InlineTag => {
+                        events.push(Event::Html(CowStr::from(inline_tag_html(&pattern[1..], opts))));
+                        remaining = remaining[pos + pattern.len()..].to_string();
+                    }
+                    MatchType::WikiLink => {
+                        remaining = remaining[pos + 2..].to_string();
+                        let inner = remaining.find("]]").map(|end| (end, remaining[..end].to_string()));
+                        match inner {
+                            Some((end_pos, inner)) if !inner.contains('[') && !inner.split('|').next().unwrap_or("").trim().is_empty() => {
+                                events.push(Event::Html(CowStr::from(wiki_link_html(&inner, opts))));
+                                remaining = remaining[end_pos + 2..].to_string();
+                            }
+                            _ => events.push(Event::Text(CowStr::from("[["))),
+                        }
+                    }
+                    MatchType::Color(color, pattern) => {
+                        remaining = remaining[pos + pattern.len()..].to_string();
+                        if let Some(end_pos) = remaining.find(&pattern) {
+                            events.push(Event::Html(CowStr::from(format!("<span style=\"color: {}\">", color))));
+                            if end_pos > 0 {
+                                events.push(Event::Text(CowStr::from(remaining[..end_pos].to_string())));
+                            }
+                            events.push(Event::Html(CowStr::from("</span>".to_string())));
+                            remaining = remaining[end_pos + pattern.len()..].to_string();
+                        } else {
+                            events.push(Event::Text(CowStr::from(pattern)));
+                        }
+                    }
+                }
+            }
+            None => {
+                events.push(Event::Text(CowStr::from(remaining)));
+                break;
+            }
+        }
+    }
+    events
+}
+
+#[derive(Clone)]
+enum MatchType {
+    DisplayMath,
+    InlineMath,
+    WikiLink,
+    InlineTag,
+    Color(String, String), // color_hex, pattern
+}
+
+/// Render a #parent/child token as a chip coloured like its leaf tag
+fn inline_tag_html(path: &str, opts: &RenderOptions<'_>) -> String {
+    let leaf = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    let color = match opts.tag_colors {
+        Some(colors) => colors.get(&leaf).cloned(),
+        None => TAG_COLORS.with(|colors| colors.borrow().get(&leaf).cloned()),
+    }
+    .unwrap_or_else(|| "#888".to_string());
+    format!(
+        r#"<span class="inline-tag" style="background-color: {};">#{}</span>"#,
+        escape_html(&color),
+        escape_html(path)
+    )
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Whether a tag token reads as a hex colour (`fff`, `a1b2c3`, `a1b2c3d4`)
+fn is_hex_color(token: &str) -> bool {
+    matches!(token.len(), 3 | 4 | 6 | 8) && token.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Find the next #tag token from byte offset `from`; the backend applies tags
+/// with the same rules. A token must start the text or follow whitespace/'(',
+/// and is skipped if purely numeric or a hex colour (`#fff`, `#a1b2c3`).
+/// Returns the byte range including the leading '#'.
+pub fn find_inline_tag(text: &str, from: usize) -> Option<(usize, usize)> {
+    let mut search = from;
+    while let Some(rel) = text.get(search..)?.find('#') {
+        let start = search + rel;
+        search = start + 1;
+
+        let prev_ok = text[..start].chars().next_back().is_none_or(|c| c.is_whitespace() || c == '(');
+        let first_ok = text[start + 1..].chars().next().is_some_and(is_tag_char);
+        if !prev_ok || !first_ok {
+            continue;
+        }
+
+        let mut end = start + 1;
+        let mut chars = text[end..].char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if is_tag_char(c) {
+                end = start + 1 + i + c.len_utf8();
+            } else if c == '/' && chars.peek().is_some_and(|(_, n)| is_tag_char(*n)) {
+                continue;
+            } else {
+                break;
+            }
+        }
+
+        let token = &text[start + 1..end];
+        if token.chars().all(|c| c.is_ascii_digit() || c == '/') || is_hex_color(token) {
+            search = end;
+            continue;
+        }
+        return Some((start, end));
+    }
+    None
+}
+
+/// Render `target|label` of a wiki link; resolved to an item on click (by source item + target)
+/// or, with a `wiki_link_href` hook, linked directly
+fn wiki_link_html(inner: &str, opts: &RenderOptions<'_>) -> String {
+    let mut parts = inner.splitn(2, '|');
+    let target = parts.next().unwrap_or("").trim();
+    let label = parts.next().map(str::trim).filter(|l| !l.is_empty()).unwrap_or(target);
+    if let Some(href_for) = opts.wiki_link_href {
+        return match href_for(target).filter(|href| is_safe_url(href, false)) {
+            Some(href) => format!(r#"<a class="wiki-link" href="{}">{}</a>"#, escape_html(&href), escape_html(label)),
+            None => format!(r#"<span class="wiki-link unresolved">{}</span>"#, escape_html(label)),
+        };
+    }
+    format!(
+        r##"<a class="wiki-link" href="#" data-wiki-link="{}">{}</a>"##,
+        escape_html(target),
+        escape_html(label)
+    )
+}
+
+/// Escape text for use in HTML content and double-quoted attributes
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footnotes() {
+        let html = parse_markdown("Claim[^1].\n\n[^1]: Source.");
+        assert!(html.contains(r##"<sup class="footnote-reference"><a href="#1">1</a></sup>"##), "{}", html);
+        assert!(html.contains(r#"<div class="footnote-definition" id="1">"#), "{}", html);
+    }
+
+    #[test]
+    fn test_heading_ids_are_unique_and_explicit_ids_kept() {
+        let html = parse_markdown("# Hello World\n\n## Hello World\n\n## 中文 标题\n\n## Custom {#my-id}");
+        assert!(html.contains(r#"<h1 id="hello-world">Hello World</h1>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="hello-world-1">Hello World</h2>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="中文-标题">中文 标题</h2>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="my-id">Custom</h2>"#), "{}", html);
+    }
+
+    #[test]
+    fn test_heading_attributes_cannot_inject_html() {
+        let html = parse_markdown("# Hi {onclick=alert(1) .cls}\n\n## Bg {style=background:url(javascript:alert(1))}\n\n## Odd {.x\"y .ok}");
+        assert!(html.contains(r#"<h1 id="hi" class="cls">Hi</h1>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="bg">Bg</h2>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="odd" class="ok">Odd</h2>"#), "{}", html);
+        assert!(!html.contains("onclick") && !html.contains("style=") && !html.contains("javascript"), "{}", html);
+    }
+
+    #[test]
+    fn test_toc_lists_headings_including_later_ones() {
+        let html = parse_markdown("[TOC]\n\n# Intro\n\n## Details `code`");
+        assert!(html.starts_with(r#"<nav class="toc"><ul>"#), "{}", html);
+        assert!(html.contains(r##"<li class="toc-level-1"><a href="#intro">Intro</a></li>"##), "{}", html);
+        assert!(html.contains(r##"<li class="toc-level-2"><a href="#details-code">Details code</a></li>"##), "{}", html);
+        assert!(!html.contains("[TOC]"), "{}", html);
+    }
+
+    #[test]
+    fn test_toc_inside_text_is_left_alone() {
+        let html = parse_markdown("see [TOC] here");
+        assert_eq!(html, "<p>see [TOC] here</p>\n");
+    }
+
+    #[test]
+    fn test_admonitions() {
+        let html = parse_markdown("> [!WARNING]\n> Be careful");
+        assert!(html.contains(r#"<div class="admonition admonition-warning"><p class="admonition-title">警告</p>"#), "{}", html);
+        assert!(html.contains("Be careful"), "{}", html);
+        assert!(!html.contains("[!WARNING]"), "{}", html);
+        assert!(!html.contains("<blockquote>"), "{}", html);
+
+        let html = parse_markdown("> [!note] inline body");
+        assert!(html.contains("admonition-note"), "{}", html);
+        assert!(html.contains("<p>inline body</p>"), "{}", html);
+
+        // Unknown kinds and plain quotes stay block quotes
+        let html = parse_markdown("> [!FOO] text\n\n> plain");
+        assert_eq!(html.matches("<blockquote>").count(), 2, "{}", html);
+    }
+
+    #[test]
+    fn test_mermaid_blocks_are_not_highlighted() {
+        let html = parse_markdown("```mermaid\ngraph TD\n  A --> B\n```");
+        assert_eq!(html, "<pre class=\"mermaid\">graph TD\n  A --&gt; B\n</pre>");
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_code_blocks_use_classes_and_bundled_syntaxes() {
+        let html = parse_markdown("```rust,ignore\nfn main() {}\n```");
+        assert!(html.starts_with("<pre class=\"hl-code\">"), "{}", html);
+        assert!(html.contains("hl-source hl-rust"), "{}", html);
+        assert!(!html.contains("style="), "{}", html);
+
+        let toml = highlight_code("[package]\nname = \"x\" # c\n", Some("toml"));
+        assert!(toml.contains("hl-entity hl-name hl-section hl-toml"), "{}", toml);
+        assert!(toml.contains("hl-comment"), "{}", toml);
+
+        let nix = highlight_code("let x = \"${pkgs.hello}\"; in x\n", Some("nix"));
+        assert!(nix.contains("hl-keyword hl-control hl-nix"), "{}", nix);
+        assert!(nix.contains("hl-meta hl-interpolation hl-nix"), "{}", nix);
+
+        let leptos = "fn app() -> impl IntoView {\n    view! { <Button on:click=move |_| set(1)>{count}</Button> }\n}\n";
+        let leptos = highlight_code(leptos, Some("leptos"));
+        assert!(leptos.contains("hl-entity hl-other hl-attribute-name hl-namespace"), "{}", leptos);
+        assert!(leptos.contains("hl-punctuation hl-definition hl-tag hl-end hl-html"), "{}", leptos);
+
+        // Unknown languages fall back to plain text
+        assert!(highlight_code("<b>", Some("nope")).contains("&lt;b&gt;"));
+    }
+
+    #[cfg(not(feature = "highlight"))]
+    #[test]
+    fn test_code_blocks_left_for_backend_highlighting() {
+        let html = parse_markdown("```rust,ignore\nlet a = 1 < 2;\n```\n\n```\nplain\n```");
+        assert!(html.contains(r#"<pre><code class="language-rust" data-lang="rust">let a = 1 &lt; 2;"#), "{}", html);
+        assert!(html.contains("<pre><code>plain\n</code></pre>"), "{}", html);
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_highlight_css_has_light_and_dark_themes() {
+        let css = highlight_css(LIGHT_THEME, DARK_THEME).unwrap();
+        let (light, dark) = css.split_once("@media (prefers-color-scheme: dark)").unwrap();
+        assert!(light.contains(".hl-code"), "{}", light);
+        assert!(dark.contains(".hl-code"), "{}", dark);
+        assert!(highlight_css("no-such-theme", DARK_THEME).is_err());
+        assert!(theme_names().iter().any(|name| name == DARK_THEME));
+    }
+
+    #[test]
+    fn test_media_embeds() {
+        let html = parse_markdown("![](clip.MP4)\n\n![voice](memo.m4a)\n\n![](pic.png)");
+        assert!(html.contains(r#"<video controls src="clip.MP4""#), "{}", html);
+        assert!(html.contains(r#"<audio controls src="memo.m4a""#), "{}", html);
+        assert!(html.contains(r#"<img src="pic.png""#), "{}", html);
+        assert!(!html.contains("voice"), "{}", html);
+    }
+
+    #[test]
+    fn test_asset_references_resolve_per_device() {
+        let hash = "ab".repeat(32);
+        let markdown = format!("![](assets/{}.png) [doc](assets/{}.pdf)", hash, hash);
+        // Unresolved until the device's asset directory is known
+        assert!(parse_markdown(&markdown).contains(&format!(r#"src="assets/{}.png""#, hash)));
+
+        set_assets_dir("C:\\Users\\me\\assets\\");
+        let html = parse_markdown(&markdown);
+        assert!(html.contains(&format!(r#"src="http://asset.localhost/C:/Users/me/assets/{}.png""#, hash)), "{}", html);
+        assert!(html.contains(&format!(r#"href="http://asset.localhost/C:/Users/me/assets/{}.pdf""#, hash)), "{}", html);
+
+        assert_eq!(asset_reference_name("assets/x.png"), None);
+        assert_eq!(asset_reference_name(&format!("assets/{}", hash)), None);
+    }
+
+    #[test]
+    fn test_task_checkboxes_are_numbered() {
+        let html = parse_markdown("- [ ] a\n- [x] b\n\n```\n- [ ] not a task\n```\n\n1. [X] c");
+        assert!(html.contains(r#"<input type="checkbox" class="task-checkbox" data-task-index="0"/>"#), "{}", html);
+        assert!(html.contains(r#"<input type="checkbox" class="task-checkbox" data-task-index="1" checked=""/>"#), "{}", html);
+        assert!(html.contains(r#"data-task-index="2" checked=""/>"#), "{}", html);
+        assert!(!html.contains("disabled"), "{}", html);
+    }
+
+    #[test]
+    fn test_memo_tasks_and_toggle() {
+        let source = "intro\n- [ ] first `code`\n- [x] %g%second%g%\n  continued\n  - [ ] nested\n";
+        let tasks = memo_tasks(source);
+        let texts: Vec<_> = tasks.iter().map(|t| (t.checked, t.text.as_str())).collect();
+        assert_eq!(texts, vec![(false, "first code"), (true, "second"), (false, "nested")]);
+        assert_eq!(&source[tasks[1].line.clone()], "- [x] %g%second%g%\n");
+
+        let toggled = toggle_task(source, 0).unwrap();
+        assert!(toggled.starts_with("intro\n- [x] first"));
+        let toggled = toggle_task(&toggled, 1).unwrap();
+        assert!(toggled.contains("- [ ] %g%second%g%"));
+        assert_eq!(toggle_task(source, 3), None);
+    }
+
+    #[test]
+    fn test_remove_task_lines() {
+        let source = "# Plan\n- [ ] a\n- [x] b\nnotes\n- [ ] c";
+        let tasks = memo_tasks(source);
+        assert_eq!(remove_task_lines(source, &tasks[..2]), "# Plan\nnotes\n- [ ] c");
+        assert_eq!(remove_task_lines(source, &tasks), "# Plan\nnotes\n");
+    }
+
+    /// No emitted tag may carry script, an event handler or a javascript: URL
+    /// (escaped text like "&lt;img onerror=...&gt;" is inert and allowed)
+    fn assert_no_script(html: &str) {
+        let lower = html.to_ascii_lowercase();
+        for tag in lower.split('<').skip(1) {
+            let tag = tag.split('>').next().unwrap_or("");
+            for bad in ["script", "javascript:", " on", "\ton", "iframe", "svg", "formaction", "data:text"] {
+                assert!(!tag.contains(bad), "found {:?} in <{}> of {}", bad, tag, html);
+            }
+        }
+        assert!(!lower.contains("alert(1)</"), "script body kept in {}", html);
+    }
+
+    #[test]
+    fn test_sanitizer_strips_scripts_and_handlers() {
+        let hostile = [
+            "<script>alert(1)</script>",
+            "<ScRiPt src=x></sCrIpT>",
+            "<script>\nvar a = '</div>';\nalert(1)\n</script>",
+            "<img src=x onerror=alert(1)>",
+            "<img src=\"x\" onerror=\"alert(1)\"/>",
+            "text <img src=x\tONERROR=alert(1)> inline",
+            "<svg onload=alert(1)><circle/></svg>",
+            "<iframe src=\"https://evil.example\"></iframe>",
+            "<a href=\"javascript:alert(1)\">x</a>",
+            "<a href=\"jav&#x61;script&colon;alert(1)\">x</a>",
+            "<a href=\" JaVaScRiPt:alert(1)\">x</a>",
+            "<a href=\"java\tscript:alert(1)\">x</a>",
+            "<a href=\"data:text/html,<script>alert(1)</script>\">x</a>",
+            "<div style=\"background:url(javascript:alert(1))\">x</div>",
+            "<p>ok</p><!-- <script>alert(1)</script> -->",
+            "<details open ontoggle=alert(1)>x</details>",
+            "<form action=x><button formaction=javascript:alert(1)>go</button></form>",
+        ];
+        for input in hostile {
+            assert_no_script(&parse_markdown(input));
+            assert_no_script(&parse_markdown_inline(input));
+        }
+    }
+
+    #[test]
+    fn test_sanitizer_covers_generated_heading_attributes() {
+        let html = parse_markdown(concat!(
+            "# x {onclick=alert(1)}\n\n",
+            "# y {style=background:url(javascript:alert(1))}\n\n",
+            "# z {#top onmouseover=alert(1) .a STYLE=color:red}\n\n",
+            "# w {#a\"b}\n",
+        ));
+        let lower = html.to_ascii_lowercase();
+        let handler = lower.split(|c: char| c.is_whitespace() || c == '<')
+            .any(|word| word.starts_with("on") && word.contains('='));
+        assert!(!handler && !lower.contains("style="), "{}", html);
+        assert!(html.contains(r#"<h1 id="top" class="a">z</h1>"#), "{}", html);
+        // An unsafe explicit id falls back to the generated one
+        assert!(html.contains(r#"<h1 id="w">w</h1>"#), "{}", html);
+    }
+
+    #[test]
+    fn test_markdown_urls_and_math_are_escaped() {
+        assert_no_script(&parse_markdown("[x](javascript:alert(1))"));
+        assert_no_script(&parse_markdown("[x](JAVASCRIPT&#58;alert(1))"));
+        assert_no_script(&parse_markdown("![x](javascript:alert(1))"));
+
+        let html = parse_markdown("![x](<x\" onerror=\"alert(1)>)");
+        assert!(html.contains(r#"src="x&quot; onerror=&quot;alert(1)""#), "{}", html);
+        let html = parse_markdown("![v](<a.mp4\" onloadstart=\"alert(1)>)");
+        assert!(!html.contains("\" onloadstart"), "{}", html);
+
+        // Entities decode to "<" in text events, so math content must be escaped again
+        let html = parse_markdown("$&lt;img src=x onerror=alert(1)&gt;$ $$&lt;script&gt;$$");
+        assert!(!html.contains("<img") && !html.contains("<script"), "{}", html);
+        assert!(html.contains("$&lt;img src=x onerror=alert(1)&gt;$"), "{}", html);
+    }
+
+    #[test]
+    fn test_sanitizer_keeps_safe_html() {
+        let html = parse_markdown("<b>bold</b> <kbd>Ctrl</kbd> <span style=\"color: red\" class=\"x\">red</span>");
+        assert!(html.contains("<b>bold</b>"), "{}", html);
+        assert!(html.contains("<kbd>Ctrl</kbd>"), "{}", html);
+        assert!(html.contains(r#"<span style="color: red" class="x">red</span>"#), "{}", html);
+
+        let html = parse_markdown("<a href=\"https://example.com/?a=1&amp;b=2\" onclick=\"x()\">link</a>");
+        assert!(html.contains(r#"<a href="https://example.com/?a=1&amp;b=2" rel="noopener noreferrer">link</a>"#), "{}", html);
+
+        let html = parse_markdown("<div align=center>\n\n<img src=\"C:/pics/a.png\" width=100>\n\n</div>");
+        assert!(html.contains(r#"<div align="center">"#), "{}", html);
+        assert!(html.contains(r#"<img src="C:/pics/a.png" width="100">"#), "{}", html);
+
+        // Stray angle brackets become text
+        assert_eq!(parse_markdown_inline("a <3 b"), "a &lt;3 b");
+        assert!(parse_markdown("[ok](https://example.com)").contains(r#"href="https://example.com""#));
+    }
+
+    #[test]
+    fn test_sanitize_html_fragment() {
+        let hash = "ab".repeat(32);
+        let page = format!(
+            "<article><h1 onclick=\"x()\">Title</h1><script>alert(1)</script><p>Text <img src=\"assets/{}.png\"></p></article>",
+            hash
+        );
+        let html = sanitize_html(&page, false);
+        assert_eq!(html, format!(r#"<h1>Title</h1><p>Text <img src="assets/{}.png"></p>"#, hash));
+
+        set_assets_dir("/data/assets");
+        let html = sanitize_html(&page, true);
+        assert!(html.contains(&format!(r#"src="http://asset.localhost//data/assets/{}.png""#, hash)), "{}", html);
+    }
+
+    #[test]
+    fn test_render_options_hooks() {
+        let colors: HashMap<String, String> = [("rust".to_string(), "#dea584".to_string())].into_iter().collect();
+        let wiki = |target: &str| (target == "Known").then(|| "item-7.html".to_string());
+        let rewrite = |url: &str| url.starts_with("C:/").then(|| "assets/a.png".to_string());
+        let opts = RenderOptions {
+            tag_colors: Some(&colors),
+            wiki_link_href: Some(&wiki),
+            rewrite_url: Some(&rewrite),
+            readonly_tasks: true,
+        };
+
+        let html = parse_markdown_with("[[Known|see]] [[Missing]] #rust ![x](C:/pics/a.png)\n\n- [x] done", &opts);
+        assert!(html.contains(r#"data-task-index="0" checked="" disabled=""/>"#), "{}", html);
+        assert!(html.contains(r#"<a class="wiki-link" href="item-7.html">see</a>"#), "{}", html);
+        assert!(html.contains(r#"<span class="wiki-link unresolved">Missing</span>"#), "{}", html);
+        assert!(html.contains("background-color: #dea584;"), "{}", html);
+        assert!(html.contains(r#"<img src="assets/a.png""#), "{}", html);
+
+        // Without hooks links stay in-app and local paths use the asset protocol
+        let html = parse_markdown("[[Known]] ![x](C:/pics/a.png)");
+        assert!(html.contains(r#"data-wiki-link="Known""#), "{}", html);
+        assert!(html.contains("http://asset.localhost/C:/pics/a.png"), "{}", html);
+    }
+
+    #[test]
+    fn test_hex_colours_are_not_tags() {
+        assert_eq!(find_inline_tag("border #fff and #a1b2c3, #A1B2C3D4", 0), None);
+        assert_eq!(find_inline_tag("#fffe5 #cafe", 0), Some((0, 6)));
+        assert_eq!(find_inline_tag("#a1b2c3 #face/off", 0), Some((8, 17)));
+        let html = parse_markdown("Colour #fff then #rust");
+        assert_eq!(html.matches("inline-tag").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn test_colors_and_math_still_work() {
+        let html = parse_markdown("# %r%Red%r% title\n\nInline $x^2$ and $$y$$");
+        assert!(html.contains(r#"<h1 id="red-title">"#), "{}", html);
+        assert!(html.contains(r#"<span style="color: #e74c3c">Red</span>"#), "{}", html);
+        assert!(html.contains("$x^2$"), "{}", html);
+        assert!(html.contains("$$y$$"), "{}", html);
+    }
+}
@@ -0,0 +1,318 @@
+//! HTML Sanitiser
+//!
+//! Allow-list filtering for raw HTML embedded in memos and for attributes
+//! written on headings, plus the URL and inline-style checks shared with the
+//! generated tags.
+
+use crate::{asset_reference_name, convert_local_url, escape_html};
+use pulldown_cmark::Tag;
+
+/// Tags kept by the sanitiser (attributes are filtered separately)
+const ALLOWED_TAGS: &[&str] = &[
+    "a", "abbr", "audio", "b", "blockquote", "br", "center", "code", "dd", "del", "details", "div",
+    "dl", "dt", "em", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img",
+    "ins", "kbd", "li", "mark", "ol", "p", "pre", "q", "s", "samp", "small", "source", "span",
+    "strike", "strong", "sub", "summary", "sup", "table", "tbody", "td", "tfoot", "th", "thead",
+    "tr", "u", "ul", "video",
+];
+
+/// Tags dropped together with everything up to their closing tag
+const DROPPED_WITH_CONTENT: &[&str] = &[
+    "script", "style", "iframe", "object", "embed", "noscript", "template", "textarea", "title",
+    "xmp", "noembed", "noframes", "frameset", "frame", "svg", "math", "applet",
+];
+
+/// Attributes kept on any allowed tag (`href`/`src` are checked as URLs, `style` is filtered)
+const ALLOWED_ATTRS: &[&str] = &[
+    "class", "title", "alt", "width", "height", "align", "colspan", "rowspan", "start", "lang",
+    "dir", "controls", "open", "loop", "muted", "style", "href", "src",
+];
+
+/// Allow-list HTML sanitiser for raw HTML in memos. Keeps only `ALLOWED_TAGS` with
+/// `ALLOWED_ATTRS` (values re-escaped), drops event handlers, comments, unsafe URLs
+/// and the content of <script>-like elements. Stateful so a comment or script
+/// spanning several HTML events is still removed.
+#[derive(Default)]
+pub(crate) struct HtmlSanitizer {
+    in_comment: bool,
+    /// Inside a DROPPED_WITH_CONTENT element: its name
+    skipping: Option<String>,
+    /// Point `assets/{hash}.{ext}` sources and links at this device's asset directory
+    resolve_assets: bool,
+}
+
+impl HtmlSanitizer {
+    pub(crate) fn resolving_assets() -> Self {
+        Self { resolve_assets: true, ..Default::default() }
+    }
+
+    pub(crate) fn clean(&mut self, html: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        
+        while !rest.is_empty() {
+            if self.in_comment {
+                match rest.find("-->") {
+                    Some(end) => {
+                        rest = &rest[end + 3..];
+                        self.in_comment = false;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            if let Some(name) = self.skipping.clone() {
+                let lower = rest.to_ascii_lowercase();
+                match lower.find(&format!("</{}", name)) {
+                    Some(start) => {
+                        let close = rest[start..].find('>').map_or(rest.len(), |i| start + i + 1);
+                        rest = &rest[close..];
+                        self.skipping = None;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            
+            let Some(lt) = rest.find('<') else {
+                out.push_str(&rest.replace('>', "&gt;"));
+                break;
+            };
+            out.push_str(&rest[..lt].replace('>', "&gt;"));
+            rest = &rest[lt..];
+            
+            if let Some(after) = rest.strip_prefix("<!--") {
+                self.in_comment = true;
+                rest = after;
+                continue;
+            }
+            match parse_tag(rest) {
+                Some((tag, len)) => {
+                    rest = &rest[len..];
+                    if DROPPED_WITH_CONTENT.contains(&tag.name.as_str()) {
+                        if !tag.closing && !tag.self_closing {
+                            self.skipping = Some(tag.name);
+                        }
+                    } else if ALLOWED_TAGS.contains(&tag.name.as_str()) {
+                        out.push_str(&render_tag(&tag, self.resolve_assets));
+                    }
+                }
+                None => {
+                    out.push_str("&lt;");
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A parsed HTML tag: lower-cased name and attributes with entity-decoded values
+struct HtmlTag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attrs: Vec<(String, String)>,
+}
+
+/// Parse the tag at the start of `html` (which starts with '<').
+/// Returns the tag and its byte length, or None if this is not a complete tag.
+fn parse_tag(html: &str) -> Option<(HtmlTag, usize)> {
+    let bytes = html.as_bytes();
+    let mut i = 1;
+    let closing = bytes.get(i) == Some(&b'/');
+    if closing {
+        i += 1;
+    }
+    let name_start = i;
+    while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+        i += 1;
+    }
+    if i == name_start || !bytes[name_start].is_ascii_alphabetic() {
+        return None;
+    }
+    let name = html[name_start..i].to_ascii_lowercase();
+    
+    let mut attrs = Vec::new();
+    let mut self_closing = false;
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            self_closing = bytes[i] == b'/';
+            i += 1;
+        }
+        match bytes.get(i) {
+            None => return None,
+            Some(b'>') => {
+                return Some((HtmlTag { name, closing, self_closing, attrs }, i + 1));
+            }
+            _ => {}
+        }
+        self_closing = false;
+        
+        let attr_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        let attr_name = html[attr_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(&quote) if quote == b'"' || quote == b'\'' => {
+                    let end = html[i + 1..].find(quote as char)? + i + 1;
+                    value = decode_entities(&html[i + 1..end]);
+                    i = end + 1;
+                }
+                _ => {
+                    let value_start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    value = decode_entities(&html[value_start..i]);
+                }
+            }
+        }
+        if !attr_name.is_empty() {
+            attrs.push((attr_name, value));
+        }
+    }
+}
+
+/// Re-emit an allowed tag with only safe attributes, values escaped
+fn render_tag(tag: &HtmlTag, resolve_assets: bool) -> String {
+    if tag.closing {
+        return format!("</{}>", tag.name);
+    }
+    let mut html = format!("<{}", tag.name);
+    for (name, value) in &tag.attrs {
+        if !ALLOWED_ATTRS.contains(&name.as_str()) {
+            continue;
+        }
+        let safe = match name.as_str() {
+            "href" => is_safe_url(value, false),
+            "src" => is_safe_url(value, true),
+            "style" => is_safe_style(value),
+            _ => true,
+        };
+        if !safe {
+            continue;
+        }
+        let is_asset = resolve_assets && matches!(name.as_str(), "href" | "src") && asset_reference_name(value).is_some();
+        let value = if is_asset { convert_local_url(value) } else { value.clone() };
+        html.push_str(&format!(" {}=\"{}\"", name, escape_html(&value)));
+    }
+    if tag.name == "a" {
+        html.push_str(" rel=\"noopener noreferrer\"");
+    }
+    html.push('>');
+    html
+}
+
+/// Decode the character references used to disguise URLs (`jav&#x61;script&colon;`)
+fn decode_entities(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let end = rest.find(';').filter(|&end| end <= 12);
+        let decoded = end.and_then(|end| {
+            let entity = &rest[1..end];
+            let c = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse::<u32>().ok().and_then(char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "colon" => Some(':'),
+                    "tab" => Some('\t'),
+                    "newline" => Some('\n'),
+                    "nbsp" => Some('\u{a0}'),
+                    _ => None,
+                }
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// URLs may be relative, http(s), mailto, tel, asset, or a Windows drive path;
+/// `data:image/*` (not SVG) is allowed for image sources only
+pub fn is_safe_url(url: &str, is_media: bool) -> bool {
+    let cleaned: String = url.chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let scheme_end = cleaned.find([':', '/', '?', '#']);
+    let scheme = match scheme_end {
+        Some(end) if cleaned[end..].starts_with(':') => &cleaned[..end],
+        _ => return true, // relative URL
+    };
+    match scheme {
+        "http" | "https" | "mailto" | "tel" | "asset" => true,
+        "data" => is_media && cleaned.starts_with("data:image/") && !cleaned.starts_with("data:image/svg"),
+        s => s.len() == 1 && s.chars().all(|c| c.is_ascii_alphabetic()), // C:/path
+    }
+}
+
+/// Heading attributes written in markdown (`# Title {#id .class key=value}`)
+/// bypass the raw HTML allow-list, so they are filtered here: `key=value`
+/// pairs (event handlers, style) are dropped, classes and explicit ids are
+/// kept only if they are plain names.
+pub(crate) fn clean_heading(tag: Tag<'_>) -> Tag<'_> {
+    match tag {
+        Tag::Heading { level, id, mut classes, .. } => {
+            classes.retain(|class| is_safe_class(class));
+            let id = id.filter(|id| is_safe_id(id));
+            Tag::Heading { level, id, classes, attrs: Vec::new() }
+        }
+        other => other,
+    }
+}
+
+/// Class names: ASCII letters, digits, `-` and `_`, starting with a letter
+fn is_safe_class(class: &str) -> bool {
+    class.len() <= 40
+        && class.starts_with(|c: char| c.is_ascii_alphabetic())
+        && class.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Explicit heading ids: no whitespace, quotes or markup characters
+fn is_safe_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().count() <= 100
+        && !id.chars().any(|c| c.is_whitespace() || c.is_control() || "\"'<>&`=".contains(c))
+}
+
+/// Inline styles may not load resources or run script (`url(...)`, `expression(...)`)
+fn is_safe_style(style: &str) -> bool {
+    let lower: String = style.chars()
+        .filter(|c| !c.is_whitespace() && *c != '\\')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    !["url(", "expression", "javascript:", "behavior", "-moz-binding", "@import", "<"]
+        .iter()
+        .any(|bad| lower.contains(bad))
+}
@@ -0,0 +1,77 @@
+//! Task Lists
+//!
+//! Locate `- [ ]` tasks in memo source so preview checkboxes can write back
+//! and checklists can be promoted to items.
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::{get_options, strip_color_markers};
+
+/// A `- [ ]` task in a memo's markdown source
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoTask {
+    pub checked: bool,
+    /// Plain text of the task's own line
+    pub text: String,
+    /// Byte range of the `[ ]` / `[x]` marker
+    pub marker: std::ops::Range<usize>,
+    /// Byte range of the whole source line holding the marker (including its newline)
+    pub line: std::ops::Range<usize>,
+}
+
+/// List the tasks of a memo in source order (index = `data-task-index` in the preview)
+pub fn memo_tasks(source: &str) -> Vec<MemoTask> {
+    let mut tasks: Vec<MemoTask> = Vec::new();
+    // Collecting text for the last task until its line ends
+    let mut collecting = false;
+    
+    for (event, range) in Parser::new_ext(source, get_options()).into_offset_iter() {
+        match event {
+            Event::TaskListMarker(checked) => {
+                let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = source[range.end..].find('\n').map_or(source.len(), |i| range.end + i + 1);
+                tasks.push(MemoTask { checked, text: String::new(), marker: range, line: line_start..line_end });
+                collecting = true;
+            }
+            Event::Text(text) | Event::Code(text) if collecting => {
+                let task = tasks.last_mut().expect("collecting implies a task");
+                if range.start >= task.line.end {
+                    collecting = false;
+                } else {
+                    task.text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Item) | Event::Start(Tag::List(_)) | Event::SoftBreak | Event::HardBreak => {
+                collecting = false;
+            }
+            _ => {}
+        }
+    }
+    
+    for task in &mut tasks {
+        task.text = strip_color_markers(task.text.trim());
+    }
+    tasks
+}
+
+/// Flip the checkbox of task `index` in the markdown source ("[ ]" <-> "[x]")
+pub fn toggle_task(source: &str, index: usize) -> Option<String> {
+    let task = memo_tasks(source).into_iter().nth(index)?;
+    let replacement = if task.checked { "[ ]" } else { "[x]" };
+    Some(format!("{}{}{}", &source[..task.marker.start], replacement, &source[task.marker.end..]))
+}
+
+/// Remove the lines of the given tasks from the source (after promoting them to items)
+pub fn remove_task_lines(source: &str, tasks: &[MemoTask]) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut pos = 0;
+    for task in tasks {
+        if task.line.start < pos {
+            continue;
+        }
+        result.push_str(&source[pos..task.line.start]);
+        pos = task.line.end;
+    }
+    result.push_str(&source[pos..]);
+    result
+}
@@ -2,32 +2,20 @@
 
 document.addEventListener('DOMContentLoaded', async () => {
     // 加载已保存的配置
-    const config = await browser.storage.local.get(['tursoUrl', 'tursoToken']);
+    const config = await loadApiConfig();
 
-    if (config.tursoUrl) {
-        document.getElementById('turso-url').value = config.tursoUrl;
-    }
-
-    if (config.tursoToken) {
-        document.getElementById('turso-token').value = config.tursoToken;
+    document.getElementById('api-port').value = config.port;
+    if (config.token) {
+        document.getElementById('api-token').value = config.token;
     }
 
     // 保存配置
     document.getElementById('save').addEventListener('click', async () => {
-        const url = document.getElementById('turso-url').value.trim();
-        const token = document.getElementById('turso-token').value.trim();
-
-        if (!url || !token) {
-            showStatus('请填写完整的URL和Token', 'error');
-            return;
-        }
+        const form = readForm();
+        if (!form) return;
 
         try {
-            await browser.storage.local.set({
-                tursoUrl: url,
-                tursoToken: token
-            });
-
+            await saveForm(form);
             showStatus('配置已保存！', 'success');
         } catch (error) {
             showStatus('保存失败: ' + error.message, 'error');
@@ -36,43 +24,18 @@ document.addEventListener('DOMContentLoaded', async () => {
 
     // 测试连接
     document.getElementById('test').addEventListener('click', async () => {
-        const url = document.getElementById('turso-url').value.trim();
-        const token = document.getElementById('turso-token').value.trim();
-
-        if (!url || !token) {
-            showStatus('请先填写URL和Token', 'error');
-            return;
-        }
+        const form = readForm();
+        if (!form) return;
 
         showStatus('正在测试连接...', 'info');
 
         try {
-            const client = createClient({
-                url: url,
-                authToken: token
-            });
+            // 验证token（桌面应用未运行时fetch会失败）
+            await apiRequest('GET', '/api/tags', undefined, form);
 
-            // 先确保schema正确
-            showStatus('正在检查数据库结构...', 'info');
-            await ensureSchema(client);
-
-            // 测试查询
-            const result = await client.execute({
-                sql: 'SELECT 1 as test',
-                args: []
-            });
-
-            if (result) {
-                // 测试成功，自动保存配置
-                await browser.storage.local.set({
-                    tursoUrl: url,
-                    tursoToken: token
-                });
-
-                showStatus('✅ 连接成功！配置已自动保存', 'success');
-            } else {
-                showStatus('连接失败：无法执行查询', 'error');
-            }
+            // 测试成功，自动保存配置
+            await saveForm(form);
+            showStatus('✅ 连接成功！配置已自动保存', 'success');
         } catch (error) {
             console.error('连接测试失败:', error);
             showStatus('❌ 连接失败: ' + error.message, 'error');
@@ -80,27 +43,23 @@ document.addEventListener('DOMContentLoaded', async () => {
     });
 });
 
-// 确保数据库有url和summary字段
-async function ensureSchema(client) {
-    try {
-        // 尝试添加url字段
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN url TEXT',
-            args: []
-        });
-    } catch (e) {
-        // 字段已存在，忽略错误
-    }
+// 读取并校验表单
+function readForm() {
+    const port = parseInt(document.getElementById('api-port').value.trim(), 10);
+    const token = document.getElementById('api-token').value.trim();
 
-    try {
-        // 尝试添加summary字段
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN summary TEXT',
-            args: []
-        });
-    } catch (e) {
-        // 字段已存在，忽略错误
+    if (!port || port < 1 || port > 65535 || !token) {
+        showStatus('请填写有效的端口和Token', 'error');
+        return null;
     }
+    return { port, token };
+}
+
+async function saveForm({ port, token }) {
+    await browser.storage.local.set({
+        [API_PORT_KEY]: port,
+        [API_TOKEN_KEY]: token
+    });
 }
 
 function showStatus(message, type) {
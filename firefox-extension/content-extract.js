@@ -38,7 +38,9 @@ function extractContent() {
             return {
                 title: article.title || document.title,
                 summary: generateSummary(article.textContent),
-                textContent: article.textContent || ''
+                textContent: article.textContent || '',
+                // 正文HTML，由桌面应用清洗后存档供离线阅读
+                content: article.content || ''
             };
         }
     }
@@ -47,7 +49,8 @@ function extractContent() {
     return {
         title: document.title,
         summary: generateSummary(getPageText()),
-        textContent: getPageText()
+        textContent: getPageText(),
+        content: ''
     };
 }
 
@@ -0,0 +1,77 @@
+// local-api.js - tag-all桌面应用本地API客户端（background / sidebar / config共用）
+
+const API_PORT_KEY = 'apiPort';
+const API_TOKEN_KEY = 'apiToken';
+const DEFAULT_API_PORT = 27124;
+
+// 书签所在的tag（由桌面应用创建）
+const BOOKMARK_TAG = 'web-bookmark';
+
+// 时间戳统一为毫秒；小于此值的是旧版本写入的秒
+const SECONDS_CUTOFF = 100000000000;
+
+function toMillis(timestamp) {
+    return timestamp > 0 && timestamp < SECONDS_CUTOFF ? timestamp * 1000 : timestamp;
+}
+
+// 读取本地API配置
+async function loadApiConfig() {
+    const data = await browser.storage.local.get([API_PORT_KEY, API_TOKEN_KEY]);
+    return {
+        port: data[API_PORT_KEY] || DEFAULT_API_PORT,
+        token: data[API_TOKEN_KEY] || ''
+    };
+}
+
+// 调用本地API，失败时抛出带状态码的错误；204返回null
+async function apiRequest(method, path, body, config) {
+    const { port, token } = config || await loadApiConfig();
+    if (!token) {
+        throw new Error('Local API not configured');
+    }
+
+    const headers = { 'Authorization': `Bearer ${token}` };
+    if (body !== undefined) {
+        headers['Content-Type'] = 'application/json';
+    }
+
+    const response = await fetch(`http://127.0.0.1:${port}${path}`, {
+        method,
+        headers,
+        body: body !== undefined ? JSON.stringify(body) : undefined
+    });
+
+    if (!response.ok) {
+        let message = response.statusText;
+        try {
+            message = (await response.json()).error || message;
+        } catch (e) {
+            // 非JSON错误体
+        }
+        throw new Error(`${response.status} ${message}`);
+    }
+
+    return response.status === 204 ? null : response.json();
+}
+
+// 搜索条目；tagIds全部匹配
+function searchItems({ query = '', tagIds = [], withUrl = true, limit = 100 } = {}) {
+    const params = new URLSearchParams();
+    if (query) params.append('q', query);
+    tagIds.forEach(id => params.append('tag', id));
+    if (withUrl) params.append('with_url', '1');
+    params.append('limit', limit);
+    return apiRequest('GET', `/api/search?${params}`);
+}
+
+// 按规范化URL查找已保存的书签（忽略utm_*等跟踪参数、大小写和末尾斜杠），没有时为null
+function findItemByUrl(url) {
+    return apiRequest('GET', `/api/items/by-url?url=${encodeURIComponent(url)}`);
+}
+
+// web-bookmark tag的id（还没有书签时为null）
+async function findBookmarkTagId() {
+    const tags = await apiRequest('GET', '/api/tags');
+    const tag = tags.find(t => t.name === BOOKMARK_TAG);
+    return tag ? tag.id : null;
+}
@@ -1,6 +1,5 @@
 // sidebar.js - 侧边栏逻辑
 
-let client = null;
 let searchTimeout = null;
 
 // 初始化
@@ -67,14 +66,14 @@ async function loadBookmarks(searchQuery = '') {
 
     try {
         // 获取配置和本地队列
-        const data = await browser.storage.local.get(['tursoUrl', 'tursoToken', 'syncQueue']);
-        const config = { tursoUrl: data.tursoUrl, tursoToken: data.tursoToken };
+        const config = await loadApiConfig();
+        const data = await browser.storage.local.get('syncQueue');
         const syncQueue = data.syncQueue || [];
 
-        if (!config.tursoUrl || !config.tursoToken) {
+        if (!config.token) {
             listEl.innerHTML = `
         <div class="empty-state">
-          <p>⚙️ 请先配置数据库连接</p>
+          <p>⚙️ 请先配置桌面应用连接</p>
           <button id="open-settings-btn">打开设置</button>
         </div>
       `;
@@ -94,7 +93,7 @@ async function loadBookmarks(searchQuery = '') {
             text: item.title,
             url: item.url,
             summary: item.selection,
-            created_at: item.created_at,
+            created_at: toMillis(item.created_at),
             pending: true
         })).reverse();
 
@@ -105,60 +104,22 @@ async function loadBookmarks(searchQuery = '') {
             listEl.innerHTML = `<div class="loading">⏳ 加载中...</div>`;
         }
 
-        // 2. 创建客户端并异步加载DB数据
-        client = createClient({
-            url: config.tursoUrl,
-            authToken: config.tursoToken
-        });
-
-        // 加载Tags (不阻塞主列表)
-        loadTags(client).catch(console.warn);
-
-        // 3. 查询DB数据
+        // 2. 从桌面应用加载书签
         let dbItems = [];
         try {
-            let sql = '';
-            let args = [];
-
-            if (currentTagFilter) {
-                sql = `
-                  SELECT DISTINCT i.id, i.text, i.url, i.summary, i.created_at
-                  FROM items i
-                  JOIN item_tags it1 ON i.id = it1.item_id
-                  JOIN tags t1 ON it1.tag_id = t1.id
-                  JOIN item_tags it2 ON i.id = it2.item_id
-                  JOIN tags t2 ON it2.tag_id = t2.id
-                  WHERE t1.name = 'web-bookmark' 
-                  AND t2.id = ? 
-                  ${searchQuery ? 'AND i.text LIKE ?' : ''}
-                  ORDER BY i.created_at DESC
-                  LIMIT 100
-               `;
-                args = [currentTagFilter];
-                if (searchQuery) args.push(`%${searchQuery}%`);
-
-            } else {
-                sql = `
-                  SELECT DISTINCT i.id, i.text, i.url, i.summary, i.created_at
-                  FROM items i
-                  JOIN item_tags it ON i.id = it.item_id
-                  JOIN tags t ON it.tag_id = t.id
-                  WHERE t.name = 'web-bookmark'
-                  ${searchQuery ? 'AND i.text LIKE ?' : ''}
-                  ORDER BY i.created_at DESC
-                  LIMIT 100
-                `;
-                if (searchQuery) args.push(`%${searchQuery}%`);
-            }
-
-            const result = await client.execute({ sql, args });
-            dbItems = result.rows;
+            const bookmarkTagId = await findBookmarkTagId();
+            if (bookmarkTagId !== null) {
+                // 加载Tags (不阻塞主列表)
+                loadTags(bookmarkTagId).catch(console.warn);
 
+                const tagIds = currentTagFilter ? [bookmarkTagId, currentTagFilter] : [bookmarkTagId];
+                dbItems = await searchItems({ query: searchQuery, tagIds, limit: 100 });
+            }
         } catch (error) {
-            console.warn('DB Query failed:', error);
+            console.warn('Local API query failed:', error);
         }
 
-        // 4. 合并并最终渲染
+        // 3. 合并并最终渲染
         const finalItems = [...queuedItems, ...dbItems];
         renderBookmarks(finalItems);
 
@@ -168,28 +129,18 @@ async function loadBookmarks(searchQuery = '') {
     }
 }
 
-async function loadTags(client) {
+async function loadTags(bookmarkTagId) {
     const tagsEl = document.getElementById('tags');
     if (!tagsEl) return;
 
-    // 获取所有在该workspace下使用过的Tags
-    // (关联了 web-bookmark 里的items 的 tags)
-    // SQL: Find tags used by items that also have 'web-bookmark' tag.
+    // 书签上用过的其他Tags
     try {
-        const sql = `
-            SELECT DISTINCT t.id, t.name, t.color
-            FROM tags t
-            JOIN item_tags it ON t.id = it.tag_id
-            JOIN items i ON it.item_id = i.id
-            JOIN item_tags it_wb ON i.id = it_wb.item_id
-            JOIN tags t_wb ON it_wb.tag_id = t_wb.id
-            WHERE t_wb.name = 'web-bookmark'
-            AND t.name != 'web-bookmark' -- Exclude itself
-            ORDER BY t.name
-        `;
-
-        const result = await client.execute({ sql, args: [] });
-        const tags = result.rows;
+        const bookmarks = await searchItems({ tagIds: [bookmarkTagId], limit: 500 });
+        const byId = new Map();
+        bookmarks.forEach(item => item.tags
+            .filter(tag => tag.id !== bookmarkTagId)
+            .forEach(tag => byId.set(tag.id, tag)));
+        const tags = [...byId.values()].sort((a, b) => a.name.localeCompare(b.name));
 
         // Render
         const allClass = !currentTagFilter ? 'active' : '';
@@ -366,8 +317,6 @@ function getErrorHtml(msg) {
     listEl.innerHTML = `<div class="error"><p>❌ 加载失败</p><small>${escapeHtml(msg)}</small></div>`;
 }
 
-// ensureSchema ... existing ...
-
 function formatDate(timestamp) {
     const date = new Date(timestamp);
     const now = new Date();
@@ -391,53 +340,6 @@ function formatDate(timestamp) {
     }
 }
 
-// 确保数据库有url和summary字段
-async function ensureSchema(client) {
-    console.log('ensureSchema: Adding url column...');
-    try {
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN url TEXT',
-            args: []
-        });
-        console.log('ensureSchema: url column added');
-    } catch (e) {
-        console.log('ensureSchema: url column exists or error:', e.message);
-    }
-
-    console.log('ensureSchema: Adding summary column...');
-    try {
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN summary TEXT',
-            args: []
-        });
-        console.log('ensureSchema: summary column added');
-    } catch (e) {
-        console.log('ensureSchema: summary column exists or error:', e.message);
-    }
-
-    console.log('ensureSchema: Adding created_at column...');
-    try {
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN created_at INTEGER DEFAULT 0',
-            args: []
-        });
-        console.log('ensureSchema: created_at column added');
-    } catch (e) {
-        console.log('ensureSchema: created_at column exists or error:', e.message);
-    }
-
-    console.log('ensureSchema: Adding updated_at column...');
-    try {
-        await client.execute({
-            sql: 'ALTER TABLE items ADD COLUMN updated_at INTEGER DEFAULT 0',
-            args: []
-        });
-        console.log('ensureSchema: updated_at column added');
-    } catch (e) {
-        console.log('ensureSchema: updated_at column exists or error:', e.message);
-    }
-}
-
 function escapeHtml(text) {
     const div = document.createElement('div');
     div.textContent = text;
@@ -0,0 +1,289 @@
+//! Page Archive Layer
+//!
+//! Keeps an offline reader copy of a bookmarked page (see `domain::Archive`):
+//! the main content is cut out of the page, sanitised with the memo
+//! allow-list, links are made absolute, images are downloaded into the asset
+//! store and the HTML itself is stored as an asset.
+//!
+//! The page can be passed in already fetched (by the clipper, or by tests);
+//! only missing pages and images go through the `PageFetcher`.
+
+mod reader;
+
+pub(crate) use reader::decode_entities;
+
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri_plugin_http::reqwest::{self, Url};
+use tokio::sync::Mutex;
+
+use crate::domain::{sniff_mime, Archive, Asset, DomainError, DomainResult, FileIdentifier, Item, Timestamp, ARCHIVE_HTML_EXT};
+use crate::repository::{ArchiveRepository, AssetRepository};
+
+/// Images downloaded per page; later ones keep their web address
+const MAX_IMAGES: usize = 100;
+/// Largest image (or page) downloaded
+const MAX_DOWNLOAD_BYTES: usize = 20 * 1024 * 1024;
+
+/// Downloads pages and images
+#[async_trait]
+pub trait PageFetcher: Send + Sync {
+    /// Body of a successful GET request
+    async fn fetch(&self, url: &str) -> DomainResult<Vec<u8>>;
+}
+
+/// Fetches over HTTP(S)
+pub struct HttpFetcher {
+    client: reqwest::Client,
+}
+
+impl HttpFetcher {
+    pub fn new() -> DomainResult<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("Mozilla/5.0 (compatible; tag-all/", env!("CARGO_PKG_VERSION"), ")"))
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(http_err)?;
+        Ok(Self { client })
+    }
+}
+
+fn http_err(e: reqwest::Error) -> DomainError {
+    DomainError::Internal(format!("Download failed: {}", e))
+}
+
+#[async_trait]
+impl PageFetcher for HttpFetcher {
+    async fn fetch(&self, url: &str) -> DomainResult<Vec<u8>> {
+        let response = self.client.get(url).send().await.map_err(http_err)?;
+        if !response.status().is_success() {
+            return Err(DomainError::Internal(format!("Download of {} failed: HTTP {}", url, response.status())));
+        }
+        if response.content_length().is_some_and(|len| len as usize > MAX_DOWNLOAD_BYTES) {
+            return Err(DomainError::Internal(format!("{} is too large", url)));
+        }
+        let bytes = response.bytes().await.map_err(http_err)?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// A stored archive and what could not be made offline
+#[derive(Debug, Serialize)]
+pub struct ArchiveResult {
+    #[serde(flatten)]
+    pub archive: Archive,
+    /// Images that could not be downloaded; they still point at the web
+    pub missing_images: Vec<String>,
+}
+
+/// Archive the page of a bookmark (or any item with an http(s) URL),
+/// replacing an earlier archive. `page` is the page's HTML if already fetched
+/// (a full page or just the article); otherwise it is downloaded.
+pub async fn archive_item(
+    conn: Arc<Mutex<Option<Connection>>>,
+    assets_dir: &Path,
+    fetcher: &dyn PageFetcher,
+    item: &Item,
+    page: Option<String>,
+) -> DomainResult<ArchiveResult> {
+    let url = item.url.as_deref().map(str::trim).filter(|u| !u.is_empty())
+        .ok_or_else(|| DomainError::InvalidInput(format!("Item {} has no URL", item.id)))?;
+    let base = Url::parse(url).ok().filter(|u| matches!(u.scheme(), "http" | "https"))
+        .ok_or_else(|| DomainError::InvalidInput(format!("Not a web page: {}", url)))?;
+
+    let page = match page {
+        Some(page) => page,
+        None => String::from_utf8_lossy(&fetcher.fetch(base.as_str()).await?).into_owned(),
+    };
+    let title = reader::page_title(&page).unwrap_or_else(|| item.text.clone());
+    let content = reader::promote_lazy_sources(&reader::reader_content(&page));
+    let html = reader::resolve_urls(&tag_all_markdown::sanitize_html(&content, false), &base);
+
+    let mut images: Vec<(String, Asset)> = Vec::new();
+    let mut missing_images = Vec::new();
+    for src in reader::image_sources(&html) {
+        if images.len() >= MAX_IMAGES {
+            missing_images.push(src);
+            continue;
+        }
+        match store_image(conn.clone(), assets_dir, fetcher, &src).await {
+            Ok(asset) => images.push((src, asset)),
+            Err(_) => missing_images.push(src),
+        }
+    }
+    let html = reader::replace_image_sources(&html, |src| {
+        images.iter().find(|(s, _)| s == src).map(|(_, asset)| asset.reference())
+    });
+
+    let html_asset = Asset {
+        hash: FileIdentifier::compute_data_hash(html.as_bytes()),
+        ext: ARCHIVE_HTML_EXT.to_string(),
+        mime: "text/html".to_string(),
+        size: html.len() as u64,
+        original_name: None,
+        created_at: Timestamp::now(),
+    };
+    let html_asset = store_asset(conn.clone(), assets_dir, html_asset, html.as_bytes()).await?;
+
+    let mut image_hashes: Vec<String> = Vec::new();
+    for (_, asset) in images {
+        if !image_hashes.contains(&asset.hash) {
+            image_hashes.push(asset.hash);
+        }
+    }
+    let archive = Archive {
+        item_id: item.id,
+        url: url.to_string(),
+        title,
+        html_hash: html_asset.hash,
+        text: reader::html_to_text(&html),
+        images: image_hashes,
+        created_at: Timestamp::now(),
+    };
+    let archive = ArchiveRepository::new(conn).save(&archive).await?;
+    Ok(ArchiveResult { archive, missing_images })
+}
+
+/// The reader HTML of an archive, if its file is on this device (it may not
+/// have been downloaded by asset sync yet)
+pub fn read_archive_html(assets_dir: &Path, archive: &Archive) -> Option<String> {
+    fs::read_to_string(assets_dir.join(format!("{}.{}", archive.html_hash, ARCHIVE_HTML_EXT))).ok()
+}
+
+/// Download (or decode a `data:` URL) and store one image
+async fn store_image(
+    conn: Arc<Mutex<Option<Connection>>>,
+    assets_dir: &Path,
+    fetcher: &dyn PageFetcher,
+    src: &str,
+) -> DomainResult<Asset> {
+    let (bytes, original_name) = match src.strip_prefix("data:") {
+        Some(data) => {
+            let (_, payload) = data.split_once(";base64,")
+                .ok_or_else(|| DomainError::InvalidInput("Unsupported data URL".to_string()))?;
+            let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, payload)
+                .map_err(|e| DomainError::InvalidInput(format!("Failed to decode image: {}", e)))?;
+            (bytes, None)
+        }
+        None => {
+            let name = Url::parse(src).ok()
+                .and_then(|url| url.path_segments().and_then(|mut s| s.next_back()).map(str::to_string))
+                .filter(|name| !name.is_empty());
+            (fetcher.fetch(src).await?, name)
+        }
+    };
+    if bytes.len() > MAX_DOWNLOAD_BYTES {
+        return Err(DomainError::InvalidInput(format!("{} is too large", src)));
+    }
+    // Only raster images: error pages served under image URLs are dropped, and
+    // SVG is refused like `data:image/svg` in memos since it can carry script
+    let (mime, ext) = sniff_mime(&bytes).filter(|(mime, _)| mime.starts_with("image/") && *mime != "image/svg+xml")
+        .ok_or_else(|| DomainError::InvalidInput(format!("Not an image: {}", src)))?;
+
+    let asset = Asset {
+        hash: FileIdentifier::compute_data_hash(&bytes),
+        ext: ext.to_string(),
+        mime: mime.to_string(),
+        size: bytes.len() as u64,
+        original_name,
+        created_at: Timestamp::now(),
+    };
+    store_asset(conn, assets_dir, asset, &bytes).await
+}
+
+/// Write the file of a content-addressed asset (unless present) and record it
+async fn store_asset(
+    conn: Arc<Mutex<Option<Connection>>>,
+    assets_dir: &Path,
+    mut asset: Asset,
+    bytes: &[u8],
+) -> DomainResult<Asset> {
+    let io_err = |e: std::io::Error| DomainError::Internal(format!("Failed to write asset: {}", e));
+    fs::create_dir_all(assets_dir).map_err(io_err)?;
+
+    let repo = AssetRepository::new(conn);
+    // Same content stored before: keep its extension (and file)
+    if let Some(existing) = repo.find(&asset.hash).await? {
+        asset.ext = existing.ext;
+    }
+    let path = assets_dir.join(asset.file_name());
+    if !path.exists() {
+        // Write under a temporary name so a partial file never carries the content hash
+        let tmp = assets_dir.join(format!("{}.tmp", asset.hash));
+        fs::write(&tmp, bytes).map_err(io_err)?;
+        fs::rename(&tmp, &path).map_err(io_err)?;
+    }
+    repo.upsert(&asset).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ItemType, ASSET_OWNER_ARCHIVE};
+    use crate::repository::init_db;
+    use crate::repository::item::ItemWorkspaceOperations;
+    use crate::repository::ItemRepository;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// Serves fixed responses; anything else is a network error
+    struct FakeFetcher(HashMap<String, Vec<u8>>);
+
+    #[async_trait]
+    impl PageFetcher for FakeFetcher {
+        async fn fetch(&self, url: &str) -> DomainResult<Vec<u8>> {
+            self.0.get(url).cloned().ok_or_else(|| DomainError::Internal(format!("offline: {}", url)))
+        }
+    }
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n fake image";
+
+    #[tokio::test]
+    async fn test_archive_prefetched_page() {
+        let dir = std::env::temp_dir().join(format!("tag-all-archive-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let mut bookmark = Item::new(0, "Ownership".to_string(), ItemType::Bookmark);
+        bookmark.url = Some("https://example.com/book/ch04.html".to_string());
+        let bookmark = items.create_with_workspace(&bookmark, 4).await.unwrap();
+
+        let page = r#"<html><head><title>Understanding Ownership</title><script>track()</script></head>
+            <body><nav>Menu</nav><article><h1>What is Ownership?</h1>
+            <p>Each value in Rust has an <a href="ch05.html">owner</a>.</p>
+            <img src="img/trpl04-01.png" onerror="x()"><img src="https://cdn.example.com/gone.png">
+            <img src="img/trpl04-01.png"></article><footer>Footer</footer></body></html>"#;
+        let fetcher = FakeFetcher(HashMap::from([("https://example.com/book/img/trpl04-01.png".to_string(), PNG.to_vec())]));
+
+        let result = archive_item(db.conn.clone(), &dir, &fetcher, &bookmark, Some(page.to_string())).await.unwrap();
+        let archive = &result.archive;
+        assert_eq!(archive.title, "Understanding Ownership");
+        assert_eq!(archive.text, "What is Ownership?\nEach value in Rust has an owner.");
+        assert_eq!(result.missing_images, vec!["https://cdn.example.com/gone.png"]);
+        assert_eq!(archive.images, vec![FileIdentifier::compute_data_hash(PNG)]);
+
+        let html = read_archive_html(&dir, archive).unwrap();
+        assert!(!html.contains("script") && !html.contains("onerror") && !html.contains("Menu"), "{}", html);
+        assert!(html.contains(r#"href="https://example.com/book/ch05.html""#), "{}", html);
+        assert_eq!(html.matches(&format!(r#"src="assets/{}.png""#, archive.images[0])).count(), 2, "{}", html);
+        assert!(html.contains(r#"src="https://cdn.example.com/gone.png""#), "{}", html);
+        assert!(dir.join(format!("{}.png", archive.images[0])).is_file());
+
+        // Both files are kept by the item's archive
+        let assets = AssetRepository::new(db.conn.clone());
+        for hash in archive.asset_hashes() {
+            let refs = assets.get_refs(&hash).await.unwrap();
+            assert_eq!(refs.iter().map(|r| (r.owner_type.as_str(), r.owner_id)).collect::<Vec<_>>(), vec![(ASSET_OWNER_ARCHIVE, bookmark.id)]);
+        }
+
+        // Without a page and without network, nothing changes
+        assert!(archive_item(db.conn.clone(), &dir, &fetcher, &bookmark, None).await.is_err());
+        assert_eq!(ArchiveRepository::new(db.conn.clone()).find(bookmark.id).await.unwrap().as_ref(), Some(archive));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,321 @@
+//! Reader View Extraction
+//!
+//! Pulls the readable part out of a web page and post-processes the
+//! sanitised result. Pages are not parsed into a tree: elements are located
+//! by their tags and matched by nesting depth, which is enough for picking
+//! `<article>`/`<main>` and dropping navigation around it.
+
+use regex::{Captures, Regex};
+use tauri_plugin_http::reqwest::Url;
+
+/// Elements that hold the main content, most specific first
+const CONTENT_ELEMENTS: &[&str] = &["article", "main", "body"];
+
+/// Page furniture dropped from the content (with everything inside)
+const BOILERPLATE_ELEMENTS: &[&str] = &["nav", "aside", "footer", "form", "button", "dialog"];
+
+/// Block-level closing tags that end a line of text
+const BLOCK_END: &str = r"(?i)<br\s*/?>|</(p|div|h[1-6]|li|tr|blockquote|pre|figure|figcaption|dd|dt|table|ul|ol)>";
+
+/// An element in `html`: where its opening tag starts, where its content starts and ends
+/// and where the closing tag ends (the end of the input if it is never closed)
+struct Element {
+    start: usize,
+    inner_start: usize,
+    inner_end: usize,
+    end: usize,
+}
+
+/// Elements named `name` in `html`, outermost only
+fn find_elements(html: &str, name: &str) -> Vec<Element> {
+    // ASCII lower-casing keeps byte offsets
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    let close = format!("</{}", name);
+    let is_tag_at = |pos: usize, prefix: &str| {
+        lower[pos..].starts_with(prefix)
+            && lower.as_bytes().get(pos + prefix.len()).is_none_or(|b| b.is_ascii_whitespace() || matches!(b, b'>' | b'/'))
+    };
+
+    let mut elements = Vec::new();
+    let mut search = 0;
+    while let Some(found) = lower[search..].find(&open) {
+        let start = search + found;
+        search = start + open.len();
+        if !is_tag_at(start, &open) {
+            continue;
+        }
+        let Some(inner_start) = lower[start..].find('>').map(|i| start + i + 1) else { break };
+
+        let mut depth = 1;
+        let mut pos = inner_start;
+        let (inner_end, end) = loop {
+            let Some(next) = lower[pos..].find('<').map(|i| pos + i) else {
+                break (html.len(), html.len());
+            };
+            if is_tag_at(next, &close) {
+                depth -= 1;
+                if depth == 0 {
+                    let end = lower[next..].find('>').map_or(html.len(), |i| next + i + 1);
+                    break (next, end);
+                }
+            } else if is_tag_at(next, &open) {
+                depth += 1;
+            }
+            pos = next + 1;
+        };
+        elements.push(Element { start, inner_start, inner_end, end });
+        search = end;
+    }
+    elements
+}
+
+/// The main content of a page: the largest `<article>`, else `<main>`, else
+/// `<body>`, else the whole input (e.g. a fragment extracted by the clipper),
+/// without navigation, sidebars and forms
+pub fn reader_content(page: &str) -> String {
+    let content = CONTENT_ELEMENTS.iter()
+        .find_map(|name| {
+            find_elements(page, name).into_iter()
+                .max_by_key(|e| e.inner_end - e.inner_start)
+                .map(|e| &page[e.inner_start..e.inner_end])
+        })
+        .unwrap_or(page);
+
+    let mut html = content.to_string();
+    for name in BOILERPLATE_ELEMENTS {
+        let mut kept = String::with_capacity(html.len());
+        let mut copied = 0;
+        for element in find_elements(&html, name) {
+            kept.push_str(&html[copied..element.start]);
+            copied = element.end;
+        }
+        kept.push_str(&html[copied..]);
+        html = kept;
+    }
+    html
+}
+
+/// `<meta property="og:title">`, else `<title>`
+pub fn page_title(page: &str) -> Option<String> {
+    let og_title = Regex::new(r#"(?is)<meta\s[^>]*property\s*=\s*["']og:title["'][^>]*>"#).expect("valid regex");
+    let content = Regex::new(r#"(?is)\scontent\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("valid regex");
+    let title = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("valid regex");
+
+    let from_meta = og_title.find(page)
+        .and_then(|meta| content.captures(meta.as_str()))
+        .and_then(|c| c.get(1).or_else(|| c.get(2)))
+        .map(|m| m.as_str());
+    let raw = from_meta.or_else(|| title.captures(page).and_then(|c| c.get(1)).map(|m| m.as_str()))?;
+    let title = collapse_whitespace(&decode_entities(raw));
+    (!title.is_empty()).then_some(title)
+}
+
+/// Lazy-loaded images keep the real address in `data-src` and a placeholder
+/// (or nothing) in `src`; make `data-src` the source
+pub fn promote_lazy_sources(html: &str) -> String {
+    let img = Regex::new(r"(?is)<img\b[^>]*>").expect("valid regex");
+    let src = Regex::new(r#"(?is)\ssrc\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#).expect("valid regex");
+    let data_src = Regex::new(r"(?is)\sdata-(?:src|original)\s*=").expect("valid regex");
+
+    img.replace_all(html, |c: &Captures| {
+        let tag = &c[0];
+        match data_src.find(tag) {
+            Some(lazy) => {
+                let tag = format!("{} src={}", &tag[..lazy.start()], &tag[lazy.end()..]);
+                // The placeholder comes first or last; drop whichever `src` is not the promoted one
+                let promoted = lazy.start();
+                let mut out = String::with_capacity(tag.len());
+                let mut copied = 0;
+                for m in src.find_iter(&tag) {
+                    if m.start() != promoted {
+                        out.push_str(&tag[copied..m.start()]);
+                        copied = m.end();
+                    }
+                }
+                out.push_str(&tag[copied..]);
+                out
+            }
+            None => tag.to_string(),
+        }
+    })
+    .into_owned()
+}
+
+/// Resolve relative `src`/`href` values of sanitised HTML against the page URL.
+/// Values that do not resolve to http(s) (or a `data:` image) are removed.
+pub fn resolve_urls(html: &str, base: &Url) -> String {
+    let attr = Regex::new(r#"\s(src|href)="([^"]*)""#).expect("valid regex");
+    attr.replace_all(html, |c: &Captures| {
+        let value = decode_entities(&c[2]);
+        if value.starts_with('#') || (&c[1] == "src" && value.starts_with("data:image/")) {
+            return c[0].to_string();
+        }
+        match base.join(&value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "mailto") => {
+                format!(" {}=\"{}\"", &c[1], tag_all_markdown::escape_html(url.as_str()))
+            }
+            _ => String::new(),
+        }
+    })
+    .into_owned()
+}
+
+/// Sources of the `<img>` tags in sanitised HTML, without duplicates
+pub fn image_sources(html: &str) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    for c in img_src_regex().captures_iter(html) {
+        let src = decode_entities(&c[2]);
+        if !sources.contains(&src) {
+            sources.push(src);
+        }
+    }
+    sources
+}
+
+/// Replace `<img>` sources in sanitised HTML; `replace` gets the decoded source
+/// and returns the new one, or `None` to keep it
+pub fn replace_image_sources(html: &str, replace: impl Fn(&str) -> Option<String>) -> String {
+    img_src_regex().replace_all(html, |c: &Captures| {
+        match replace(&decode_entities(&c[2])) {
+            Some(src) => format!("{}{}\"", &c[1], tag_all_markdown::escape_html(&src)),
+            None => c[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+fn img_src_regex() -> Regex {
+    Regex::new(r#"(<img\b[^>]*?\ssrc=")([^"]*)""#).expect("valid regex")
+}
+
+/// Plain text of sanitised HTML: one line per block, whitespace collapsed
+pub fn html_to_text(html: &str) -> String {
+    let block_end = Regex::new(BLOCK_END).expect("valid regex");
+    let tag = Regex::new(r"(?s)<[^>]*>").expect("valid regex");
+
+    let text = block_end.replace_all(html, "\n");
+    let text = decode_entities(&tag.replace_all(&text, ""));
+    text.lines()
+        .map(collapse_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decode character references (numeric and the common named ones)
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse::<u32>().ok().and_then(char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "ndash" => Some('–'),
+                    "mdash" => Some('—'),
+                    "hellip" => Some('…'),
+                    "lsquo" => Some('‘'),
+                    "rsquo" => Some('’'),
+                    "ldquo" => Some('“'),
+                    "rdquo" => Some('”'),
+                    "copy" => Some('©'),
+                    _ => None,
+                }
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_content_picks_article_without_boilerplate() {
+        let page = r#"<html><head><title>Site</title></head><body>
+            <nav><a href="/">Home</a></nav>
+            <article class="teaser"><p>Short</p></article>
+            <ARTICLE><h1>Post</h1><p>Body <article>nested</article> text</p>
+              <aside>Related <aside>inner</aside> posts</aside><form><input></form><p>End</p></ARTICLE>
+            <footer>(c)</footer></body></html>"#;
+        let content = reader_content(page);
+        assert_eq!(
+            content.split_whitespace().collect::<String>(),
+            "<h1>Post</h1><p>Body<article>nested</article>text</p><p>End</p>"
+        );
+
+        assert!(reader_content("<main><p>Main</p></main><div>x</div>").starts_with("<p>Main</p>"));
+        assert_eq!(reader_content("<div><p>Fragment</p></div>"), "<div><p>Fragment</p></div>");
+        assert_eq!(reader_content("<body><p>Unclosed"), "<p>Unclosed");
+    }
+
+    #[test]
+    fn test_page_title() {
+        assert_eq!(page_title("<title>\n  A &amp; B  </title>").as_deref(), Some("A & B"));
+        assert_eq!(
+            page_title(r#"<title>Site</title><meta property="og:title" content="Real &quot;title&quot;">"#).as_deref(),
+            Some("Real \"title\"")
+        );
+        assert_eq!(page_title("<title> </title>"), None);
+    }
+
+    #[test]
+    fn test_urls_and_images() {
+        let base = Url::parse("https://example.com/blog/post.html").unwrap();
+        let html = promote_lazy_sources(r#"<img src="data:image/gif;base64,R0lG" data-src="/img/a.png" alt="a"><img data-original='b.jpg'>"#);
+        assert_eq!(html, r#"<img src="/img/a.png" alt="a"><img src='b.jpg'>"#);
+
+        let html = tag_all_markdown::sanitize_html(
+            &format!(r##"<p><a href="../about?x=1&amp;y=2">About</a> <a href="javascript:alert(1)">x</a> <a href="#top">top</a></p>{}<img src="https://cdn.example.com/a.png">"##, html),
+            false,
+        );
+        let html = resolve_urls(&html, &base);
+        assert!(html.contains(r#"<a href="https://example.com/about?x=1&amp;y=2" rel="noopener noreferrer">About</a>"#), "{}", html);
+        assert!(html.contains(r#"<a rel="noopener noreferrer">x</a>"#), "{}", html);
+        assert!(html.contains(r##"<a href="#top""##), "{}", html);
+        assert_eq!(image_sources(&html), vec![
+            "https://example.com/img/a.png",
+            "https://example.com/blog/b.jpg",
+            "https://cdn.example.com/a.png",
+        ]);
+
+        let html = replace_image_sources(&html, |src| src.ends_with("a.png").then(|| "assets/x.png".to_string()));
+        assert_eq!(image_sources(&html), vec!["assets/x.png", "https://example.com/blog/b.jpg"]);
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<h1>Title</h1><p>First&nbsp;line<br>second   line</p><ul><li>a &amp; b</li><li>c</li></ul>";
+        assert_eq!(html_to_text(html), "Title\nFirst line\nsecond line\na & b\nc");
+    }
+}
@@ -0,0 +1,414 @@
+//! Asset Protocol
+//!
+//! Serves local files to the webview as `http://asset.localhost/{path}`
+//! (memo images, video, audio, linked files). Only files under the scope's
+//! roots are served: mounted workspace directories, the asset directories and
+//! paths the user explicitly allowed. Paths are canonicalized before the
+//! check, so `..` segments and symlinks cannot leave a root.
+//!
+//! Range requests read just the requested bytes (capped per response, media
+//! elements ask for the rest); a request without `Range` gets the whole file
+//! with 200, as the client expects. Responses carry `ETag`/`Last-Modified` so
+//! unchanged files are answered with 304.
+
+use percent_encoding::percent_decode_str;
+use rusqlite::Connection;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tauri::http::{header, Request, Response, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::domain::{DomainError, DomainResult, ASSETS_DIR, LEGACY_CLIPBOARD_DIR};
+use crate::repository::WorkspaceRepository;
+
+/// Largest body of one range response
+const MAX_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Device-local list of allowed paths, next to the database. Not a setting:
+/// settings are synced, and a path allowed on one device must not widen the
+/// scope on another.
+const ALLOWED_PATHS_FILE: &str = "asset_allowed_paths.json";
+
+/// Origins of the app's own pages (production on macOS/Linux and Windows, dev server)
+const APP_ORIGINS: &[&str] = &["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost", "http://localhost:1437"];
+
+/// Directories the protocol may serve from
+#[derive(Debug, Default)]
+pub struct AssetScope {
+    /// Canonicalized, existing directories or files
+    roots: Vec<PathBuf>,
+}
+
+impl AssetScope {
+    /// Scope over `roots`; roots that don't exist are skipped
+    pub fn new(roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut roots: Vec<PathBuf> = roots.into_iter().filter_map(|root| root.canonicalize().ok()).collect();
+        roots.sort();
+        roots.dedup();
+        Self { roots }
+    }
+
+    /// Canonical path of a file inside the scope; 404 if it doesn't exist,
+    /// 403 if it is outside every root
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf, StatusCode> {
+        if !path.is_absolute() {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let path = path.canonicalize().map_err(|_| StatusCode::NOT_FOUND)?;
+        if !self.roots.iter().any(|root| path.starts_with(root)) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if !path.is_file() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        Ok(path)
+    }
+}
+
+/// Paths the user allowed on this device (none if nothing was saved)
+pub fn load_allowed_paths(app_dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(app_dir.join(ALLOWED_PATHS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Save the paths the user allowed on this device
+pub fn save_allowed_paths(app_dir: &Path, paths: &[String]) -> DomainResult<()> {
+    let json = serde_json::to_string_pretty(paths).map_err(|e| DomainError::Internal(e.to_string()))?;
+    std::fs::write(app_dir.join(ALLOWED_PATHS_FILE), json)
+        .map_err(|e| DomainError::Internal(format!("Failed to save allowed asset paths: {}", e)))
+}
+
+/// Canonical form of a path the user wants to allow. Filesystem roots, the
+/// home directory and anything containing it are rejected: allowing them
+/// would let the webview read almost every file on the device.
+pub fn allowable_path(path: &Path, home_dir: Option<&Path>) -> DomainResult<PathBuf> {
+    if !path.is_absolute() {
+        return Err(DomainError::InvalidInput(format!("Not an absolute path: {}", path.display())));
+    }
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| DomainError::InvalidInput(format!("Cannot allow {}: {}", path.display(), e)))?;
+    let contains_home = home_dir
+        .and_then(|home| home.canonicalize().ok())
+        .is_some_and(|home| home.starts_with(&canonical));
+    if canonical.parent().is_none() || contains_home {
+        return Err(DomainError::InvalidInput(format!("Too broad to allow: {}", canonical.display())));
+    }
+    Ok(canonical)
+}
+
+/// Build the scope from the database: all workspace directories, the asset
+/// directories under `app_dir` and the paths allowed on this device.
+/// Workspace dirs are synced, so they pass the same `allowable_path` check
+/// as allowed paths: a root or home directory from another device is skipped.
+pub async fn load_scope(conn: Arc<Mutex<Option<Connection>>>, app_dir: &Path, home_dir: Option<&Path>) -> DomainResult<AssetScope> {
+    let mut roots = vec![app_dir.join(ASSETS_DIR), app_dir.join(LEGACY_CLIPBOARD_DIR)];
+
+    let mut chosen = load_allowed_paths(app_dir);
+    let workspace_repo = WorkspaceRepository::new(conn);
+    for workspace in workspace_repo.list().await? {
+        chosen.extend(workspace_repo.list_paths(workspace.id).await?.into_iter().map(|dir| dir.path));
+    }
+    roots.extend(chosen.iter().filter_map(|path| allowable_path(Path::new(path), home_dir).ok()));
+
+    Ok(AssetScope::new(roots))
+}
+
+/// File path of a request URI path (percent-decoded; `/C:/...` -> `C:/...`)
+pub fn request_path(uri_path: &str) -> PathBuf {
+    let path = percent_decode_str(uri_path).decode_utf8_lossy().to_string();
+    let path = if path.starts_with('/') && path.chars().nth(2) == Some(':') {
+        path[1..].to_string()
+    } else {
+        path
+    };
+    PathBuf::from(path)
+}
+
+/// Byte range `start..=end` of a `Range` header for a file of `len` bytes:
+/// `None` if absent or unsupported (multiple ranges), `Some(Err)` if unsatisfiable
+fn parse_range(header: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return Some(Err(()));
+            }
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        (start, "") => (start.parse().ok()?, len.saturating_sub(1)),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len.saturating_sub(1))),
+    };
+    if len == 0 || start > end || start >= len {
+        return Some(Err(()));
+    }
+    Some(Ok((start, end)))
+}
+
+/// `Last-Modified` / `If-Modified-Since` format
+fn http_date(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+fn status_response(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder().status(status).body(Vec::new()).expect("valid response")
+}
+
+/// Answer an asset request for a file inside `scope`
+pub fn respond(request: &Request<Vec<u8>>, scope: &AssetScope) -> Response<Vec<u8>> {
+    let path = match scope.resolve(&request_path(request.uri().path())) {
+        Ok(path) => path,
+        Err(status) => return status_response(status),
+    };
+    let Ok(metadata) = path.metadata() else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let len = metadata.len();
+    let modified = metadata.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs() as i64)
+        .unwrap_or(0);
+    let etag = format!("\"{:x}-{:x}\"", len, modified);
+    let last_modified = http_date(modified);
+    let header_str = |name: header::HeaderName| request.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let mut builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::LAST_MODIFIED, &last_modified)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ACCEPT_RANGES, "bytes");
+    if let Some(origin) = header_str(header::ORIGIN).filter(|origin| APP_ORIGINS.contains(origin)) {
+        builder = builder.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin).header(header::VARY, "Origin");
+    }
+
+    // Conditional requests: If-None-Match takes precedence over If-Modified-Since
+    let not_modified = match header_str(header::IF_NONE_MATCH) {
+        Some(tags) => tags.split(',').any(|tag| {
+            let tag = tag.trim();
+            tag == "*" || tag.trim_start_matches("W/") == etag
+        }),
+        None => header_str(header::IF_MODIFIED_SINCE)
+            .and_then(|since| chrono::DateTime::parse_from_rfc2822(since).ok())
+            .is_some_and(|since| modified <= since.timestamp()),
+    };
+    if not_modified {
+        return builder.status(StatusCode::NOT_MODIFIED).body(Vec::new()).expect("valid response");
+    }
+
+    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+    builder = builder.header(header::CONTENT_TYPE, mime.as_ref());
+
+    // A range that no longer matches the client's copy is ignored (full response)
+    let if_range_ok = header_str(header::IF_RANGE).is_none_or(|tag| tag == etag || tag == last_modified);
+    let range = header_str(header::RANGE).filter(|_| if_range_ok).and_then(|range| parse_range(range, len));
+    let (status, start, end) = match range {
+        Some(Ok((start, end))) => {
+            let end = end.min(start + MAX_RANGE_BYTES - 1);
+            builder = builder.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len));
+            (StatusCode::PARTIAL_CONTENT, start, end)
+        }
+        Some(Err(())) => {
+            return builder.status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .expect("valid response");
+        }
+        None => (StatusCode::OK, 0, len.saturating_sub(1)),
+    };
+    let body_len = if len == 0 { 0 } else { end - start + 1 };
+    builder = builder.status(status).header(header::CONTENT_LENGTH, body_len);
+    if request.method() == tauri::http::Method::HEAD {
+        return builder.body(Vec::new()).expect("valid response");
+    }
+
+    let mut body = Vec::with_capacity(body_len as usize);
+    let read = File::open(&path).and_then(|mut file| {
+        file.seek(SeekFrom::Start(start))?;
+        file.take(body_len).read_to_end(&mut body)
+    });
+    match read {
+        Ok(_) => builder.body(body).expect("valid response"),
+        Err(_) => status_response(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(scope: &AssetScope, path: &str, headers: &[(&str, &str)]) -> Response<Vec<u8>> {
+        let mut request = Request::builder().uri(format!("http://asset.localhost/{}", path.trim_start_matches('/')));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        respond(&request.body(Vec::new()).unwrap(), scope)
+    }
+
+    fn encode(path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    fn setup(name: &str) -> (PathBuf, AssetScope) {
+        let root = std::env::temp_dir().join(format!("tag-all-protocol-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("allowed/sub")).unwrap();
+        std::fs::write(root.join("allowed/sub/video.mp4"), b"0123456789").unwrap();
+        std::fs::write(root.join("secret.txt"), b"secret").unwrap();
+        let scope = AssetScope::new([root.join("allowed"), root.join("missing")]);
+        (root, scope)
+    }
+
+    #[test]
+    fn test_scope_rejects_path_traversal() {
+        let (root, scope) = setup("scope");
+        let allowed = encode(&root.join("allowed"));
+
+        assert_eq!(get(&scope, &format!("{}/sub/video.mp4", allowed), &[]).status(), StatusCode::OK);
+        assert_eq!(get(&scope, &format!("{}/sub/../sub/video.mp4", allowed), &[]).status(), StatusCode::OK);
+        assert_eq!(get(&scope, &format!("{}/../secret.txt", allowed), &[]).status(), StatusCode::FORBIDDEN);
+        assert_eq!(get(&scope, &format!("{}/%2E%2E/secret.txt", allowed), &[]).status(), StatusCode::FORBIDDEN);
+        assert_eq!(get(&scope, &format!("{}/sub/%2e%2e%2f..%2fsecret.txt", allowed), &[]).status(), StatusCode::FORBIDDEN);
+        assert_eq!(get(&scope, &encode(&root.join("secret.txt")), &[]).status(), StatusCode::FORBIDDEN);
+        assert_eq!(get(&scope, &format!("{}/nope.png", allowed), &[]).status(), StatusCode::NOT_FOUND);
+        assert_eq!(scope.resolve(Path::new("allowed/sub/video.mp4")), Err(StatusCode::FORBIDDEN));
+        // A directory is not a file
+        assert_eq!(get(&scope, &format!("{}/sub", allowed), &[]).status(), StatusCode::NOT_FOUND);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret.txt"), root.join("allowed/link.txt")).unwrap();
+            assert_eq!(get(&scope, &format!("{}/link.txt", allowed), &[]).status(), StatusCode::FORBIDDEN);
+        }
+
+        // Only the app's own origin gets CORS access
+        let response = get(&scope, &format!("{}/sub/video.mp4", allowed), &[("Origin", "https://evil.example")]);
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        let response = get(&scope, &format!("{}/sub/video.mp4", allowed), &[("Origin", "tauri://localhost")]);
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "tauri://localhost");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_allowed_paths_are_device_local() {
+        let (root, _) = setup("allowed");
+        assert!(load_allowed_paths(&root).is_empty());
+
+        let allowed = vec![encode(&root.join("allowed"))];
+        save_allowed_paths(&root, &allowed).unwrap();
+        assert_eq!(load_allowed_paths(&root), allowed);
+        assert!(root.join(ALLOWED_PATHS_FILE).is_file());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_allowable_path_rejects_broad_paths() {
+        let (root, _) = setup("allowable");
+        let home = root.join("home");
+        std::fs::create_dir_all(home.join("media")).unwrap();
+
+        let allowed = allowable_path(&home.join("media/../media"), Some(&home)).unwrap();
+        assert_eq!(allowed, home.join("media").canonicalize().unwrap());
+        assert!(allowable_path(&root.join("allowed/sub/video.mp4"), Some(&home)).is_ok());
+
+        assert!(allowable_path(Path::new("media"), Some(&home)).is_err());
+        assert!(allowable_path(&root.join("missing"), Some(&home)).is_err());
+        assert!(allowable_path(&home, Some(&home)).is_err());
+        assert!(allowable_path(&home.join("media/.."), Some(&home)).is_err());
+        assert!(allowable_path(&root, Some(&home)).is_err());
+        let fs_root = root.ancestors().last().unwrap();
+        assert!(allowable_path(fs_root, None).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_scope_skips_broad_workspace_dirs() {
+        let (root, _) = setup("workspace");
+        let home = root.join("home");
+        std::fs::create_dir_all(&home).unwrap();
+        let db = crate::repository::init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let workspaces = WorkspaceRepository::new(db.conn.clone());
+        // Synced from another device: this device's home, the filesystem root
+        for dir in [root.join("allowed"), home.clone(), root.ancestors().last().unwrap().to_path_buf()] {
+            workspaces.add_path(2, &dir.to_string_lossy()).await.unwrap();
+        }
+
+        let scope = load_scope(db.conn.clone(), &root, Some(&home)).await.unwrap();
+        assert!(scope.resolve(&root.join("allowed/sub/video.mp4")).is_ok());
+        assert_eq!(scope.resolve(&root.join("secret.txt")), Err(StatusCode::FORBIDDEN));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_range_requests_and_caching() {
+        let (root, scope) = setup("range");
+        let video = encode(&root.join("allowed/sub/video.mp4"));
+
+        let response = get(&scope, &video, &[("Range", "bytes=2-5")]);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body(), b"2345");
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 2-5/10");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "video/mp4");
+        assert_eq!(get(&scope, &video, &[("Range", "bytes=7-")]).body(), b"789");
+        assert_eq!(get(&scope, &video, &[("Range", "bytes=-3")]).body(), b"789");
+        assert_eq!(get(&scope, &video, &[("Range", "bytes=8-100")]).body(), b"89");
+        let unsatisfiable = get(&scope, &video, &[("Range", "bytes=10-")]);
+        assert_eq!(unsatisfiable.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(unsatisfiable.headers()[header::CONTENT_RANGE], "bytes */10");
+        // Multiple ranges are answered with the whole file
+        assert_eq!(get(&scope, &video, &[("Range", "bytes=0-1,4-5")]).status(), StatusCode::OK);
+
+        let full = get(&scope, &video, &[]);
+        assert_eq!(full.body(), b"0123456789");
+        let etag = full.headers()[header::ETAG].to_str().unwrap().to_string();
+        let last_modified = full.headers()[header::LAST_MODIFIED].to_str().unwrap().to_string();
+        assert_eq!(get(&scope, &video, &[("If-None-Match", etag.as_str())]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&scope, &video, &[("If-None-Match", "\"other\"")]).status(), StatusCode::OK);
+        assert_eq!(get(&scope, &video, &[("If-Modified-Since", last_modified.as_str())]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&scope, &video, &[("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT")]).status(), StatusCode::OK);
+        // A stale If-Range turns the range request into a full one
+        let stale = get(&scope, &video, &[("Range", "bytes=0-1"), ("If-Range", "\"stale\"")]);
+        assert_eq!((stale.status(), stale.body().len()), (StatusCode::OK, 10));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_large_file_is_ranged_only_on_request() {
+        let (root, scope) = setup("large");
+        let path = root.join("allowed/large.bin");
+        let len = MAX_RANGE_BYTES * 2;
+        File::create(&path).unwrap().set_len(len).unwrap();
+        let large = encode(&path);
+
+        // No Range header: the whole file, never a 206
+        let response = get(&scope, &large, &[]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().len() as u64, len);
+        assert!(response.headers().get(header::CONTENT_RANGE).is_none());
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+
+        let first = get(&scope, &large, &[("Range", "bytes=0-")]);
+        assert_eq!((first.status(), first.body().len() as u64), (StatusCode::PARTIAL_CONTENT, MAX_RANGE_BYTES));
+        let last = get(&scope, &large, &[("Range", "bytes=-2")]);
+        assert_eq!((last.status(), last.body().len()), (StatusCode::PARTIAL_CONTENT, 2));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
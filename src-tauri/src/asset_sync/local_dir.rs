@@ -0,0 +1,54 @@
+//! Local Directory Remote
+//!
+//! Keeps synced assets in a plain directory, e.g. a mounted network share or
+//! a folder synced by another tool. Also used by the tests.
+
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::RemoteAssetStore;
+use crate::domain::{DomainError, DomainResult};
+
+pub struct LocalDirRemote {
+    dir: PathBuf,
+}
+
+impl LocalDirRemote {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+fn io_err(e: std::io::Error) -> DomainError {
+    DomainError::Internal(format!("Remote directory error: {}", e))
+}
+
+#[async_trait]
+impl RemoteAssetStore for LocalDirRemote {
+    async fn list(&self) -> DomainResult<HashSet<String>> {
+        let mut names = HashSet::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(names),
+            Err(e) => return Err(io_err(e)),
+        };
+        while let Some(entry) = entries.next_entry().await.map_err(io_err)? {
+            if let Some(name) = entry.file_name().to_str().filter(|name| !name.ends_with(".tmp")) {
+                names.insert(name.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> DomainResult<()> {
+        tokio::fs::create_dir_all(&self.dir).await.map_err(io_err)?;
+        let tmp = self.dir.join(format!("{}.tmp", name));
+        tokio::fs::write(&tmp, data).await.map_err(io_err)?;
+        tokio::fs::rename(&tmp, self.dir.join(name)).await.map_err(io_err)
+    }
+
+    async fn get(&self, name: &str) -> DomainResult<Vec<u8>> {
+        tokio::fs::read(self.dir.join(name)).await.map_err(io_err)
+    }
+}
@@ -0,0 +1,218 @@
+//! Asset Sync Layer
+//!
+//! Database sync only carries asset records (`assets` table); the files are
+//! copied to and from a remote store here. Assets are content-addressed, so a
+//! file is never modified: each side only uploads what the other lacks, and
+//! downloads are verified against their hash.
+//!
+//! Remotes:
+//! - local_dir: A plain directory (tests, network shares)
+//! - webdav: WebDAV server (Nextcloud, Synology, ...)
+//! - s3: S3-compatible object storage (AWS, R2, MinIO, ...)
+
+mod local_dir;
+mod s3;
+mod webdav;
+
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::domain::{DomainError, DomainResult, FileIdentifier};
+use crate::repository::AssetRepository;
+
+pub use local_dir::LocalDirRemote;
+pub use s3::S3Remote;
+pub use webdav::WebDavRemote;
+
+/// Storage for asset files, keyed by file name (`{hash}.{ext}`)
+#[async_trait]
+pub trait RemoteAssetStore: Send + Sync {
+    /// File names of all stored assets
+    async fn list(&self) -> DomainResult<HashSet<String>>;
+
+    /// Store a file (overwriting is harmless: same name, same content)
+    async fn put(&self, name: &str, data: Vec<u8>) -> DomainResult<()>;
+
+    /// Fetch a file
+    async fn get(&self, name: &str) -> DomainResult<Vec<u8>>;
+}
+
+/// Where assets are synced to; stored per device next to the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetSyncConfig {
+    Local {
+        dir: String,
+    },
+    WebDav {
+        /// Base URL; assets go into `{url}/assets/`
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    S3 {
+        /// e.g. `https://s3.eu-central-1.amazonaws.com` (path-style addressing)
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl AssetSyncConfig {
+    /// Open the configured remote
+    pub fn remote(&self) -> DomainResult<Box<dyn RemoteAssetStore>> {
+        Ok(match self {
+            AssetSyncConfig::Local { dir } => Box::new(LocalDirRemote::new(PathBuf::from(dir))),
+            AssetSyncConfig::WebDav { url, username, password } => {
+                Box::new(WebDavRemote::new(url, username.clone(), password.clone())?)
+            }
+            AssetSyncConfig::S3 { endpoint, bucket, region, access_key, secret_key } => Box::new(S3Remote::new(
+                endpoint,
+                bucket.clone(),
+                region.clone(),
+                access_key.clone(),
+                secret_key.clone(),
+            )?),
+        })
+    }
+}
+
+/// Asset sync configuration file (next to the database)
+fn config_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("asset_sync.json")
+}
+
+/// Load the asset sync configuration, if any
+pub fn load_config(db_path: &Path) -> Option<AssetSyncConfig> {
+    let json = std::fs::read_to_string(config_path(db_path)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Save (or with `None`, remove) the asset sync configuration
+pub fn save_config(db_path: &Path, config: Option<&AssetSyncConfig>) -> DomainResult<()> {
+    let path = config_path(db_path);
+    match config {
+        Some(config) => {
+            let json = serde_json::to_string_pretty(config).map_err(|e| DomainError::Internal(e.to_string()))?;
+            std::fs::write(&path, json)
+        }
+        None => match std::fs::remove_file(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    }
+    .map_err(|e| DomainError::Internal(format!("Failed to save asset sync config: {}", e)))
+}
+
+/// Outcome of one asset sync
+#[derive(Debug, Default, Serialize)]
+pub struct AssetSyncReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    /// Hashes of assets neither this device nor the remote has (yet)
+    pub missing: Vec<String>,
+}
+
+/// Upload the local asset files the remote lacks and download the files of
+/// known assets (e.g. synced from another device) missing locally
+pub async fn sync_assets(
+    conn: Arc<Mutex<Option<Connection>>>,
+    assets_dir: &Path,
+    remote: &dyn RemoteAssetStore,
+) -> DomainResult<AssetSyncReport> {
+    let io_err = |e: std::io::Error| DomainError::Internal(format!("Asset file error: {}", e));
+    std::fs::create_dir_all(assets_dir).map_err(io_err)?;
+
+    let assets = AssetRepository::new(conn).list().await?;
+    let remote_names = remote.list().await?;
+    let mut report = AssetSyncReport::default();
+
+    for asset in assets {
+        let name = asset.file_name();
+        let path = assets_dir.join(&name);
+        let on_remote = remote_names.contains(&name);
+
+        if path.exists() {
+            if !on_remote {
+                let data = tokio::fs::read(&path).await.map_err(io_err)?;
+                remote.put(&name, data).await?;
+                report.uploaded += 1;
+            }
+        } else if on_remote {
+            let data = remote.get(&name).await?;
+            if FileIdentifier::compute_data_hash(&data) != asset.hash {
+                return Err(DomainError::Internal(format!("Downloaded asset {} does not match its hash", name)));
+            }
+            // Write under a temporary name so a partial file never carries the content hash
+            let tmp = assets_dir.join(format!("{}.tmp", asset.hash));
+            tokio::fs::write(&tmp, &data).await.map_err(io_err)?;
+            tokio::fs::rename(&tmp, &path).await.map_err(io_err)?;
+            report.downloaded += 1;
+        } else {
+            report.missing.push(asset.hash);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Asset, Timestamp};
+    use crate::repository::init_db;
+
+    #[tokio::test]
+    async fn test_sync_assets_between_devices() {
+        let root = std::env::temp_dir().join(format!("tag-all-asset-sync-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let remote = LocalDirRemote::new(root.join("remote"));
+
+        // Device A stores an asset and syncs
+        let a = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let data = b"\x89PNG\r\n\x1a\nimage".to_vec();
+        let asset = Asset {
+            hash: FileIdentifier::compute_data_hash(&data),
+            ext: "png".to_string(),
+            mime: "image/png".to_string(),
+            size: data.len() as u64,
+            original_name: None,
+            created_at: Timestamp::from_millis(1),
+        };
+        AssetRepository::new(a.conn.clone()).upsert(&asset).await.unwrap();
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        std::fs::write(root.join("a").join(asset.file_name()), &data).unwrap();
+
+        let report = sync_assets(a.conn.clone(), &root.join("a"), &remote).await.unwrap();
+        assert_eq!((report.uploaded, report.downloaded), (1, 0));
+        let report = sync_assets(a.conn.clone(), &root.join("a"), &remote).await.unwrap();
+        assert_eq!(report.uploaded, 0, "already on the remote");
+
+        // Device B got the record through database sync, plus one the remote lacks
+        let b = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let repo = AssetRepository::new(b.conn.clone());
+        repo.upsert(&asset).await.unwrap();
+        let absent = Asset { hash: "0f".repeat(32), ..asset.clone() };
+        repo.upsert(&absent).await.unwrap();
+
+        let report = sync_assets(b.conn.clone(), &root.join("b"), &remote).await.unwrap();
+        assert_eq!((report.uploaded, report.downloaded), (0, 1));
+        assert_eq!(report.missing, vec![absent.hash.clone()]);
+        assert_eq!(std::fs::read(root.join("b").join(asset.file_name())).unwrap(), data);
+
+        // A corrupted remote file is rejected
+        std::fs::remove_file(root.join("b").join(asset.file_name())).unwrap();
+        remote.put(&asset.file_name(), b"tampered".to_vec()).await.unwrap();
+        assert!(sync_assets(b.conn.clone(), &root.join("b"), &remote).await.is_err());
+        assert!(!root.join("b").join(asset.file_name()).exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,195 @@
+//! S3 Remote
+//!
+//! Stores assets as objects `assets/{hash}.{ext}` in a bucket of any
+//! S3-compatible service. Requests use path-style addressing
+//! (`{endpoint}/{bucket}/{key}`) and are signed with AWS Signature V4.
+
+use async_trait::async_trait;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use tauri_plugin_http::reqwest::{self, Method, RequestBuilder, StatusCode, Url};
+
+use super::RemoteAssetStore;
+use crate::domain::{DomainError, DomainResult, ASSETS_DIR};
+
+/// SigV4 URI encoding: everything but unreserved characters
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+pub struct S3Remote {
+    client: reqwest::Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Remote {
+    pub fn new(endpoint: &str, bucket: String, region: String, access_key: String, secret_key: String) -> DomainResult<Self> {
+        let endpoint = Url::parse(endpoint)
+            .map_err(|e| DomainError::InvalidInput(format!("Invalid S3 endpoint: {}", e)))?;
+        let client = reqwest::Client::builder().build().map_err(http_err)?;
+        Ok(Self { client, endpoint, bucket, region, access_key, secret_key })
+    }
+
+    /// A request for `key` (or the bucket itself) signed for the current time
+    fn signed(&self, method: Method, key: Option<&str>, query: &[(&str, &str)], body: Vec<u8>) -> RequestBuilder {
+        let mut segments: Vec<&str> = self.endpoint.path().split('/').filter(|s| !s.is_empty()).collect();
+        segments.push(&self.bucket);
+        segments.extend(key.into_iter().flat_map(|key| key.split('/')));
+        let uri: String = segments.iter().map(|s| format!("/{}", utf8_percent_encode(s, URI_ENCODE_SET))).collect();
+
+        let mut query: Vec<(String, String)> = query.iter()
+            .map(|(k, v)| (utf8_percent_encode(k, URI_ENCODE_SET).to_string(), utf8_percent_encode(v, URI_ENCODE_SET).to_string()))
+            .collect();
+        query.sort();
+        let query = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, uri, query, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        let mut url = format!("{}://{}{}", self.endpoint.scheme(), host, uri);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        self.client.request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, SIGNED_HEADERS, signature
+            ))
+            .body(body)
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+fn http_err(e: reqwest::Error) -> DomainError {
+    DomainError::Internal(format!("S3 request failed: {}", e))
+}
+
+fn status_err(action: &str, status: StatusCode, body: &str) -> DomainError {
+    DomainError::Internal(format!("S3 {} failed: {} {}", action, status, body))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HMAC-SHA256 (RFC 2104)
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(data);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// SigV4 signing key for a day, region and service
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// Text of the first `<tag>` element in an XML response
+fn xml_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let len = xml[start..].find("</")?;
+    Some(&xml[start..start + len])
+}
+
+#[async_trait]
+impl RemoteAssetStore for S3Remote {
+    async fn list(&self) -> DomainResult<HashSet<String>> {
+        let prefix = format!("{}/", ASSETS_DIR);
+        let key_pattern = regex::Regex::new(r"<Key>([^<]*)</Key>").expect("valid regex");
+        let mut names = HashSet::new();
+        let mut token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix.as_str())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = self.signed(Method::GET, None, &query, Vec::new()).send().await.map_err(http_err)?;
+            let status = response.status();
+            let xml = response.text().await.map_err(http_err)?;
+            if !status.is_success() {
+                return Err(status_err("list", status, &xml));
+            }
+
+            names.extend(key_pattern.captures_iter(&xml)
+                .filter_map(|caps| caps[1].strip_prefix(&prefix).map(|name| name.replace("&amp;", "&")))
+                .filter(|name| !name.is_empty() && !name.contains('/')));
+
+            token = match (xml_value(&xml, "IsTruncated"), xml_value(&xml, "NextContinuationToken")) {
+                (Some("true"), Some(next)) => Some(next.replace("&amp;", "&")),
+                _ => break,
+            };
+        }
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> DomainResult<()> {
+        let key = format!("{}/{}", ASSETS_DIR, name);
+        let response = self.signed(Method::PUT, Some(&key), &[], data).send().await.map_err(http_err)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(status_err("upload", status, &response.text().await.unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> DomainResult<Vec<u8>> {
+        let key = format!("{}/{}", ASSETS_DIR, name);
+        let response = self.signed(Method::GET, Some(&key), &[], Vec::new()).send().await.map_err(http_err)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(status_err("download", status, &response.text().await.unwrap_or_default()));
+        }
+        Ok(response.bytes().await.map_err(http_err)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+}
@@ -0,0 +1,133 @@
+//! WebDAV Remote
+//!
+//! Stores assets as `{url}/assets/{hash}.{ext}`. The collection is listed
+//! with a depth-1 PROPFIND and created (MKCOL) on first use.
+
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use std::collections::HashSet;
+use tauri_plugin_http::reqwest::{self, Method, RequestBuilder, StatusCode};
+
+use super::RemoteAssetStore;
+use crate::domain::{DomainError, DomainResult, ASSETS_DIR};
+
+pub struct WebDavRemote {
+    client: reqwest::Client,
+    /// Collection URL, ending in `/`
+    base: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavRemote {
+    pub fn new(url: &str, username: Option<String>, password: Option<String>) -> DomainResult<Self> {
+        let client = reqwest::Client::builder().build().map_err(http_err)?;
+        Ok(Self {
+            client,
+            base: format!("{}/{}/", url.trim_end_matches('/'), ASSETS_DIR),
+            username,
+            password,
+        })
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+}
+
+fn http_err(e: reqwest::Error) -> DomainError {
+    DomainError::Internal(format!("WebDAV request failed: {}", e))
+}
+
+fn status_err(action: &str, status: StatusCode) -> DomainError {
+    DomainError::Internal(format!("WebDAV {} failed: {}", action, status))
+}
+
+/// File names in a PROPFIND multistatus response (the collection itself excluded)
+fn parse_propfind_names(xml: &str) -> HashSet<String> {
+    let href = regex::Regex::new(r"(?i)<(?:[a-z0-9_]+:)?href>([^<]*)</").expect("valid regex");
+    href.captures_iter(xml)
+        .filter_map(|caps| {
+            let href = caps.get(1)?.as_str().trim();
+            // Collections (the listed one included) end in a slash
+            if href.ends_with('/') {
+                return None;
+            }
+            let name = percent_decode_str(href.rsplit('/').next()?).decode_utf8_lossy().to_string();
+            (!name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+#[async_trait]
+impl RemoteAssetStore for WebDavRemote {
+    async fn list(&self) -> DomainResult<HashSet<String>> {
+        let propfind = Method::from_bytes(b"PROPFIND").expect("valid method");
+        let response = self.request(propfind, &self.base)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#)
+            .send()
+            .await
+            .map_err(http_err)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            // First sync: create the collection
+            let mkcol = Method::from_bytes(b"MKCOL").expect("valid method");
+            let created = self.request(mkcol, &self.base).send().await.map_err(http_err)?;
+            if !created.status().is_success() {
+                return Err(status_err("MKCOL", created.status()));
+            }
+            return Ok(HashSet::new());
+        }
+        if !response.status().is_success() {
+            return Err(status_err("PROPFIND", response.status()));
+        }
+        let xml = response.text().await.map_err(http_err)?;
+        Ok(parse_propfind_names(&xml))
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> DomainResult<()> {
+        let response = self.request(Method::PUT, &format!("{}{}", self.base, name))
+            .body(data)
+            .send()
+            .await
+            .map_err(http_err)?;
+        if !response.status().is_success() {
+            return Err(status_err("PUT", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> DomainResult<Vec<u8>> {
+        let response = self.request(Method::GET, &format!("{}{}", self.base, name))
+            .send()
+            .await
+            .map_err(http_err)?;
+        if !response.status().is_success() {
+            return Err(status_err("GET", response.status()));
+        }
+        Ok(response.bytes().await.map_err(http_err)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_propfind_names() {
+        let xml = r#"<?xml version="1.0"?>
+            <d:multistatus xmlns:d="DAV:">
+              <d:response><d:href>/remote.php/dav/files/me/tag-all/assets/</d:href></d:response>
+              <d:response><d:href>/remote.php/dav/files/me/tag-all/assets/abc.png</d:href></d:response>
+              <D:response><D:href>http://nas/tag-all/assets/with%20space.pdf</D:href></D:response>
+            </d:multistatus>"#;
+        let names = parse_propfind_names(xml);
+        assert_eq!(names, HashSet::from(["abc.png".to_string(), "with space.pdf".to_string()]));
+    }
+}
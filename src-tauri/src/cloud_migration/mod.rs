@@ -0,0 +1,507 @@
+//! Cloud Migration
+//!
+//! Moving the local database onto cloud sync, in stages that never touch
+//! the live file until the result is known to be complete:
+//!
+//! 1. `write_snapshot` copies the live database (a consistent copy, WAL
+//!    included) next to it. The snapshot stays until the user confirms the
+//!    migration (`discard_snapshot`) or goes back to it (`restore_snapshot`).
+//! 2. The cloud database is created at `staging_path`, and `copy_tables`
+//!    fills it from the snapshot.
+//! 3. `verify` compares row counts and content hashes per synced table.
+//! 4. Only if every row arrived, `swap_in` renames the staged file over the
+//!    live one.
+//!
+//! `swap_in_and_open` and `restore_and_open` replace the live file and open
+//! the result; whatever fails, a database is open afterwards (the one asked
+//! for, or the one it was to replace).
+
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::domain::{DomainError, DomainResult};
+use crate::repository::SYNCED_TABLES;
+
+/// Schema name the source database is attached under
+const SOURCE: &str = "source";
+
+fn internal(e: impl ToString) -> DomainError {
+    DomainError::Internal(e.to_string())
+}
+
+/// One synced table, before and after the copy
+#[derive(Debug, Clone, Serialize)]
+pub struct TableCheck {
+    pub table: String,
+    pub source_rows: u64,
+    pub staged_rows: u64,
+    /// Order-independent hash of the rows (blake3, hex)
+    pub source_hash: String,
+    pub staged_hash: String,
+    /// Source rows with no identical row in the staged database
+    pub missing: u64,
+}
+
+/// Outcome of moving the local database onto cloud sync
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    /// Pre-migration database, kept until confirmed
+    pub snapshot: PathBuf,
+    pub tables: Vec<TableCheck>,
+    /// Whether the first sync after the swap succeeded (retried in the
+    /// background if not)
+    pub synced: bool,
+    pub sync_error: Option<String>,
+}
+
+/// Where the pre-migration snapshot is kept
+pub fn snapshot_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.pre_cloud_sync")
+}
+
+/// Where the cloud database is built before the swap
+pub fn staging_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.staging")
+}
+
+/// Where the live database waits while the snapshot is put back
+fn replaced_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.replaced")
+}
+
+/// Files SQLite and the sync backend keep beside a database file
+fn sidecars(db_path: &Path) -> [PathBuf; 3] {
+    let name = db_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    [
+        db_path.with_file_name(format!("{}-wal", name)),
+        db_path.with_file_name(format!("{}-shm", name)),
+        db_path.with_file_name(format!("{}-sync", name)),
+    ]
+}
+
+fn remove_sidecars(db_path: &Path) {
+    for path in sidecars(db_path) {
+        if path.is_dir() {
+            let _ = std::fs::remove_dir_all(&path);
+        } else {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+/// Rename a (closed) database file and everything beside it
+fn move_db_files(from: &Path, to: &Path) -> DomainResult<()> {
+    std::fs::rename(from, to).map_err(internal)?;
+    for (from, to) in sidecars(from).into_iter().zip(sidecars(to)) {
+        if from.exists() {
+            std::fs::rename(from, to).map_err(internal)?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete a (closed) database file and everything beside it
+pub fn remove_db_files(db_path: &Path) {
+    let _ = std::fs::remove_file(db_path);
+    remove_sidecars(db_path);
+}
+
+/// Copy the open database to `path`
+pub fn write_snapshot(conn: &Connection, path: &Path) -> DomainResult<()> {
+    if path.exists() {
+        return Err(DomainError::Conflict(format!(
+            "An earlier migration snapshot is still kept at {}; confirm or restore it first",
+            path.display()
+        )));
+    }
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()]).map_err(internal)?;
+    Ok(())
+}
+
+/// Run `f` with the database at `source` attached as `SOURCE`
+fn with_source<T>(conn: &Connection, source: &Path, f: impl FnOnce(&Connection) -> DomainResult<T>) -> DomainResult<T> {
+    conn.execute(&format!("ATTACH DATABASE ?1 AS {}", SOURCE), [source.to_string_lossy()]).map_err(internal)?;
+    let result = f(conn);
+    conn.execute(&format!("DETACH DATABASE {}", SOURCE), []).map_err(internal)?;
+    result
+}
+
+fn columns(conn: &Connection, schema: &str, table: &str) -> DomainResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table)).map_err(internal)?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1)).map_err(internal)?;
+    names.collect::<Result<_, _>>().map_err(internal)
+}
+
+/// Columns of `table` present in both databases (empty if either lacks it)
+fn shared_columns(conn: &Connection, table: &str) -> DomainResult<Vec<String>> {
+    let staged = columns(conn, "main", table)?;
+    Ok(columns(conn, SOURCE, table)?.into_iter().filter(|c| staged.contains(c)).collect())
+}
+
+/// Copy the synced tables from the database at `source` into `staged`.
+/// Source rows replace staged rows with the same key.
+pub fn copy_tables(staged: &Connection, source: &Path) -> DomainResult<()> {
+    // Rows arrive table by table, so references may point ahead for a while
+    staged.execute_batch("PRAGMA foreign_keys = OFF").map_err(internal)?;
+    let copied = with_source(staged, source, |conn| {
+        let tx = conn.unchecked_transaction().map_err(internal)?;
+        for table in SYNCED_TABLES {
+            let columns = shared_columns(&tx, table)?;
+            if columns.is_empty() {
+                continue;
+            }
+            let list = columns.join(", ");
+            tx.execute(&format!("INSERT OR REPLACE INTO main.{table} ({list}) SELECT {list} FROM {SOURCE}.{table}"), [])
+                .map_err(internal)?;
+        }
+        tx.commit().map_err(internal)
+    });
+    staged.execute_batch("PRAGMA foreign_keys = ON").map_err(internal)?;
+    copied
+}
+
+/// Hash of every row of `schema.table` (over `columns`), sorted
+fn row_hashes(conn: &Connection, schema: &str, table: &str, columns: &[String]) -> DomainResult<Vec<[u8; 32]>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM {}.{}", columns.join(", "), schema, table)).map_err(internal)?;
+    let mut rows = stmt.query([]).map_err(internal)?;
+    let mut hashes = Vec::new();
+    while let Some(row) = rows.next().map_err(internal)? {
+        let mut hasher = blake3::Hasher::new();
+        for i in 0..columns.len() {
+            // Type tag and length keep distinct rows from hashing alike
+            match row.get_ref(i).map_err(internal)? {
+                ValueRef::Null => hasher.update(&[0]),
+                ValueRef::Integer(v) => hasher.update(&[1]).update(&v.to_le_bytes()),
+                ValueRef::Real(v) => hasher.update(&[2]).update(&v.to_bits().to_le_bytes()),
+                ValueRef::Text(v) => hasher.update(&[3]).update(&(v.len() as u64).to_le_bytes()).update(v),
+                ValueRef::Blob(v) => hasher.update(&[4]).update(&(v.len() as u64).to_le_bytes()).update(v),
+            };
+        }
+        hashes.push(*hasher.finalize().as_bytes());
+    }
+    hashes.sort_unstable();
+    Ok(hashes)
+}
+
+fn digest(hashes: &[[u8; 32]]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for hash in hashes {
+        hasher.update(hash);
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Entries of sorted `source` not matched in sorted `staged`
+fn count_missing(source: &[[u8; 32]], staged: &[[u8; 32]]) -> u64 {
+    let mut missing = 0;
+    let mut j = 0;
+    for hash in source {
+        while j < staged.len() && staged[j] < *hash {
+            j += 1;
+        }
+        if j < staged.len() && staged[j] == *hash {
+            j += 1;
+        } else {
+            missing += 1;
+        }
+    }
+    missing
+}
+
+/// Compare the synced tables of `staged` with the database at `source`.
+/// The staged database may hold more rows (already in the cloud), but no
+/// source row may be missing or different.
+pub fn verify(staged: &Connection, source: &Path) -> DomainResult<Vec<TableCheck>> {
+    with_source(staged, source, |conn| {
+        let mut checks = Vec::new();
+        for table in SYNCED_TABLES {
+            let columns = shared_columns(conn, table)?;
+            if columns.is_empty() {
+                continue;
+            }
+            let source_rows = row_hashes(conn, SOURCE, table, &columns)?;
+            let staged_rows = row_hashes(conn, "main", table, &columns)?;
+            checks.push(TableCheck {
+                table: table.to_string(),
+                source_rows: source_rows.len() as u64,
+                staged_rows: staged_rows.len() as u64,
+                source_hash: digest(&source_rows),
+                staged_hash: digest(&staged_rows),
+                missing: count_missing(&source_rows, &staged_rows),
+            });
+        }
+        Ok(checks)
+    })
+}
+
+/// Replace the (closed) live database with the (closed) staged one
+pub fn swap_in(staging: &Path, db_path: &Path) -> DomainResult<()> {
+    let [_, _, live_sync] = sidecars(db_path);
+    let [_, _, staged_sync] = sidecars(staging);
+    // A leftover WAL would be replayed into the new file
+    remove_sidecars(db_path);
+    std::fs::rename(staging, db_path).map_err(internal)?;
+    if staged_sync.exists() {
+        std::fs::rename(staged_sync, live_sync).map_err(internal)?;
+    }
+    Ok(())
+}
+
+/// Put the pre-migration snapshot back in place of the (closed) live database
+pub fn restore_snapshot(db_path: &Path) -> DomainResult<()> {
+    let snapshot = snapshot_path(db_path);
+    if !snapshot.exists() {
+        return Err(DomainError::NotFound("No migration snapshot to restore".to_string()));
+    }
+    remove_sidecars(db_path);
+    std::fs::rename(snapshot, db_path).map_err(internal)
+}
+
+/// Which database a reopen is for: the one migrated to cloud sync, or the
+/// one from before (the snapshot, or the live file it was taken from)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveDb {
+    Migrated,
+    PreMigration,
+}
+
+/// The live database after a swap or restore
+pub struct Reopened {
+    /// `None` only if no database could be opened at all
+    pub conn: Option<Connection>,
+    /// Why the live database is not the one asked for
+    pub error: Option<DomainError>,
+    /// Which database is live
+    pub live: LiveDb,
+}
+
+/// Swap the staged database in and open it with `open`. If the swap fails or
+/// the staged database doesn't open, the snapshot is put back and opened.
+pub async fn swap_in_and_open<F, Fut>(staging: &Path, db_path: &Path, open: F) -> Reopened
+where
+    F: Fn(PathBuf, LiveDb) -> Fut,
+    Fut: Future<Output = DomainResult<Connection>>,
+{
+    let error = match swap_in(staging, db_path) {
+        Ok(()) => match open(db_path.to_path_buf(), LiveDb::Migrated).await {
+            Ok(conn) => return Reopened { conn: Some(conn), error: None, live: LiveDb::Migrated },
+            Err(e) => e,
+        },
+        Err(e) => e,
+    };
+    // A failed swap may have left either file in place; the snapshot is the
+    // live database as it was before
+    let _ = restore_snapshot(db_path);
+    Reopened {
+        conn: open(db_path.to_path_buf(), LiveDb::PreMigration).await.ok(),
+        error: Some(error),
+        live: LiveDb::PreMigration,
+    }
+}
+
+/// Put the snapshot back in place of the (closed) live database and open it
+/// with `open`. If that fails, the live database is put back and opened, and
+/// the snapshot is kept.
+pub async fn restore_and_open<F, Fut>(db_path: &Path, open: F) -> Reopened
+where
+    F: Fn(PathBuf, LiveDb) -> Fut,
+    Fut: Future<Output = DomainResult<Connection>>,
+{
+    let snapshot = snapshot_path(db_path);
+    let aside = replaced_path(db_path);
+    remove_db_files(&aside);
+    let error = match move_db_files(db_path, &aside).and_then(|()| restore_snapshot(db_path)) {
+        Ok(()) => match open(db_path.to_path_buf(), LiveDb::PreMigration).await {
+            Ok(conn) => {
+                remove_db_files(&aside);
+                return Reopened { conn: Some(conn), error: None, live: LiveDb::PreMigration };
+            }
+            Err(e) => {
+                remove_sidecars(db_path);
+                let _ = std::fs::rename(db_path, &snapshot);
+                e
+            }
+        },
+        Err(e) => e,
+    };
+    if aside.exists() {
+        let _ = move_db_files(&aside, db_path);
+    }
+    Reopened {
+        conn: open(db_path.to_path_buf(), LiveDb::Migrated).await.ok(),
+        error: Some(error),
+        live: LiveDb::Migrated,
+    }
+}
+
+/// Drop the snapshot once the migration is confirmed
+pub fn discard_snapshot(db_path: &Path) -> DomainResult<()> {
+    match std::fs::remove_file(snapshot_path(db_path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(internal(e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Item, ItemType, Tag};
+    use crate::repository::{init_db, ItemRepository, Repository, TagRepository};
+
+    async fn seeded() -> crate::repository::DbState {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let tags = TagRepository::new(db.conn.clone());
+        for text in ["One", "Two", "Three"] {
+            let mut item = Item::new(0, text.to_string(), ItemType::Document);
+            item.memo = Some(format!("{} memo", text));
+            items.create(&item).await.unwrap();
+        }
+        tags.create(&Tag::new(0, "rust".to_string())).await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_staged_copy_is_verified_and_swapped_in() {
+        let dir = std::env::temp_dir().join(format!("tag-all-cloud-migration-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("tag_all.db");
+        let live = seeded().await;
+
+        let snapshot = snapshot_path(&db_path);
+        write_snapshot(live.conn.lock().await.as_ref().unwrap(), &snapshot).unwrap();
+        assert!(matches!(write_snapshot(live.conn.lock().await.as_ref().unwrap(), &snapshot), Err(DomainError::Conflict(_))));
+
+        let staged = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let guard = staged.conn.lock().await;
+        let conn = guard.as_ref().unwrap();
+        copy_tables(conn, &snapshot).unwrap();
+        let checks = verify(conn, &snapshot).unwrap();
+        let items = checks.iter().find(|c| c.table == "items").unwrap();
+        assert_eq!((items.source_rows, items.staged_rows, items.missing), (3, 3, 0));
+        assert_eq!(items.source_hash, items.staged_hash);
+        assert!(checks.iter().all(|c| c.missing == 0));
+
+        // A row lost or changed on the way is caught
+        conn.execute("UPDATE items SET memo = 'changed' WHERE text = 'Two'", []).unwrap();
+        conn.execute("DELETE FROM tags", []).unwrap();
+        let checks = verify(conn, &snapshot).unwrap();
+        let items = checks.iter().find(|c| c.table == "items").unwrap();
+        assert_eq!((items.staged_rows, items.missing), (3, 1));
+        assert_ne!(items.source_hash, items.staged_hash);
+        assert_eq!(checks.iter().find(|c| c.table == "tags").unwrap().missing, 1);
+        drop(guard);
+
+        // Swap a closed staged file in, then go back to the snapshot
+        let staging = staging_path(&db_path);
+        std::fs::write(&db_path, b"live").unwrap();
+        std::fs::write(dir.join("tag_all.db-wal"), b"stale").unwrap();
+        std::fs::write(&staging, b"staged").unwrap();
+        std::fs::create_dir_all(dir.join("tag_all.db.staging-sync")).unwrap();
+        swap_in(&staging, &db_path).unwrap();
+        assert_eq!(std::fs::read(&db_path).unwrap(), b"staged");
+        assert!(!dir.join("tag_all.db-wal").exists() && !staging.exists());
+        assert!(dir.join("tag_all.db-sync").is_dir());
+
+        restore_snapshot(&db_path).unwrap();
+        assert!(!snapshot.exists() && !dir.join("tag_all.db-sync").exists());
+        let restored = Connection::open(&db_path).unwrap();
+        let count: i64 = restored.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 3);
+        assert!(matches!(restore_snapshot(&db_path), Err(DomainError::NotFound(_))));
+        discard_snapshot(&db_path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// A closed database file holding `marker`
+    fn marked_db(path: &Path, marker: &str) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch("CREATE TABLE marker (name TEXT)").unwrap();
+        conn.execute("INSERT INTO marker VALUES (?1)", [marker]).unwrap();
+    }
+
+    fn marker(reopened: &Reopened) -> String {
+        let conn = reopened.conn.as_ref().expect("a database is open");
+        conn.query_row("SELECT name FROM marker", [], |row| row.get(0)).unwrap()
+    }
+
+    /// Opens like the app, except for databases listed in `failing`
+    fn opener(failing: &'static [LiveDb]) -> impl Fn(PathBuf, LiveDb) -> std::future::Ready<DomainResult<Connection>> {
+        move |path, live| {
+            std::future::ready(if failing.contains(&live) {
+                Err(DomainError::Internal(format!("cannot open {:?}", live)))
+            } else {
+                Connection::open(path).map_err(internal)
+            })
+        }
+    }
+
+    fn migration_dir(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tag-all-cloud-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("tag_all.db");
+        marked_db(&db_path, "live");
+        marked_db(&snapshot_path(&db_path), "snapshot");
+        (dir, db_path)
+    }
+
+    #[tokio::test]
+    async fn test_failed_swap_reopens_the_pre_migration_database() {
+        let (dir, db_path) = migration_dir("swap");
+
+        // Nothing staged: the swap fails before the live file is touched
+        let reopened = swap_in_and_open(&staging_path(&db_path), &db_path, opener(&[])).await;
+        assert!(reopened.error.is_some());
+        assert_eq!((reopened.live, marker(&reopened).as_str()), (LiveDb::PreMigration, "snapshot"));
+        assert!(!snapshot_path(&db_path).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_reopen_falls_back_to_the_other_database() {
+        let (dir, db_path) = migration_dir("reopen");
+        let staging = staging_path(&db_path);
+        marked_db(&staging, "cloud");
+
+        // The swapped-in database doesn't open: back to the snapshot
+        let reopened = swap_in_and_open(&staging, &db_path, opener(&[LiveDb::Migrated])).await;
+        assert!(reopened.error.is_some());
+        assert_eq!((reopened.live, marker(&reopened).as_str()), (LiveDb::PreMigration, "snapshot"));
+        drop(reopened);
+
+        marked_db(&snapshot_path(&db_path), "snapshot");
+        marked_db(&staging, "cloud");
+        let reopened = swap_in_and_open(&staging, &db_path, opener(&[])).await;
+        assert!(reopened.error.is_none());
+        assert_eq!((reopened.live, marker(&reopened).as_str()), (LiveDb::Migrated, "cloud"));
+        drop(reopened);
+
+        // The restored snapshot doesn't open: the cloud database stays live
+        // and the snapshot is kept for another try
+        let reopened = restore_and_open(&db_path, opener(&[LiveDb::PreMigration])).await;
+        assert!(reopened.error.is_some());
+        assert_eq!((reopened.live, marker(&reopened).as_str()), (LiveDb::Migrated, "cloud"));
+        assert!(snapshot_path(&db_path).exists() && !replaced_path(&db_path).exists());
+        drop(reopened);
+
+        let reopened = restore_and_open(&db_path, opener(&[])).await;
+        assert_eq!((reopened.error.is_none(), marker(&reopened).as_str()), (true, "snapshot"));
+        assert!(!snapshot_path(&db_path).exists() && !replaced_path(&db_path).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_count_missing() {
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        assert_eq!(count_missing(&[a, b, b], &[a, b, c]), 1);
+        assert_eq!(count_missing(&[a, b], &[a, a, b, c]), 0);
+        assert_eq!(count_missing(&[], &[a]), 0);
+    }
+}
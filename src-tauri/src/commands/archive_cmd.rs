@@ -0,0 +1,82 @@
+//! Archive Commands
+//!
+//! Offline reader copies of bookmarked pages (see `archive`): create, show,
+//! search and remove them.
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::archive::{self, ArchiveResult, HttpFetcher};
+use crate::domain::{Archive, ArchiveMatch};
+use crate::repository::{ArchiveRepository, ItemRepository, Repository};
+use crate::AppState;
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// An archive with its reader HTML
+#[derive(Debug, Serialize)]
+pub struct ArchiveView {
+    #[serde(flatten)]
+    pub archive: Archive,
+    /// Sanitised HTML with `assets/{hash}.{ext}` images; `None` while the file
+    /// has not been synced to this device
+    pub html: Option<String>,
+}
+
+/// Archive the page of an item for offline reading, replacing an earlier
+/// archive. `html` is the page if already fetched; otherwise it is downloaded.
+#[tauri::command]
+pub async fn archive_url(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item_id: u32,
+    html: Option<String>,
+) -> Result<ArchiveResult, String> {
+    let item = ItemRepository::new(state.db_state.conn.clone()).find_by_id(item_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item {} not found", item_id))?;
+    let assets_dir = super::asset_cmd::assets_dir(&app_handle)?;
+    let fetcher = HttpFetcher::new().map_err(|e| e.to_string())?;
+    archive::archive_item(state.db_state.conn.clone(), &assets_dir, &fetcher, &item, html)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The archive of an item, if it has one
+#[tauri::command]
+pub async fn get_archive(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    item_id: u32,
+) -> Result<Option<ArchiveView>, String> {
+    let found = ArchiveRepository::new(state.db_state.conn.clone()).find(item_id).await
+        .map_err(|e| e.to_string())?;
+    let Some(archive) = found else {
+        return Ok(None);
+    };
+    let assets_dir = super::asset_cmd::assets_dir(&app_handle)?;
+    let html = archive::read_archive_html(&assets_dir, &archive);
+    Ok(Some(ArchiveView { archive, html }))
+}
+
+/// Archived pages containing the query, newest first
+#[tauri::command]
+pub async fn search_archives(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ArchiveMatch>, String> {
+    ArchiveRepository::new(state.db_state.conn.clone())
+        .search(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove the archive of an item; its files go with the next asset cleanup
+#[tauri::command]
+pub async fn delete_archive(state: State<'_, AppState>, item_id: u32) -> Result<(), String> {
+    ArchiveRepository::new(state.db_state.conn.clone())
+        .delete(item_id)
+        .await
+        .map_err(|e| e.to_string())
+}
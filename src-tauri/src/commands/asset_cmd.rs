@@ -0,0 +1,403 @@
+//! Asset Commands
+//!
+//! Store pasted and dropped media content-addressed under `assets/` in the app
+//! data dir, and clean up files that nothing references any more.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Manager, State};
+use tokio::sync::Mutex;
+
+use crate::domain::{
+    extract_asset_refs, rewrite_dir_paths, safe_extension, sniff_mime, to_asset_references, Asset, AssetRef,
+    FileIdentifier, Timestamp, ASSETS_DIR, ASSET_OWNER_ARCHIVE, ASSET_OWNER_ITEM, ASSET_OWNER_TAG, LEGACY_CLIPBOARD_DIR,
+};
+use crate::repository::{ArchiveRepository, AssetRepository, ItemRepository, Repository, TagRepository};
+use crate::AppState;
+
+/// A stored asset, the absolute path of its file on this device and the
+/// reference memos should use (`assets/{hash}.{ext}`)
+#[derive(Debug, Serialize)]
+pub struct SavedAsset {
+    pub path: String,
+    pub reference: String,
+    #[serde(flatten)]
+    pub asset: Asset,
+}
+
+/// Files in `assets/` younger than this are left alone by the cleanup: a save
+/// in progress has written its file but not recorded or referenced it yet
+const CLEANUP_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+fn app_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+/// Directory holding this device's asset files
+pub(crate) fn assets_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_dir(app_handle)?.join(ASSETS_DIR))
+}
+
+/// Type of the content (`head` = leading bytes), falling back to the file name
+fn detect_type(head: &[u8], original_name: Option<&str>) -> (String, String) {
+    if let Some((mime, ext)) = sniff_mime(head) {
+        return (mime.to_string(), ext.to_string());
+    }
+    let ext = original_name.and_then(safe_extension);
+    let mime = ext.as_ref()
+        .and_then(|ext| mime_guess::from_ext(ext).first())
+        .map(|m| m.essence_str().to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    (mime, ext.unwrap_or_else(|| "bin".to_string()))
+}
+
+/// Record the asset and return it with its path; `write` puts the content at
+/// the given path and is skipped when the file already exists
+async fn save_asset(
+    conn: Arc<Mutex<Option<Connection>>>,
+    dir: &Path,
+    mut asset: Asset,
+    write: impl FnOnce(&Path) -> std::io::Result<()>,
+) -> Result<SavedAsset, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create assets directory: {}", e))?;
+
+    let repo = AssetRepository::new(conn);
+    // Same content stored before: keep its extension (and file)
+    if let Some(existing) = repo.find(&asset.hash).await.map_err(|e| e.to_string())? {
+        asset.ext = existing.ext;
+    }
+
+    let path = dir.join(asset.file_name());
+    if !path.exists() {
+        // Write under a temporary name so a partial file never carries the content hash
+        let tmp = dir.join(format!("{}.tmp", asset.hash));
+        write(&tmp).map_err(|e| format!("Failed to write asset: {}", e))?;
+        fs::rename(&tmp, &path).map_err(|e| format!("Failed to write asset: {}", e))?;
+    }
+
+    let asset = repo.upsert(&asset).await.map_err(|e| e.to_string())?;
+    Ok(SavedAsset { path: path.to_string_lossy().to_string(), reference: asset.reference(), asset })
+}
+
+/// Store pasted data (base64, optionally a data URL) as an asset
+pub(crate) async fn store_asset_data(
+    app_handle: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    data: &str,
+    file_name: Option<String>,
+) -> Result<SavedAsset, String> {
+    // The data may have a data URL prefix like "data:image/png;base64,"
+    let base64_data = data.split_once(',').map(|(_, d)| d).unwrap_or(data);
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_data)
+        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+
+    let (mime, ext) = detect_type(&bytes, file_name.as_deref());
+    let asset = Asset {
+        hash: FileIdentifier::compute_data_hash(&bytes),
+        ext,
+        mime,
+        size: bytes.len() as u64,
+        original_name: file_name,
+        created_at: Timestamp::now(),
+    };
+    let dir = assets_dir(app_handle)?;
+    save_asset(state.db_state.conn.clone(), &dir, asset, |path| fs::write(path, &bytes)).await
+}
+
+/// Save pasted data (image, PDF, audio, video, ...) as an asset
+#[tauri::command]
+pub async fn save_asset_data(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    data: String,
+    file_name: Option<String>,
+) -> Result<SavedAsset, String> {
+    store_asset_data(&app_handle, &state, &data, file_name).await
+}
+
+/// Copy a dropped file into the asset store
+#[tauri::command]
+pub async fn import_asset_file(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<SavedAsset, String> {
+    let dir = assets_dir(&app_handle)?;
+    store_file(state.db_state.conn.clone(), &dir, &PathBuf::from(path)).await
+}
+
+/// Copy a file into the asset store in `dir`
+pub(crate) async fn store_file(
+    conn: Arc<Mutex<Option<Connection>>>,
+    dir: &Path,
+    source: &Path,
+) -> Result<SavedAsset, String> {
+    let path = source.display();
+    if !source.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+
+    let mut head = Vec::with_capacity(512);
+    fs::File::open(source)
+        .and_then(|file| file.take(512).read_to_end(&mut head))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let size = fs::metadata(source).map_err(|e| e.to_string())?.len();
+
+    let original_name = source.file_name().map(|n| n.to_string_lossy().to_string());
+    let (mime, ext) = detect_type(&head, original_name.as_deref());
+    let asset = Asset {
+        hash: FileIdentifier::compute_content_hash(source)?,
+        ext,
+        mime,
+        size,
+        original_name,
+        created_at: Timestamp::now(),
+    };
+    save_asset(conn, dir, asset, |dest| fs::copy(source, dest).map(|_| ())).await
+}
+
+/// This device's asset directory, for resolving `assets/{hash}.{ext}` references
+#[tauri::command]
+pub fn get_assets_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
+    Ok(assets_dir(&app_handle)?.to_string_lossy().to_string())
+}
+
+/// Reload the files the asset protocol may serve (workspace dirs, asset
+/// directories, allowed paths)
+pub(crate) async fn refresh_asset_scope(state: &AppState) -> Result<(), String> {
+    let app_dir = state.db_path.parent().ok_or("Invalid database path")?;
+    let home_dir = std::env::home_dir();
+    let scope = crate::asset_protocol::load_scope(state.db_state.conn.clone(), app_dir, home_dir.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    *state.asset_scope.write().unwrap_or_else(|e| e.into_inner()) = scope;
+    Ok(())
+}
+
+/// Paths outside the workspace dirs that the asset protocol may serve on this device
+#[tauri::command]
+pub async fn list_allowed_asset_paths(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let app_dir = state.db_path.parent().ok_or("Invalid database path")?;
+    Ok(crate::asset_protocol::load_allowed_paths(app_dir))
+}
+
+/// Let the asset protocol serve a file or directory (e.g. one linked from memos)
+#[tauri::command]
+pub async fn allow_asset_path(state: State<'_, AppState>, path: String) -> Result<Vec<String>, String> {
+    let home_dir = std::env::home_dir();
+    let path = crate::asset_protocol::allowable_path(Path::new(&path), home_dir.as_deref())
+        .map_err(|e| e.to_string())?
+        .to_string_lossy()
+        .to_string();
+    let app_dir = state.db_path.parent().ok_or("Invalid database path")?;
+    let mut paths = crate::asset_protocol::load_allowed_paths(app_dir);
+    if !paths.contains(&path) {
+        paths.push(path);
+        crate::asset_protocol::save_allowed_paths(app_dir, &paths).map_err(|e| e.to_string())?;
+        refresh_asset_scope(&state).await?;
+    }
+    Ok(paths)
+}
+
+/// Stop serving a previously allowed path
+#[tauri::command]
+pub async fn disallow_asset_path(state: State<'_, AppState>, path: String) -> Result<Vec<String>, String> {
+    let app_dir = state.db_path.parent().ok_or("Invalid database path")?;
+    let mut paths = crate::asset_protocol::load_allowed_paths(app_dir);
+    paths.retain(|p| p != &path);
+    crate::asset_protocol::save_allowed_paths(app_dir, &paths).map_err(|e| e.to_string())?;
+    refresh_asset_scope(&state).await?;
+    Ok(paths)
+}
+
+/// Make memos refer to assets by reference instead of by absolute path, so
+/// they stay valid on other devices: paths into `assets/` become
+/// `assets/{hash}.{ext}`, and legacy clipboard images are imported into the
+/// asset store first. Returns the number of updated items and tags.
+pub(crate) async fn adopt_asset_references(
+    conn: Arc<Mutex<Option<Connection>>>,
+    app_dir: &Path,
+) -> Result<usize, String> {
+    let item_repo = ItemRepository::new(conn.clone());
+    let tag_repo = TagRepository::new(conn.clone());
+    let asset_repo = AssetRepository::new(conn.clone());
+    let items = item_repo.list().await.map_err(|e| format!("Failed to list items: {}", e))?;
+    let tags = tag_repo.list().await.map_err(|e| format!("Failed to list tags: {}", e))?;
+
+    // Legacy images mentioned anywhere, imported once each
+    let mut legacy_names: Vec<String> = Vec::new();
+    let texts = items.iter().flat_map(|i| [i.memo.as_deref(), i.summary.as_deref()])
+        .chain(tags.iter().map(|t| t.memo.as_deref()))
+        .flatten();
+    for text in texts {
+        rewrite_dir_paths(text, LEGACY_CLIPBOARD_DIR, |name| {
+            legacy_names.push(name.to_string());
+            None
+        });
+    }
+    let mut imported: HashMap<String, String> = HashMap::new();
+    for name in legacy_names {
+        let source = app_dir.join(LEGACY_CLIPBOARD_DIR).join(&name);
+        if imported.contains_key(&name) || name.contains(['/', '\\']) || name.contains("..") || !source.is_file() {
+            continue;
+        }
+        let saved = store_file(conn.clone(), &app_dir.join(ASSETS_DIR), &source).await?;
+        imported.insert(name, saved.reference);
+    }
+
+    let rewrite = |text: &Option<String>| text.as_deref().map(|text| {
+        to_asset_references(&rewrite_dir_paths(text, LEGACY_CLIPBOARD_DIR, |name| imported.get(name).cloned()))
+    });
+    let mut updated = 0;
+    for mut item in items {
+        let (memo, summary) = (rewrite(&item.memo), rewrite(&item.summary));
+        if memo == item.memo && summary == item.summary {
+            continue;
+        }
+        item.memo = memo;
+        item.summary = summary;
+        let saved = item_repo.update(&item).await.map_err(|e| e.to_string())?;
+        let text = [saved.memo.as_deref(), saved.summary.as_deref()].into_iter().flatten().collect::<Vec<_>>().join("\n");
+        asset_repo.set_refs(ASSET_OWNER_ITEM, saved.id, &extract_asset_refs(&text)).await.map_err(|e| e.to_string())?;
+        updated += 1;
+    }
+    for mut tag in tags {
+        let memo = rewrite(&tag.memo);
+        if memo == tag.memo {
+            continue;
+        }
+        tag.memo = memo;
+        let saved = tag_repo.update(&tag).await.map_err(|e| e.to_string())?;
+        let refs = extract_asset_refs(saved.memo.as_deref().unwrap_or_default());
+        asset_repo.set_refs(ASSET_OWNER_TAG, saved.id, &refs).await.map_err(|e| e.to_string())?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// Delete stored assets that no item memo, item summary, tag memo or page
+/// archive refers to.
+///
+/// References are rescanned from all items, tags and archives first (synced
+/// data may have changed them). Files in the legacy `clipboard_images` directory are
+/// removed when no text mentions them. Asset files a save may still be working
+/// on are kept (see `maybe_being_saved`).
+#[tauri::command]
+pub async fn clean_unused_assets(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let items = ItemRepository::new(state.db_state.conn.clone()).list().await
+        .map_err(|e| format!("Failed to list items: {}", e))?;
+    let tags = TagRepository::new(state.db_state.conn.clone()).list().await
+        .map_err(|e| format!("Failed to list tags: {}", e))?;
+    let archives = ArchiveRepository::new(state.db_state.conn.clone()).list().await
+        .map_err(|e| format!("Failed to list archives: {}", e))?;
+
+    let mut texts: Vec<String> = Vec::new();
+    let mut refs: Vec<AssetRef> = Vec::new();
+    for item in &items {
+        let text = [item.memo.as_deref(), item.summary.as_deref()].into_iter().flatten().collect::<Vec<_>>().join("\n");
+        refs.extend(extract_asset_refs(&text).into_iter().map(|hash| AssetRef {
+            asset_hash: hash,
+            owner_type: ASSET_OWNER_ITEM.to_string(),
+            owner_id: item.id,
+        }));
+        texts.push(text);
+    }
+    for tag in &tags {
+        let text = tag.memo.clone().unwrap_or_default();
+        refs.extend(extract_asset_refs(&text).into_iter().map(|hash| AssetRef {
+            asset_hash: hash,
+            owner_type: ASSET_OWNER_TAG.to_string(),
+            owner_id: tag.id,
+        }));
+        texts.push(text);
+    }
+    for archive in &archives {
+        refs.extend(archive.asset_hashes().into_iter().map(|hash| AssetRef {
+            asset_hash: hash,
+            owner_type: ASSET_OWNER_ARCHIVE.to_string(),
+            owner_id: archive.item_id,
+        }));
+    }
+
+    let repo = AssetRepository::new(state.db_state.conn.clone());
+    repo.replace_all_refs(&refs).await.map_err(|e| e.to_string())?;
+
+    let app_dir = app_dir(&app_handle)?;
+    let mut deleted_count = 0;
+
+    // Unreferenced assets: the file and the record
+    for asset in repo.list_unreferenced().await.map_err(|e| e.to_string())? {
+        let path = app_dir.join(ASSETS_DIR).join(asset.file_name());
+        if maybe_being_saved(&path) {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => deleted_count += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("Failed to delete {}: {}", path.display(), e);
+                continue;
+            }
+        }
+        repo.delete(&asset.hash).await.map_err(|e| e.to_string())?;
+    }
+
+    // Files in assets/ without a record (e.g. left over from an interrupted save)
+    let referenced: HashSet<&str> = refs.iter().map(|r| r.asset_hash.as_str()).collect();
+    let known: HashSet<String> = repo.list().await.map_err(|e| e.to_string())?
+        .into_iter().map(|a| a.hash).collect();
+    for path in list_files(&app_dir.join(ASSETS_DIR)) {
+        if maybe_being_saved(&path) {
+            continue;
+        }
+        let hash = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_ascii_lowercase();
+        if !known.contains(&hash) && !referenced.contains(hash.as_str()) && fs::remove_file(&path).is_ok() {
+            deleted_count += 1;
+        }
+    }
+
+    // Legacy clipboard images are referenced by file name
+    for path in list_files(&app_dir.join(LEGACY_CLIPBOARD_DIR)) {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue; };
+        let used = texts.iter().any(|text| text.contains(file_name));
+        if !used {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("Failed to delete {}: {}", file_name, e);
+            } else {
+                deleted_count += 1;
+            }
+        }
+    }
+
+    Ok(deleted_count)
+}
+
+/// Whether a file in `assets/` may belong to a save in progress: the
+/// temporary file `save_asset` writes, or any file modified recently (see
+/// `CLEANUP_MIN_AGE`). A missing file is not being saved.
+fn maybe_being_saved(path: &Path) -> bool {
+    if path.extension().is_some_and(|ext| ext == "tmp") {
+        return true;
+    }
+    match fs::metadata(path).and_then(|meta| meta.modified()) {
+        // A modification time in the future counts as recent
+        Ok(modified) => modified.elapsed().unwrap_or_default() < CLEANUP_MIN_AGE,
+        Err(e) => e.kind() != std::io::ErrorKind::NotFound,
+    }
+}
+
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default()
+}
@@ -17,3 +17,36 @@ pub async fn pick_folder<R: Runtime>(app: AppHandle<R>) -> Result<Option<String>
         Ok(None)
     }
 }
+
+#[command]
+pub async fn pick_save_file<R: Runtime>(app: AppHandle<R>, file_name: String) -> Result<Option<String>, String> {
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        let result = app.dialog().file().set_file_name(file_name).blocking_save_file();
+        match result {
+            Some(path) => Ok(Some(path.to_string())),
+            None => Ok(None),
+        }
+    }
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        let _ = file_name;
+        Ok(None)
+    }
+}
+
+#[command]
+pub async fn pick_file<R: Runtime>(app: AppHandle<R>) -> Result<Option<String>, String> {
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        let result = app.dialog().file().blocking_pick_file();
+        match result {
+            Some(path) => Ok(Some(path.to_string())),
+            None => Ok(None),
+        }
+    }
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        Ok(None)
+    }
+}
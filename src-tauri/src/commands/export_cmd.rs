@@ -0,0 +1,38 @@
+//! Export Commands
+//!
+//! Commands for publishing a workspace outside the app.
+
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::export::{BookmarkExportFormat, BookmarkExportReport, SiteExportReport};
+use crate::AppState;
+
+/// Export a workspace as a static, read-only HTML site into `dir`
+#[tauri::command]
+pub async fn export_site(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    workspace_id: u32,
+    dir: String,
+) -> Result<SiteExportReport, String> {
+    let assets_dir = super::asset_cmd::assets_dir(&app_handle)?;
+    crate::export::export_site(state.db_state.conn.clone(), workspace_id, &PathBuf::from(dir), &assets_dir)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Export the bookmarks (items with a URL) of a workspace to `path` as
+/// Netscape HTML, OPML or CSV; with `tag_filter` only those under that tag
+#[tauri::command]
+pub async fn export_bookmarks(
+    state: State<'_, AppState>,
+    workspace_id: u32,
+    tag_filter: Option<u32>,
+    format: BookmarkExportFormat,
+    path: String,
+) -> Result<BookmarkExportReport, String> {
+    crate::export::export_bookmarks(state.db_state.conn.clone(), workspace_id, tag_filter, format, &PathBuf::from(path))
+        .await
+        .map_err(|e| e.to_string())
+}
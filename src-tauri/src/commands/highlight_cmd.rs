@@ -0,0 +1,27 @@
+//! Highlight Commands
+//!
+//! Code highlighting for the frontend preview, which is built without syntect
+//! to keep the WASM bundle small.
+
+/// Highlight a code block (`hl-*` classes, styled by `highlight_css`)
+#[tauri::command]
+pub async fn highlight_code(code: String, lang: Option<String>) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || tag_all_markdown::highlight_code(&code, lang.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stylesheet for highlighted code; themes default to the bundled light/dark pair
+#[tauri::command]
+pub async fn highlight_css(light: Option<String>, dark: Option<String>) -> Result<String, String> {
+    tag_all_markdown::highlight_css(
+        light.as_deref().unwrap_or(tag_all_markdown::LIGHT_THEME),
+        dark.as_deref().unwrap_or(tag_all_markdown::DARK_THEME),
+    )
+}
+
+/// Names of the themes accepted by `highlight_css`
+#[tauri::command]
+pub fn list_highlight_themes() -> Vec<String> {
+    tag_all_markdown::theme_names()
+}
@@ -0,0 +1,23 @@
+//! Import Commands
+//!
+//! Commands for bringing data from other applications into the app.
+
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::import::{BookmarkFolders, BookmarkImportReport};
+use crate::AppState;
+
+/// Import browser bookmarks into the web-bookmarks workspace from a bookmark
+/// HTML export, a Chrome `Bookmarks` file or a Firefox `places.sqlite`.
+/// Folders become nested items or tags as chosen (items by default).
+#[tauri::command]
+pub async fn import_bookmarks(
+    state: State<'_, AppState>,
+    path: String,
+    folders: Option<BookmarkFolders>,
+) -> Result<BookmarkImportReport, String> {
+    crate::import::import_bookmarks(state.db_state.conn.clone(), &PathBuf::from(path), folders.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
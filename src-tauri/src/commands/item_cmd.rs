@@ -3,9 +3,10 @@
 //! Exposes Item operations to the frontend via Tauri IPC.
 
 use tauri::State;
-use crate::domain::{Item, ItemType};
-use crate::repository::{Repository, ItemRepository};
-use crate::repository::item::{ItemHierarchyOperations, ItemWorkspaceOperations, ItemPositioningOperations};
+use crate::domain::{Item, ItemType, ItemLink, extract_asset_refs, ASSET_OWNER_ITEM};
+use crate::repository::{Repository, ItemRepository, TagRepository, AssetRepository};
+use crate::repository::tag::InlineTagOperations;
+use crate::repository::item::{ItemHierarchyOperations, ItemWorkspaceOperations, ItemPositioningOperations, ItemLinkOperations, ItemSearchOperations};
 use crate::AppState;
 use std::str::FromStr;
 
@@ -17,6 +18,7 @@ pub async fn create_item(
     item_type: Option<String>,
     parent_id: Option<u32>,
     workspace_id: Option<u32>,
+    url: Option<String>,
 ) -> Result<Item, String> {
     let repo = ItemRepository::new(state.db_state.conn.clone());
     
@@ -26,10 +28,21 @@ pub async fn create_item(
         item_type.map(|t| ItemType::from_str(&t)).unwrap_or_default(),
     );
     item.parent_id = parent_id;
+    item.url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
     
     // Use provided workspace_id or default to 1
     let ws_id = workspace_id.unwrap_or(1);
-    repo.create_with_workspace(&item, ws_id).await.map_err(|e| e.to_string())
+    let created = repo.create_with_workspace(&item, ws_id).await.map_err(|e| e.to_string())?;
+    
+    // Apply #tag tokens from the text
+    let tag_repo = TagRepository::new(state.db_state.conn.clone());
+    tag_repo.sync_inline_tags(created.id, &created.text, created.memo.as_deref()).await.map_err(|e| e.to_string())?;
+    
+    // Index its [[...]] links, and links elsewhere that were waiting for its title
+    repo.update_links(created.id, created.memo.as_deref()).await.map_err(|e| e.to_string())?;
+    repo.resolve_pending_links().await.map_err(|e| e.to_string())?;
+    
+    Ok(created)
 }
 
 /// List all items
@@ -66,6 +79,18 @@ pub async fn get_item(state: State<'_, AppState>, id: u32) -> Result<Option<Item
     repo.find_by_id(id).await.map_err(|e| e.to_string())
 }
 
+/// Find the item saved under the same normalised URL (tracking params,
+/// host case, trailing slash and plain fragments are ignored)
+#[tauri::command]
+pub async fn find_item_by_url(
+    state: State<'_, AppState>,
+    url: String,
+    workspace_id: Option<u32>,
+) -> Result<Option<Item>, String> {
+    let repo = ItemRepository::new(state.db_state.conn.clone());
+    repo.find_by_url(&url, workspace_id).await.map_err(|e| e.to_string())
+}
+
 /// Update item
 #[tauri::command]
 pub async fn update_item(
@@ -81,6 +106,9 @@ pub async fn update_item(
     // First get existing item
     let existing = repo.find_by_id(id).await.map_err(|e| e.to_string())?
         .ok_or_else(|| format!("Item {} not found", id))?;
+    let memo_given = memo.is_some();
+    let existing_memo = existing.memo.clone();
+    let existing_text = existing.text.clone();
     
     // Update fields
     let updated = Item {
@@ -104,7 +132,61 @@ pub async fn update_item(
         is_dir: existing.is_dir,
     };
     
-    repo.update(&updated).await.map_err(|e| e.to_string())
+    let memo_changed = memo_given && updated.memo != existing_memo;
+    let saved = repo.update(&updated).await.map_err(|e| e.to_string())?;
+    
+    // Keep item_links in sync with [[...]] links in the memo
+    if memo_changed {
+        repo.update_links(saved.id, saved.memo.as_deref()).await.map_err(|e| e.to_string())?;
+        
+        // Track pasted/dropped assets the memo (or summary) refers to
+        let text = [saved.memo.as_deref(), saved.summary.as_deref()].into_iter().flatten().collect::<Vec<_>>().join("\n");
+        AssetRepository::new(state.db_state.conn.clone())
+            .set_refs(ASSET_OWNER_ITEM, saved.id, &extract_asset_refs(&text))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    // Re-apply #tag tokens from text and memo
+    if memo_changed || saved.text != existing_text {
+        let tag_repo = TagRepository::new(state.db_state.conn.clone());
+        tag_repo.sync_inline_tags(saved.id, &saved.text, saved.memo.as_deref()).await.map_err(|e| e.to_string())?;
+    }
+
+    // Links waiting for an item with the new title
+    if saved.text != existing_text {
+        repo.resolve_pending_links().await.map_err(|e| e.to_string())?;
+    }
+    
+    Ok(saved)
+}
+
+/// Items whose memos link to this item ([[...]] backlinks)
+#[tauri::command]
+pub async fn get_backlinks(state: State<'_, AppState>, item_id: u32) -> Result<Vec<ItemLink>, String> {
+    let repo = ItemRepository::new(state.db_state.conn.clone());
+    repo.get_backlinks(item_id).await.map_err(|e| e.to_string())
+}
+
+/// Items linked from this item's memo
+#[tauri::command]
+pub async fn get_outgoing_links(state: State<'_, AppState>, item_id: u32) -> Result<Vec<ItemLink>, String> {
+    let repo = ItemRepository::new(state.db_state.conn.clone());
+    repo.get_outgoing_links(item_id).await.map_err(|e| e.to_string())
+}
+
+/// Resolve a [[...]] link written in an item's memo to the target item
+#[tauri::command]
+pub async fn resolve_item_link(
+    state: State<'_, AppState>,
+    source_item_id: u32,
+    link_text: String,
+) -> Result<Option<Item>, String> {
+    let repo = ItemRepository::new(state.db_state.conn.clone());
+    match repo.resolve_link(source_item_id, &link_text).await.map_err(|e| e.to_string())? {
+        Some(id) => repo.find_by_id(id).await.map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
 }
 
 /// Delete item (cascade deletes children)
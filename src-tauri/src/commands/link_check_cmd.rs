@@ -0,0 +1,78 @@
+//! Link Check Commands
+//!
+//! Check bookmark links in the background (see `link_check`) and list the
+//! ones found broken.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{Emitter, Manager, State};
+
+use crate::domain::{BrokenLink, Timestamp};
+use crate::link_check::{self, HttpProber, LINK_CHECK_PROGRESS_EVENT};
+use crate::repository::item::ItemLinkHealthOperations;
+use crate::repository::ItemRepository;
+use crate::AppState;
+
+/// Links checked more recently than this are skipped unless asked otherwise
+const DEFAULT_RECHECK_AFTER_DAYS: u32 = 7;
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Only one check runs at a time
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Start checking the links of items in a workspace (all workspaces if none)
+/// that were never checked or not within `recheck_after_days` (default 7;
+/// 0 rechecks everything). Returns at once; progress arrives as
+/// `link-check-progress` events, the last one with `done` set.
+#[tauri::command]
+pub async fn start_link_check(
+    app_handle: tauri::AppHandle,
+    workspace_id: Option<u32>,
+    recheck_after_days: Option<u32>,
+) -> Result<(), String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A link check is already running".to_string());
+    }
+    let prober = match HttpProber::new() {
+        Ok(prober) => Arc::new(prober),
+        Err(e) => {
+            RUNNING.store(false, Ordering::SeqCst);
+            return Err(e.to_string());
+        }
+    };
+    let days = recheck_after_days.unwrap_or(DEFAULT_RECHECK_AFTER_DAYS) as i64;
+    let checked_before = Timestamp::from_millis(Timestamp::now().millis() - days * MILLIS_PER_DAY);
+
+    tauri::async_runtime::spawn(async move {
+        let conn = app_handle.state::<AppState>().db_state.conn.clone();
+        let emitter = app_handle.clone();
+        let result = link_check::check_links(conn, prober, workspace_id, Some(checked_before), move |progress| {
+            let _ = emitter.emit(LINK_CHECK_PROGRESS_EVENT, progress);
+        })
+        .await;
+        match result {
+            Ok(summary) => {
+                let _ = rolling_logger::info(&format!("Checked {} links, {} broken", summary.checked, summary.broken));
+            }
+            Err(e) => {
+                let _ = rolling_logger::info(&format!("Link check failed: {}", e));
+                // Let the frontend stop waiting
+                let _ = app_handle.emit(LINK_CHECK_PROGRESS_EVENT, link_check::LinkCheckProgress { done: true, ..Default::default() });
+            }
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+    Ok(())
+}
+
+/// Items whose last link check failed, most recently checked first
+#[tauri::command]
+pub async fn list_broken_links(
+    state: State<'_, AppState>,
+    workspace_id: Option<u32>,
+) -> Result<Vec<BrokenLink>, String> {
+    ItemRepository::new(state.db_state.conn.clone())
+        .list_broken_links(workspace_id)
+        .await
+        .map_err(|e| e.to_string())
+}
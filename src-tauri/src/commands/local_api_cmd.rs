@@ -0,0 +1,77 @@
+//! Local API Commands
+//!
+//! Tauri commands for configuring the localhost API used by the browser
+//! clipper (see `local_api`).
+
+use std::sync::Arc;
+use tauri::{Emitter, Manager, State};
+
+use crate::local_api::{self, LocalApi, LocalApiConfig, Notify};
+use crate::AppState;
+
+/// Stop the running server and start it again if enabled in the saved config
+pub(crate) async fn restart_local_api(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let mut server = state.local_api.lock().await;
+    // Release the port before binding it again
+    *server = None;
+
+    let config = local_api::load_config(&state.db_path);
+    if !config.enabled {
+        return Ok(());
+    }
+    let emitter = app_handle.clone();
+    let notify: Notify = Arc::new(move |event| {
+        let _ = emitter.emit(event, ());
+    });
+    let assets_dir = super::asset_cmd::assets_dir(app_handle)?;
+    let started = LocalApi::new(state.db_state.conn.clone(), assets_dir, config.token, notify)
+        .start(config.port)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = rolling_logger::info(&format!("Local API listening on {}", started.addr()));
+    *server = Some(started);
+    Ok(())
+}
+
+/// Get the local API configuration (including the token to paste into the clipper)
+#[tauri::command]
+pub fn get_local_api_config(state: State<'_, AppState>) -> Result<LocalApiConfig, String> {
+    Ok(local_api::load_config(&state.db_path))
+}
+
+/// Enable or disable the local API and set its port; a token is generated on
+/// first use. The server is restarted with the new settings.
+#[tauri::command]
+pub async fn save_local_api_config(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    port: u16,
+) -> Result<LocalApiConfig, String> {
+    if port == 0 {
+        return Err("端口无效".to_string());
+    }
+    let mut config = local_api::load_config(&state.db_path);
+    config.enabled = enabled;
+    config.port = port;
+    if config.token.is_empty() {
+        config.token = local_api::generate_token();
+    }
+    local_api::save_config(&state.db_path, &config).map_err(|e| e.to_string())?;
+    restart_local_api(&app_handle).await?;
+    Ok(config)
+}
+
+/// Replace the token; clients using the old one are rejected from now on
+#[tauri::command]
+pub async fn regenerate_local_api_token(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<LocalApiConfig, String> {
+    let mut config = local_api::load_config(&state.db_path);
+    config.token = local_api::generate_token();
+    local_api::save_config(&state.db_path, &config).map_err(|e| e.to_string())?;
+    restart_local_api(&app_handle).await?;
+    Ok(config)
+}
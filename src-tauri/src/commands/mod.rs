@@ -10,6 +10,14 @@ mod sync_cmd;
 mod file_cmd;
 mod dialog_cmd;
 mod clipboard_cmd;
+mod asset_cmd;
+mod export_cmd;
+mod import_cmd;
+mod highlight_cmd;
+mod local_api_cmd;
+mod archive_cmd;
+mod link_check_cmd;
+mod sync_conflict_cmd;
 
 pub use item_cmd::*;
 pub use tag_cmd::*;
@@ -19,3 +27,11 @@ pub use sync_cmd::*;
 pub use file_cmd::*;
 pub use dialog_cmd::*;
 pub use clipboard_cmd::*;
+pub use asset_cmd::*;
+pub use export_cmd::*;
+pub use import_cmd::*;
+pub use highlight_cmd::*;
+pub use local_api_cmd::*;
+pub use archive_cmd::*;
+pub use link_check_cmd::*;
+pub use sync_conflict_cmd::*;
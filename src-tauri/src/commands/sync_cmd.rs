@@ -1,10 +1,20 @@
 //! Cloud Sync Commands
 //!
-//! Tauri commands for managing cloud synchronization with Turso.
+//! Tauri commands for managing cloud synchronization with Turso, and for
+//! syncing asset files alongside it (see `asset_sync`).
 
-use crate::repository::{configure_sync as repo_configure_sync, get_sync_config as repo_get_sync_config, SyncConfig};
-use std::path::PathBuf;
-use tauri::Manager;
+use crate::asset_sync::{self, AssetSyncConfig, AssetSyncReport};
+use crate::cloud_migration::{self, LiveDb, MigrationReport, TableCheck};
+use crate::domain::{DomainError, DomainResult, Timestamp};
+use crate::repository::{configure_sync as repo_configure_sync, get_sync_config as repo_get_sync_config, ItemRepository, SyncConfig, SYNCED_TABLES};
+use crate::repository::item::ItemLinkOperations;
+use crate::repository::timestamp::repair_timestamps;
+use crate::sync_conflict::{self, TursoRemote, SYNC_CONFLICTS_EVENT};
+use crate::sync_scheduler::{SchedulerConfig, SchedulerStatus, StatusNotify, SyncScheduler, SyncState, SyncTarget, SYNC_STATUS_EVENT};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
 
 // Import validate_cloud_connection from backend crate
 use tauri_sync_db_backend::validate_cloud_connection;
@@ -13,25 +23,18 @@ use tauri_sync_db_backend::validate_cloud_connection;
 use tauri_sync_db_backend::sync::{sync_all, DynamicSchema};
 use tauri_plugin_http::reqwest;
 
-/// Helper to perform sync using generic backend
-async fn perform_sync(state: &tauri_sync_db_backend::DbState) -> Result<(), String> {
+/// Held for the whole of a database sync
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Helper to perform sync using generic backend; returns how many rows were
+/// found edited on both sides (see `sync_conflict`)
+async fn perform_sync(state: &tauri_sync_db_backend::DbState) -> Result<usize, String> {
+    // Manual and background syncs must not interleave
+    let _running = SYNC_LOCK.lock().await;
     let config = repo_get_sync_config(&state.db_path).ok_or("Sync not configured")?;
     
     // 1. Load Dynamic Schema from DB
-    // We want to sync: items, tags, item_tags, tag_tags, workspaces, workspace_dirs, window_state
-    // Settings? tag-all doesn't seem to have settings table yet, or it's implicitly handled.
-    // Based on db.rs migrations:
-    let tables = vec![
-        "workspaces", 
-        "workspace_dirs", 
-        "tags", 
-        "items", 
-        "item_tags", 
-        "tag_tags", 
-        "window_state"
-    ];
-    
-    let schema = DynamicSchema::load(state, tables).await
+    let schema = DynamicSchema::load(state, SYNCED_TABLES.to_vec()).await
         .map_err(|e| format!("Failed to load schema: {}", e))?;
         
     // 2. HTTP Client
@@ -40,8 +43,51 @@ async fn perform_sync(state: &tauri_sync_db_backend::DbState) -> Result<(), Stri
         .build()
         .map_err(|e| e.to_string())?;
         
-    // 3. Sync All
-    sync_all(&client, state, &schema, &config.url, &config.token).await
+    // 3. Sync All, with timestamps in milliseconds on both sides: local rows are
+    // repaired before they are pushed, pulled rows (e.g. written by an older
+    // clipper in seconds) before they are used
+    repair_local_timestamps(state).await?;
+    // Both versions of rows edited here and remotely are kept, not just the winner
+    let remote = TursoRemote::new(client.clone(), &config.url, &config.token);
+    let pending = sync_conflict::prepare(state.conn.clone(), &remote).await.map_err(|e| e.to_string())?;
+    sync_all(&client, state, &schema, &config.url, &config.token).await?;
+    repair_local_timestamps(state).await?;
+    let conflicts = sync_conflict::finish(state.conn.clone(), pending).await.map_err(|e| e.to_string())?;
+    // Pulled items may be the targets of links written here
+    ItemRepository::new(state.conn.clone()).resolve_pending_links().await.map_err(|e| e.to_string())?;
+    if !conflicts.is_empty() {
+        let _ = rolling_logger::info(&format!("Sync found {} rows edited on both sides", conflicts.len()));
+    }
+    Ok(conflicts.len())
+}
+
+/// Run `repository::timestamp::repair_timestamps` on the open database
+async fn repair_local_timestamps(state: &tauri_sync_db_backend::DbState) -> Result<(), String> {
+    let guard = state.conn.lock().await;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+    let repaired = repair_timestamps(conn)?;
+    if repaired > 0 {
+        let _ = rolling_logger::info(&format!("Repaired {} timestamps in synced rows", repaired));
+    }
+    Ok(())
+}
+
+/// Point memos at assets by reference (see `asset_cmd::adopt_asset_references`)
+async fn adopt_asset_references(app_handle: &tauri::AppHandle, state: &crate::AppState) -> Result<usize, String> {
+    let app_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    super::asset_cmd::adopt_asset_references(state.db_state.conn.clone(), &app_dir).await
+}
+
+/// Copy asset files to and from the configured remote
+async fn perform_asset_sync(
+    app_handle: &tauri::AppHandle,
+    state: &crate::AppState,
+    config: &AssetSyncConfig,
+) -> Result<AssetSyncReport, String> {
+    let remote = config.remote().map_err(|e| e.to_string())?;
+    asset_sync::sync_assets(state.db_state.conn.clone(), &super::asset_cmd::assets_dir(app_handle)?, remote.as_ref())
+        .await
+        .map_err(|e| format!("资源同步失败: {}", e))
 }
 
 /// Get database path from app handle
@@ -51,17 +97,17 @@ fn get_db_path(app_handle: &tauri::AppHandle) -> PathBuf {
     app_dir.join("tag_all.db")
 }
 
-/// Configure cloud sync with Turso database (with data backup and migration)
+/// Configure cloud sync with Turso database, moving local data over in
+/// stages (see `cloud_migration`). The live database is replaced only after
+/// the staged copy is verified; the pre-migration snapshot is kept until
+/// `confirm_cloud_migration` or `restore_cloud_migration`.
 #[tauri::command]
 pub async fn configure_cloud_sync(
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, crate::AppState>,
     url: String,
     token: String,
-) -> Result<(), String> {
-    use crate::repository;
-    use std::time::Duration;
-    
+) -> Result<MigrationReport, String> {
     eprintln!("=== Cloud Sync Configuration Start ===");
     eprintln!("URL: {}, Token len: {}", url, token.len());
     
@@ -72,162 +118,186 @@ pub async fn configure_cloud_sync(
     }
     
     let db_path = get_db_path(&app_handle);
-    let backup_json_path = db_path.with_extension("db.backup.json");
-    let safety_backup_path = db_path.with_extension("db.safety_backup");
+    let snapshot_path = cloud_migration::snapshot_path(&db_path);
+    let staging_path = cloud_migration::staging_path(&db_path);
+    let previous_config = repo_get_sync_config(&db_path);
     
-    // === STEP 1: Backup existing data ===
-    eprintln!("[1/7] Backing up existing data...");
-    let backup_data = {
-        let conn_opt_guard = state.db_state.conn.lock().await;
-        if let Some(old_conn) = conn_opt_guard.as_ref() {
-            // Synchronous call - safe because it's just rusqlite on local thread buffer
-            match repository::db::backup_local_data(old_conn) {
-                Ok(backup) => {
-                    eprintln!("✓ Memory backup: {} items, {} tags, {} workspaces",
-                              backup.items.len(), backup.tags.len(), backup.workspaces.len());
-                    
-                    // Save to JSON file as extra insurance
-                    if let Ok(json_str) = serde_json::to_string(&backup) {
-                        let _ = std::fs::write(&backup_json_path, json_str);
-                        eprintln!("✓ JSON backup saved");
-                    }
-                    
-                    Some(backup)
-                }
-                Err(e) => {
-                    eprintln!("⚠ Backup failed: {}, continuing without backup", e);
-                    None
-                }
-            }
-        } else {
-            eprintln!("⚠ No existing connection, skipping backup");
-            None
-        }
-    };
+    // No background sync while databases are being swapped
+    state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner()).take();
+    // Held until the swap, so no write lands in the live database after the snapshot
+    let mut app_conn = state.db_state.conn.lock().await;
     
-    // === STEP 2: Close existing connections ===
-    eprintln!("[2/7] Closing existing connections...");
-    state.db_state.conn.lock().await.take(); // Manually take connection to drop it
+    // === STEP 1: Snapshot the live database (left untouched until the swap) ===
+    eprintln!("[1/4] Writing snapshot...");
+    let snapshot = match app_conn.as_ref() {
+        Some(conn) => cloud_migration::write_snapshot(conn, &snapshot_path).map_err(|e| e.to_string()),
+        None => Err("Database not initialized".to_string()),
+    };
+    if let Err(e) = snapshot {
+        drop(app_conn);
+        restart_sync_scheduler(&app_handle);
+        return Err(format!("快照失败: {}", e));
+    }
+    eprintln!("✓ Snapshot at {}", snapshot_path.display());
     
-    tokio::time::sleep(Duration::from_millis(200)).await;
-    eprintln!("✓ Connections closed");
+    // === STEP 2: Save configuration, then build and verify the cloud database ===
+    eprintln!("[2/4] Staging cloud database...");
+    cloud_migration::remove_db_files(&staging_path);
+    let staged = match repo_configure_sync(&db_path, url.clone(), token.clone()).await {
+        Ok(()) => stage_cloud_db(&staging_path, &snapshot_path).await,
+        Err(e) => Err(e),
+    };
+    let tables = match staged {
+        Ok(tables) => tables,
+        Err(e) => {
+            // === ROLLBACK: the live database was never touched ===
+            eprintln!("✗ Staging failed: {}", e);
+            drop(app_conn);
+            let rolled_back = roll_back_staging(&db_path, previous_config.as_ref()).await;
+            restart_sync_scheduler(&app_handle);
+            rolled_back?;
+            eprintln!("=== Rolled back, local database unchanged ===");
+            return Err(format!("云同步配置失败，本地数据库未改动: {}", e));
+        }
+    };
+    eprintln!("✓ {} tables verified", tables.len());
     
-    //=== STEP 3: Create safety backup of database file ===
-    eprintln!("[3/7] Creating safety backup...");
-    if db_path.exists() {
-        match std::fs::copy(&db_path, &safety_backup_path) {
-            Ok(_) => eprintln!("✓ Safety backup created"),
-            Err(e) => eprintln!("⚠ Safety backup failed: {}", e),
+    // === STEP 3: Swap the staged database in ===
+    eprintln!("[3/4] Swapping databases...");
+    // Close the live database before its file is replaced
+    app_conn.take();
+    let open = |path: PathBuf, live: LiveDb| {
+        let previous_config = previous_config.as_ref();
+        async move {
+            // The database from before goes back to its own configuration
+            if live == LiveDb::PreMigration {
+                restore_config(&path, previous_config).await.map_err(DomainError::Internal)?;
+            }
+            open_db(path).await
         }
+    };
+    let reopened = cloud_migration::swap_in_and_open(&staging_path, &db_path, open).await;
+    *app_conn = reopened.conn;
+    drop(app_conn);
+    if let Some(e) = reopened.error {
+        cloud_migration::remove_db_files(&staging_path);
+        restart_sync_scheduler(&app_handle);
+        return Err(format!("替换数据库失败，已恢复迁移前的数据库: {}", e));
     }
-
-    // === STEP 4: Save configuration ===
-    eprintln!("[4/7] Saving sync configuration...");
-    repo_configure_sync(&db_path, url.clone(), token.clone()).await?;
+    eprintln!("✓ Cloud database in place");
     
-    // === STEP 5: Delete old DB files to force fresh cloud sync ===
-    eprintln!("[5/7] Cleaning old database files...");
-    if db_path.exists() {
-        let _ = std::fs::remove_file(&db_path);
+    // === STEP 4: Initial sync (retried in the background if it fails) ===
+    eprintln!("[4/4] Triggering initial sync...");
+    let sync_error = perform_sync(&state.db_state).await.err();
+    if let Some(e) = &sync_error {
+        eprintln!("✗ Initial sync failed: {}", e);
     }
-    let _ = std::fs::remove_file(db_path.with_extension("db-wal"));
-    let _ = std::fs::remove_file(db_path.with_extension("db-shm"));
+    restart_sync_scheduler(&app_handle);
+    eprintln!("=== Cloud Sync Configuration Complete ===");
     
-    // Remove sync metadata
-    let sync_dir = db_path.parent().unwrap()
-        .join(format!("{}-sync", db_path.file_name().unwrap().to_str().unwrap()));
-    if sync_dir.exists() {
-        let _ = std::fs::remove_dir_all(&sync_dir);
+    Ok(MigrationReport {
+        snapshot: snapshot_path,
+        tables,
+        synced: sync_error.is_none(),
+        sync_error,
+    })
+}
+
+/// Create the cloud database at `staging_path`, copy the snapshot into it and
+/// check that every row arrived
+async fn stage_cloud_db(staging_path: &Path, snapshot_path: &Path) -> Result<Vec<TableCheck>, String> {
+    let staged = crate::repository::init_db(&staging_path.to_path_buf()).await?;
+    let guard = staged.conn.lock().await;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+    cloud_migration::copy_tables(conn, snapshot_path).map_err(|e| e.to_string())?;
+    let tables = cloud_migration::verify(conn, snapshot_path).map_err(|e| e.to_string())?;
+    let incomplete: Vec<String> = tables.iter()
+        .filter(|check| check.missing > 0)
+        .map(|check| format!("{} ({}/{} rows missing)", check.table, check.missing, check.source_rows))
+        .collect();
+    if !incomplete.is_empty() {
+        return Err(format!("Verification failed: {}", incomplete.join(", ")));
     }
-    
-    // === STEP 6: Initialize cloud database ===
-    eprintln!("[6/7] Initializing cloud database...");
-    match repository::init_db(&db_path).await {
-        Ok(new_db_state) => {
-            eprintln!("✓ Cloud database initialized");
-            
-            // Restore data if we have backup
-            if let Some(backup) = backup_data {
-                eprintln!("Migrating local data to cloud...");
-                let conn_guard = new_db_state.conn.lock().await;
-                if let Some(new_conn) = conn_guard.as_ref() {
-                     // Synchronous call
-                     match repository::db::restore_data(new_conn, backup) {
-                        Ok(_) => eprintln!("✓ Data migrated successfully"),
-                        Err(e) => eprintln!("⚠ Data migration failed: {}", e),
-                    }
-                } else {
-                    eprintln!("⚠ Failed to get connection for restore");
-                }
-            }
-            
-            // === STEP 7: Update application state ===
-            eprintln!("[7/7] Updating application state...");
-            
-            // Update DbState
-            {
-                let mut app_conn = state.db_state.conn.lock().await;
-                let new_conn_opt = new_db_state.conn.lock().await.take();
-                *app_conn = new_conn_opt;
-            }
-            
-            eprintln!("✓ Application state updated");
-            
-            // Trigger initial sync
-            eprintln!("Triggering initial sync...");
-            // Use local helper
-            match perform_sync(&state.db_state).await {
-                Ok(_) => {
-                    eprintln!("✓ Initial sync complete");
-                    
-                    // Clean up backup files on success
-                    let _ = std::fs::remove_file(&backup_json_path);
-                    let _ = std::fs::remove_file(&safety_backup_path);
-                    eprintln!("=== Cloud Sync Configuration Complete ===");
-                    
-                    Ok(())
-                }
-                Err(e) => {
-                    eprintln!("✗ Initial sync failed: {}", e);
-                    Err(format!("同步失败: {}", e))
-                }
-            }
+    Ok(tables)
+}
+
+/// Undo a migration that never replaced the live database: remove the staged
+/// files and snapshot, and put the previous sync configuration back
+async fn roll_back_staging(db_path: &PathBuf, previous_config: Option<&SyncConfig>) -> Result<(), String> {
+    cloud_migration::remove_db_files(&cloud_migration::staging_path(db_path));
+    let _ = cloud_migration::discard_snapshot(db_path);
+    restore_config(db_path, previous_config).await
+}
+
+/// Write `config` back, or remove the configuration (local mode) if `None`
+async fn restore_config(db_path: &PathBuf, config: Option<&SyncConfig>) -> Result<(), String> {
+    match config {
+        Some(config) => repo_configure_sync(db_path, config.url.clone(), config.token.clone()).await,
+        None => {
+            let _ = std::fs::remove_file(crate::repository::db::get_config_path(db_path));
+            Ok(())
         }
-        Err(e) => {
-            // === ROLLBACK: Cloud init failed ===
-            eprintln!("✗ Cloud database initialization failed: {}", e);
-            eprintln!("=== ROLLING BACK ===");
-            
-            // Restore safety backup
-            if safety_backup_path.exists() {
-                match std::fs::copy(&safety_backup_path, &db_path) {
-                    Ok(_) => eprintln!("✓ Database file restored from safety backup"),
-                    Err(e) => eprintln!("✗ Failed to restore database: {}", e),
-                }
-            }
-            
-            // Delete sync configuration
-            let config_path = db_path.parent().unwrap().join("sync_config.json");
-            let _ = std::fs::remove_file(&config_path);
-            eprintln!("✓ Sync configuration deleted");
-            
-            // Reinitialize local database
-            eprintln!("Reinitializing local database...");
-            match repository::init_db(&db_path).await {
-                Ok(local_state) => {
-                    // Restore connection
-                     let mut app_conn = state.db_state.conn.lock().await;
-                     let new_conn_opt = local_state.conn.lock().await.take();
-                     *app_conn = new_conn_opt;
-                    eprintln!("✓ Local database restored");
-                }
-                Err(e) => eprintln!("✗ Failed to reinit local database: {}", e),
-            }
-            
-            eprintln!("=== Rollback Complete ===");
-            Err(format!("云同步配置失败已回滚: {}", e))
+    }
+}
+
+/// Open the database at `path` (migrations included) as the app's connection
+async fn open_db(path: PathBuf) -> DomainResult<rusqlite::Connection> {
+    let db = crate::repository::init_db(&path).await.map_err(DomainError::Internal)?;
+    let conn = db.conn.lock().await.take();
+    conn.ok_or_else(|| DomainError::Internal("Database not initialized".to_string()))
+}
+
+/// Keep the cloud database: drop the pre-migration snapshot
+#[tauri::command]
+pub fn confirm_cloud_migration(app_handle: tauri::AppHandle) -> Result<(), String> {
+    cloud_migration::discard_snapshot(&get_db_path(&app_handle)).map_err(|e| e.to_string())
+}
+
+/// Whether a pre-migration snapshot is waiting for confirmation
+#[tauri::command]
+pub fn has_pending_cloud_migration(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    Ok(cloud_migration::snapshot_path(&get_db_path(&app_handle)).exists())
+}
+
+/// Go back to the pre-migration database and local mode
+#[tauri::command]
+pub async fn restore_cloud_migration(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app_handle);
+    if !cloud_migration::snapshot_path(&db_path).exists() {
+        return Err("No migration snapshot to restore".to_string());
+    }
+    let mut app_conn = state.db_state.conn.lock().await;
+    // Startup would otherwise put its own connection to the replaced file in place
+    if app_conn.is_none() {
+        return Err("Database not initialized".to_string());
+    }
+    state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner()).take();
+    app_conn.take();
+    let current_config = repo_get_sync_config(&db_path);
+    let open = |path: PathBuf, live: LiveDb| {
+        // The snapshot opens in local mode, the cloud database as configured
+        let config = match live {
+            LiveDb::PreMigration => None,
+            LiveDb::Migrated => current_config.as_ref(),
+        };
+        async move {
+            restore_config(&path, config).await.map_err(DomainError::Internal)?;
+            open_db(path).await
         }
+    };
+    let reopened = cloud_migration::restore_and_open(&db_path, open).await;
+    *app_conn = reopened.conn;
+    drop(app_conn);
+    if let Some(e) = reopened.error {
+        restart_sync_scheduler(&app_handle);
+        return Err(e.to_string());
     }
+    super::asset_cmd::refresh_asset_scope(&state).await?;
+    let _ = rolling_logger::info("Restored the database from before cloud sync was configured");
+    Ok(())
 }
 
 /// Get current cloud sync configuration
@@ -252,16 +322,154 @@ pub async fn save_cloud_sync_config(
             .map_err(|e| format!("验证连接失败: {}", e))?;
     }
 
+    let disabled = url.is_empty() || token.is_empty();
     let db_path = get_db_path(&app_handle);
-    repo_configure_sync(&db_path, url, token).await
+    repo_configure_sync(&db_path, url, token).await?;
+    // Saving never starts a sync, but clearing the config stops background sync
+    if disabled {
+        restart_sync_scheduler(&app_handle);
+    }
+    Ok(())
 }
 
-/// Manually trigger cloud database sync
+/// Manually trigger cloud database sync (followed by asset sync, if configured)
 #[tauri::command]
 pub async fn sync_cloud_db(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, crate::AppState>,
 ) -> Result<(), String> {
-    perform_sync(&state.db_state).await
+    run_full_sync(&app_handle).await?;
+    // The next background pull is a full interval away
+    if let Some(scheduler) = state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        scheduler.synced();
+    }
+    Ok(())
+}
+
+/// Database sync, then asset sync if configured (manual and background syncs)
+async fn run_full_sync(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<crate::AppState>();
+    let asset_config = asset_sync::load_config(&get_db_path(app_handle));
+    if asset_config.is_some() {
+        // Rewrite memos to asset references before the database sync so other devices get them
+        adopt_asset_references(app_handle, &state).await?;
+    }
+    let conflicts = perform_sync(&state.db_state).await?;
+    if conflicts > 0 {
+        let _ = app_handle.emit(SYNC_CONFLICTS_EVENT, conflicts);
+    }
+    // Synced workspace dirs change what the asset protocol serves
+    super::asset_cmd::refresh_asset_scope(&state).await?;
+
+    let Some(asset_config) = asset_config else {
+        return Ok(());
+    };
+
+    let report = perform_asset_sync(app_handle, &state, &asset_config).await?;
+    eprintln!("✓ Assets synced: {} up, {} down, {} missing", report.uploaded, report.downloaded, report.missing.len());
+    Ok(())
+}
+
+/// The app database as seen by the sync scheduler
+struct AppSyncTarget {
+    app_handle: tauri::AppHandle,
+}
+
+#[async_trait]
+impl SyncTarget for AppSyncTarget {
+    async fn change_count(&self) -> u64 {
+        let state = self.app_handle.state::<crate::AppState>();
+        let guard = state.db_state.conn.lock().await;
+        guard.as_ref().map_or(0, |conn| conn.total_changes())
+    }
+
+    async fn sync(&self) -> Result<u64, String> {
+        let started = Timestamp::now();
+        let before = self.change_count().await;
+        run_full_sync(&self.app_handle).await?;
+        let after = self.change_count().await;
+        // Rows stamped after the sync started were edited here meanwhile (a
+        // pulled row rarely is): then none of the writes count as the sync's
+        // own, so the edits are pushed after a debounce
+        let state = self.app_handle.state::<crate::AppState>();
+        let guard = state.db_state.conn.lock().await;
+        let conn = guard.as_ref().ok_or("Database not initialized")?;
+        let edited = edited_since(conn, started).map_err(|e| e.to_string())?;
+        Ok(if edited { 0 } else { after.saturating_sub(before) })
+    }
+}
+
+/// Whether any synced row was written at or after `since`
+fn edited_since(conn: &rusqlite::Connection, since: Timestamp) -> rusqlite::Result<bool> {
+    for table in SYNCED_TABLES {
+        let edited: bool = conn.query_row(
+            &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE updated_at >= ?1)", table),
+            [since],
+            |row| row.get(0),
+        )?;
+        if edited {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// (Re)start background sync with the current configuration; stopped while
+/// cloud sync is not configured
+pub(crate) fn restart_sync_scheduler(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<crate::AppState>();
+    let mut scheduler = state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner());
+    *scheduler = None;
+
+    let configured = repo_get_sync_config(&state.db_path)
+        .is_some_and(|config| !config.url.is_empty() && !config.token.is_empty());
+    if !configured {
+        return;
+    }
+    let emitter = app_handle.clone();
+    let notify: StatusNotify = Arc::new(move |status: &SchedulerStatus| {
+        if let SyncState::Error { message } = &status.state {
+            let _ = rolling_logger::error(&format!("Background sync failed: {}", message));
+        }
+        let _ = emitter.emit(SYNC_STATUS_EVENT, status);
+    });
+    let target = Arc::new(AppSyncTarget { app_handle: app_handle.clone() });
+    *scheduler = Some(SyncScheduler::start(target, SchedulerConfig::default(), notify));
+}
+
+/// Get the asset sync configuration
+#[tauri::command]
+pub fn get_asset_sync_config(
+    app_handle: tauri::AppHandle,
+) -> Result<Option<AssetSyncConfig>, String> {
+    Ok(asset_sync::load_config(&get_db_path(&app_handle)))
+}
+
+/// Save (or with `None`, remove) the asset sync configuration
+#[tauri::command]
+pub async fn save_asset_sync_config(
+    app_handle: tauri::AppHandle,
+    config: Option<AssetSyncConfig>,
+) -> Result<(), String> {
+    // Validate the remote before saving
+    if let Some(config) = &config {
+        config.remote().map_err(|e| e.to_string())?
+            .list().await
+            .map_err(|e| format!("验证连接失败: {}", e))?;
+    }
+
+    asset_sync::save_config(&get_db_path(&app_handle), config.as_ref()).map_err(|e| e.to_string())
+}
+
+/// Sync asset files only (without the database)
+#[tauri::command]
+pub async fn sync_assets(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+) -> Result<AssetSyncReport, String> {
+    let config = asset_sync::load_config(&get_db_path(&app_handle)).ok_or("Asset sync not configured")?;
+    adopt_asset_references(&app_handle, &state).await?;
+    perform_asset_sync(&app_handle, &state, &config).await
 }
 
 /// Check if cloud sync is currently enabled for this session
@@ -285,9 +493,10 @@ pub async fn configure_sync(
 /// Alias for sync_cloud_db (for compatibility with SyncButton)
 #[tauri::command]
 pub async fn sync_database(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, crate::AppState>,
 ) -> Result<(), String> {
-    sync_cloud_db(state).await
+    sync_cloud_db(app_handle, state).await
 }
 
 /// Alias for get_cloud_sync_config (for compatibility with SyncSettingsForm)
@@ -302,9 +511,12 @@ pub fn get_sync_config(
 pub struct AppSyncStatus {
     last_sync_time: Option<String>,
     sync_count: i32,
+    /// Background sync state (idle while it is not running)
+    #[serde(flatten)]
+    scheduler: SchedulerStatus,
 }
 
-/// Get sync status (latest sync time across all tables)
+/// Get sync status (latest sync time across all tables, background sync state)
 #[tauri::command]
 pub async fn get_sync_status(
     state: tauri::State<'_, crate::AppState>,
@@ -328,8 +540,13 @@ pub async fn get_sync_status(
         |row| row.get(0)
     ).unwrap_or(0);
     
+    let scheduler = state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(SyncScheduler::status)
+        .unwrap_or_default();
     Ok(AppSyncStatus {
         last_sync_time: last_sync,
         sync_count: total_count,
+        scheduler,
     })
 }
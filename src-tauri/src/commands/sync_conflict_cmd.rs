@@ -0,0 +1,83 @@
+//! Sync Conflict Commands
+//!
+//! List rows edited on two devices between syncs (see `sync_conflict`) and
+//! settle them.
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::domain::{extract_asset_refs, ConflictResolution, SyncConflict, TextMerge, ASSET_OWNER_ITEM, ASSET_OWNER_TAG};
+use crate::repository::item::ItemLinkOperations;
+use crate::repository::tag::InlineTagOperations;
+use crate::repository::{AssetRepository, ItemRepository, Repository, SyncConflictRepository, TagRepository};
+use crate::AppState;
+
+/// A conflict with the automatic merge of its memos
+#[derive(Debug, Serialize)]
+pub struct ConflictView {
+    #[serde(flatten)]
+    pub conflict: SyncConflict,
+    /// Three-way merge of both memos; None if neither version has one
+    pub merged_memo: Option<TextMerge>,
+}
+
+/// Unresolved sync conflicts, newest first
+#[tauri::command]
+pub async fn list_conflicts(state: State<'_, AppState>) -> Result<Vec<ConflictView>, String> {
+    let conflicts = SyncConflictRepository::new(state.db_state.conn.clone()).list().await
+        .map_err(|e| e.to_string())?;
+    Ok(conflicts.into_iter()
+        .map(|conflict| ConflictView { merged_memo: conflict.merged_memo(), conflict })
+        .collect())
+}
+
+/// Settle a conflict with `keep_local`, `keep_remote` or `merged`. For
+/// `merged`, `memo` is the merged memo as edited by the user; without it the
+/// automatic merge is used (conflicting lines between markers).
+#[tauri::command]
+pub async fn resolve_conflict(
+    state: State<'_, AppState>,
+    id: u32,
+    resolution: ConflictResolution,
+    memo: Option<String>,
+) -> Result<(), String> {
+    let conn = state.db_state.conn.clone();
+    let repo = SyncConflictRepository::new(conn.clone());
+    let conflict = repo.find(id).await.map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Conflict {} not found", id))?;
+    repo.resolve(id, resolution, memo).await.map_err(|e| e.to_string())?;
+
+    // The row was written directly: refresh what is derived from its memo
+    let Some(row_id) = conflict.row_id() else {
+        return Ok(());
+    };
+    match conflict.table_name.as_str() {
+        "items" => {
+            let items = ItemRepository::new(conn.clone());
+            let Some(item) = items.find_by_id(row_id).await.map_err(|e| e.to_string())? else {
+                return Ok(());
+            };
+            items.update_links(item.id, item.memo.as_deref()).await.map_err(|e| e.to_string())?;
+            let text = [item.memo.as_deref(), item.summary.as_deref()].into_iter().flatten().collect::<Vec<_>>().join("\n");
+            AssetRepository::new(conn.clone())
+                .set_refs(ASSET_OWNER_ITEM, item.id, &extract_asset_refs(&text))
+                .await
+                .map_err(|e| e.to_string())?;
+            TagRepository::new(conn)
+                .sync_inline_tags(item.id, &item.text, item.memo.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        "tags" => {
+            let Some(tag) = TagRepository::new(conn.clone()).find_by_id(row_id).await.map_err(|e| e.to_string())? else {
+                return Ok(());
+            };
+            AssetRepository::new(conn)
+                .set_refs(ASSET_OWNER_TAG, tag.id, &extract_asset_refs(tag.memo.as_deref().unwrap_or_default()))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -2,14 +2,20 @@
 //!
 //! Exposes Tag CRUD and item-tag relationships to the frontend.
 
-use crate::repository::TagRepository;
+use crate::repository::{TagRepository, SettingsRepository, AssetRepository};
+use crate::repository::settings_repo::TAG_PALETTE_KEY;
 use tauri::State;
-use crate::domain::Tag;
+use crate::domain::{Tag, TagAlias, TagGraphReport, TagStats, DEFAULT_TAG_PALETTE, extract_asset_refs, ASSET_OWNER_TAG};
 use crate::repository::Repository;
-use crate::repository::tag::{ItemTagOperations, TagHierarchyOperations, TagPositioningOperations};
+use crate::repository::tag::{ItemTagOperations, TagHierarchyOperations, TagPositioningOperations, TagAliasOperations, TagStatsOperations, TagColorOperations};
 use crate::AppState;
 
 /// Create a new tag
+///
+/// If the name matches an existing tag (case-insensitive) or one of its aliases,
+/// the canonical tag is returned instead of creating a duplicate.
+/// Without an explicit colour the least-used palette colour is assigned; it is
+/// dropped once the tag is nested under a coloured tag, so children inherit.
 #[tauri::command]
 pub async fn create_tag(
     state: State<'_, AppState>,
@@ -18,13 +24,15 @@ pub async fn create_tag(
 ) -> Result<Tag, String> {
     let repo = TagRepository::new(state.db_state.conn.clone());
     
-    let tag = if let Some(c) = color {
-        Tag::with_color(0, name, c)
-    } else {
-        Tag::new(0, name)
-    };
+    if let Some(existing) = repo.resolve_tag_name(&name).await.map_err(|e| e.to_string())? {
+        return Ok(existing);
+    }
     
-    repo.create(&tag).await.map_err(|e| e.to_string())
+    match color {
+        Some(c) => repo.create(&Tag::with_color(0, name, c)).await,
+        None => repo.create_with_palette_color(&name).await,
+    }
+    .map_err(|e| e.to_string())
 }
 
 /// List all tags
@@ -48,22 +56,92 @@ pub async fn update_tag(
     id: u32,
     name: Option<String>,
     color: Option<String>,
+    icon: Option<String>,
+    description: Option<String>,
+    pinned: Option<bool>,
 ) -> Result<Tag, String> {
     let repo = TagRepository::new(state.db_state.conn.clone());
     
     let existing = repo.find_by_id(id).await.map_err(|e| e.to_string())?
         .ok_or_else(|| format!("Tag {} not found", id))?;
     
+    // Empty strings clear color/icon/description (a cleared colour is inherited again)
     let updated = Tag {
         id: existing.id,
         name: name.unwrap_or(existing.name),
-        color: color.or(existing.color),
+        color: match color {
+            Some(c) => Some(c).filter(|s| !s.trim().is_empty()),
+            None => existing.color,
+        },
         position: existing.position,
+        memo: existing.memo,
+        icon: match icon {
+            Some(i) => Some(i).filter(|s| !s.trim().is_empty()),
+            None => existing.icon,
+        },
+        description: match description {
+            Some(d) => Some(d).filter(|s| !s.trim().is_empty()),
+            None => existing.description,
+        },
+        pinned: pinned.unwrap_or(existing.pinned),
+        effective_color: None,
     };
     
     repo.update(&updated).await.map_err(|e| e.to_string())
 }
 
+/// Update tag memo (Markdown notes documenting the tag)
+#[tauri::command]
+pub async fn update_tag_memo(
+    state: State<'_, AppState>,
+    id: u32,
+    memo: Option<String>,
+) -> Result<Tag, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    
+    let mut tag = repo.find_by_id(id).await.map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Tag {} not found", id))?;
+    tag.memo = memo;
+    
+    let saved = repo.update(&tag).await.map_err(|e| e.to_string())?;
+    
+    // Track assets the memo refers to
+    let refs = extract_asset_refs(saved.memo.as_deref().unwrap_or_default());
+    AssetRepository::new(state.db_state.conn.clone())
+        .set_refs(ASSET_OWNER_TAG, saved.id, &refs)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(saved)
+}
+
+/// Get the effective colour of a tag (own or inherited from its first parent)
+#[tauri::command]
+pub async fn get_tag_effective_color(state: State<'_, AppState>, id: u32) -> Result<Option<String>, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.effective_color(id).await.map_err(|e| e.to_string())
+}
+
+/// Get the palette used for automatic tag colours (default palette if unset)
+#[tauri::command]
+pub async fn get_tag_palette(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let settings = SettingsRepository::new(state.db_state.conn.clone());
+    let palette: Option<Vec<String>> = settings.get(TAG_PALETTE_KEY).await.map_err(|e| e.to_string())?;
+    Ok(palette.unwrap_or_else(|| DEFAULT_TAG_PALETTE.iter().map(|c| c.to_string()).collect()))
+}
+
+/// Set the palette used for automatic root tag colours (empty = no automatic
+/// colour; child tags inherit from their parents either way)
+#[tauri::command]
+pub async fn set_tag_palette(state: State<'_, AppState>, palette: Vec<String>) -> Result<(), String> {
+    let settings = SettingsRepository::new(state.db_state.conn.clone());
+    let palette: Vec<String> = palette.into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    settings.set(TAG_PALETTE_KEY, &palette).await.map_err(|e| e.to_string())
+}
+
 /// Delete tag
 #[tauri::command]
 pub async fn delete_tag(state: State<'_, AppState>, id: u32) -> Result<(), String> {
@@ -143,6 +221,23 @@ pub async fn remove_tag_parent(
     repo.remove_parent_tag(child_tag_id, parent_tag_id).await.map_err(|e| e.to_string())
 }
 
+/// Check the tag graph for cycles and dangling edges, optionally repairing them
+#[tauri::command]
+pub async fn check_tag_graph(
+    state: State<'_, AppState>,
+    repair: Option<bool>,
+) -> Result<TagGraphReport, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.check_tag_graph(repair.unwrap_or(false)).await.map_err(|e| e.to_string())
+}
+
+/// Usage statistics for all tags (direct/descendant counts, last applied, per workspace)
+#[tauri::command]
+pub async fn tag_stats(state: State<'_, AppState>) -> Result<Vec<TagStats>, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.tag_stats().await.map_err(|e| e.to_string())
+}
+
 /// Get all parent tags for a given tag
 #[tauri::command]
 pub async fn get_tag_parents(
@@ -192,3 +287,66 @@ pub async fn move_child_tag(
     let repo = TagRepository::new(state.db_state.conn.clone());
     repo.move_child_tag(child_tag_id, parent_tag_id, position).await.map_err(|e| e.to_string())
 }
+
+// ========================
+// Tag Aliases + Merge
+// ========================
+
+/// Resolve a tag name or alias to its canonical tag
+#[tauri::command]
+pub async fn resolve_tag(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<Option<Tag>, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.resolve_tag_name(&name).await.map_err(|e| e.to_string())
+}
+
+/// Add an alias for a tag
+#[tauri::command]
+pub async fn add_tag_alias(
+    state: State<'_, AppState>,
+    tag_id: u32,
+    alias: String,
+) -> Result<TagAlias, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.add_alias(tag_id, &alias).await.map_err(|e| e.to_string())
+}
+
+/// Remove a tag alias
+#[tauri::command]
+pub async fn remove_tag_alias(
+    state: State<'_, AppState>,
+    alias: String,
+) -> Result<(), String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.remove_alias(&alias).await.map_err(|e| e.to_string())
+}
+
+/// Get aliases of a tag
+#[tauri::command]
+pub async fn get_tag_aliases(
+    state: State<'_, AppState>,
+    tag_id: u32,
+) -> Result<Vec<TagAlias>, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.get_aliases(tag_id).await.map_err(|e| e.to_string())
+}
+
+/// List all tag aliases (for autocomplete)
+#[tauri::command]
+pub async fn list_tag_aliases(state: State<'_, AppState>) -> Result<Vec<TagAlias>, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.list_aliases().await.map_err(|e| e.to_string())
+}
+
+/// Merge source tag into target tag (source becomes an alias of target)
+#[tauri::command]
+pub async fn merge_tags(
+    state: State<'_, AppState>,
+    source: u32,
+    target: u32,
+) -> Result<Tag, String> {
+    let repo = TagRepository::new(state.db_state.conn.clone());
+    repo.merge_tags(source, target).await.map_err(|e| e.to_string())
+}
@@ -31,7 +31,8 @@ pub async fn delete_workspace(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let repo = WorkspaceRepository::new(state.db_state.conn.clone());
-    repo.delete(id).await.map_err(|e| e.to_string())
+    repo.delete(id).await.map_err(|e| e.to_string())?;
+    super::asset_cmd::refresh_asset_scope(&state).await
 }
 
 #[tauri::command]
@@ -59,8 +60,14 @@ pub async fn add_workspace_path(
     path: String,
     state: State<'_, AppState>,
 ) -> Result<crate::domain::WorkspaceDir, String> {
+    // Files in mounted directories may be shown in memos, so they are held
+    // to the same limits as allowed asset paths
+    let home_dir = std::env::home_dir();
+    crate::asset_protocol::allowable_path(std::path::Path::new(&path), home_dir.as_deref()).map_err(|e| e.to_string())?;
     let repo = WorkspaceRepository::new(state.db_state.conn.clone());
-    repo.add_path(workspace_id, &path).await.map_err(|e| e.to_string())
+    let dir = repo.add_path(workspace_id, &path).await.map_err(|e| e.to_string())?;
+    super::asset_cmd::refresh_asset_scope(&state).await?;
+    Ok(dir)
 }
 
 #[tauri::command]
@@ -69,7 +76,8 @@ pub async fn remove_workspace_path(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let repo = WorkspaceRepository::new(state.db_state.conn.clone());
-    repo.remove_path(id).await.map_err(|e| e.to_string())
+    repo.remove_path(id).await.map_err(|e| e.to_string())?;
+    super::asset_cmd::refresh_asset_scope(&state).await
 }
 
 #[tauri::command]
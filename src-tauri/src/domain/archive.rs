@@ -0,0 +1,57 @@
+//! Page Archive
+//!
+//! A readable offline copy of a bookmarked page. The sanitised reader HTML is
+//! stored as a content-addressed asset (`assets/{hash}.html`) and shows the
+//! page's images through their own `assets/{hash}.{ext}` references, so the
+//! archive syncs like any other media. The plain text is kept with the record
+//! for full-text search. An item has at most one archive; archiving the page
+//! again replaces it.
+
+use serde::{Deserialize, Serialize};
+
+use super::asset::asset_reference;
+use super::timestamp::Timestamp;
+
+/// Owner kind in `asset_refs` for the files of an archive (owner id = item id)
+pub const ASSET_OWNER_ARCHIVE: &str = "archive";
+
+/// Extension of the stored reader HTML
+pub const ARCHIVE_HTML_EXT: &str = "html";
+
+/// The archived copy of an item's page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archive {
+    pub item_id: u32,
+    /// The page that was archived
+    pub url: String,
+    pub title: String,
+    /// Asset holding the reader HTML
+    pub html_hash: String,
+    /// Plain text of the reader HTML
+    pub text: String,
+    /// Assets of the images the HTML shows
+    pub images: Vec<String>,
+    pub created_at: Timestamp,
+}
+
+impl Archive {
+    /// Reference of the reader HTML (`assets/{hash}.html`)
+    pub fn html_reference(&self) -> String {
+        asset_reference(&format!("{}.{}", self.html_hash, ARCHIVE_HTML_EXT))
+    }
+
+    /// Every asset the archive needs: the HTML and its images
+    pub fn asset_hashes(&self) -> Vec<String> {
+        std::iter::once(self.html_hash.clone()).chain(self.images.iter().cloned()).collect()
+    }
+}
+
+/// A full-text search hit in archived pages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveMatch {
+    pub item_id: u32,
+    pub title: String,
+    pub url: String,
+    /// Text around the match, matched terms wrapped in `[` `]`
+    pub snippet: String,
+}
@@ -0,0 +1,321 @@
+//! Asset Entity
+//!
+//! Media pasted or dropped into memos (images, PDFs, audio, video). Assets are
+//! stored content-addressed as `assets/{blake3}.{ext}` in the app data dir, so
+//! the same file pasted twice is stored once. References from item memos,
+//! item summaries and tag memos are tracked in `asset_refs`.
+//!
+//! Memos refer to assets by the device-independent reference
+//! `assets/{blake3}.{ext}`, which each device resolves against its own app
+//! data dir, so the text stays valid after syncing.
+
+use serde::{Deserialize, Serialize};
+
+use super::timestamp::Timestamp;
+
+/// Directory (under the app data dir) holding content-addressed assets
+pub const ASSETS_DIR: &str = "assets";
+/// Directory used by older versions for timestamp-named clipboard images
+pub const LEGACY_CLIPBOARD_DIR: &str = "clipboard_images";
+
+/// Owner kinds in `asset_refs`: an item (memo + summary) or a tag (memo)
+pub const ASSET_OWNER_ITEM: &str = "item";
+pub const ASSET_OWNER_TAG: &str = "tag";
+
+/// A stored asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Asset {
+    /// blake3 content hash (hex)
+    pub hash: String,
+    /// File extension matching the detected type (without dot)
+    pub ext: String,
+    pub mime: String,
+    pub size: u64,
+    /// File name the asset was dropped/pasted with, if known
+    pub original_name: Option<String>,
+    pub created_at: Timestamp,
+}
+
+impl Asset {
+    /// Name of the stored file inside `ASSETS_DIR`
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.hash, self.ext)
+    }
+
+    /// Device-independent reference used in memos
+    pub fn reference(&self) -> String {
+        asset_reference(&self.file_name())
+    }
+}
+
+/// `assets/{file name}`
+pub fn asset_reference(file_name: &str) -> String {
+    format!("{}/{}", ASSETS_DIR, file_name)
+}
+
+/// Content hash and extension of a stored asset's file name (`{64 hex}.{ext}`), lower-cased
+pub fn parse_asset_file_name(file_name: &str) -> Option<(String, String)> {
+    let (hash, ext) = file_name.split_once('.')?;
+    let valid = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        && !ext.is_empty() && ext.len() <= 10 && ext.bytes().all(|b| b.is_ascii_alphanumeric());
+    valid.then(|| (hash.to_ascii_lowercase(), ext.to_ascii_lowercase()))
+}
+
+/// A reference from an item or tag to an asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetRef {
+    pub asset_hash: String,
+    /// `ASSET_OWNER_ITEM` or `ASSET_OWNER_TAG`
+    pub owner_type: String,
+    pub owner_id: u32,
+}
+
+/// Detect the type of a file from its leading bytes: `(mime, extension)`.
+/// Returns `None` for unrecognised content (callers fall back to the file name).
+pub fn sniff_mime(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        return Some(("image/png", "png"));
+    }
+    if starts(b"\xFF\xD8\xFF") {
+        return Some(("image/jpeg", "jpg"));
+    }
+    if starts(b"GIF87a") || starts(b"GIF89a") {
+        return Some(("image/gif", "gif"));
+    }
+    if starts(b"RIFF") {
+        if at(8, b"WEBP") {
+            return Some(("image/webp", "webp"));
+        }
+        if at(8, b"WAVE") {
+            return Some(("audio/wav", "wav"));
+        }
+        if at(8, b"AVI ") {
+            return Some(("video/x-msvideo", "avi"));
+        }
+    }
+    if starts(b"%PDF-") {
+        return Some(("application/pdf", "pdf"));
+    }
+    if starts(b"ID3") || starts(b"\xFF\xFB") || starts(b"\xFF\xF3") || starts(b"\xFF\xF2") {
+        return Some(("audio/mpeg", "mp3"));
+    }
+    if starts(b"OggS") {
+        return Some(("audio/ogg", "ogg"));
+    }
+    if starts(b"fLaC") {
+        return Some(("audio/flac", "flac"));
+    }
+    if at(4, b"ftyp") {
+        return Some(match bytes.get(8..12) {
+            Some(b"M4A ") => ("audio/mp4", "m4a"),
+            Some(b"qt  ") => ("video/quicktime", "mov"),
+            Some(b"avif") => ("image/avif", "avif"),
+            Some(b"heic") | Some(b"heix") | Some(b"mif1") => ("image/heic", "heic"),
+            _ => ("video/mp4", "mp4"),
+        });
+    }
+    if starts(b"\x1A\x45\xDF\xA3") {
+        let head = &bytes[..bytes.len().min(64)];
+        return Some(if head.windows(4).any(|w| w == b"webm") {
+            ("video/webm", "webm")
+        } else {
+            ("video/x-matroska", "mkv")
+        });
+    }
+    if starts(b"PK\x03\x04") {
+        return Some(("application/zip", "zip"));
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return Some(("image/svg+xml", "svg"));
+    }
+    None
+}
+
+/// Lower-cased extension of a file name if it is a plausible one (short, alphanumeric)
+pub fn safe_extension(file_name: &str) -> Option<String> {
+    let (stem, ext) = file_name.rsplit_once('.')?;
+    let valid = !stem.is_empty() && !ext.is_empty() && ext.len() <= 10
+        && ext.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then(|| ext.to_ascii_lowercase())
+}
+
+/// Hashes of the assets referenced in a text (memo / summary), without duplicates.
+///
+/// Matches any `assets/{hash}.` path, whether written as a file path (either
+/// separator) or as a percent-encoded asset:// URL.
+pub fn extract_asset_refs(text: &str) -> Vec<String> {
+    let mut hashes: Vec<String> = Vec::new();
+    let lower = text.to_ascii_lowercase();
+
+    for (pos, _) in lower.match_indices(ASSETS_DIR) {
+        let rest = &lower[pos + ASSETS_DIR.len()..];
+        let Some(rest) = ["/", "\\", "%2f", "%5c"].iter().find_map(|sep| rest.strip_prefix(sep)) else {
+            continue;
+        };
+        let hex_len = rest.bytes().take_while(u8::is_ascii_hexdigit).count();
+        if hex_len == 64 && rest[64..].starts_with('.') {
+            let hash = rest[..64].to_string();
+            if !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+    }
+    hashes
+}
+
+const PATH_SEPARATORS: [&str; 4] = ["/", "\\", "%2f", "%5c"];
+
+/// Rewrite local paths into `dir_name` (a lower-case directory name) found in
+/// a text: plain file paths with either separator, or asset:// URLs.
+///
+/// `replace` gets the file name inside the directory and returns the new text
+/// for the whole path, or `None` to leave it. Relative paths and web URLs are
+/// never touched.
+pub fn rewrite_dir_paths(text: &str, dir_name: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    // ASCII lower-casing keeps byte offsets
+    let lower = text.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut search = 0;
+
+    while let Some(found) = lower[search..].find(dir_name) {
+        let pos = search + found;
+        search = pos + dir_name.len();
+
+        let Some(sep) = PATH_SEPARATORS.iter().find(|sep| lower[search..].starts_with(*sep)) else { continue; };
+        let name_start = search + sep.len();
+        let name_len = text[name_start..]
+            .find(|c: char| c.is_whitespace() || matches!(c, ')' | '>' | '"' | '\'' | ']' | '?' | '#'))
+            .unwrap_or(text.len() - name_start);
+        let start = path_start(text, pos);
+        let prefix = &lower[start..pos];
+        let is_dir = PATH_SEPARATORS.iter().any(|sep| prefix.ends_with(sep));
+        if name_len == 0 || start < copied || !is_dir || !is_local_prefix(prefix) {
+            continue;
+        }
+
+        let name_end = name_start + name_len;
+        if let Some(replacement) = replace(&text[name_start..name_end]) {
+            out.push_str(&text[copied..start]);
+            out.push_str(&replacement);
+            copied = name_end;
+        }
+        search = name_end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Start of the path that contains byte offset `pos`
+fn path_start(text: &str, pos: usize) -> usize {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let before = &text[line_start..pos];
+    // Inside a `<...>` link destination the path may contain spaces
+    if let Some(open) = before.rfind('<').filter(|open| !before[*open..].contains('>')) {
+        return line_start + open + 1;
+    }
+    line_start + before.char_indices().rev()
+        .find(|(_, c)| c.is_whitespace() || matches!(c, '(' | '"' | '\'' | '['))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// An absolute file path (Unix or Windows drive) or an asset-protocol URL
+fn is_local_prefix(lower: &str) -> bool {
+    let bytes = lower.as_bytes();
+    (lower.starts_with('/') && !lower.starts_with("//"))
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        || ["asset://localhost/", "http://asset.localhost/", "https://asset.localhost/"].iter().any(|p| lower.starts_with(p))
+}
+
+/// Replace absolute paths and asset:// URLs of stored assets with their
+/// device-independent `assets/{hash}.{ext}` reference
+pub fn to_asset_references(text: &str) -> String {
+    rewrite_dir_paths(text, ASSETS_DIR, |name| {
+        parse_asset_file_name(name).map(|(hash, ext)| asset_reference(&format!("{}.{}", hash, ext)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n...."), Some(("image/png", "png")));
+        assert_eq!(sniff_mime(b"%PDF-1.7\n"), Some(("application/pdf", "pdf")));
+        assert_eq!(sniff_mime(b"ID3\x04\x00"), Some(("audio/mpeg", "mp3")));
+        assert_eq!(sniff_mime(b"RIFF\x00\x00\x00\x00WAVEfmt "), Some(("audio/wav", "wav")));
+        assert_eq!(sniff_mime(b"\x00\x00\x00\x18ftypisom"), Some(("video/mp4", "mp4")));
+        assert_eq!(sniff_mime(b"\x00\x00\x00\x18ftypM4A "), Some(("audio/mp4", "m4a")));
+        assert_eq!(sniff_mime(b"\x1A\x45\xDF\xA3\x9f\x42\x82\x84webm"), Some(("video/webm", "webm")));
+        assert_eq!(sniff_mime(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"\">"), Some(("image/svg+xml", "svg")));
+        assert_eq!(sniff_mime(b"plain text"), None);
+        assert_eq!(sniff_mime(b""), None);
+    }
+
+    #[test]
+    fn test_safe_extension() {
+        assert_eq!(safe_extension("Report.PDF"), Some("pdf".to_string()));
+        assert_eq!(safe_extension("archive.tar.gz"), Some("gz".to_string()));
+        assert_eq!(safe_extension(".bashrc"), None);
+        assert_eq!(safe_extension("noext"), None);
+        assert_eq!(safe_extension("a.b c"), None);
+    }
+
+    #[test]
+    fn test_extract_asset_refs() {
+        let hash = "ab".repeat(32);
+        let other = "0f".repeat(32);
+        let memo = format!(
+            "![](C:\\Users\\me\\AppData\\com.tag-all.app\\assets\\{hash}.png)\n\
+             [doc](/home/me/.local/share/com.tag-all.app/assets/{other}.pdf)\n\
+             ![again](asset://localhost/%2Fhome%2Fme%2Fassets%2F{upper}.png)\n\
+             assets/{short}.png and assets/{hash}png",
+            hash = hash,
+            other = other,
+            upper = hash.to_uppercase(),
+            short = &hash[..10],
+        );
+        assert_eq!(extract_asset_refs(&memo), vec![hash, other]);
+    }
+
+    #[test]
+    fn test_to_asset_references() {
+        let hash = "ab".repeat(32);
+        let memo = format!(
+            "![](C:\\Users\\me\\AppData\\com.tag-all.app\\assets\\{hash}.PNG)\n\
+             ![a b](</home/me/My Data/assets/{hash}.png>)\n\
+             ![url](http://asset.localhost/%2Fhome%2Fme%2Fassets%2F{upper}.png \"title\")\n\
+             ![kept](assets/{hash}.png) [web](https://example.com/assets/{hash}.png) /tmp/assets/readme.md",
+            hash = hash,
+            upper = hash.to_uppercase(),
+        );
+        let reference = format!("assets/{}.png", hash);
+        assert_eq!(
+            to_asset_references(&memo),
+            format!(
+                "![]({r})\n![a b](<{r}>)\n![url]({r} \"title\")\n\
+                 ![kept]({r}) [web](https://example.com/assets/{h}.png) /tmp/assets/readme.md",
+                r = reference,
+                h = hash,
+            )
+        );
+    }
+
+    #[test]
+    fn test_rewrite_dir_paths_legacy_images() {
+        let memo = "截图：![](D:/data/clipboard_images/clipboard_1.png)　![](/x/clipboard_images/clipboard_2.png)";
+        let mut seen = Vec::new();
+        let rewritten = rewrite_dir_paths(memo, LEGACY_CLIPBOARD_DIR, |name| {
+            seen.push(name.to_string());
+            (name == "clipboard_1.png").then(|| "assets/new.png".to_string())
+        });
+        assert_eq!(seen, vec!["clipboard_1.png", "clipboard_2.png"]);
+        assert_eq!(rewritten, "截图：![](assets/new.png)　![](/x/clipboard_images/clipboard_2.png)");
+    }
+}
@@ -0,0 +1,116 @@
+//! Web Bookmarks
+//!
+//! Bookmarks are `ItemType::Bookmark` items with a `url`, kept in the fixed
+//! web-bookmarks workspace. They are deduplicated per workspace by a URL key:
+//! the URL with tracking parameters and plain fragments removed, scheme and
+//! host lower-cased, default ports and trailing slashes dropped.
+
+/// The fixed workspace clipped pages go to
+pub const BOOKMARK_WORKSPACE_ID: u32 = 4;
+
+/// Tag given to clipped and imported pages when none is named
+pub const BOOKMARK_TAG: &str = "web-bookmark";
+
+/// Query parameters that only record where a visit came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "twclid", "igshid",
+    "mc_cid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok", "ref_src", "si",
+];
+/// Prefixes of tracking query parameters (`utm_source`, `pk_campaign`, ...)
+const TRACKING_PREFIXES: &[&str] = &["utm_", "pk_"];
+
+fn is_tracking_param(param: &str) -> bool {
+    let name = param.split('=').next().unwrap_or_default().to_ascii_lowercase();
+    TRACKING_PARAMS.contains(&name.as_str()) || TRACKING_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// The deduplication key of a URL (None for an empty URL).
+///
+/// Only http(s) URLs are normalised; others are just trimmed. Fragments are
+/// dropped unless they look like client-side routes (`#/...`, `#!...`), and
+/// the order of the remaining query parameters is kept.
+pub fn url_key(url: &str) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return None;
+    }
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Some(url.to_string());
+    };
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => ":80",
+        "https" => ":443",
+        _ => return Some(format!("{}://{}", scheme, rest)),
+    };
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    // Userinfo is case-sensitive, the host is not
+    let (userinfo, host) = match authority.rsplit_once('@') {
+        Some((userinfo, host)) => (Some(userinfo), host),
+        None => (None, authority),
+    };
+    let host = host.to_ascii_lowercase();
+    let host = host.strip_suffix(default_port).unwrap_or(&host);
+
+    let mut key = format!("{}://", scheme);
+    if let Some(userinfo) = userinfo {
+        key.push_str(userinfo);
+        key.push('@');
+    }
+    key.push_str(host);
+    match path.trim_end_matches('/') {
+        "" => key.push('/'),
+        path => key.push_str(path),
+    }
+
+    let params: Vec<&str> = query.into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|param| !param.is_empty() && !is_tracking_param(param))
+        .collect();
+    if !params.is_empty() {
+        key.push('?');
+        key.push_str(&params.join("&"));
+    }
+    if let Some(route) = fragment.filter(|f| f.starts_with('/') || f.starts_with('!')) {
+        key.push('#');
+        key.push_str(route);
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_key_normalises_variants() {
+        let key = url_key("https://Example.COM/docs/").unwrap();
+        assert_eq!(key, "https://example.com/docs");
+        for variant in [
+            "https://example.com/docs",
+            "  https://example.com:443/docs  ",
+            "HTTPS://EXAMPLE.com/docs?utm_source=rss&utm_medium=feed",
+            "https://example.com/docs/?fbclid=abc#section-2",
+        ] {
+            assert_eq!(url_key(variant).unwrap(), key, "{}", variant);
+        }
+
+        // Meaningful parts stay
+        assert_eq!(url_key("https://example.com/Docs?id=1&utm_campaign=x&b=2").unwrap(), "https://example.com/Docs?id=1&b=2");
+        assert_eq!(url_key("http://example.com").unwrap(), "http://example.com/");
+        assert_eq!(url_key("http://example.com:8080/").unwrap(), "http://example.com:8080/");
+        assert_eq!(url_key("https://app.example.com/#/inbox/3").unwrap(), "https://app.example.com/#/inbox/3");
+        assert_eq!(url_key("https://User@Example.com/a").unwrap(), "https://User@example.com/a");
+        assert_eq!(url_key("file:///C:/Notes/a.md").unwrap(), "file:///C:/Notes/a.md");
+        assert_eq!(url_key("   "), None);
+    }
+}
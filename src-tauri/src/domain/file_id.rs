@@ -49,4 +49,10 @@ impl FileIdentifier {
         
         Ok(hasher.finalize().to_hex().to_string())
     }
+
+    /// Content hash of in-memory data (same digest as `compute_content_hash`
+    /// of a file with these bytes). Used for pasted assets.
+    pub fn compute_data_hash(data: &[u8]) -> String {
+        blake3::hash(data).to_hex().to_string()
+    }
 }
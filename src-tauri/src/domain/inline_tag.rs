@@ -0,0 +1,81 @@
+//! Inline Tag Syntax
+//!
+//! `#tag` and `#parent/child` tokens in item text and memos apply tags
+//! automatically. Item-tag rows created this way are marked with
+//! [`TAG_SOURCE_INLINE`] so removing the token removes only those tags.
+//! Tokens are found by `tag_all_markdown::find_inline_tag`, so the tags
+//! applied here are the chips the renderer shows.
+
+use tag_all_markdown::find_inline_tag;
+
+/// `item_tags.source` of tags applied through the UI
+pub const TAG_SOURCE_MANUAL: &str = "manual";
+/// `item_tags.source` of tags applied from `#tag` tokens
+pub const TAG_SOURCE_INLINE: &str = "inline";
+
+/// Extract tag paths (`#parent/child` -> `["parent", "child"]`) from text, in order,
+/// without duplicates. Fenced code blocks and inline code spans are ignored.
+pub fn extract_inline_tags(text: &str) -> Vec<Vec<String>> {
+    let mut paths: Vec<Vec<String>> = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 1 {
+                continue; // inline code
+            }
+            let mut from = 0;
+            while let Some((start, end)) = find_inline_tag(part, from) {
+                let path: Vec<String> = part[start + 1..end].split('/').map(|s| s.to_string()).collect();
+                let key: Vec<String> = path.iter().map(|s| s.to_lowercase()).collect();
+                if !paths.iter().any(|p| p.iter().map(|s| s.to_lowercase()).eq(key.iter().cloned())) {
+                    paths.push(path);
+                }
+                from = end;
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_inline_tags() {
+        let text = "Buy milk #shopping and #lang/rust, (#todo) #Shopping\n\
+                    C# url.com/#anchor [[#42]] #123 `#code`\n\
+                    ```\n#fenced\n```\n#中文 #a/ #x/y/z";
+        let paths = extract_inline_tags(text);
+        assert_eq!(paths, vec![
+            vec!["shopping".to_string()],
+            vec!["lang".to_string(), "rust".to_string()],
+            vec!["todo".to_string()],
+            vec!["中文".to_string()],
+            vec!["a".to_string()],
+            vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_hex_colours_are_not_tags() {
+        let paths = extract_inline_tags("Use #fff or #A1B2C3 for #design, not #cafe");
+        assert_eq!(paths, vec![vec!["design".to_string()]]);
+    }
+
+    #[test]
+    fn test_markdown_heading_is_not_a_tag() {
+        assert!(extract_inline_tags("# Heading\n## Sub").is_empty());
+    }
+}
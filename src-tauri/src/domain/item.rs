@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use super::entity::Entity;
+use super::timestamp::Timestamp;
 
 /// Item type determines behavior and appearance
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -18,6 +19,8 @@ pub enum ItemType {
     Countdown,
     /// Document/note (no checkbox)
     Document,
+    /// Web bookmark (URL in `url`, no checkbox)
+    Bookmark,
     /// Label/tag type (will be separate Tag entity in Level 3)
     Label,
 }
@@ -29,6 +32,7 @@ impl ItemType {
             ItemType::Once => "once",
             ItemType::Countdown => "countdown",
             ItemType::Document => "document",
+            ItemType::Bookmark => "bookmark",
             ItemType::Label => "label",
         }
     }
@@ -38,6 +42,7 @@ impl ItemType {
             "once" => ItemType::Once,
             "countdown" => ItemType::Countdown,
             "document" => ItemType::Document,
+            "bookmark" => ItemType::Bookmark,
             "label" => ItemType::Label,
             _ => ItemType::Daily,
         }
@@ -78,8 +83,8 @@ pub struct Item {
     // Level 6 fields:
     pub url: Option<String>,
     pub summary: Option<String>,
-    pub created_at: Option<i64>,
-    pub updated_at: Option<i64>,
+    pub created_at: Option<Timestamp>,
+    pub updated_at: Option<Timestamp>,
 
     // Level 7 fields:
     pub content_hash: Option<String>,
@@ -176,5 +181,6 @@ mod tests {
     fn test_item_type_serialization() {
         assert_eq!(ItemType::Daily.as_str(), "daily");
         assert_eq!(ItemType::from_str("countdown"), ItemType::Countdown);
+        assert_eq!(ItemType::from_str(ItemType::Bookmark.as_str()), ItemType::Bookmark);
     }
 }
@@ -0,0 +1,105 @@
+//! Item Link Entity
+//!
+//! Wiki-style links between items written in memos as `[[Item title]]`,
+//! `[[#42]]` or `[[Item title|label]]`. Links are stored resolved to ids
+//! (item_links table), so they survive renames of the target item.
+
+use serde::{Deserialize, Serialize};
+
+/// A resolved link from one item's memo to another item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemLink {
+    pub source_item_id: u32,
+    pub target_item_id: u32,
+    /// Link target as written in the memo (without `[[`, `]]` and label)
+    pub link_text: String,
+    /// Current text (title) of the source item
+    pub source_text: String,
+    /// Current text (title) of the target item
+    pub target_text: String,
+}
+
+/// What a `[[...]]` link points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WikiLinkTarget {
+    /// `[[#42]]`
+    Id(u32),
+    /// `[[Item title]]`
+    Title(String),
+}
+
+impl WikiLinkTarget {
+    /// Parse the link target (label already stripped)
+    pub fn parse(link_text: &str) -> Option<Self> {
+        let text = link_text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        match text.strip_prefix('#').map(|id| id.parse::<u32>()) {
+            Some(Ok(id)) => Some(WikiLinkTarget::Id(id)),
+            _ => Some(WikiLinkTarget::Title(text.to_string())),
+        }
+    }
+}
+
+/// Extract link targets (`Item title` / `#42`) from a memo, in order, without duplicates.
+///
+/// Content of fenced code blocks and inline code spans is ignored.
+pub fn extract_wiki_links(memo: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    let mut in_fence = false;
+
+    for line in memo.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // Drop inline code spans
+        let mut plain = String::with_capacity(line.len());
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 0 {
+                plain.push_str(part);
+            }
+        }
+
+        let mut rest = plain.as_str();
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else { break };
+            let inner = &after[..end];
+            let target = inner.split('|').next().unwrap_or("").trim();
+            if !target.is_empty() && !inner.contains('[') && !links.iter().any(|l| l == target) {
+                links.push(target.to_string());
+            }
+            rest = &after[end + 2..];
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_wiki_links() {
+        let memo = "See [[Project plan]] and [[#42|the bug]].\n\
+                    Again [[Project plan]], `[[not a link]]`\n\
+                    ```\n[[inside code]]\n```\n[[ ]] [[Last]]";
+        assert_eq!(extract_wiki_links(memo), vec!["Project plan", "#42", "Last"]);
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(WikiLinkTarget::parse("#42"), Some(WikiLinkTarget::Id(42)));
+        assert_eq!(WikiLinkTarget::parse("#tag-ish"), Some(WikiLinkTarget::Title("#tag-ish".to_string())));
+        assert_eq!(WikiLinkTarget::parse(" Notes "), Some(WikiLinkTarget::Title("Notes".to_string())));
+        assert_eq!(WikiLinkTarget::parse("  "), None);
+    }
+}
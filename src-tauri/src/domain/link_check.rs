@@ -0,0 +1,65 @@
+//! Link Health
+//!
+//! The result of the last check of an item's URL, kept in the item row
+//! (`link_status`, `link_redirect`, `link_error`, `link_checked_at`).
+
+use serde::{Deserialize, Serialize};
+
+use super::timestamp::Timestamp;
+
+/// HTTP 429: the site is rate limiting, not gone
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// Outcome of requesting a URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkCheck {
+    /// Final HTTP status; None when no response arrived
+    pub status: Option<u16>,
+    /// Where redirects ended, if elsewhere than the URL itself
+    pub redirect_to: Option<String>,
+    /// Why no response arrived (DNS, TLS, timeout, ...)
+    pub error: Option<String>,
+    pub checked_at: Timestamp,
+}
+
+impl LinkCheck {
+    /// No response, or an error status other than rate limiting
+    pub fn is_broken(&self) -> bool {
+        match self.status {
+            Some(status) => status >= 400 && status != TOO_MANY_REQUESTS,
+            None => true,
+        }
+    }
+}
+
+/// An item whose URL failed its last check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub item_id: u32,
+    pub text: String,
+    pub url: String,
+    pub workspace_id: u32,
+    #[serde(flatten)]
+    pub check: LinkCheck,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_broken() {
+        let check = |status, error: Option<&str>| LinkCheck {
+            status,
+            redirect_to: None,
+            error: error.map(String::from),
+            checked_at: Timestamp::from_millis(1),
+        };
+        assert!(!check(Some(200), None).is_broken());
+        assert!(!check(Some(301), None).is_broken());
+        assert!(!check(Some(429), None).is_broken());
+        assert!(check(Some(404), None).is_broken());
+        assert!(check(Some(503), None).is_broken());
+        assert!(check(None, Some("dns error")).is_broken());
+    }
+}
@@ -9,10 +9,31 @@ mod tag;
 mod workspace;
 mod workspace_dir;
 mod file_id;
+mod item_link;
+mod inline_tag;
+mod asset;
+mod bookmark;
+mod timestamp;
+mod archive;
+mod link_check;
+mod text_merge;
+mod sync_conflict;
 
 pub use entity::{Entity, DomainError, DomainResult};
 pub use item::{Item, ItemType};
-pub use tag::{Tag};
+pub use tag::{Tag, TagAlias, TagEdge, TagGraphReport, TagStats, WorkspaceTagCount, normalize_tag_name,
+    pick_palette_color, DEFAULT_TAG_PALETTE};
 pub use workspace::Workspace;
 pub use workspace_dir::WorkspaceDir;
 pub use file_id::FileIdentifier;
+pub use item_link::{ItemLink, WikiLinkTarget, extract_wiki_links};
+pub use inline_tag::{extract_inline_tags, TAG_SOURCE_INLINE, TAG_SOURCE_MANUAL};
+pub use asset::{Asset, AssetRef, asset_reference, extract_asset_refs, parse_asset_file_name, rewrite_dir_paths,
+    safe_extension, sniff_mime, to_asset_references, ASSETS_DIR, ASSET_OWNER_ITEM, ASSET_OWNER_TAG, LEGACY_CLIPBOARD_DIR};
+pub use bookmark::{url_key, BOOKMARK_TAG, BOOKMARK_WORKSPACE_ID};
+pub use timestamp::{Timestamp, SECONDS_CUTOFF};
+pub use archive::{Archive, ArchiveMatch, ARCHIVE_HTML_EXT, ASSET_OWNER_ARCHIVE};
+pub use link_check::{BrokenLink, LinkCheck};
+pub use text_merge::{merge3, TextMerge};
+pub use sync_conflict::{ConflictResolution, ConflictSide, ConflictTable, SyncConflict, SyncRow, same_content,
+    CONFLICT_TABLES};
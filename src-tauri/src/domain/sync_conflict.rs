@@ -0,0 +1,174 @@
+//! Sync Conflicts
+//!
+//! A row edited on this device and on another one between two syncs. Sync
+//! itself is last-writer-wins; the version that lost is kept here until the
+//! user picks one or merges them.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::text_merge::{merge3, TextMerge};
+use super::timestamp::Timestamp;
+
+/// A row as column name -> value (integers, reals, text or null)
+pub type SyncRow = BTreeMap<String, Value>;
+
+/// A table whose concurrent edits are detected, with the columns that
+/// identify a row on every device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConflictTable {
+    pub name: &'static str,
+    pub key: &'static [&'static str],
+}
+
+impl ConflictTable {
+    /// Whether two versions of a row are the same row
+    pub fn same_row(&self, a: &SyncRow, b: &SyncRow) -> bool {
+        self.key.iter().all(|column| a.get(*column).is_some_and(|value| b.get(*column) == Some(value)))
+    }
+}
+
+/// Synced tables whose concurrent edits are detected. Rows are identified by
+/// their key columns as a JSON array (`[12]`, `[3,4]`, `["tag_palette"]`);
+/// only `memo` can be merged, other rows are settled by keeping one version.
+pub const CONFLICT_TABLES: &[ConflictTable] = &[
+    ConflictTable { name: "items", key: &["id"] },
+    ConflictTable { name: "tags", key: &["id"] },
+    ConflictTable { name: "tag_tags", key: &["child_tag_id", "parent_tag_id"] },
+    ConflictTable { name: "item_tags", key: &["item_id", "tag_id"] },
+    ConflictTable { name: "workspaces", key: &["id"] },
+    ConflictTable { name: "workspace_dirs", key: &["id"] },
+    ConflictTable { name: "settings", key: &["key"] },
+];
+
+/// Columns that differ without an edit: the sync time itself, and link checks
+/// (recorded without touching `updated_at`)
+const UNCOMPARED_COLUMNS: &[&str] = &["updated_at", "link_status", "link_redirect", "link_error", "link_checked_at"];
+
+/// Whether two versions of a row hold the same content (compared on the
+/// columns both have, so schema differences between devices do not count)
+pub fn same_content(a: &SyncRow, b: &SyncRow) -> bool {
+    a.iter()
+        .filter(|(column, _)| !UNCOMPARED_COLUMNS.contains(&column.as_str()))
+        .all(|(column, value)| b.get(column).is_none_or(|other| other == value))
+}
+
+/// Which side of a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Local,
+    Remote,
+}
+
+impl ConflictSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictSide::Local => "local",
+            ConflictSide::Remote => "remote",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "local" => Some(ConflictSide::Local),
+            "remote" => Some(ConflictSide::Remote),
+            _ => None,
+        }
+    }
+}
+
+/// How to settle a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeepLocal,
+    KeepRemote,
+    /// The row as synced, with the memos of both sides merged
+    Merged,
+}
+
+/// Both versions of a row edited on two devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: u32,
+    pub table_name: String,
+    /// Key columns of the row as a JSON array (see `CONFLICT_TABLES`)
+    pub row_key: String,
+    /// The row as of the previous sync, if known
+    pub base: Option<SyncRow>,
+    pub local: SyncRow,
+    pub remote: SyncRow,
+    /// The version now in the table (the other one lost the sync)
+    pub kept: ConflictSide,
+    pub created_at: Timestamp,
+}
+
+impl SyncConflict {
+    /// The id of a row keyed by `id` alone
+    pub fn row_id(&self) -> Option<u32> {
+        match serde_json::from_str::<Vec<Value>>(&self.row_key).ok()?.as_slice() {
+            [id] => id.as_u64().map(|id| id as u32),
+            _ => None,
+        }
+    }
+
+    /// Three-way merge of the `memo` of both versions; None if neither has one
+    pub fn merged_memo(&self) -> Option<TextMerge> {
+        let memo = |row: &SyncRow| row.get("memo").and_then(Value::as_str).map(str::to_string);
+        let (local, remote) = (memo(&self.local), memo(&self.remote));
+        if local.is_none() && remote.is_none() {
+            return None;
+        }
+        let base = self.base.as_ref().and_then(memo).unwrap_or_default();
+        Some(merge3(&base, &local.unwrap_or_default(), &remote.unwrap_or_default()))
+    }
+
+    /// The version that is not in the table
+    pub fn lost(&self) -> &SyncRow {
+        match self.kept {
+            ConflictSide::Local => &self.remote,
+            ConflictSide::Remote => &self.local,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(memo: Option<&str>) -> SyncRow {
+        SyncRow::from([("id".to_string(), json!(1)), ("memo".to_string(), json!(memo))])
+    }
+
+    #[test]
+    fn test_merged_memo() {
+        let conflict = SyncConflict {
+            id: 1,
+            table_name: "items".to_string(),
+            row_key: "[1]".to_string(),
+            base: Some(row(Some("a\nb\n"))),
+            local: row(Some("a\nb\nlocal\n")),
+            remote: row(Some("remote\na\nb\n")),
+            kept: ConflictSide::Remote,
+            created_at: Timestamp::from_millis(1),
+        };
+        assert_eq!(conflict.merged_memo().unwrap().text, "remote\na\nb\nlocal\n");
+        assert_eq!(conflict.lost(), &conflict.local);
+        assert_eq!(conflict.row_id(), Some(1));
+        assert!(CONFLICT_TABLES[0].same_row(&conflict.local, &conflict.remote));
+
+        let mut synced = conflict.remote.clone();
+        synced.insert("updated_at".to_string(), json!(5));
+        synced.insert("extra".to_string(), json!("only here"));
+        assert!(same_content(&conflict.remote, &synced));
+        assert!(!same_content(&conflict.local, &synced));
+
+        let no_memo = SyncConflict { base: None, local: row(None), remote: row(None), ..conflict };
+        assert!(no_memo.merged_memo().is_none());
+        let edge = SyncConflict { row_key: "[3,4]".to_string(), ..no_memo };
+        assert_eq!(edge.row_id(), None);
+    }
+}
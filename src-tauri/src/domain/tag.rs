@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use super::entity::Entity;
+use super::timestamp::Timestamp;
 
 /// A tag for categorizing items
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,6 +17,25 @@ pub struct Tag {
     pub color: Option<String>,
     /// Position for ordering (root tags)
     pub position: i32,
+    
+    // Level 11: Tag metadata
+    /// Optional memo/notes (Markdown content) documenting what the tag means
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Icon or emoji shown next to the tag name
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Short one-line description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Pinned tags are shown at the top of the tag column
+    #[serde(default)]
+    pub pinned: bool,
+    
+    /// Resolved colour: own colour, else inherited from the first parent
+    /// (by `tag_tags.position`). Computed on read, never stored.
+    #[serde(default)]
+    pub effective_color: Option<String>,
 }
 
 impl Tag {
@@ -25,6 +45,11 @@ impl Tag {
             name,
             color: None,
             position: 0,
+            memo: None,
+            icon: None,
+            description: None,
+            pinned: false,
+            effective_color: None,
         }
     }
 
@@ -32,8 +57,13 @@ impl Tag {
         Self {
             id,
             name,
-            color: Some(color),
+            color: Some(color.clone()),
             position: 0,
+            memo: None,
+            icon: None,
+            description: None,
+            pinned: false,
+            effective_color: Some(color),
         }
     }
 }
@@ -46,6 +76,89 @@ impl Entity for Tag {
     }
 }
 
+/// Alternative name that resolves to a canonical tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagAlias {
+    /// Normalized alias text (see `normalize_tag_name`)
+    pub alias: String,
+    /// Canonical tag this alias points to
+    pub tag_id: u32,
+}
+
+/// Normalize a tag name or alias for lookup (trimmed, lower-cased)
+///
+/// `js`, ` JS ` and `Js` all normalize to the same key.
+pub fn normalize_tag_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Default palette for automatic tag colours (overridable via the `tag_palette` setting)
+pub const DEFAULT_TAG_PALETTE: [&str; 12] = [
+    "#e06c75", "#e5c07b", "#98c379", "#56b6c2", "#61afef", "#c678dd",
+    "#d19a66", "#be5046", "#7ec699", "#4a9eff", "#f78c6c", "#a9a1e1",
+];
+
+/// Pick the palette colour used by the fewest existing tags (ties: palette order)
+pub fn pick_palette_color(palette: &[String], used: &[String]) -> Option<String> {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(i, c)| {
+            let uses = used.iter().filter(|u| u.eq_ignore_ascii_case(c)).count();
+            (uses, *i)
+        })
+        .map(|(_, c)| c.clone())
+}
+
+/// A child -> parent edge in the tag DAG (tag_tags table)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagEdge {
+    pub child_tag_id: u32,
+    pub parent_tag_id: u32,
+}
+
+/// Result of a tag graph consistency check
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagGraphReport {
+    /// Cycles found, each as the tag ids along the cycle
+    pub cycles: Vec<Vec<u32>>,
+    /// Edges whose child or parent tag is missing or deleted
+    pub dangling_edges: Vec<TagEdge>,
+    /// Edges from a tag to itself
+    pub self_edges: Vec<TagEdge>,
+    /// Edges tombstoned by the repair (empty when not repairing)
+    pub removed_edges: Vec<TagEdge>,
+}
+
+impl TagGraphReport {
+    pub fn is_healthy(&self) -> bool {
+        self.cycles.is_empty() && self.dangling_edges.is_empty() && self.self_edges.is_empty()
+    }
+}
+
+/// Number of items carrying a tag within one workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceTagCount {
+    pub workspace_id: u32,
+    pub count: u32,
+}
+
+/// Usage statistics of a tag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagStats {
+    pub tag_id: u32,
+    /// Items tagged directly with this tag
+    pub direct_count: u32,
+    /// Distinct items tagged with this tag or any of its descendant tags
+    pub total_count: u32,
+    /// Latest `item_tags.updated_at` (millis) of a direct application
+    pub last_applied_at: Option<Timestamp>,
+    /// Direct counts per workspace
+    pub workspaces: Vec<WorkspaceTagCount>,
+    /// No item carries this tag or any descendant
+    pub unused: bool,
+}
+
 /// Join table entry for item-tag relationship
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemTag {
@@ -63,6 +176,8 @@ mod tests {
         assert_eq!(tag.id(), 1);
         assert_eq!(tag.name, "Work");
         assert!(tag.color.is_none());
+        assert!(tag.memo.is_none());
+        assert!(!tag.pinned);
     }
 
     #[test]
@@ -70,4 +185,19 @@ mod tests {
         let tag = Tag::with_color(2, "Urgent".to_string(), "#FF0000".to_string());
         assert_eq!(tag.color, Some("#FF0000".to_string()));
     }
+
+    #[test]
+    fn test_pick_palette_color() {
+        let palette: Vec<String> = vec!["#111".into(), "#222".into(), "#333".into()];
+        assert_eq!(pick_palette_color(&palette, &[]), Some("#111".to_string()));
+        let used: Vec<String> = vec!["#111".into(), "#333".into()];
+        assert_eq!(pick_palette_color(&palette, &used), Some("#222".to_string()));
+        assert_eq!(pick_palette_color(&[], &used), None);
+    }
+
+    #[test]
+    fn test_normalize_tag_name() {
+        assert_eq!(normalize_tag_name("  JavaScript "), "javascript");
+        assert_eq!(normalize_tag_name("js"), normalize_tag_name("JS"));
+    }
 }
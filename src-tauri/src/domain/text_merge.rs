@@ -0,0 +1,123 @@
+//! Three-way Text Merge
+//!
+//! Line-based diff3: changes made on one side only are taken as they are,
+//! identical changes once, and overlapping different changes are kept both,
+//! between conflict markers.
+
+use serde::Serialize;
+
+pub const CONFLICT_START: &str = "<<<<<<< local\n";
+pub const CONFLICT_SEPARATOR: &str = "=======\n";
+pub const CONFLICT_END: &str = ">>>>>>> remote\n";
+
+/// Result of merging two edits of a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextMerge {
+    pub text: String,
+    /// Regions changed differently on both sides (left between markers)
+    pub conflicts: usize,
+}
+
+/// Merge `local` and `remote`, both edited from `base`
+pub fn merge3(base: &str, local: &str, remote: &str) -> TextMerge {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let local: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote: Vec<&str> = remote.split_inclusive('\n').collect();
+    let in_local = matching_lines(&base, &local);
+    let in_remote = matching_lines(&base, &remote);
+
+    let mut merge = TextMerge { text: String::new(), conflicts: 0 };
+    let (mut b, mut l, mut r) = (0, 0, 0);
+    loop {
+        // Next base line kept unchanged on both sides
+        let anchor = (b..base.len()).find_map(|i| Some((i, in_local[i]?, in_remote[i]?)));
+        let (next_b, next_l, next_r) = anchor.unwrap_or((base.len(), local.len(), remote.len()));
+        if (next_b, next_l, next_r) != (b, l, r) {
+            merge_chunk(&mut merge, &base[b..next_b], &local[l..next_l], &remote[r..next_r]);
+        }
+        if anchor.is_none() {
+            return merge;
+        }
+        merge.text.push_str(base[next_b]);
+        (b, l, r) = (next_b + 1, next_l + 1, next_r + 1);
+    }
+}
+
+fn merge_chunk(merge: &mut TextMerge, base: &[&str], local: &[&str], remote: &[&str]) {
+    if local == remote || remote == base {
+        merge.text.extend(local.iter().copied());
+    } else if local == base {
+        merge.text.extend(remote.iter().copied());
+    } else {
+        merge.conflicts += 1;
+        merge.text.push_str(CONFLICT_START);
+        push_lines(&mut merge.text, local);
+        merge.text.push_str(CONFLICT_SEPARATOR);
+        push_lines(&mut merge.text, remote);
+        merge.text.push_str(CONFLICT_END);
+    }
+}
+
+/// Lines inside conflict markers must end with a newline
+fn push_lines(text: &mut String, lines: &[&str]) {
+    text.extend(lines.iter().copied());
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// For each line of `base`, its index in `other` if it is part of their
+/// longest common subsequence
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (base.len(), other.len());
+    // lcs[i][j]: length of the LCS of base[i..] and other[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if base[i] == other[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut matches = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if base[i] == other[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge3_takes_changes_from_both_sides() {
+        let base = "title\none\ntwo\nthree\n";
+        let merged = merge3(base, "title\nONE\ntwo\nthree\n", "title\none\ntwo\nthree\nfour\n");
+        assert_eq!(merged, TextMerge { text: "title\nONE\ntwo\nthree\nfour\n".to_string(), conflicts: 0 });
+
+        // The same change on both sides appears once
+        let merged = merge3(base, "title\none\n2\nthree\n", "title\none\n2\nthree\n");
+        assert_eq!(merged.text, "title\none\n2\nthree\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn test_merge3_marks_conflicts() {
+        let merged = merge3("a\nb\nc", "a\nlocal\nc", "a\nremote\nc");
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(merged.text, "a\n<<<<<<< local\nlocal\n=======\nremote\n>>>>>>> remote\nc");
+
+        // Both sides started from nothing
+        let merged = merge3("", "mine", "theirs");
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(merged.text, "<<<<<<< local\nmine\n=======\ntheirs\n>>>>>>> remote\n");
+    }
+}
@@ -0,0 +1,62 @@
+//! Timestamps
+//!
+//! Every `created_at`/`updated_at`/`deleted_at` column holds milliseconds
+//! since the Unix epoch (UTC). Older clipper versions wrote seconds and
+//! columns added by migrations default to 0, so values read from outside
+//! (sync, the local API) go through `Timestamp::normalize`.
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stored values below this are seconds: 1e11 ms is March 1973, while 1e11 s
+/// is beyond year 5000
+pub const SECONDS_CUTOFF: i64 = 100_000_000_000;
+
+/// Milliseconds since the Unix epoch (UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0);
+        Timestamp(millis)
+    }
+
+    pub fn from_millis(millis: i64) -> Self {
+        Timestamp(millis)
+    }
+
+    pub fn millis(self) -> i64 {
+        self.0
+    }
+
+    /// Interpret a value of unknown unit: seconds are scaled to milliseconds,
+    /// and zero or negative values (never set) give None
+    pub fn normalize(raw: i64) -> Option<Self> {
+        match raw {
+            i64::MIN..=0 => None,
+            1..SECONDS_CUTOFF => Some(Timestamp(raw.saturating_mul(1000))),
+            _ => Some(Timestamp(raw)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_seconds_and_zero() {
+        let clipped_secs = 1_700_000_000;
+        assert_eq!(Timestamp::normalize(clipped_secs), Some(Timestamp::from_millis(1_700_000_000_000)));
+        assert_eq!(Timestamp::normalize(1_700_000_000_123), Some(Timestamp::from_millis(1_700_000_000_123)));
+        assert_eq!(Timestamp::normalize(0), None);
+        assert_eq!(Timestamp::normalize(-5), None);
+
+        let now = Timestamp::now();
+        assert!(now.millis() >= SECONDS_CUTOFF);
+        assert_eq!(Timestamp::normalize(now.millis()), Some(now));
+        assert_eq!(serde_json::to_string(&now).unwrap(), now.millis().to_string());
+    }
+}
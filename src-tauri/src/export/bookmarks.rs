@@ -0,0 +1,423 @@
+//! Bookmark Export
+//!
+//! Writes the items of a workspace that have a URL as a bookmark file other
+//! tools read:
+//! - Netscape bookmark HTML, importable by every browser. Folders follow the
+//!   tag hierarchy (`tag_tags`): a bookmark is filed under its most specific
+//!   tag, and all its tag names go into the `TAGS` attribute, so Firefox
+//!   restores them as tags on re-import.
+//! - OPML, one outline per tag (nested like the tags) listing every
+//!   bookmark carrying it
+//! - CSV with one row per bookmark
+//!
+//! With a tag filter only bookmarks carrying that tag or one of its
+//! descendants are written, and folders start below the filter tag.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tag_all_markdown::escape_html;
+use tokio::sync::Mutex;
+
+use crate::domain::{DomainError, DomainResult, Item, Tag, BOOKMARK_TAG};
+use crate::repository::item::ItemWorkspaceOperations;
+use crate::repository::tag::{ItemTagOperations, TagHierarchyOperations};
+use crate::repository::{ItemRepository, Repository, TagRepository};
+
+/// Output format of `export_bookmarks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookmarkExportFormat {
+    /// Netscape bookmark HTML
+    Html,
+    Opml,
+    Csv,
+}
+
+/// Result of an `export_bookmarks` run
+#[derive(Debug, Clone, Serialize)]
+pub struct BookmarkExportReport {
+    /// Written file
+    pub path: String,
+    /// Bookmarks written
+    pub bookmarks: usize,
+    /// Folders (HTML) or tag outlines (OPML) written; 0 for CSV
+    pub folders: usize,
+}
+
+/// Bookmarks with their tags and the tag hierarchy above them
+struct BookmarkData {
+    bookmarks: Vec<Item>,
+    /// item id -> tags
+    item_tags: HashMap<u32, Vec<Tag>>,
+    /// tag id -> parent tag ids (ordered by name)
+    tag_parents: HashMap<u32, Vec<u32>>,
+    /// tag id -> name, for every tag above
+    tag_names: HashMap<u32, String>,
+    /// Folders start below this tag
+    root_tag: Option<u32>,
+}
+
+/// A folder of the exported tree; sub-folders ordered by name
+#[derive(Default)]
+struct Folder {
+    folders: BTreeMap<String, Folder>,
+    /// Indices into `BookmarkData::bookmarks`
+    bookmarks: Vec<usize>,
+}
+
+impl Folder {
+    fn at(&mut self, path: &[String]) -> &mut Folder {
+        path.iter().fold(self, |folder, name| folder.folders.entry(name.clone()).or_default())
+    }
+
+    fn count(&self) -> usize {
+        self.folders.values().map(|f| 1 + f.count()).sum()
+    }
+}
+
+impl BookmarkData {
+    /// Whether `ancestor` is `tag` or above it
+    fn is_within(&self, tag: u32, ancestor: u32) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![tag];
+        while let Some(id) = pending.pop() {
+            if id == ancestor {
+                return true;
+            }
+            if seen.insert(id) {
+                pending.extend(self.tag_parents.get(&id).into_iter().flatten());
+            }
+        }
+        false
+    }
+
+    /// Folder names from the top (or below the filter tag) down to `tag`,
+    /// following the first parent that leads there
+    fn folder_path(&self, tag: u32) -> Vec<String> {
+        fn walk(data: &BookmarkData, tag: u32, seen: &mut HashSet<u32>) -> Option<Vec<String>> {
+            if Some(tag) == data.root_tag {
+                return Some(Vec::new());
+            }
+            if !seen.insert(tag) {
+                return None;
+            }
+            let parents = data.tag_parents.get(&tag).map(Vec::as_slice).unwrap_or_default();
+            let path = if parents.is_empty() {
+                data.root_tag.is_none().then(Vec::new)
+            } else {
+                parents.iter().find_map(|parent| walk(data, *parent, seen))
+            };
+            seen.remove(&tag);
+            let mut path = path?;
+            path.push(data.tag_names.get(&tag).cloned().unwrap_or_default());
+            Some(path)
+        }
+        walk(self, tag, &mut HashSet::new()).unwrap_or_default()
+    }
+
+    /// Folder paths of a bookmark: its most specific tags (within the filter),
+    /// leaving out the tag every clipped page carries
+    fn placements(&self, item_id: u32) -> Vec<Vec<String>> {
+        let tags: Vec<u32> = self.item_tags.get(&item_id).into_iter().flatten()
+            .filter(|t| t.name != BOOKMARK_TAG)
+            .filter(|t| self.root_tag.is_none_or(|root| self.is_within(t.id, root)))
+            .map(|t| t.id)
+            .collect();
+        let mut paths: Vec<Vec<String>> = tags.iter()
+            .filter(|&&tag| !tags.iter().any(|&other| other != tag && self.is_within(other, tag)))
+            .map(|&tag| self.folder_path(tag))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        if paths.is_empty() {
+            paths.push(Vec::new());
+        }
+        paths
+    }
+
+    /// The folder tree; each bookmark once (HTML) or under each of its tags (OPML)
+    fn tree(&self, every_tag: bool) -> Folder {
+        let mut root = Folder::default();
+        for (index, item) in self.bookmarks.iter().enumerate() {
+            let placements = self.placements(item.id);
+            let count = if every_tag { placements.len() } else { 1 };
+            for path in placements.into_iter().take(count) {
+                root.at(&path).bookmarks.push(index);
+            }
+        }
+        root
+    }
+
+    fn tag_list(&self, item_id: u32) -> Vec<String> {
+        self.item_tags.get(&item_id).into_iter().flatten().map(|t| t.name.clone()).collect()
+    }
+}
+
+async fn load_bookmarks(conn: Arc<Mutex<Option<Connection>>>, workspace_id: u32, tag_filter: Option<u32>) -> DomainResult<BookmarkData> {
+    let item_repo = ItemRepository::new(conn.clone());
+    let tag_repo = TagRepository::new(conn);
+    if let Some(tag_id) = tag_filter {
+        tag_repo.find_by_id(tag_id).await?
+            .ok_or_else(|| DomainError::NotFound(format!("Tag {} not found", tag_id)))?;
+    }
+
+    let mut bookmarks: Vec<Item> = item_repo.list_by_workspace(workspace_id).await?
+        .into_iter()
+        .filter(|item| item.url.as_deref().is_some_and(|url| !url.trim().is_empty()))
+        .collect();
+    bookmarks.sort_by_key(|item| (item.parent_id, item.position, item.id));
+
+    let mut item_tags = HashMap::new();
+    let mut tag_names = HashMap::new();
+    for item in &bookmarks {
+        let applied = tag_repo.get_tags_for_item(item.id).await?;
+        for tag in &applied {
+            tag_names.insert(tag.id, tag.name.clone());
+        }
+        item_tags.insert(item.id, applied);
+    }
+
+    let mut tag_parents = HashMap::new();
+    let mut pending: Vec<u32> = tag_names.keys().copied().collect();
+    while let Some(tag_id) = pending.pop() {
+        if tag_parents.contains_key(&tag_id) {
+            continue;
+        }
+        let parents = tag_repo.get_parent_tags(tag_id).await?;
+        tag_parents.insert(tag_id, parents.iter().map(|t| t.id).collect::<Vec<_>>());
+        for parent in parents {
+            pending.push(parent.id);
+            tag_names.insert(parent.id, parent.name);
+        }
+    }
+
+    let mut data = BookmarkData { bookmarks: Vec::new(), item_tags, tag_parents, tag_names, root_tag: tag_filter };
+    data.bookmarks = bookmarks.into_iter()
+        .filter(|item| tag_filter.is_none_or(|root| {
+            data.item_tags.get(&item.id).into_iter().flatten().any(|t| data.is_within(t.id, root))
+        }))
+        .collect();
+    Ok(data)
+}
+
+/// Export the bookmarks of a workspace (items with a URL), optionally only
+/// those under one tag, to `path`
+pub async fn export_bookmarks(
+    conn: Arc<Mutex<Option<Connection>>>,
+    workspace_id: u32,
+    tag_filter: Option<u32>,
+    format: BookmarkExportFormat,
+    path: &Path,
+) -> DomainResult<BookmarkExportReport> {
+    let data = load_bookmarks(conn, workspace_id, tag_filter).await?;
+    let (content, folders) = match format {
+        BookmarkExportFormat::Html => {
+            let tree = data.tree(false);
+            (render_netscape(&data, &tree), tree.count())
+        }
+        BookmarkExportFormat::Opml => {
+            let tree = data.tree(true);
+            (render_opml(&data, &tree), tree.count())
+        }
+        BookmarkExportFormat::Csv => (render_csv(&data), 0),
+    };
+    std::fs::write(path, content)
+        .map_err(|e| DomainError::Internal(format!("Failed to write {}: {}", path.display(), e)))?;
+
+    Ok(BookmarkExportReport {
+        path: path.to_string_lossy().to_string(),
+        bookmarks: data.bookmarks.len(),
+        folders,
+    })
+}
+
+fn seconds(item: &Item) -> i64 {
+    item.created_at.map(|t| t.millis() / 1000).unwrap_or(0)
+}
+
+fn render_netscape(data: &BookmarkData, tree: &Folder) -> String {
+    fn write_folder(data: &BookmarkData, folder: &Folder, depth: usize, out: &mut String) {
+        let indent = "    ".repeat(depth);
+        out.push_str(&format!("{}<DL><p>\n", indent));
+        for (name, sub) in &folder.folders {
+            out.push_str(&format!("{}    <DT><H3>{}</H3>\n", indent, escape_html(name)));
+            write_folder(data, sub, depth + 1, out);
+        }
+        for &index in &folder.bookmarks {
+            let item = &data.bookmarks[index];
+            // Firefox splits TAGS on commas
+            let tags: Vec<String> = data.tag_list(item.id).iter().map(|t| t.replace(',', " ")).collect();
+            let tags = if tags.is_empty() { String::new() } else { format!(" TAGS=\"{}\"", escape_html(&tags.join(","))) };
+            out.push_str(&format!(
+                "{}    <DT><A HREF=\"{}\" ADD_DATE=\"{}\"{}>{}</A>\n",
+                indent,
+                escape_html(item.url.as_deref().unwrap_or_default()),
+                seconds(item),
+                tags,
+                escape_html(&item.text),
+            ));
+            if let Some(summary) = item.summary.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+                out.push_str(&format!("{}    <DD>{}\n", indent, escape_html(&summary.replace('\n', " "))));
+            }
+        }
+        out.push_str(&format!("{}</DL><p>\n", indent));
+    }
+
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     It will be read and overwritten.\n     DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n",
+    );
+    write_folder(data, tree, 0, &mut out);
+    out
+}
+
+fn rfc822(item: &Item) -> Option<String> {
+    let millis = item.created_at?.millis();
+    chrono::DateTime::from_timestamp_millis(millis).map(|t| t.to_rfc2822())
+}
+
+fn render_opml(data: &BookmarkData, tree: &Folder) -> String {
+    fn write_folder(data: &BookmarkData, folder: &Folder, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        for (name, sub) in &folder.folders {
+            out.push_str(&format!("{}<outline text=\"{}\">\n", indent, escape_html(name)));
+            write_folder(data, sub, depth + 1, out);
+            out.push_str(&format!("{}</outline>\n", indent));
+        }
+        for &index in &folder.bookmarks {
+            let item = &data.bookmarks[index];
+            let mut attrs = format!(
+                "type=\"link\" text=\"{}\" url=\"{}\"",
+                escape_html(&item.text),
+                escape_html(item.url.as_deref().unwrap_or_default()),
+            );
+            if let Some(created) = rfc822(item) {
+                attrs.push_str(&format!(" created=\"{}\"", created));
+            }
+            let tags = data.tag_list(item.id);
+            if !tags.is_empty() {
+                attrs.push_str(&format!(" category=\"{}\"", escape_html(&tags.join(","))));
+            }
+            if let Some(summary) = item.summary.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+                attrs.push_str(&format!(" description=\"{}\"", escape_html(&summary.replace('\n', " "))));
+            }
+            out.push_str(&format!("{}<outline {}/>\n", indent, attrs));
+        }
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>Bookmarks</title>\n");
+    if let Some(now) = chrono::DateTime::from_timestamp_millis(crate::domain::Timestamp::now().millis()) {
+        out.push_str(&format!("    <dateCreated>{}</dateCreated>\n", now.to_rfc2822()));
+    }
+    out.push_str("  </head>\n  <body>\n");
+    write_folder(data, tree, 2, &mut out);
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+/// A CSV field, quoted when needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(data: &BookmarkData) -> String {
+    let mut out = String::from("title,url,folder,tags,created_at,summary\r\n");
+    for item in &data.bookmarks {
+        let folder = data.placements(item.id).into_iter().next().unwrap_or_default().join("/");
+        let created = item.created_at
+            .and_then(|t| chrono::DateTime::from_timestamp_millis(t.millis()))
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        let fields = [
+            item.text.as_str(),
+            item.url.as_deref().unwrap_or_default(),
+            &folder,
+            &data.tag_list(item.id).join(","),
+            &created,
+            item.summary.as_deref().unwrap_or_default(),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ItemType, Timestamp, BOOKMARK_WORKSPACE_ID};
+    use crate::import::{import_bookmarks, BookmarkFolders};
+    use crate::repository::init_db;
+    use crate::repository::tag::TagAliasOperations;
+    use std::path::PathBuf;
+
+    async fn add(items: &ItemRepository, tags: &TagRepository, title: &str, url: &str, tag_ids: &[u32]) {
+        let mut item = Item::new(0, title.to_string(), ItemType::Bookmark);
+        item.url = Some(url.to_string());
+        item.summary = Some(format!("About {}", title));
+        item.created_at = Some(Timestamp::from_millis(1_700_000_000_000));
+        let created = items.create_with_workspace(&item, BOOKMARK_WORKSPACE_ID).await.unwrap();
+        for tag_id in tag_ids {
+            tags.add_tag_to_item(created.id, *tag_id).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_bookmarks_round_trip() {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let tags = TagRepository::new(db.conn.clone());
+        let dev = tags.create(&Tag::new(0, "Dev".to_string())).await.unwrap();
+        let rust = tags.create(&Tag::new(0, "Rust".to_string())).await.unwrap();
+        tags.add_parent_tag(rust.id, dev.id).await.unwrap();
+        let news = tags.create(&Tag::new(0, "News, daily".to_string())).await.unwrap();
+
+        add(&items, &tags, "The Book", "https://doc.rust-lang.org/book/", &[dev.id, rust.id]).await;
+        add(&items, &tags, "Hacker \"News\"", "https://news.ycombinator.com/", &[news.id]).await;
+        add(&items, &tags, "Plain", "https://example.com/?a=1&b=2", &[]).await;
+
+        let dir = std::env::temp_dir().join(format!("tag-all-bookmark-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let html_path = dir.join("bookmarks.html");
+        let report = export_bookmarks(db.conn.clone(), BOOKMARK_WORKSPACE_ID, None, BookmarkExportFormat::Html, &html_path).await.unwrap();
+        assert_eq!((report.bookmarks, report.folders), (3, 3));
+        let html = std::fs::read_to_string(&html_path).unwrap();
+        assert!(html.contains("<DT><H3>Dev</H3>\n    <DL><p>\n        <DT><H3>Rust</H3>"), "{}", html);
+        assert!(html.contains(r#"HREF="https://doc.rust-lang.org/book/" ADD_DATE="1700000000" TAGS="Dev,Rust">The Book</A>"#), "{}", html);
+        assert!(html.contains(r#"TAGS="News  daily">Hacker &quot;News&quot;</A>"#), "{}", html);
+        assert!(html.contains(r#"HREF="https://example.com/?a=1&amp;b=2""#), "{}", html);
+
+        let opml_path = dir.join("rust.opml");
+        let report = export_bookmarks(db.conn.clone(), BOOKMARK_WORKSPACE_ID, Some(dev.id), BookmarkExportFormat::Opml, &opml_path).await.unwrap();
+        assert_eq!((report.bookmarks, report.folders), (1, 1));
+        let opml = std::fs::read_to_string(&opml_path).unwrap();
+        assert!(opml.contains("<outline text=\"Rust\">\n      <outline type=\"link\" text=\"The Book\""), "{}", opml);
+        assert!(!opml.contains("<outline text=\"Dev\">"), "{}", opml);
+
+        let csv_path = dir.join("bookmarks.csv");
+        export_bookmarks(db.conn.clone(), BOOKMARK_WORKSPACE_ID, None, BookmarkExportFormat::Csv, &csv_path).await.unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(csv.contains("The Book,https://doc.rust-lang.org/book/,Dev/Rust,\"Dev,Rust\",2023-11-14T22:13:20+00:00,About The Book\r\n"), "{}", csv);
+        assert!(csv.contains("\"Hacker \"\"News\"\"\""), "{}", csv);
+
+        // The HTML re-imports into a fresh database with the same tags
+        let other = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let imported = import_bookmarks(other.conn.clone(), &html_path, BookmarkFolders::Items).await.unwrap();
+        assert_eq!(imported.imported, 3);
+        let other_tags = TagRepository::new(other.conn.clone());
+        assert!(other_tags.resolve_tag_name("Rust").await.unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
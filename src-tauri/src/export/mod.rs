@@ -0,0 +1,11 @@
+//! Export Layer
+//!
+//! Publishes workspace data outside the app:
+//! - site: Static HTML site (item pages, tag index, backlinks, copied assets)
+//! - bookmarks: Bookmark files (Netscape HTML, OPML, CSV)
+
+mod site;
+mod bookmarks;
+
+pub use site::{export_site, SiteExportReport};
+pub use bookmarks::{export_bookmarks, BookmarkExportFormat, BookmarkExportReport};
@@ -0,0 +1,635 @@
+//! Static Site Export
+//!
+//! Renders a workspace as a read-only HTML site: an index with the item tree,
+//! one page per item (memo, tags, children, backlinks), a tag index and one
+//! page per tag. Local files referenced by memos are copied into `assets/`.
+//! All pages sit in one directory, so every link is a plain relative file name.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use percent_encoding::percent_decode_str;
+use rusqlite::Connection;
+use serde::Serialize;
+use tag_all_markdown::{
+    asset_reference_name, escape_html, is_local_path, is_safe_url, parse_markdown_inline_with, parse_markdown_with, RenderOptions,
+};
+use tokio::sync::Mutex;
+
+use crate::domain::{DomainError, DomainResult, Item, ItemLink, Tag};
+use crate::repository::item::{ItemLinkOperations, ItemWorkspaceOperations};
+use crate::repository::tag::{ItemTagOperations, TagHierarchyOperations};
+use crate::repository::{ItemRepository, TagRepository, WorkspaceRepository};
+
+/// Result of an `export_site` run
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteExportReport {
+    /// Output directory
+    pub dir: String,
+    /// Number of HTML pages written
+    pub pages: usize,
+    /// Number of files copied into `assets/`
+    pub assets: usize,
+    /// Local files referenced by memos that could not be copied
+    pub missing_assets: Vec<String>,
+}
+
+/// Everything a site is rendered from, loaded up front so rendering is synchronous
+struct SiteData {
+    title: String,
+    items: Vec<Item>,
+    /// Tags applied in the workspace plus their ancestors
+    tags: Vec<Tag>,
+    /// item id -> tag ids
+    item_tags: HashMap<u32, Vec<u32>>,
+    /// tag id -> parent tag ids (within `tags`)
+    tag_parents: HashMap<u32, Vec<u32>>,
+    /// item id -> resolved [[links]] in its memo
+    outgoing: HashMap<u32, Vec<ItemLink>>,
+    /// item id -> items linking to it
+    backlinks: HashMap<u32, Vec<ItemLink>>,
+}
+
+/// Rendered pages and the local files they reference
+struct Site {
+    /// file name -> HTML
+    pages: BTreeMap<String, String>,
+    /// local source path -> path relative to the site root
+    assets: BTreeMap<String, String>,
+}
+
+/// Export a workspace as a static HTML site into `dir` (created if missing;
+/// existing pages are overwritten). `assets_dir` resolves the memos'
+/// `assets/{hash}.{ext}` references.
+pub async fn export_site(
+    conn: Arc<Mutex<Option<Connection>>>,
+    workspace_id: u32,
+    dir: &Path,
+    assets_dir: &Path,
+) -> DomainResult<SiteExportReport> {
+    let data = load_site_data(conn, workspace_id).await?;
+    let site = render_site(&data, assets_dir);
+
+    let io_err = |e: std::io::Error| DomainError::Internal(format!("Failed to write site: {}", e));
+    std::fs::create_dir_all(dir.join("assets")).map_err(io_err)?;
+    for (name, html) in &site.pages {
+        std::fs::write(dir.join(name), html).map_err(io_err)?;
+    }
+    let highlight_css = tag_all_markdown::highlight_css(tag_all_markdown::LIGHT_THEME, tag_all_markdown::DARK_THEME)
+        .map_err(DomainError::Internal)?;
+    std::fs::write(dir.join("style.css"), format!("{}\n{}", STYLE_CSS, highlight_css)).map_err(io_err)?;
+
+    let mut copied = 0;
+    let mut missing_assets = Vec::new();
+    for (source, relative) in &site.assets {
+        match std::fs::copy(source, dir.join(relative)) {
+            Ok(_) => copied += 1,
+            Err(_) => missing_assets.push(source.clone()),
+        }
+    }
+
+    Ok(SiteExportReport {
+        dir: dir.to_string_lossy().to_string(),
+        pages: site.pages.len(),
+        assets: copied,
+        missing_assets,
+    })
+}
+
+async fn load_site_data(conn: Arc<Mutex<Option<Connection>>>, workspace_id: u32) -> DomainResult<SiteData> {
+    let item_repo = ItemRepository::new(conn.clone());
+    let tag_repo = TagRepository::new(conn.clone());
+    let workspace_repo = WorkspaceRepository::new(conn);
+
+    let title = workspace_repo.list().await?
+        .into_iter()
+        .find(|w| w.id == workspace_id)
+        .map(|w| w.name)
+        .ok_or_else(|| DomainError::NotFound(format!("Workspace {} not found", workspace_id)))?;
+
+    let mut items = item_repo.list_by_workspace(workspace_id).await?;
+    items.sort_by_key(|item| (item.parent_id, item.position, item.id));
+
+    let mut tags: BTreeMap<u32, Tag> = BTreeMap::new();
+    let mut item_tags = HashMap::new();
+    let mut outgoing = HashMap::new();
+    let mut backlinks = HashMap::new();
+    for item in &items {
+        let applied = tag_repo.get_tags_for_item(item.id).await?;
+        item_tags.insert(item.id, applied.iter().map(|t| t.id).collect::<Vec<_>>());
+        for tag in applied {
+            tags.entry(tag.id).or_insert(tag);
+        }
+        outgoing.insert(item.id, item_repo.get_outgoing_links(item.id).await?);
+        backlinks.insert(item.id, item_repo.get_backlinks(item.id).await?);
+    }
+
+    // Pull in ancestors so the tag index keeps its hierarchy
+    let mut tag_parents = HashMap::new();
+    let mut pending: Vec<u32> = tags.keys().copied().collect();
+    while let Some(tag_id) = pending.pop() {
+        if tag_parents.contains_key(&tag_id) {
+            continue;
+        }
+        let parents = tag_repo.get_parent_tags(tag_id).await?;
+        tag_parents.insert(tag_id, parents.iter().map(|t| t.id).collect::<Vec<_>>());
+        for parent in parents {
+            pending.push(parent.id);
+            tags.entry(parent.id).or_insert(parent);
+        }
+    }
+
+    Ok(SiteData {
+        title,
+        items,
+        tags: tags.into_values().collect(),
+        item_tags,
+        tag_parents,
+        outgoing,
+        backlinks,
+    })
+}
+
+fn item_page(id: u32) -> String {
+    format!("item-{}.html", id)
+}
+
+fn tag_page(id: u32) -> String {
+    format!("tag-{}.html", id)
+}
+
+/// `assets/<hash>-<file name>`; the hash of the source path keeps equal names apart
+fn asset_name(source: &str) -> String {
+    let file_name = source.rsplit(['/', '\\']).next().unwrap_or("file");
+    let safe: String = file_name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let hash = blake3::hash(source.as_bytes()).to_hex();
+    format!("assets/{}-{}", &hash[..12], safe)
+}
+
+/// Local path behind a memo URL: an asset reference, a plain path or an asset-protocol URL
+fn local_source(url: &str, assets_dir: &Path) -> Option<String> {
+    if let Some(name) = asset_reference_name(url) {
+        return Some(assets_dir.join(name).to_string_lossy().replace('\\', "/"));
+    }
+    let path = url.strip_prefix("http://asset.localhost/")
+        .or_else(|| url.strip_prefix("asset://localhost/"))
+        .map(|p| percent_decode_str(p).decode_utf8_lossy().to_string())
+        .unwrap_or_else(|| url.to_string());
+    is_local_path(&path).then(|| path.replace('\\', "/"))
+}
+
+fn render_site(data: &SiteData, assets_dir: &Path) -> Site {
+    let item_ids: HashSet<u32> = data.items.iter().map(|i| i.id).collect();
+    let items_by_id: HashMap<u32, &Item> = data.items.iter().map(|i| (i.id, i)).collect();
+    let tags_by_id: HashMap<u32, &Tag> = data.tags.iter().map(|t| (t.id, t)).collect();
+    let tag_colors: HashMap<String, String> = data.tags.iter()
+        .filter_map(|t| t.effective_color.clone().map(|c| (t.name.to_lowercase(), c)))
+        .collect();
+
+    // Roots are items without a parent in this workspace
+    let mut children: BTreeMap<Option<u32>, Vec<&Item>> = BTreeMap::new();
+    for item in &data.items {
+        let parent = item.parent_id.filter(|p| item_ids.contains(p));
+        children.entry(parent).or_default().push(item);
+    }
+    let mut tag_children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for (tag_id, parents) in &data.tag_parents {
+        for parent in parents {
+            tag_children.entry(*parent).or_default().push(*tag_id);
+        }
+    }
+    let tag_order = |id: &u32| tags_by_id.get(id).map(|t| (!t.pinned, t.position, t.name.to_lowercase()));
+    for kids in tag_children.values_mut() {
+        kids.sort_by_key(tag_order);
+    }
+    let mut tagged_items: HashMap<u32, Vec<&Item>> = HashMap::new();
+    for item in &data.items {
+        for tag_id in data.item_tags.get(&item.id).into_iter().flatten() {
+            tagged_items.entry(*tag_id).or_default().push(item);
+        }
+    }
+
+    let assets = RefCell::new(BTreeMap::new());
+    let rewrite_url = |url: &str| {
+        let source = local_source(url, assets_dir)?;
+        let relative = asset_name(&source);
+        assets.borrow_mut().insert(source, relative.clone());
+        Some(relative)
+    };
+    let no_links = |_: &str| -> Option<String> { None };
+    let plain = RenderOptions {
+        tag_colors: Some(&tag_colors),
+        wiki_link_href: Some(&no_links),
+        rewrite_url: Some(&rewrite_url),
+        readonly_tasks: true,
+    };
+    let item_title = |item: &Item| parse_markdown_inline_with(&item.text, &plain);
+    let item_link = |item: &Item| {
+        let class = if item.completed { "item-link completed" } else { "item-link" };
+        format!(r#"<a class="{}" href="{}">{}</a>"#, class, item_page(item.id), item_title(item))
+    };
+    let tag_chip = |tag: &Tag| {
+        let color = tag.effective_color.as_deref().unwrap_or("#888");
+        format!(
+            r#"<a class="tag-chip" href="{}" style="background-color: {};">{}{}</a>"#,
+            tag_page(tag.id),
+            escape_html(color),
+            tag.icon.as_deref().map(|i| format!("{} ", escape_html(i))).unwrap_or_default(),
+            escape_html(&tag.name)
+        )
+    };
+
+    let mut pages = BTreeMap::new();
+
+    // --- index.html: the item tree ---
+    fn item_tree(parent: Option<u32>, children: &BTreeMap<Option<u32>, Vec<&Item>>, link: &dyn Fn(&Item) -> String) -> String {
+        let Some(items) = children.get(&parent) else { return String::new(); };
+        let mut html = String::from("<ul class=\"item-tree\">");
+        for item in items {
+            html.push_str(&format!("<li>{}{}</li>", link(item), item_tree(Some(item.id), children, link)));
+        }
+        html.push_str("</ul>");
+        html
+    }
+    let index_body = format!(
+        "<h1>{}</h1>\n{}",
+        escape_html(&data.title),
+        item_tree(None, &children, &item_link)
+    );
+    pages.insert("index.html".to_string(), page(&data.title, &data.title, &index_body));
+
+    // --- item-N.html ---
+    for item in &data.items {
+        let links: HashMap<&str, u32> = data.outgoing.get(&item.id).into_iter().flatten()
+            .map(|l| (l.link_text.as_str(), l.target_item_id))
+            .collect();
+        let wiki_link_href = |target: &str| {
+            links.get(target).copied().filter(|id| item_ids.contains(id)).map(item_page)
+        };
+        let opts = RenderOptions {
+            tag_colors: Some(&tag_colors),
+            wiki_link_href: Some(&wiki_link_href),
+            rewrite_url: Some(&rewrite_url),
+            readonly_tasks: true,
+        };
+
+        let mut body = String::new();
+
+        // Breadcrumb of ancestors (guarded against parent cycles)
+        let mut ancestors = Vec::new();
+        let mut parent = item.parent_id;
+        while let Some(parent_item) = parent.and_then(|p| items_by_id.get(&p)) {
+            if ancestors.len() > data.items.len() {
+                break;
+            }
+            ancestors.push(item_link(parent_item));
+            parent = parent_item.parent_id;
+        }
+        if !ancestors.is_empty() {
+            ancestors.reverse();
+            body.push_str(&format!("<nav class=\"breadcrumb\">{}</nav>\n", ancestors.join(" / ")));
+        }
+
+        let title_class = if item.completed { "item-title completed" } else { "item-title" };
+        body.push_str(&format!("<h1 class=\"{}\">{}</h1>\n", title_class, item_title(item)));
+
+        if let Some(url) = item.url.as_deref().filter(|u| is_safe_url(u, false)) {
+            body.push_str(&format!(
+                "<p class=\"item-url\"><a href=\"{0}\" rel=\"noopener noreferrer\">{0}</a></p>\n",
+                escape_html(url)
+            ));
+        }
+
+        let chips: Vec<String> = data.item_tags.get(&item.id).into_iter().flatten()
+            .filter_map(|id| tags_by_id.get(id))
+            .map(|tag| tag_chip(tag))
+            .collect();
+        if !chips.is_empty() {
+            body.push_str(&format!("<div class=\"tag-list\">{}</div>\n", chips.join(" ")));
+        }
+
+        if let Some(summary) = item.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+            body.push_str(&format!("<div class=\"item-summary\">{}</div>\n", parse_markdown_with(summary, &opts)));
+        }
+        if let Some(memo) = item.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+            body.push_str(&format!("<article class=\"memo\">{}</article>\n", parse_markdown_with(memo, &opts)));
+        }
+
+        if let Some(kids) = children.get(&Some(item.id)) {
+            body.push_str("<h2>子项</h2>\n<ul>");
+            for kid in kids {
+                body.push_str(&format!("<li>{}</li>", item_link(kid)));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        let backlinks: Vec<String> = data.backlinks.get(&item.id).into_iter().flatten()
+            .filter_map(|l| items_by_id.get(&l.source_item_id))
+            .map(|source| format!("<li>{}</li>", item_link(source)))
+            .collect();
+        if !backlinks.is_empty() {
+            body.push_str(&format!("<h2>反向链接</h2>\n<ul class=\"backlinks\">{}</ul>\n", backlinks.join("")));
+        }
+
+        pages.insert(item_page(item.id), page(&data.title, &item.text, &body));
+    }
+
+    // --- tags.html: tag hierarchy ---
+    fn tag_tree(
+        ids: &[u32],
+        tag_children: &BTreeMap<u32, Vec<u32>>,
+        chip: &dyn Fn(u32) -> Option<String>,
+        path: &mut Vec<u32>,
+    ) -> String {
+        let mut html = String::from("<ul class=\"tag-tree\">");
+        for id in ids {
+            // A tag may sit under several parents; only stop on cycles
+            if path.contains(id) {
+                continue;
+            }
+            let Some(chip_html) = chip(*id) else { continue; };
+            path.push(*id);
+            let nested = tag_children.get(id)
+                .map(|kids| tag_tree(kids, tag_children, chip, path))
+                .unwrap_or_default();
+            path.pop();
+            html.push_str(&format!("<li>{}{}</li>", chip_html, nested));
+        }
+        html.push_str("</ul>");
+        html
+    }
+    let counted_chip = |id: u32| {
+        let tag = tags_by_id.get(&id)?;
+        let count = tagged_items.get(&id).map_or(0, Vec::len);
+        Some(format!("{} <span class=\"count\">{}</span>", tag_chip(tag), count))
+    };
+    let mut root_tags: Vec<u32> = data.tags.iter()
+        .filter(|t| data.tag_parents.get(&t.id).is_none_or(|p| p.iter().all(|p| !tags_by_id.contains_key(p))))
+        .map(|t| t.id)
+        .collect();
+    root_tags.sort_by_key(tag_order);
+    let tags_body = format!(
+        "<h1>标签</h1>\n{}",
+        tag_tree(&root_tags, &tag_children, &counted_chip, &mut Vec::new())
+    );
+    pages.insert("tags.html".to_string(), page(&data.title, "标签", &tags_body));
+
+    // --- tag-N.html ---
+    for tag in &data.tags {
+        let mut body = format!("<h1>{}</h1>\n", tag_chip(tag));
+        if let Some(description) = tag.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            body.push_str(&format!("<p class=\"tag-description\">{}</p>\n", escape_html(description)));
+        }
+
+        let parents: Vec<String> = data.tag_parents.get(&tag.id).into_iter().flatten()
+            .filter_map(|id| tags_by_id.get(id))
+            .map(|parent| tag_chip(parent))
+            .collect();
+        if !parents.is_empty() {
+            body.push_str(&format!("<p class=\"tag-parents\">上级: {}</p>\n", parents.join(" ")));
+        }
+        if let Some(memo) = tag.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+            body.push_str(&format!("<article class=\"memo\">{}</article>\n", parse_markdown_with(memo, &plain)));
+        }
+
+        let kids: Vec<String> = tag_children.get(&tag.id).into_iter().flatten()
+            .filter_map(|id| counted_chip(*id))
+            .map(|chip| format!("<li>{}</li>", chip))
+            .collect();
+        if !kids.is_empty() {
+            body.push_str(&format!("<h2>子标签</h2>\n<ul class=\"tag-tree\">{}</ul>\n", kids.join("")));
+        }
+
+        let tagged: Vec<String> = tagged_items.get(&tag.id).into_iter().flatten()
+            .map(|item| format!("<li>{}</li>", item_link(item)))
+            .collect();
+        if !tagged.is_empty() {
+            body.push_str(&format!("<h2>条目</h2>\n<ul>{}</ul>\n", tagged.join("")));
+        }
+
+        pages.insert(tag_page(tag.id), page(&data.title, &tag.name, &body));
+    }
+
+    Site { pages, assets: assets.into_inner() }
+}
+
+fn page(site_title: &str, title: &str, body: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <meta name="viewport" content="width=device-width, initial-scale=1" />
+  <title>{title} - {site}</title>
+  <link rel="stylesheet" href="style.css" />
+  <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/katex.min.css">
+  <script defer src="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/katex.min.js"></script>
+  <script defer src="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/contrib/auto-render.min.js"
+    onload="renderMathInElement(document.body, {{delimiters: [{{left: '$$', right: '$$', display: true}}, {{left: '$', right: '$', display: false}}], throwOnError: false}});"></script>
+  <script type="module">
+    import mermaid from 'https://cdn.jsdelivr.net/npm/mermaid@10.9.1/dist/mermaid.esm.min.mjs';
+    mermaid.initialize({{ startOnLoad: true }});
+  </script>
+</head>
+<body>
+  <nav class="site-nav"><a href="index.html">{site}</a><a href="tags.html">标签</a></nav>
+  <main>
+{body}
+  </main>
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        site = escape_html(site_title),
+        body = body
+    )
+}
+
+/// Stylesheet shared by all exported pages
+const STYLE_CSS: &str = r#"body {
+  margin: 0;
+  font-family: -apple-system, "Segoe UI", "Microsoft YaHei", sans-serif;
+  line-height: 1.6;
+  color: #24292f;
+}
+
+main {
+  max-width: 860px;
+  margin: 0 auto;
+  padding: 16px 24px 48px;
+}
+
+a {
+  color: #0969da;
+}
+
+.site-nav {
+  display: flex;
+  gap: 16px;
+  padding: 10px 24px;
+  border-bottom: 1px solid #d0d7de;
+}
+
+.breadcrumb {
+  font-size: 13px;
+  color: #57606a;
+}
+
+.completed {
+  text-decoration: line-through;
+  opacity: 0.6;
+}
+
+.tag-chip, .inline-tag {
+  display: inline-block;
+  padding: 0 8px;
+  border-radius: 8px;
+  font-size: 0.85em;
+  color: white;
+  text-decoration: none;
+}
+
+.count {
+  font-size: 12px;
+  color: #57606a;
+}
+
+.item-summary {
+  padding: 4px 12px;
+  border-left: 3px solid #d0d7de;
+  color: #57606a;
+}
+
+.wiki-link.unresolved {
+  color: #cf222e;
+  border-bottom: 1px dashed #cf222e;
+}
+
+.toc {
+  padding: 6px 10px;
+  border-left: 3px solid #d0d7de;
+}
+
+.toc ul {
+  list-style: none;
+  padding-left: 0;
+}
+
+.toc-level-2 { padding-left: 12px; }
+.toc-level-3 { padding-left: 24px; }
+.toc-level-4 { padding-left: 36px; }
+
+.admonition {
+  margin: 8px 0;
+  padding: 6px 12px;
+  border-left: 4px solid #0969da;
+  border-radius: 4px;
+  background: rgba(9, 105, 218, 0.06);
+}
+
+.admonition-title {
+  font-weight: bold;
+}
+
+.admonition-tip { border-left-color: #1a7f37; }
+.admonition-important { border-left-color: #8250df; }
+.admonition-warning { border-left-color: #bf8700; }
+.admonition-caution { border-left-color: #cf222e; }
+
+pre {
+  padding: 8px 12px;
+  overflow-x: auto;
+  border-radius: 4px;
+}
+
+pre.mermaid {
+  background: transparent;
+  text-align: center;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ItemType;
+    use crate::repository::{init_db, Repository};
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_export_site_pages_links_and_assets() {
+        let db_state = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let item_repo = ItemRepository::new(db_state.conn.clone());
+        let tag_repo = TagRepository::new(db_state.conn.clone());
+
+        let out = std::env::temp_dir().join(format!("tag-all-site-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&out);
+        std::fs::create_dir_all(&out).unwrap();
+        let image = out.join("source image.png");
+        std::fs::write(&image, b"png").unwrap();
+        let image_path = image.to_string_lossy().replace('\\', "/");
+        let assets_dir = out.join("store");
+        let hash = "ab".repeat(32);
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        std::fs::write(assets_dir.join(format!("{}.png", hash)), b"png").unwrap();
+
+        let target = item_repo.create_with_workspace(&Item::new(0, "Target & Co".to_string(), ItemType::Daily), 1).await.unwrap();
+        let mut source = item_repo.create_with_workspace(&Item::new(0, "Source".to_string(), ItemType::Daily), 1).await.unwrap();
+        source.memo = Some(format!(
+            "See [[Target & Co]] and [[Nowhere]]\n\n![pic](<{}>)\n\n![gone](/no/such/file.png)\n\n![synced](assets/{}.png)\n\n- [x] done",
+            image_path, hash
+        ));
+        item_repo.update(&source).await.unwrap();
+        item_repo.update_links(source.id, source.memo.as_deref()).await.unwrap();
+        let mut child = Item::new(0, "Child".to_string(), ItemType::Daily);
+        child.parent_id = Some(source.id);
+        let child = item_repo.create_with_workspace(&child, 1).await.unwrap();
+        item_repo.create_with_workspace(&Item::new(0, "Other workspace".to_string(), ItemType::Daily), 2).await.unwrap();
+
+        let parent_tag = tag_repo.create(&Tag::with_color(0, "Lang".to_string(), "#ff0000".to_string())).await.unwrap();
+        let tag = tag_repo.create(&Tag::new(0, "Rust".to_string())).await.unwrap();
+        tag_repo.add_parent_tag(tag.id, parent_tag.id).await.unwrap();
+        tag_repo.add_tag_to_item(source.id, tag.id).await.unwrap();
+
+        let report = export_site(db_state.conn.clone(), 1, &out.join("site"), &assets_dir).await.expect("export failed");
+        let site = out.join("site");
+        let read = |name: &str| std::fs::read_to_string(site.join(name)).unwrap_or_default();
+
+        // index + tags + 3 items + 2 tags
+        assert_eq!(report.pages, 7);
+        assert_eq!(report.assets, 2);
+        assert_eq!(report.missing_assets, vec!["/no/such/file.png".to_string()]);
+
+        let index = read("index.html");
+        assert!(index.contains(&format!(r#"href="item-{}.html""#, child.id)));
+        assert!(!index.contains("Other workspace"));
+
+        let source_page = read(&item_page(source.id));
+        assert!(source_page.contains(&format!(r#"<a class="wiki-link" href="item-{}.html">Target &amp; Co</a>"#, target.id)), "{}", source_page);
+        assert!(source_page.contains(r#"<span class="wiki-link unresolved">Nowhere</span>"#));
+        assert!(source_page.contains(&format!(r#"href="tag-{}.html""#, tag.id)));
+        assert!(source_page.contains("background-color: #ff0000;"), "inherited colour: {}", source_page);
+        assert!(source_page.contains("disabled=\"\""));
+        let asset = asset_name(&image_path);
+        assert!(source_page.contains(&format!(r#"<img src="{}""#, asset)), "{}", source_page);
+        assert!(site.join(&asset).exists());
+        let synced = asset_name(&assets_dir.join(format!("{}.png", hash)).to_string_lossy().replace('\\', "/"));
+        assert!(source_page.contains(&format!(r#"<img src="{}""#, synced)), "{}", source_page);
+
+        let target_page = read(&item_page(target.id));
+        assert!(target_page.contains("反向链接"));
+        assert!(target_page.contains(&format!(r#"href="item-{}.html">Source</a>"#, source.id)));
+
+        let tags_page = read("tags.html");
+        let lang_pos = tags_page.find("Lang").unwrap();
+        let rust_pos = tags_page.find("Rust").unwrap();
+        assert!(lang_pos < rust_pos, "child tag nested under its parent");
+        assert!(read(&tag_page(tag.id)).contains(&format!(r#"href="item-{}.html">Source</a>"#, source.id)));
+        assert!(site.join("style.css").exists());
+
+        let _ = std::fs::remove_dir_all(&out);
+    }
+}
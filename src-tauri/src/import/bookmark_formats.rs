@@ -0,0 +1,469 @@
+//! Browser Bookmark Formats
+//!
+//! Reads the three formats browsers export or keep bookmarks in into one
+//! folder tree:
+//! - Netscape bookmark HTML (`<DL>` / `<DT><H3>` / `<DT><A>`), written by
+//!   every browser's "export bookmarks"
+//! - Chrome / Chromium `Bookmarks` JSON from the profile directory
+//! - Firefox `places.sqlite` from the profile directory
+//!
+//! The browsers' own top-level folders (toolbar, menu, "other bookmarks") are
+//! marked as roots so the importer can tell them from the user's folders.
+
+use regex::Regex;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::archive::decode_entities;
+use crate::domain::{DomainError, DomainResult, Timestamp};
+
+/// Milliseconds between 1601-01-01 (Chrome's epoch) and the Unix epoch
+const CHROME_EPOCH_OFFSET_MILLIS: i64 = 11_644_473_600_000;
+
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Firefox's built-in folders (guid, display name) in the order they are imported
+const FIREFOX_ROOTS: &[(&str, &str)] = &[
+    ("toolbar_____", "Bookmarks Toolbar"),
+    ("menu________", "Bookmarks Menu"),
+    ("unfiled_____", "Other Bookmarks"),
+    ("mobile______", "Mobile Bookmarks"),
+];
+/// Folder holding Firefox tags (one sub-folder per tag)
+const FIREFOX_TAGS_ROOT: &str = "tags________";
+
+/// Chrome's built-in folders in the order they are imported
+const CHROME_ROOTS: &[&str] = &["bookmark_bar", "other", "synced"];
+
+/// Where a bookmark file came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookmarkFormat {
+    /// Netscape bookmark HTML
+    Netscape,
+    /// Chrome `Bookmarks` JSON
+    Chrome,
+    /// Firefox `places.sqlite`
+    Firefox,
+}
+
+/// A bookmarked page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkLink {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub added_at: Option<Timestamp>,
+    /// Browser tags (Firefox)
+    pub tags: Vec<String>,
+}
+
+/// A bookmark folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkFolder {
+    pub name: String,
+    /// One of the browser's built-in folders
+    pub root: bool,
+    pub children: Vec<BookmarkNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookmarkNode {
+    Folder(BookmarkFolder),
+    Link(BookmarkLink),
+}
+
+/// Recognise the format from the file contents
+pub fn detect_format(data: &[u8]) -> DomainResult<BookmarkFormat> {
+    if data.starts_with(SQLITE_MAGIC) {
+        return Ok(BookmarkFormat::Firefox);
+    }
+    let head = String::from_utf8_lossy(&data[..data.len().min(4096)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with('{') {
+        return Ok(BookmarkFormat::Chrome);
+    }
+    let lower = head.to_ascii_lowercase();
+    if lower.contains("netscape-bookmark-file") || lower.contains("<dl") {
+        return Ok(BookmarkFormat::Netscape);
+    }
+    Err(DomainError::InvalidInput(
+        "Not a bookmark file (expected bookmark HTML, Chrome Bookmarks JSON or Firefox places.sqlite)".to_string(),
+    ))
+}
+
+/// Read a bookmark file of any supported format
+pub fn read_bookmarks(path: &Path) -> DomainResult<(BookmarkFormat, Vec<BookmarkNode>)> {
+    let data = std::fs::read(path)
+        .map_err(|e| DomainError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+    let format = detect_format(&data)?;
+    let nodes = match format {
+        BookmarkFormat::Netscape => parse_netscape(&String::from_utf8_lossy(&data)),
+        BookmarkFormat::Chrome => parse_chrome(&String::from_utf8_lossy(&data))?,
+        BookmarkFormat::Firefox => read_firefox_places(path)?,
+    };
+    Ok((format, nodes))
+}
+
+/// `NAME="value"` attributes of a tag, names upper-cased
+fn attributes(raw: &str) -> HashMap<String, String> {
+    let attr = Regex::new(r#"(?s)([A-Za-z_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("valid regex");
+    attr.captures_iter(raw)
+        .map(|c| {
+            let value = c.get(2).or_else(|| c.get(3)).map(|m| m.as_str()).unwrap_or_default();
+            (c[1].to_ascii_uppercase(), decode_entities(value))
+        })
+        .collect()
+}
+
+fn inner_text(html: &str) -> String {
+    let tag = Regex::new(r"(?s)<[^>]*>").expect("valid regex");
+    decode_entities(&tag.replace_all(html, "")).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A folder being read: its header (None for a bare `<DL>`) and contents
+#[derive(Default)]
+struct OpenList {
+    header: Option<(String, bool)>,
+    children: Vec<BookmarkNode>,
+}
+
+impl OpenList {
+    /// Append a finished list to its parent; a bare list's contents join the parent
+    fn close_into(self, parent: &mut Vec<BookmarkNode>) {
+        match self.header {
+            Some((name, root)) => parent.push(BookmarkNode::Folder(BookmarkFolder { name, root, children: self.children })),
+            None => parent.extend(self.children),
+        }
+    }
+}
+
+/// Parse Netscape bookmark HTML. The format is loose (unclosed `<DT>` and
+/// `<p>`), so it is read as a stream of `<DL>`, `</DL>`, `<H3>`, `<A>` and
+/// `<DD>` tokens rather than as a document.
+pub fn parse_netscape(html: &str) -> Vec<BookmarkNode> {
+    let token = Regex::new(
+        r"(?is)<dl\b[^>]*>|</dl\s*>|<h3\b([^>]*)>(.*?)</h3\s*>|<a\b([^>]*)>(.*?)</a\s*>|<dd>([^<]*)",
+    )
+    .expect("valid regex");
+
+    let mut lists = vec![OpenList::default()];
+    let mut pending_folder = None;
+    // A <DD> describes the link right before it
+    let mut last_was_link = false;
+    for c in token.captures_iter(html) {
+        let whole = c[0].to_ascii_lowercase();
+        if whole.starts_with("</dl") {
+            if lists.len() > 1 {
+                let list = lists.pop().expect("more than one list");
+                list.close_into(&mut lists.last_mut().expect("outer list").children);
+            }
+            last_was_link = false;
+        } else if whole.starts_with("<dl") {
+            lists.push(OpenList { header: pending_folder.take(), children: Vec::new() });
+            last_was_link = false;
+        } else if let (Some(attrs), Some(name)) = (c.get(1), c.get(2)) {
+            let attrs = attributes(attrs.as_str());
+            let root = attrs.contains_key("PERSONAL_TOOLBAR_FOLDER") || attrs.contains_key("UNFILED_BOOKMARKS_FOLDER");
+            pending_folder = Some((inner_text(name.as_str()), root));
+            last_was_link = false;
+        } else if let (Some(attrs), Some(title)) = (c.get(3), c.get(4)) {
+            let attrs = attributes(attrs.as_str());
+            let link = BookmarkLink {
+                title: inner_text(title.as_str()),
+                url: attrs.get("HREF").cloned().unwrap_or_default(),
+                description: None,
+                added_at: attrs.get("ADD_DATE").and_then(|d| d.trim().parse().ok()).and_then(Timestamp::normalize),
+                tags: attrs.get("TAGS")
+                    .map(|tags| tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect())
+                    .unwrap_or_default(),
+            };
+            lists.last_mut().expect("outer list").children.push(BookmarkNode::Link(link));
+            last_was_link = true;
+        } else if let Some(description) = c.get(5) {
+            let description = inner_text(description.as_str());
+            if let (true, Some(BookmarkNode::Link(link))) = (last_was_link, lists.last_mut().and_then(|l| l.children.last_mut())) {
+                link.description = Some(description).filter(|d| !d.is_empty());
+            }
+            last_was_link = false;
+        }
+    }
+    // Unclosed lists (truncated files) keep what was read
+    while lists.len() > 1 {
+        let list = lists.pop().expect("more than one list");
+        list.close_into(&mut lists.last_mut().expect("outer list").children);
+    }
+    lists.pop().map(|list| list.children).unwrap_or_default()
+}
+
+/// Chrome `date_added`: microseconds since 1601-01-01, as a string
+fn chrome_time(value: Option<&Value>) -> Option<Timestamp> {
+    let micros: i64 = match value? {
+        Value::String(s) => s.parse().ok()?,
+        Value::Number(n) => n.as_i64()?,
+        _ => return None,
+    };
+    Some(micros / 1000 - CHROME_EPOCH_OFFSET_MILLIS).filter(|millis| *millis > 0).map(Timestamp::from_millis)
+}
+
+fn chrome_node(node: &Value, root: bool) -> Option<BookmarkNode> {
+    let name = node.get("name").and_then(Value::as_str).unwrap_or_default().trim().to_string();
+    match node.get("type").and_then(Value::as_str) {
+        Some("url") => Some(BookmarkNode::Link(BookmarkLink {
+            title: name,
+            url: node.get("url").and_then(Value::as_str).unwrap_or_default().to_string(),
+            description: None,
+            added_at: chrome_time(node.get("date_added")),
+            tags: Vec::new(),
+        })),
+        Some("folder") => Some(BookmarkNode::Folder(BookmarkFolder {
+            name,
+            root,
+            children: node.get("children").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(|child| chrome_node(child, false))
+                .collect(),
+        })),
+        _ => None,
+    }
+}
+
+/// Parse Chrome's `Bookmarks` JSON (`{"roots": {"bookmark_bar": {...}, ...}}`)
+pub fn parse_chrome(json: &str) -> DomainResult<Vec<BookmarkNode>> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| DomainError::InvalidInput(format!("Invalid Chrome bookmarks: {}", e)))?;
+    let roots = value.get("roots").and_then(Value::as_object)
+        .ok_or_else(|| DomainError::InvalidInput("Invalid Chrome bookmarks: no roots".to_string()))?;
+    let known = CHROME_ROOTS.iter().filter_map(|key| roots.get(*key));
+    let others = roots.iter().filter(|(key, _)| !CHROME_ROOTS.contains(&key.as_str())).map(|(_, root)| root);
+    Ok(known.chain(others)
+        .filter(|root| root.is_object())
+        .filter_map(|root| chrome_node(root, true))
+        .collect())
+}
+
+/// A row of `moz_bookmarks` joined with its place
+struct PlacesRow {
+    id: i64,
+    kind: i64,
+    parent: i64,
+    title: String,
+    added_micros: Option<i64>,
+    guid: String,
+    fk: Option<i64>,
+    url: Option<String>,
+}
+
+/// Read Firefox's `places.sqlite`. Firefox keeps the database locked while
+/// it runs, so a copy (with its write-ahead log) is read instead.
+pub fn read_firefox_places(path: &Path) -> DomainResult<Vec<BookmarkNode>> {
+    let dir = std::env::temp_dir().join(format!("tag-all-places-{}-{}", std::process::id(), Timestamp::now().millis()));
+    std::fs::create_dir_all(&dir).map_err(|e| DomainError::Internal(e.to_string()))?;
+    let copy = dir.join("places.sqlite");
+    let result = std::fs::copy(path, &copy)
+        .map_err(|e| DomainError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))
+        .and_then(|_| {
+            let wal = path.with_file_name(format!("{}-wal", path.file_name().unwrap_or_default().to_string_lossy()));
+            if wal.is_file() {
+                let _ = std::fs::copy(&wal, dir.join("places.sqlite-wal"));
+            }
+            let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_WRITE)
+                .map_err(|e| DomainError::InvalidInput(format!("Cannot open places database: {}", e)))?;
+            parse_places(&conn)
+        });
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// Build the tree from an open `places.sqlite`
+pub fn parse_places(conn: &Connection) -> DomainResult<Vec<BookmarkNode>> {
+    let invalid = |e: rusqlite::Error| DomainError::InvalidInput(format!("Not a Firefox places database: {}", e));
+    let mut stmt = conn.prepare(
+            "SELECT b.id, b.type, b.parent, COALESCE(NULLIF(b.title, ''), p.title, ''), b.dateAdded, b.guid, b.fk, p.url
+             FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk
+             ORDER BY b.parent, b.position"
+        )
+        .map_err(invalid)?;
+    let rows = stmt.query_map([], |row| {
+            Ok(PlacesRow {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                parent: row.get(2)?,
+                title: row.get(3)?,
+                added_micros: row.get(4)?,
+                guid: row.get(5)?,
+                fk: row.get(6)?,
+                url: row.get(7)?,
+            })
+        })
+        .map_err(invalid)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+
+    let mut children: HashMap<i64, Vec<&PlacesRow>> = HashMap::new();
+    for row in &rows {
+        children.entry(row.parent).or_default().push(row);
+    }
+    // Tags are folders under the tags root holding one entry per tagged place
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    if let Some(tags_root) = rows.iter().find(|r| r.guid == FIREFOX_TAGS_ROOT) {
+        for tag in children.get(&tags_root.id).into_iter().flatten() {
+            for entry in children.get(&tag.id).into_iter().flatten() {
+                if let Some(fk) = entry.fk {
+                    tags.entry(fk).or_default().push(tag.title.clone());
+                }
+            }
+        }
+    }
+
+    fn build(row: &PlacesRow, name: String, root: bool, children: &HashMap<i64, Vec<&PlacesRow>>, tags: &HashMap<i64, Vec<String>>) -> Option<BookmarkNode> {
+        match row.kind {
+            // TYPE_BOOKMARK
+            1 => Some(BookmarkNode::Link(BookmarkLink {
+                title: name,
+                url: row.url.clone().unwrap_or_default(),
+                description: None,
+                added_at: row.added_micros.and_then(|micros| Timestamp::normalize(micros / 1000)),
+                tags: row.fk.and_then(|fk| tags.get(&fk)).cloned().unwrap_or_default(),
+            })),
+            // TYPE_FOLDER
+            2 => Some(BookmarkNode::Folder(BookmarkFolder {
+                name,
+                root,
+                children: children.get(&row.id).into_iter().flatten()
+                    .filter_map(|child| build(child, child.title.clone(), false, children, tags))
+                    .collect(),
+            })),
+            // Separators
+            _ => None,
+        }
+    }
+
+    Ok(FIREFOX_ROOTS.iter()
+        .filter_map(|(guid, name)| {
+            let row = rows.iter().find(|r| r.guid == *guid)?;
+            build(row, name.to_string(), true, &children, &tags)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(node: &BookmarkNode) -> &BookmarkLink {
+        match node {
+            BookmarkNode::Link(link) => link,
+            other => panic!("expected a link, got {:?}", other),
+        }
+    }
+
+    fn folder(node: &BookmarkNode) -> &BookmarkFolder {
+        match node {
+            BookmarkNode::Folder(folder) => folder,
+            other => panic!("expected a folder, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_netscape() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://doc.rust-lang.org/book/" ADD_DATE="1700000100" TAGS="rust,docs">The Rust &amp; Book</A>
+        <DD>Learn Rust
+        <DT><H3>Dev</H3>
+        <DL><p>
+            <DT><A HREF="https://github.com/">GitHub</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/">Example</A>
+</DL><p>"#;
+        assert_eq!(detect_format(html.as_bytes()).unwrap(), BookmarkFormat::Netscape);
+
+        let nodes = parse_netscape(html);
+        assert_eq!(nodes.len(), 2);
+        let bar = folder(&nodes[0]);
+        assert_eq!((bar.name.as_str(), bar.root), ("Bookmarks bar", true));
+        let book = link(&bar.children[0]);
+        assert_eq!(book.title, "The Rust & Book");
+        assert_eq!(book.url, "https://doc.rust-lang.org/book/");
+        assert_eq!(book.description.as_deref(), Some("Learn Rust"));
+        assert_eq!(book.added_at, Some(Timestamp::from_millis(1_700_000_100_000)));
+        assert_eq!(book.tags, vec!["rust", "docs"]);
+        let dev = folder(&bar.children[1]);
+        assert_eq!((dev.name.as_str(), dev.root), ("Dev", false));
+        assert_eq!(link(&dev.children[0]).url, "https://github.com/");
+        assert_eq!(link(&nodes[1]).title, "Example");
+    }
+
+    #[test]
+    fn test_parse_chrome() {
+        let json = r#"{"checksum": "x", "roots": {
+            "bookmark_bar": {"type": "folder", "name": "Bookmarks bar", "children": [
+                {"type": "url", "name": "Rust", "url": "https://www.rust-lang.org/", "date_added": "13345000000000000"},
+                {"type": "folder", "name": "Dev", "children": [{"type": "url", "name": "GitHub", "url": "https://github.com/", "date_added": "0"}]}
+            ]},
+            "other": {"type": "folder", "name": "Other bookmarks", "children": []},
+            "sync_transaction_version": "1"
+        }, "version": 1}"#;
+        assert_eq!(detect_format(json.as_bytes()).unwrap(), BookmarkFormat::Chrome);
+
+        let nodes = parse_chrome(json).unwrap();
+        assert_eq!(nodes.len(), 2);
+        let bar = folder(&nodes[0]);
+        assert!(bar.root);
+        let rust = link(&bar.children[0]);
+        assert_eq!(rust.added_at, Some(Timestamp::from_millis(13_345_000_000_000 - CHROME_EPOCH_OFFSET_MILLIS)));
+        let dev = folder(&bar.children[1]);
+        assert!(!dev.root);
+        assert_eq!(link(&dev.children[0]).added_at, None);
+        assert_eq!(folder(&nodes[1]).name, "Other bookmarks");
+
+        assert!(parse_chrome("{}").is_err());
+        assert!(detect_format(b"just text").is_err());
+    }
+
+    #[test]
+    fn test_parse_places() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+             CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER,
+                position INTEGER, title TEXT, dateAdded INTEGER, guid TEXT);
+             INSERT INTO moz_places VALUES (1, 'https://www.mozilla.org/', 'Mozilla'), (2, 'https://developer.mozilla.org/', 'MDN');
+             INSERT INTO moz_bookmarks VALUES
+                (1, 2, NULL, 0, 0, '', 0, 'root________'),
+                (2, 2, NULL, 1, 0, 'menu', 0, 'menu________'),
+                (3, 2, NULL, 1, 1, 'toolbar', 0, 'toolbar_____'),
+                (4, 2, NULL, 1, 2, 'tags', 0, 'tags________'),
+                (5, 2, NULL, 1, 3, 'unfiled', 0, 'unfiled_____'),
+                (10, 1, 1, 3, 0, NULL, 1700000000000000, 'a'),
+                (11, 2, NULL, 3, 1, 'Web', 0, 'b'),
+                (12, 3, NULL, 11, 0, NULL, 0, 'c'),
+                (13, 1, 2, 11, 1, 'Docs', 0, 'd'),
+                (20, 2, NULL, 4, 0, 'reference', 0, 'e'),
+                (21, 1, 2, 20, 0, NULL, 0, 'f');",
+        )
+        .unwrap();
+
+        let nodes = parse_places(&conn).unwrap();
+        // Toolbar, menu and unfiled in import order; the tags root is not a folder
+        let names: Vec<_> = nodes.iter().map(|n| folder(n).name.as_str()).collect();
+        assert_eq!(names, vec!["Bookmarks Toolbar", "Bookmarks Menu", "Other Bookmarks"]);
+        let toolbar = folder(&nodes[0]);
+        let mozilla = link(&toolbar.children[0]);
+        assert_eq!((mozilla.title.as_str(), mozilla.url.as_str()), ("Mozilla", "https://www.mozilla.org/"));
+        assert_eq!(mozilla.added_at, Some(Timestamp::from_millis(1_700_000_000_000)));
+        let web = folder(&toolbar.children[1]);
+        assert_eq!(web.children.len(), 1, "separator skipped");
+        let docs = link(&web.children[0]);
+        assert_eq!((docs.title.as_str(), docs.tags.clone()), ("Docs", vec!["reference".to_string()]));
+    }
+}
@@ -0,0 +1,317 @@
+//! Bookmark Import
+//!
+//! Adds browser bookmarks (see `bookmark_formats`) to the web-bookmarks
+//! workspace as `Bookmark` items tagged `web-bookmark`, like clipped pages.
+//! URLs already saved there (compared by their normalised key) are skipped,
+//! so importing the same file again only adds what is new.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::bookmark_formats::{read_bookmarks, BookmarkFolder, BookmarkFormat, BookmarkLink, BookmarkNode};
+use crate::domain::{DomainError, DomainResult, Item, ItemType, Timestamp, BOOKMARK_TAG, BOOKMARK_WORKSPACE_ID};
+use crate::repository::item::{ItemLinkOperations, ItemSearchOperations, ItemWorkspaceOperations};
+use crate::repository::tag::{InlineTagOperations, ItemTagOperations, TagAliasOperations, TagColorOperations, TagHierarchyOperations};
+use crate::repository::{ItemRepository, Repository, TagRepository};
+
+/// What bookmark folders become
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookmarkFolders {
+    /// Document items the bookmarks are nested under (existing ones of the
+    /// same name and place are reused)
+    #[default]
+    Items,
+    /// Tags; a sub-folder's tag gets its folder's tag as parent. The
+    /// browser's own top-level folders are left out.
+    Tags,
+}
+
+/// Result of an `import_bookmarks` run
+#[derive(Debug, Clone, Serialize)]
+pub struct BookmarkImportReport {
+    pub format: BookmarkFormat,
+    /// Bookmarks added
+    pub imported: usize,
+    /// Bookmarks whose URL was already saved (or came earlier in the file)
+    pub duplicates: usize,
+    /// Entries that are not web pages (`javascript:`, `place:`, empty, ...)
+    pub skipped: usize,
+    /// Folder items created (`Items`) or folder tags used (`Tags`)
+    pub folders: usize,
+    /// Tags that did not exist before
+    pub tags_created: usize,
+}
+
+/// A bookmark with the folders it sits in, outermost first
+struct Entry<'a> {
+    path: Vec<&'a BookmarkFolder>,
+    link: &'a BookmarkLink,
+}
+
+fn flatten<'a>(nodes: &'a [BookmarkNode], path: &mut Vec<&'a BookmarkFolder>, out: &mut Vec<Entry<'a>>) {
+    for node in nodes {
+        match node {
+            BookmarkNode::Link(link) => out.push(Entry { path: path.clone(), link }),
+            BookmarkNode::Folder(folder) => {
+                path.push(folder);
+                flatten(&folder.children, path, out);
+                path.pop();
+            }
+        }
+    }
+}
+
+fn is_web_url(url: &str) -> bool {
+    let lower = url.trim().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Creates bookmarks, folders and tags for one import, remembering what it
+/// resolved so each folder and tag is looked up once
+struct Importer {
+    items: ItemRepository,
+    tags: TagRepository,
+    folders: BookmarkFolders,
+    /// (parent id, name) -> folder item in the bookmarks workspace
+    folder_items: HashMap<(Option<u32>, String), u32>,
+    /// tag name -> id
+    tag_ids: HashMap<String, u32>,
+    /// (child, parent) tag edges already ensured
+    tag_edges: HashSet<(u32, u32)>,
+    /// Folder tags applied so far
+    folder_tags: HashSet<u32>,
+    report: BookmarkImportReport,
+}
+
+impl Importer {
+    /// The tag with this name or alias, created (with the next palette colour)
+    /// if missing
+    async fn tag(&mut self, name: &str) -> DomainResult<u32> {
+        if let Some(id) = self.tag_ids.get(name) {
+            return Ok(*id);
+        }
+        let id = match self.tags.resolve_tag_name(name).await? {
+            Some(existing) => existing.id,
+            None => {
+                self.report.tags_created += 1;
+                self.tags.create_with_palette_color(name).await?.id
+            }
+        };
+        self.tag_ids.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    /// The folder item for a folder path, creating the missing levels
+    async fn folder_item(&mut self, path: &[&BookmarkFolder]) -> DomainResult<Option<u32>> {
+        let mut parent = None;
+        for folder in path.iter().filter(|f| !f.name.trim().is_empty()) {
+            let key = (parent, folder.name.trim().to_string());
+            parent = Some(match self.folder_items.get(&key) {
+                Some(id) => *id,
+                None => {
+                    let mut item = Item::new(0, key.1.clone(), ItemType::Document);
+                    item.parent_id = parent;
+                    let created = self.items.create_with_workspace(&item, BOOKMARK_WORKSPACE_ID).await?;
+                    self.report.folders += 1;
+                    self.folder_items.insert(key, created.id);
+                    created.id
+                }
+            });
+        }
+        Ok(parent)
+    }
+
+    /// The tag of the innermost user folder, nested under its folder's tag
+    async fn folder_tag(&mut self, path: &[&BookmarkFolder]) -> DomainResult<Option<u32>> {
+        let mut parent: Option<u32> = None;
+        for folder in path.iter().filter(|f| !f.root && !f.name.trim().is_empty()) {
+            let id = self.tag(folder.name.trim()).await?;
+            if let Some(parent_id) = parent.filter(|p| *p != id) {
+                if self.tag_edges.insert((id, parent_id)) {
+                    // An existing edge the other way round would make a cycle; keep the tags flat then
+                    match self.tags.add_parent_tag(id, parent_id).await {
+                        Ok(()) | Err(DomainError::Conflict(_)) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            parent = Some(id);
+        }
+        if let Some(id) = parent {
+            if self.folder_tags.insert(id) {
+                self.report.folders += 1;
+            }
+        }
+        Ok(parent)
+    }
+
+    async fn import(&mut self, entry: &Entry<'_>) -> DomainResult<()> {
+        let url = entry.link.url.trim();
+        if !is_web_url(url) {
+            self.report.skipped += 1;
+            return Ok(());
+        }
+        if self.items.find_by_url(url, Some(BOOKMARK_WORKSPACE_ID)).await?.is_some() {
+            self.report.duplicates += 1;
+            return Ok(());
+        }
+
+        let (parent_id, folder_tag) = match self.folders {
+            BookmarkFolders::Items => (self.folder_item(&entry.path).await?, None),
+            BookmarkFolders::Tags => (None, self.folder_tag(&entry.path).await?),
+        };
+        let title = entry.link.title.trim();
+        let mut item = Item::new(0, if title.is_empty() { url.to_string() } else { title.to_string() }, ItemType::Bookmark);
+        item.parent_id = parent_id;
+        item.url = Some(url.to_string());
+        item.summary = entry.link.description.clone();
+        item.created_at = entry.link.added_at.filter(|t| *t <= Timestamp::now());
+        let created = match self.items.create_with_workspace(&item, BOOKMARK_WORKSPACE_ID).await {
+            Ok(created) => created,
+            Err(DomainError::Conflict(_)) => {
+                self.report.duplicates += 1;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut tag_ids = vec![self.tag(BOOKMARK_TAG).await?];
+        for name in entry.link.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            tag_ids.push(self.tag(name).await?);
+        }
+        tag_ids.extend(folder_tag);
+        for tag_id in tag_ids {
+            self.tags.add_tag_to_item(created.id, tag_id).await?;
+        }
+        self.tags.sync_inline_tags(created.id, &created.text, created.memo.as_deref()).await?;
+        self.report.imported += 1;
+        Ok(())
+    }
+}
+
+/// Import a Netscape bookmark HTML file, a Chrome `Bookmarks` file or a
+/// Firefox `places.sqlite` (format detected from the contents) into the
+/// web-bookmarks workspace
+pub async fn import_bookmarks(
+    conn: Arc<Mutex<Option<Connection>>>,
+    path: &Path,
+    folders: BookmarkFolders,
+) -> DomainResult<BookmarkImportReport> {
+    let (format, nodes) = read_bookmarks(path)?;
+    let mut entries = Vec::new();
+    flatten(&nodes, &mut Vec::new(), &mut entries);
+
+    let items = ItemRepository::new(conn.clone());
+    let folder_items = items.list_by_workspace(BOOKMARK_WORKSPACE_ID).await?
+        .into_iter()
+        .filter(|item| item.item_type == ItemType::Document && item.url.is_none())
+        .map(|item| ((item.parent_id, item.text), item.id))
+        .collect();
+    let mut importer = Importer {
+        items,
+        tags: TagRepository::new(conn),
+        folders,
+        folder_items,
+        tag_ids: HashMap::new(),
+        tag_edges: HashSet::new(),
+        folder_tags: HashSet::new(),
+        report: BookmarkImportReport { format, imported: 0, duplicates: 0, skipped: 0, folders: 0, tags_created: 0 },
+    };
+    for entry in &entries {
+        importer.import(entry).await?;
+    }
+    // Memos may already link to imported titles
+    importer.items.resolve_pending_links().await?;
+    Ok(importer.report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::init_db;
+    use crate::repository::item::ItemHierarchyOperations;
+    use std::path::PathBuf;
+
+    const BOOKMARKS: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><H3 PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><H3>Dev</H3>
+        <DL><p>
+            <DT><H3>Rust</H3>
+            <DL><p>
+                <DT><A HREF="https://doc.rust-lang.org/book/" ADD_DATE="1700000000" TAGS="docs">The Book</A>
+                <DT><A HREF="https://doc.rust-lang.org/book?utm_source=x">The Book again</A>
+            </DL><p>
+            <DT><A HREF="https://github.com/">GitHub</A>
+        </DL><p>
+        <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        <DT><A HREF="https://example.com/">Example</A>
+    </DL><p>
+</DL><p>"#;
+
+    async fn setup(name: &str) -> (crate::repository::DbState, PathBuf) {
+        let db_path = PathBuf::from(":memory:");
+        let db = init_db(&db_path).await.unwrap();
+        let file = std::env::temp_dir().join(format!("tag-all-bookmarks-{}-{}.html", name, std::process::id()));
+        std::fs::write(&file, BOOKMARKS).unwrap();
+        (db, file)
+    }
+
+    #[tokio::test]
+    async fn test_import_bookmarks_as_items() {
+        let (db, file) = setup("items").await;
+        let items = ItemRepository::new(db.conn.clone());
+        let mut saved = Item::new(0, "Saved".to_string(), ItemType::Bookmark);
+        saved.url = Some("https://EXAMPLE.com".to_string());
+        items.create_with_workspace(&saved, BOOKMARK_WORKSPACE_ID).await.unwrap();
+
+        let report = import_bookmarks(db.conn.clone(), &file, BookmarkFolders::Items).await.unwrap();
+        assert_eq!(report.format, BookmarkFormat::Netscape);
+        assert_eq!((report.imported, report.duplicates, report.skipped, report.folders), (2, 2, 1, 3));
+
+        let book = items.find_by_url("https://doc.rust-lang.org/book/", Some(BOOKMARK_WORKSPACE_ID)).await.unwrap().unwrap();
+        assert_eq!(book.text, "The Book");
+        assert_eq!(book.created_at, Some(Timestamp::from_millis(1_700_000_000_000)));
+        let rust = items.find_by_id(book.parent_id.unwrap()).await.unwrap().unwrap();
+        let dev = items.find_by_id(rust.parent_id.unwrap()).await.unwrap().unwrap();
+        let bar = items.find_by_id(dev.parent_id.unwrap()).await.unwrap().unwrap();
+        assert_eq!((rust.text.as_str(), dev.text.as_str(), bar.text.as_str(), bar.parent_id), ("Rust", "Dev", "Bookmarks bar", None));
+        assert_eq!(items.get_children(Some(dev.id)).await.unwrap().len(), 2);
+
+        let tag_names: Vec<String> = TagRepository::new(db.conn.clone()).get_tags_for_item(book.id).await.unwrap()
+            .into_iter().map(|t| t.name).collect();
+        assert!(tag_names.contains(&BOOKMARK_TAG.to_string()) && tag_names.contains(&"docs".to_string()), "{:?}", tag_names);
+
+        // Importing again adds nothing and reuses the folders
+        let again = import_bookmarks(db.conn.clone(), &file, BookmarkFolders::Items).await.unwrap();
+        assert_eq!((again.imported, again.duplicates, again.folders), (0, 4, 0));
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[tokio::test]
+    async fn test_import_bookmarks_as_tags() {
+        let (db, file) = setup("tags").await;
+        let report = import_bookmarks(db.conn.clone(), &file, BookmarkFolders::Tags).await.unwrap();
+        assert_eq!((report.imported, report.duplicates, report.skipped, report.folders), (3, 1, 1, 2));
+
+        let items = ItemRepository::new(db.conn.clone());
+        let tags = TagRepository::new(db.conn.clone());
+        let book = items.find_by_url("https://doc.rust-lang.org/book", Some(BOOKMARK_WORKSPACE_ID)).await.unwrap().unwrap();
+        assert_eq!(book.parent_id, None);
+        let tag_names: Vec<String> = tags.get_tags_for_item(book.id).await.unwrap().into_iter().map(|t| t.name).collect();
+        assert!(tag_names.contains(&"Rust".to_string()) && !tag_names.contains(&"Dev".to_string()), "{:?}", tag_names);
+        // The browser's toolbar folder is not a tag
+        assert!(tags.resolve_tag_name("Bookmarks bar").await.unwrap().is_none());
+
+        let rust = tags.resolve_tag_name("Rust").await.unwrap().unwrap();
+        let parents: Vec<String> = tags.get_parent_tags(rust.id).await.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(parents, vec!["Dev"]);
+        let _ = std::fs::remove_file(&file);
+    }
+}
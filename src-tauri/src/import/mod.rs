@@ -0,0 +1,10 @@
+//! Import Layer
+//!
+//! Brings data from other applications into the app:
+//! - bookmarks: Browser bookmarks (Netscape HTML, Chrome JSON, Firefox places.sqlite)
+
+mod bookmark_formats;
+mod bookmarks;
+
+pub use bookmark_formats::BookmarkFormat;
+pub use bookmarks::{import_bookmarks, BookmarkFolders, BookmarkImportReport};
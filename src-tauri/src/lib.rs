@@ -4,14 +4,33 @@
 //! - domain: Core entities and business rules
 //! - repository: Data access abstractions and implementations
 //! - commands: Tauri command handlers
+//! - export: Publishing workspaces (static HTML site)
+//! - asset_sync: Copying asset files to a remote store alongside database sync
+//! - asset_protocol: Serving scoped local files to the webview (`asset://`)
+//! - local_api: Opt-in localhost HTTP API for the browser clipper
+//! - archive: Offline snapshots of bookmarked pages
+//! - link_check: Finding broken bookmark links in the background
+//! - sync_conflict: Keeping both versions of rows edited on two devices
+//! - sync_scheduler: Background cloud sync (debounced, periodic, retried)
+//! - cloud_migration: Staged, verified move of the local database onto cloud sync
 
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use tauri::{Manager, Emitter};
-use percent_encoding::percent_decode_str;
 
 mod domain;
 mod repository;
 mod commands;
+mod export;
+mod import;
+mod asset_sync;
+mod asset_protocol;
+mod local_api;
+mod archive;
+mod link_check;
+mod sync_conflict;
+mod sync_scheduler;
+mod cloud_migration;
 
 use repository::{init_db, DbState};
 
@@ -19,6 +38,12 @@ use repository::{init_db, DbState};
 pub struct AppState {
     pub db_state: DbState,
     pub db_path: PathBuf,
+    /// Files the asset protocol may serve (refreshed when workspace dirs or allowed paths change)
+    pub asset_scope: Arc<RwLock<asset_protocol::AssetScope>>,
+    /// Running local API server, if enabled
+    pub local_api: tokio::sync::Mutex<Option<local_api::LocalApiServer>>,
+    /// Background sync, running while cloud sync is configured
+    pub sync_scheduler: std::sync::Mutex<Option<sync_scheduler::SyncScheduler>>,
 }
 
 /// Get database path from app handle
@@ -34,50 +59,20 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .register_asynchronous_uri_scheme_protocol("asset", |_ctx, request, responder| {
+        .register_asynchronous_uri_scheme_protocol("asset", |ctx, request, responder| {
+            let app_handle = ctx.app_handle().clone();
             std::thread::spawn(move || {
-                let path = request.uri().path();
-                // Decode path (percent-decoded)
-                let path = percent_decode_str(path)
-                    .decode_utf8_lossy()
-                    .to_string();
-
-                // Handle Windows paths: /C:/Users... -> C:/Users...
-                let path = if path.starts_with('/') && path.chars().nth(2) == Some(':') {
-                    path[1..].to_string()
-                } else {
-                    path
-                };
-
-                let path = std::path::PathBuf::from(&path);
-                
-                if !path.exists() {
-                    let response = tauri::http::Response::builder()
-                        .status(404)
-                        .body(Vec::new())
-                        .expect("Failed to build 404 response");
-                    responder.respond(response);
-                    return;
-                }
-                
-                match std::fs::read(&path) {
-                    Ok(content) => {
-                        let mime_type = mime_guess::from_path(&path).first_or_octet_stream();
-                        let response = tauri::http::Response::builder()
-                            .header("Content-Type", mime_type.as_ref())
-                            .header("Access-Control-Allow-Origin", "*")
-                            .body(content)
-                            .expect("Failed to build response");
-                        responder.respond(response);
-                    }
-                    Err(_) => {
-                        let response = tauri::http::Response::builder()
-                            .status(500)
-                            .body(Vec::new())
-                            .expect("Failed to build 500 response");
-                        responder.respond(response);
+                let response = match app_handle.try_state::<AppState>() {
+                    Some(state) => {
+                        let scope = state.asset_scope.read().unwrap_or_else(|e| e.into_inner());
+                        asset_protocol::respond(&request, &scope)
                     }
-                }
+                    None => tauri::http::Response::builder()
+                        .status(503)
+                        .body(Vec::new())
+                        .expect("Failed to build 503 response"),
+                };
+                responder.respond(response);
             });
         })
         .setup(|app| {
@@ -101,6 +96,15 @@ pub fn run() {
             
             let db_path = get_db_path(&app_handle);
             
+            // User-provided .sublime-syntax files for code highlighting (built off the main thread)
+            if let Some(syntax_dir) = db_path.parent().map(|dir| dir.join("syntaxes")).filter(|dir| dir.is_dir()) {
+                tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(e) = tag_all_markdown::load_extra_syntaxes(&syntax_dir) {
+                        let _ = rolling_logger::error(&format!("Failed to load syntaxes: {}", e));
+                    }
+                });
+            }
+            
             eprintln!("[{}] App setup starting", chrono::Local::now().format("%H:%M:%S%.3f"));
             
             // Create initial empty DbState (managed)
@@ -110,6 +114,9 @@ pub fn run() {
             app.manage(AppState {
                 db_state: db_state.clone(),
                 db_path: db_path.clone(),
+                asset_scope: Arc::new(RwLock::new(asset_protocol::AssetScope::default())),
+                local_api: tokio::sync::Mutex::new(None),
+                sync_scheduler: std::sync::Mutex::new(None),
             });
             
             eprintln!("[{}] State managed, app will start immediately", chrono::Local::now().format("%H:%M:%S%.3f"));
@@ -134,6 +141,17 @@ pub fn run() {
                         
                         eprintln!("[{}] Background: DbState updated", chrono::Local::now().format("%H:%M:%S%.3f"));
                         
+                        if let Err(e) = commands::refresh_asset_scope(&app_handle.state::<AppState>()).await {
+                            let _ = rolling_logger::error(&format!("Failed to load asset scope: {}", e));
+                        }
+                        
+                        if let Err(e) = commands::restart_local_api(&app_handle).await {
+                            let _ = rolling_logger::error(&format!("Failed to start local API: {}", e));
+                        }
+                        
+                        // First background sync runs right away
+                        commands::restart_sync_scheduler(&app_handle);
+                        
                         // Emit event to notify frontend
                         eprintln!("[{}] Background: Emitting db-initialized event", chrono::Local::now().format("%H:%M:%S%.3f"));
                         if let Err(e) = app_handle.emit("db-initialized", ()) {
@@ -149,6 +167,16 @@ pub fn run() {
             
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Pull other devices' changes when the user comes back to the app
+            if let tauri::WindowEvent::Focused(true) = event {
+                if let Some(state) = window.try_state::<AppState>() {
+                    if let Some(scheduler) = state.sync_scheduler.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                        scheduler.window_focused();
+                    }
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Level 1-2: Item CRUD + Hierarchy
             commands::create_item,
@@ -165,11 +193,20 @@ pub fn run() {
             commands::decrement_item,
             commands::set_item_count,
             commands::reset_all_items,
+            commands::find_item_by_url,
+            // Level 13: Wiki links + backlinks
+            commands::get_backlinks,
+            commands::get_outgoing_links,
+            commands::resolve_item_link,
             // Level 3: Tag CRUD + Item-Tag relationships
             commands::create_tag,
             commands::list_tags,
             commands::get_tag,
             commands::update_tag,
+            commands::update_tag_memo,
+            commands::get_tag_effective_color,
+            commands::get_tag_palette,
+            commands::set_tag_palette,
             commands::delete_tag,
             commands::add_item_tag,
             commands::remove_item_tag,
@@ -183,6 +220,24 @@ pub fn run() {
             commands::get_root_tags,
             commands::move_tag,
             commands::move_child_tag,
+            commands::check_tag_graph,
+            commands::tag_stats,
+            // Level 10: Tag aliases + merge
+            commands::resolve_tag,
+            commands::add_tag_alias,
+            commands::remove_tag_alias,
+            commands::get_tag_aliases,
+            commands::list_tag_aliases,
+            commands::merge_tags,
+            // Export
+            commands::export_site,
+            commands::export_bookmarks,
+            // Import
+            commands::import_bookmarks,
+            // Code highlighting (syntect runs here, not in the WASM bundle)
+            commands::highlight_code,
+            commands::highlight_css,
+            commands::list_highlight_themes,
             // Level 4: Window state
             commands::save_window_state,
             commands::load_window_state,
@@ -202,6 +257,9 @@ pub fn run() {
             commands::toggle_workspace_dir_collapsed,
             // Cloud Sync
             commands::configure_cloud_sync,
+            commands::confirm_cloud_migration,
+            commands::restore_cloud_migration,
+            commands::has_pending_cloud_migration,
             commands::get_cloud_sync_config,
             commands::save_cloud_sync_config,
             commands::configure_sync,
@@ -210,14 +268,42 @@ pub fn run() {
             commands::get_sync_config,
             commands::get_sync_status,
             commands::is_cloud_sync_enabled,
+            commands::get_asset_sync_config,
+            commands::save_asset_sync_config,
+            commands::sync_assets,
             // Level 7: Files
             commands::list_directory,
             commands::ensure_file_item,
             commands::pick_folder,
+            commands::pick_file,
+            commands::pick_save_file,
             commands::open_file,
             // Clipboard
             commands::save_clipboard_image,
+            // Level 15: Content-addressed assets
+            commands::save_asset_data,
+            commands::import_asset_file,
             commands::clean_unused_assets,
+            commands::get_assets_dir,
+            // Asset protocol scope
+            commands::list_allowed_asset_paths,
+            commands::allow_asset_path,
+            commands::disallow_asset_path,
+            // Level 18: Offline page archives
+            commands::archive_url,
+            commands::get_archive,
+            commands::search_archives,
+            commands::delete_archive,
+            // Level 19: Link health
+            commands::start_link_check,
+            commands::list_broken_links,
+            // Level 20: Sync conflicts
+            commands::list_conflicts,
+            commands::resolve_conflict,
+            // Local API (browser clipper)
+            commands::get_local_api_config,
+            commands::save_local_api_config,
+            commands::regenerate_local_api_token,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,235 @@
+//! Link Check Layer
+//!
+//! Requests the URL of every bookmark (or other item with a web URL) and
+//! records the outcome in the item row (see `domain::LinkCheck`), so dead
+//! bookmarks can be listed and cleaned up.
+//!
+//! Requests go through a `LinkProber`; the app uses `HttpProber`, tests can
+//! point one at a local server or replace it entirely.
+
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri_plugin_http::reqwest::{self, Method, Url};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+use crate::domain::{DomainError, DomainResult, LinkCheck, Timestamp};
+use crate::repository::item::ItemLinkHealthOperations;
+use crate::repository::ItemRepository;
+
+/// Event carrying a `LinkCheckProgress` while a check runs
+pub const LINK_CHECK_PROGRESS_EVENT: &str = "link-check-progress";
+
+/// Requests in flight at once
+const CONCURRENCY: usize = 8;
+
+/// Requests a URL and reports how it went
+#[async_trait]
+pub trait LinkProber: Send + Sync {
+    async fn probe(&self, url: &str) -> LinkCheck;
+}
+
+/// Probes over HTTP(S), following redirects
+pub struct HttpProber {
+    client: reqwest::Client,
+}
+
+impl HttpProber {
+    pub fn new() -> DomainResult<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("Mozilla/5.0 (compatible; tag-all/", env!("CARGO_PKG_VERSION"), ")"))
+            .timeout(Duration::from_secs(20))
+            .build()
+            .map_err(|e| DomainError::Internal(format!("HTTP client: {}", e)))?;
+        Ok(Self::with_client(client))
+    }
+
+    /// Probe with a preconfigured client
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    async fn request(&self, method: Method, url: &str) -> Result<reqwest::Response, reqwest::Error> {
+        self.client.request(method, url).send().await
+    }
+}
+
+#[async_trait]
+impl LinkProber for HttpProber {
+    async fn probe(&self, url: &str) -> LinkCheck {
+        // HEAD is cheap, but some servers reject or mishandle it; a failed
+        // HEAD is confirmed with a GET (whose body is never read)
+        let response = match self.request(Method::HEAD, url).await {
+            Ok(response) if !response.status().is_client_error() && !response.status().is_server_error() => Ok(response),
+            _ => self.request(Method::GET, url).await,
+        };
+        let checked_at = Timestamp::now();
+        match response {
+            Ok(response) => {
+                let redirect_to = Some(response.url().clone())
+                    .filter(|final_url| Url::parse(url).ok().as_ref() != Some(final_url))
+                    .map(String::from);
+                LinkCheck { status: Some(response.status().as_u16()), redirect_to, error: None, checked_at }
+            }
+            Err(e) => LinkCheck { status: None, redirect_to: None, error: Some(error_chain(&e)), checked_at },
+        }
+    }
+}
+
+/// reqwest's own message ("error sending request") hides the cause
+fn error_chain(e: &dyn Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// How far a link check has got
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LinkCheckProgress {
+    pub total: usize,
+    pub checked: usize,
+    pub broken: usize,
+    pub done: bool,
+}
+
+/// Check the links of items in `workspace_id` (all workspaces if None) that
+/// were never checked or last checked before `checked_before`. Progress is
+/// reported after every link; the last report has `done` set.
+pub async fn check_links(
+    conn: Arc<Mutex<Option<Connection>>>,
+    prober: Arc<dyn LinkProber>,
+    workspace_id: Option<u32>,
+    checked_before: Option<Timestamp>,
+    on_progress: impl Fn(&LinkCheckProgress),
+) -> DomainResult<LinkCheckProgress> {
+    let items = ItemRepository::new(conn);
+    let links = items.links_to_check(workspace_id, checked_before).await?;
+    let mut progress = LinkCheckProgress { total: links.len(), ..Default::default() };
+    on_progress(&progress);
+
+    let mut pending = links.into_iter();
+    let mut running = JoinSet::new();
+    loop {
+        while running.len() < CONCURRENCY {
+            let Some((item_id, url)) = pending.next() else { break };
+            let prober = prober.clone();
+            running.spawn(async move { (item_id, prober.probe(&url).await) });
+        }
+        let Some(joined) = running.join_next().await else { break };
+        let (item_id, check) = joined.map_err(|e| DomainError::Internal(format!("Link check task failed: {}", e)))?;
+        items.record_link_check(item_id, &check).await?;
+        progress.checked += 1;
+        if check.is_broken() {
+            progress.broken += 1;
+        }
+        on_progress(&progress);
+    }
+
+    progress.done = true;
+    on_progress(&progress);
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Item, ItemType};
+    use crate::repository::init_db;
+    use crate::repository::item::ItemWorkspaceOperations;
+    use crate::repository::Repository;
+    use std::path::PathBuf;
+    use std::sync::Mutex as StdMutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal HTTP/1.1 server: /ok is 200, /old redirects to /ok,
+    /// /head-only rejects HEAD, everything else is 404
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { break };
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    let mut words = request.split_whitespace();
+                    let (method, path) = (words.next().unwrap_or(""), words.next().unwrap_or(""));
+                    let head = match (method, path) {
+                        (_, "/ok") => "200 OK\r\nContent-Length: 2",
+                        (_, "/old") => "301 Moved Permanently\r\nLocation: /ok\r\nContent-Length: 0",
+                        ("HEAD", "/head-only") => "405 Method Not Allowed\r\nContent-Length: 0",
+                        ("GET", "/head-only") => "200 OK\r\nContent-Length: 2",
+                        _ => "404 Not Found\r\nContent-Length: 0",
+                    };
+                    let body = if method == "GET" && head.ends_with(": 2") { "ok" } else { "" };
+                    let response = format!("HTTP/1.1 {}\r\nConnection: close\r\n\r\n{}", head, body);
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_check_links_against_local_server() {
+        let base = serve().await;
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let mut ids = Vec::new();
+        for (name, url) in [
+            ("ok", format!("{}/ok", base)),
+            ("moved", format!("{}/old", base)),
+            ("head-only", format!("{}/head-only", base)),
+            ("gone", format!("{}/gone", base)),
+            ("closed", "http://127.0.0.1:1/".to_string()),
+            ("not web", "file:///tmp/x".to_string()),
+        ] {
+            let mut item = Item::new(0, name.to_string(), ItemType::Bookmark);
+            item.url = Some(url);
+            ids.push(items.create_with_workspace(&item, 4).await.unwrap().id);
+        }
+        let before = items.find_by_id(ids[0]).await.unwrap().unwrap();
+
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let prober: Arc<dyn LinkProber> = Arc::new(HttpProber::with_client(client));
+        let reports = StdMutex::new(Vec::new());
+        let summary = check_links(db.conn.clone(), prober.clone(), Some(4), None, |p| reports.lock().unwrap().push(p.clone()))
+            .await
+            .unwrap();
+        assert_eq!(summary, LinkCheckProgress { total: 5, checked: 5, broken: 2, done: true });
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), 7);
+        assert!(reports[..6].iter().all(|p| !p.done));
+
+        let broken = items.list_broken_links(Some(4)).await.unwrap();
+        let mut names: Vec<_> = broken.iter().map(|b| b.text.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["closed", "gone"]);
+        let gone = broken.iter().find(|b| b.text == "gone").unwrap();
+        assert_eq!(gone.check.status, Some(404));
+        let closed = broken.iter().find(|b| b.text == "closed").unwrap();
+        assert!(closed.check.status.is_none() && closed.check.error.is_some());
+
+        let moved = prober.probe(&format!("{}/old", base)).await;
+        assert_eq!(moved.status, Some(200));
+        assert_eq!(moved.redirect_to, Some(format!("{}/ok", base)));
+
+        // Recording a check is not an edit
+        assert_eq!(items.find_by_id(ids[0]).await.unwrap().unwrap().updated_at, before.updated_at);
+
+        // Everything was checked just now: nothing is due
+        let again = check_links(db.conn.clone(), prober, Some(4), None, |_| {}).await.unwrap();
+        assert_eq!(again.total, 0);
+    }
+}
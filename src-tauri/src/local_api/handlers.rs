@@ -0,0 +1,231 @@
+//! Local API Handlers
+//!
+//! Endpoint implementations on top of the repositories.
+
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use super::{empty, json, ApiError, LocalApi, ITEMS_CHANGED_EVENT, TAGS_CHANGED_EVENT};
+use crate::archive::{self, HttpFetcher};
+use crate::domain::{DomainError, Item, ItemType, Tag, Timestamp, BOOKMARK_TAG, BOOKMARK_WORKSPACE_ID};
+use crate::repository::item::{ItemLinkOperations, ItemSearch, ItemSearchOperations, ItemWorkspaceOperations};
+use crate::repository::tag::{InlineTagOperations, ItemTagOperations, TagAliasOperations, TagColorOperations};
+use crate::repository::{ArchiveRepository, ItemRepository, Repository, TagRepository};
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const MAX_SEARCH_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub(super) struct NewBookmark {
+    url: String,
+    title: Option<String>,
+    summary: Option<String>,
+    tags: Option<Vec<String>>,
+    /// When the page was clipped; seconds are accepted from older clippers
+    created_at: Option<i64>,
+    /// The article HTML (Readability), archived for offline reading
+    content: Option<String>,
+}
+
+/// A tag given by name (resolved or created) or by id
+#[derive(Debug, Deserialize)]
+pub(super) struct TagRef {
+    name: Option<String>,
+    tag_id: Option<u32>,
+}
+
+/// An item with its tags
+#[derive(Debug, Serialize)]
+struct ItemView {
+    #[serde(flatten)]
+    item: Item,
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Serialize)]
+struct BookmarkCreated {
+    item: ItemView,
+    /// False if an item with this URL already existed
+    created: bool,
+    /// Whether `content` was archived by this request
+    archived: bool,
+}
+
+type ApiResult = Result<Response<Full<Bytes>>, ApiError>;
+
+impl LocalApi {
+    pub(super) async fn create_bookmark(&self, mut bookmark: NewBookmark) -> ApiResult {
+        let content = bookmark.content.take().filter(|c| !c.trim().is_empty());
+        let url = bookmark.url.trim();
+        if url.is_empty() {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "url is required"));
+        }
+        let items = ItemRepository::new(self.conn.clone());
+        if let Some(existing) = items.find_by_url(url, Some(BOOKMARK_WORKSPACE_ID)).await? {
+            return self.bookmark_exists(existing, content).await;
+        }
+
+        let title = bookmark.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).unwrap_or(url);
+        let mut item = Item::new(0, title.to_string(), ItemType::Bookmark);
+        item.url = Some(url.to_string());
+        item.summary = bookmark.summary.filter(|s| !s.trim().is_empty());
+        item.created_at = bookmark.created_at.and_then(Timestamp::normalize).filter(|t| *t <= Timestamp::now());
+        let created = match items.create_with_workspace(&item, BOOKMARK_WORKSPACE_ID).await {
+            Ok(created) => created,
+            // Saved by the UI between the lookup and the insert
+            Err(DomainError::Conflict(message)) => match items.find_by_url(url, Some(BOOKMARK_WORKSPACE_ID)).await? {
+                Some(existing) => return self.bookmark_exists(existing, content).await,
+                None => return Err(DomainError::Conflict(message).into()),
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        let tags = TagRepository::new(self.conn.clone());
+        let names = bookmark.tags.unwrap_or_else(|| vec![BOOKMARK_TAG.to_string()]);
+        let mut tags_created = false;
+        for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            let (tag, is_new) = self.ensure_tag(name).await?;
+            tags_created |= is_new;
+            tags.add_tag_to_item(created.id, tag.id).await?;
+        }
+        tags_created |= !tags.sync_inline_tags(created.id, &created.text, created.memo.as_deref()).await?.is_empty();
+        // Memos may already link to the clipped page's title
+        items.resolve_pending_links().await?;
+        let archived = match content {
+            Some(content) => self.archive(&created, content).await,
+            None => false,
+        };
+
+        (self.notify)(ITEMS_CHANGED_EVENT);
+        if tags_created {
+            (self.notify)(TAGS_CHANGED_EVENT);
+        }
+        let item = self.view(created).await?;
+        Ok(json(StatusCode::CREATED, &BookmarkCreated { item, created: true, archived }))
+    }
+
+    /// An existing bookmark gets the clipped article only if it has no archive yet
+    async fn bookmark_exists(&self, existing: Item, content: Option<String>) -> ApiResult {
+        let archived = match content {
+            Some(content) if ArchiveRepository::new(self.conn.clone()).find(existing.id).await?.is_none() => {
+                self.archive(&existing, content).await
+            }
+            _ => false,
+        };
+        let item = self.view(existing).await?;
+        Ok(json(StatusCode::OK, &BookmarkCreated { item, created: false, archived }))
+    }
+
+    /// Archive a clipped article; a failure only costs the offline copy, the
+    /// bookmark is saved regardless
+    async fn archive(&self, item: &Item, content: String) -> bool {
+        let Ok(fetcher) = HttpFetcher::new() else {
+            return false;
+        };
+        archive::archive_item(self.conn.clone(), &self.assets_dir, &fetcher, item, Some(content)).await.is_ok()
+    }
+
+    /// The bookmark saved under the same normalised URL, or `null`
+    pub(super) async fn find_by_url(&self, params: &[(String, String)]) -> ApiResult {
+        let url = params.iter().find(|(key, _)| key == "url").map(|(_, value)| value.as_str()).unwrap_or_default();
+        if url.trim().is_empty() {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "url is required"));
+        }
+        let found = ItemRepository::new(self.conn.clone()).find_by_url(url, Some(BOOKMARK_WORKSPACE_ID)).await?;
+        let view = match found {
+            Some(item) => Some(self.view(item).await?),
+            None => None,
+        };
+        Ok(json(StatusCode::OK, &view))
+    }
+
+    pub(super) async fn search(&self, params: &[(String, String)]) -> ApiResult {
+        let mut search = ItemSearch { limit: DEFAULT_SEARCH_LIMIT, ..Default::default() };
+        for (key, value) in params {
+            match key.as_str() {
+                "q" => search.query = value.clone(),
+                // An id, or a tag name or alias
+                "tag" => match value.parse() {
+                    Ok(id) => search.tag_ids.push(id),
+                    Err(_) => search.tag_names.push(value.clone()),
+                },
+                "with_url" => search.with_url = value == "1" || value == "true",
+                "limit" => {
+                    let limit: usize = value.parse()
+                        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid limit: {}", value)))?;
+                    search.limit = limit.min(MAX_SEARCH_LIMIT);
+                }
+                _ => {}
+            }
+        }
+
+        let found = ItemRepository::new(self.conn.clone()).search(&search).await?;
+        let mut views = Vec::with_capacity(found.len());
+        for item in found {
+            views.push(self.view(item).await?);
+        }
+        Ok(json(StatusCode::OK, &views))
+    }
+
+    pub(super) async fn list_tags(&self) -> ApiResult {
+        let tags = TagRepository::new(self.conn.clone()).list().await?;
+        Ok(json(StatusCode::OK, &tags))
+    }
+
+    pub(super) async fn tag_item(&self, item_id: u32, tag: TagRef) -> ApiResult {
+        self.existing_item(item_id).await?;
+        let tags = TagRepository::new(self.conn.clone());
+        let (tag, is_new) = match (tag.tag_id, tag.name.as_deref().map(str::trim)) {
+            (Some(id), _) => {
+                let tag = tags.find_by_id(id).await?
+                    .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Tag {} not found", id)))?;
+                (tag, false)
+            }
+            (None, Some(name)) if !name.is_empty() => self.ensure_tag(name).await?,
+            _ => return Err(ApiError::new(StatusCode::BAD_REQUEST, "name or tag_id is required")),
+        };
+        tags.add_tag_to_item(item_id, tag.id).await?;
+
+        (self.notify)(ITEMS_CHANGED_EVENT);
+        if is_new {
+            (self.notify)(TAGS_CHANGED_EVENT);
+        }
+        Ok(json(StatusCode::OK, &tag))
+    }
+
+    pub(super) async fn untag_item(&self, item_id: u32, tag_id: u32) -> ApiResult {
+        self.existing_item(item_id).await?;
+        TagRepository::new(self.conn.clone()).remove_tag_from_item(item_id, tag_id).await?;
+        (self.notify)(ITEMS_CHANGED_EVENT);
+        Ok(empty(StatusCode::NO_CONTENT))
+    }
+
+    pub(super) async fn delete_item(&self, item_id: u32) -> ApiResult {
+        self.existing_item(item_id).await?;
+        ItemRepository::new(self.conn.clone()).delete(item_id).await?;
+        (self.notify)(ITEMS_CHANGED_EVENT);
+        Ok(empty(StatusCode::NO_CONTENT))
+    }
+
+    async fn existing_item(&self, item_id: u32) -> Result<Item, ApiError> {
+        ItemRepository::new(self.conn.clone()).find_by_id(item_id).await?
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Item {} not found", item_id)))
+    }
+
+    async fn view(&self, item: Item) -> Result<ItemView, ApiError> {
+        let tags = TagRepository::new(self.conn.clone()).get_tags_for_item(item.id).await?;
+        Ok(ItemView { item, tags })
+    }
+
+    /// Resolve a tag by name or alias, or create it with the next palette colour
+    /// (as `create_tag` does); the flag tells whether it was created
+    async fn ensure_tag(&self, name: &str) -> Result<(Tag, bool), ApiError> {
+        let tags = TagRepository::new(self.conn.clone());
+        if let Some(existing) = tags.resolve_tag_name(name).await? {
+            return Ok((existing, false));
+        }
+        Ok((tags.create_with_palette_color(name).await?, true))
+    }
+}
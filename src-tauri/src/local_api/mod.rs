@@ -0,0 +1,420 @@
+//! Local API Layer
+//!
+//! An opt-in HTTP server on `127.0.0.1` for the browser clipper, so clipped
+//! pages go straight into the local database instead of the cloud one.
+//! Every endpoint except `GET /api/health` requires the configured token
+//! (`Authorization: Bearer <token>`). Changes are reported through a notify
+//! callback so the open UI can refresh.
+//!
+//! Endpoints:
+//! - `GET /api/health`
+//! - `POST /api/bookmarks` `{url, title?, summary?, tags?, created_at?, content?}`
+//!   (`content`: the article HTML, archived for offline reading)
+//! - `GET /api/items/by-url?url=`
+//! - `GET /api/search?q=&tag=<id or name>&with_url=1&limit=` (`#tag` in `q`
+//!   filters by tag too; names and aliases resolve to the canonical tag)
+//! - `GET /api/tags`
+//! - `POST /api/items/{id}/tags` `{name}` or `{tag_id}`
+//! - `DELETE /api/items/{id}/tags/{tag_id}`
+//! - `DELETE /api/items/{id}`
+
+mod handlers;
+
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{watch, Mutex};
+
+use crate::domain::{DomainError, DomainResult};
+
+pub const DEFAULT_PORT: u16 = 27124;
+
+/// Emitted after items were created, deleted or (un)tagged
+pub const ITEMS_CHANGED_EVENT: &str = "items-changed";
+/// Emitted after tags were created
+pub const TAGS_CHANGED_EVENT: &str = "tags-changed";
+
+/// Largest accepted request body (clipped articles included)
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Receives the change events (`ITEMS_CHANGED_EVENT`, `TAGS_CHANGED_EVENT`)
+pub type Notify = Arc<dyn Fn(&'static str) + Send + Sync>;
+
+/// Local API settings; stored per device next to the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for LocalApiConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT, token: String::new() }
+    }
+}
+
+/// A fresh random API token
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Local API configuration file (next to the database)
+fn config_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("local_api.json")
+}
+
+/// Load the local API configuration (disabled if none was saved)
+pub fn load_config(db_path: &Path) -> LocalApiConfig {
+    std::fs::read_to_string(config_path(db_path))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Save the local API configuration
+pub fn save_config(db_path: &Path, config: &LocalApiConfig) -> DomainResult<()> {
+    let json = serde_json::to_string_pretty(config).map_err(|e| DomainError::Internal(e.to_string()))?;
+    std::fs::write(config_path(db_path), json)
+        .map_err(|e| DomainError::Internal(format!("Failed to save local API config: {}", e)))
+}
+
+/// A running server; stops accepting and closes its connections when dropped
+pub struct LocalApiServer {
+    addr: SocketAddr,
+    shutdown: watch::Sender<bool>,
+}
+
+impl LocalApiServer {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for LocalApiServer {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// Request handling state shared by all connections
+pub struct LocalApi {
+    conn: Arc<Mutex<Option<Connection>>>,
+    /// Where archived pages and their images are stored
+    assets_dir: PathBuf,
+    token: String,
+    notify: Notify,
+}
+
+impl LocalApi {
+    pub fn new(conn: Arc<Mutex<Option<Connection>>>, assets_dir: PathBuf, token: String, notify: Notify) -> Self {
+        Self { conn, assets_dir, token, notify }
+    }
+
+    /// Listen on `127.0.0.1:port` (0 picks a free port)
+    pub async fn start(self, port: u16) -> DomainResult<LocalApiServer> {
+        if self.token.is_empty() {
+            return Err(DomainError::InvalidInput("Local API token is empty".to_string()));
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .await
+            .map_err(|e| DomainError::Internal(format!("Failed to bind local API port {}: {}", port, e)))?;
+        let addr = listener.local_addr().map_err(|e| DomainError::Internal(e.to_string()))?;
+        let (shutdown, mut stopped) = watch::channel(false);
+        let api = Arc::new(self);
+
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    _ = stopped.changed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(_) => continue,
+                    },
+                };
+                let api = api.clone();
+                let mut stopped = stopped.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let api = api.clone();
+                        async move { Ok::<_, Infallible>(api.handle(request).await) }
+                    });
+                    let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+                    tokio::select! {
+                        _ = connection => {}
+                        _ = stopped.changed() => {}
+                    }
+                });
+            }
+        });
+
+        Ok(LocalApiServer { addr, shutdown })
+    }
+
+    async fn handle(&self, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        let origin = request.headers().get(header::ORIGIN).filter(|origin| is_extension_origin(origin)).cloned();
+
+        let mut response = if request.method() == Method::OPTIONS {
+            empty(StatusCode::NO_CONTENT)
+        } else {
+            self.route(request).await.unwrap_or_else(ApiError::into_response)
+        };
+
+        // Extension pages may call the API; web pages may not
+        if let Some(origin) = origin {
+            let headers = response.headers_mut();
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST, DELETE, OPTIONS"));
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("Authorization, Content-Type"));
+            headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static("600"));
+        }
+        response.headers_mut().insert(header::VARY, HeaderValue::from_static("Origin"));
+        response
+    }
+
+    async fn route(&self, request: Request<Incoming>) -> Result<Response<Full<Bytes>>, ApiError> {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().unwrap_or_default().to_string();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        if method == Method::GET && segments == ["api", "health"] {
+            return Ok(json(StatusCode::OK, &serde_json::json!({ "app": "tag-all", "version": env!("CARGO_PKG_VERSION") })));
+        }
+        if !self.authorized(request.headers()) {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid token"));
+        }
+
+        let body = read_body(request.into_body()).await?;
+        match (method, segments.as_slice()) {
+            (Method::POST, ["api", "bookmarks"]) => self.create_bookmark(parse_json(&body)?).await,
+            (Method::GET, ["api", "items", "by-url"]) => self.find_by_url(&query_params(&query)).await,
+            (Method::GET, ["api", "search"]) => self.search(&query_params(&query)).await,
+            (Method::GET, ["api", "tags"]) => self.list_tags().await,
+            (Method::POST, ["api", "items", id, "tags"]) => self.tag_item(parse_id(id)?, parse_json(&body)?).await,
+            (Method::DELETE, ["api", "items", id, "tags", tag_id]) => self.untag_item(parse_id(id)?, parse_id(tag_id)?).await,
+            (Method::DELETE, ["api", "items", id]) => self.delete_item(parse_id(id)?).await,
+            _ => Err(ApiError::new(StatusCode::NOT_FOUND, "Unknown endpoint")),
+        }
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| tokens_match(token.trim().as_bytes(), self.token.as_bytes()))
+    }
+}
+
+/// Compare without an early exit, so timing reveals nothing about the token
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len() && given.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn is_extension_origin(origin: &HeaderValue) -> bool {
+    origin.to_str().is_ok_and(|origin| {
+        origin.starts_with("moz-extension://") || origin.starts_with("chrome-extension://")
+    })
+}
+
+/// An error response: `{"error": message}`
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+
+    fn into_response(self) -> Response<Full<Bytes>> {
+        json(self.status, &serde_json::json!({ "error": self.message }))
+    }
+}
+
+impl From<DomainError> for ApiError {
+    fn from(e: DomainError) -> Self {
+        let status = match &e {
+            DomainError::NotFound(_) => StatusCode::NOT_FOUND,
+            DomainError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            DomainError::Conflict(_) => StatusCode::CONFLICT,
+            DomainError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, e.to_string())
+    }
+}
+
+fn json<T: Serialize>(status: StatusCode, value: &T) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .expect("valid response")
+}
+
+fn empty(status: StatusCode) -> Response<Full<Bytes>> {
+    Response::builder().status(status).body(Full::new(Bytes::new())).expect("valid response")
+}
+
+async fn read_body(body: Incoming) -> Result<Bytes, ApiError> {
+    Limited::new(body, MAX_BODY_BYTES)
+        .collect()
+        .await
+        .map(|collected| collected.to_bytes())
+        .map_err(|_| ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large or incomplete"))
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    serde_json::from_slice(body).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid JSON body: {}", e)))
+}
+
+fn parse_id(segment: &str) -> Result<u32, ApiError> {
+    segment.parse().map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid id: {}", segment)))
+}
+
+/// Decoded `key=value` pairs of a query string (keys may repeat)
+fn query_params(query: &str) -> Vec<(String, String)> {
+    let decode = |s: &str| percent_encoding::percent_decode_str(&s.replace('+', " ")).decode_utf8_lossy().to_string();
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::init_db;
+    use std::sync::Mutex as StdMutex;
+    use tauri_plugin_http::reqwest;
+
+    #[test]
+    fn test_query_params_and_token_compare() {
+        assert_eq!(
+            query_params("q=rust+book%21&tag=3&tag=4&empty"),
+            vec![
+                ("q".to_string(), "rust book!".to_string()),
+                ("tag".to_string(), "3".to_string()),
+                ("tag".to_string(), "4".to_string()),
+                ("empty".to_string(), String::new()),
+            ]
+        );
+        assert!(tokens_match(b"secret", b"secret"));
+        assert!(!tokens_match(b"secreT", b"secret"));
+        assert!(!tokens_match(b"secret-longer", b"secret"));
+    }
+
+    #[tokio::test]
+    async fn test_bookmark_search_and_tag_endpoints() {
+        let db = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let events = Arc::new(StdMutex::new(Vec::new()));
+        let recorded = events.clone();
+        let notify: Notify = Arc::new(move |event| recorded.lock().unwrap().push(event));
+        let assets_dir = std::env::temp_dir().join(format!("tag-all-local-api-{}", std::process::id()));
+        let server = LocalApi::new(db.conn.clone(), assets_dir.clone(), "t0ken".to_string(), notify).start(0).await.unwrap();
+        let base = format!("http://{}", server.addr());
+        let client = reqwest::Client::new();
+
+        // Health needs no token, everything else does
+        let health = client.get(format!("{}/api/health", base)).send().await.unwrap();
+        assert_eq!(health.status(), 200);
+        let denied = client.get(format!("{}/api/tags", base)).bearer_auth("wrong").send().await.unwrap();
+        assert_eq!(denied.status(), 401);
+
+        // Create a bookmark with its article; the same URL again is not duplicated
+        // created_at in seconds, as older clippers queued it
+        let bookmark = serde_json::json!({
+            "url": "https://example.com/a", "title": "Example", "tags": ["web-bookmark", "todo"], "created_at": 1_700_000_000,
+            "content": "<div><p>Archived <b>zebra</b> facts</p></div>",
+        });
+        let created = client.post(format!("{}/api/bookmarks", base)).bearer_auth("t0ken").json(&bookmark).send().await.unwrap();
+        assert_eq!(created.status(), 201);
+        let created: serde_json::Value = created.json().await.unwrap();
+        let id = created["item"]["id"].as_u64().unwrap();
+        assert_eq!(created["item"]["tags"].as_array().unwrap().len(), 2);
+        assert!(events.lock().unwrap().contains(&ITEMS_CHANGED_EVENT));
+        assert!(events.lock().unwrap().contains(&TAGS_CHANGED_EVENT));
+
+        assert_eq!(created["item"]["item_type"], "bookmark");
+        assert_eq!(created["item"]["created_at"], 1_700_000_000_000i64);
+        assert_eq!(created["archived"], true);
+
+        let variant = serde_json::json!({ "url": "https://EXAMPLE.com/a/?utm_source=feed", "content": "<p>Other</p>" });
+        let again = client.post(format!("{}/api/bookmarks", base)).bearer_auth("t0ken").json(&variant).send().await.unwrap();
+        assert_eq!(again.status(), 200);
+        let again: serde_json::Value = again.json().await.unwrap();
+        assert_eq!((again["item"]["id"].as_u64(), again["created"].as_bool()), (Some(id), Some(false)));
+        assert_eq!(again["archived"], false); // the first archive is kept
+
+        // Lookup by normalised URL
+        let by_url = |url: &str| client.get(format!("{}/api/items/by-url", base)).query(&[("url", url)]).bearer_auth("t0ken").send();
+        let found: serde_json::Value = by_url("https://example.com/a#intro").await.unwrap().json().await.unwrap();
+        assert_eq!(found["id"].as_u64(), Some(id));
+        let found: serde_json::Value = by_url("https://example.com/b").await.unwrap().json().await.unwrap();
+        assert!(found.is_null());
+
+        // Search by text, tag and "has URL"
+        let tags: Vec<serde_json::Value> = client.get(format!("{}/api/tags", base)).bearer_auth("t0ken").send().await.unwrap().json().await.unwrap();
+        let todo = tags.iter().find(|t| t["name"] == "todo").unwrap()["id"].as_u64().unwrap();
+        let found: Vec<serde_json::Value> = client.get(format!("{}/api/search?q=exam&with_url=1&tag={}", base, todo))
+            .bearer_auth("t0ken").send().await.unwrap().json().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["url"], "https://example.com/a");
+        let found: Vec<serde_json::Value> = client.get(format!("{}/api/search?tag=Todo", base))
+            .bearer_auth("t0ken").send().await.unwrap().json().await.unwrap();
+        assert_eq!(found.len(), 1); // by name, resolved like create_tag does
+        let found: Vec<serde_json::Value> = client.get(format!("{}/api/search?q=zebra", base))
+            .bearer_auth("t0ken").send().await.unwrap().json().await.unwrap();
+        assert_eq!(found[0]["id"].as_u64(), Some(id));
+
+        // Tag by name, untag by id
+        let tagged = client.post(format!("{}/api/items/{}/tags", base, id)).bearer_auth("t0ken")
+            .json(&serde_json::json!({ "name": "read-later" })).send().await.unwrap();
+        assert_eq!(tagged.status(), 200);
+        let untagged = client.delete(format!("{}/api/items/{}/tags/{}", base, id, todo)).bearer_auth("t0ken").send().await.unwrap();
+        assert_eq!(untagged.status(), 204);
+        let found: Vec<serde_json::Value> = client.get(format!("{}/api/search?q=example", base))
+            .bearer_auth("t0ken").send().await.unwrap().json().await.unwrap();
+        let names: Vec<&str> = found[0]["tags"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"read-later") && !names.contains(&"todo"));
+
+        // Errors map to status codes
+        let missing = client.delete(format!("{}/api/items/9999", base)).bearer_auth("t0ken").send().await.unwrap();
+        assert_eq!(missing.status(), 404);
+        let invalid = client.post(format!("{}/api/bookmarks", base)).bearer_auth("t0ken").body("{").send().await.unwrap();
+        assert_eq!(invalid.status(), 400);
+
+        // Preflight from the extension is allowed, other origins get no CORS headers
+        let preflight = client.request(reqwest::Method::OPTIONS, format!("{}/api/bookmarks", base))
+            .header("Origin", "moz-extension://abc").send().await.unwrap();
+        assert_eq!(preflight.status(), 204);
+        assert_eq!(preflight.headers()["access-control-allow-origin"], "moz-extension://abc");
+        let web = client.get(format!("{}/api/health", base)).header("Origin", "https://evil.example").send().await.unwrap();
+        assert!(web.headers().get("access-control-allow-origin").is_none());
+
+        // Dropping the handle stops the server
+        let addr = server.addr();
+        drop(server);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+        let _ = std::fs::remove_dir_all(&assets_dir);
+    }
+}
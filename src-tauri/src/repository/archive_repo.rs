@@ -0,0 +1,225 @@
+//! Archive Repository
+//!
+//! Offline copies of bookmarked pages (`archives` table, synced) and their
+//! full-text index (`archives_fts`, maintained by triggers). The HTML and
+//! image files are stored by the archive layer as ordinary assets; saving an
+//! archive records them in `asset_refs` under `ASSET_OWNER_ARCHIVE`.
+
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::domain::{Archive, ArchiveMatch, DomainError, DomainResult, Timestamp, ASSET_OWNER_ARCHIVE};
+
+/// Characters of context on each side of a match in search snippets
+const SNIPPET_RADIUS: usize = 40;
+
+pub struct ArchiveRepository {
+    conn: Arc<Mutex<Option<Connection>>>,
+}
+
+fn row_to_archive(row: &Row) -> rusqlite::Result<Archive> {
+    let images: String = row.get(5)?;
+    Ok(Archive {
+        item_id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        html_hash: row.get(3)?,
+        text: row.get(4)?,
+        images: serde_json::from_str(&images).unwrap_or_default(),
+        created_at: row.get::<_, Option<Timestamp>>(6)?.unwrap_or(Timestamp::from_millis(0)),
+    })
+}
+
+/// Escape LIKE wildcards so the query matches literally
+fn like_pattern(query: &str) -> String {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// The query as one FTS5 phrase (operators and quotes taken literally)
+fn fts_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+/// Up to `SNIPPET_RADIUS` characters around the first match of `query` in
+/// `text` (ASCII case-insensitive), the match wrapped in `[` `]`; the start of
+/// the text when the query only matched the title
+fn snippet(text: &str, query: &str) -> String {
+    let text = text.trim();
+    let Some(start) = text.to_ascii_lowercase().find(&query.to_ascii_lowercase()) else {
+        let head: String = text.chars().take(SNIPPET_RADIUS * 2).collect();
+        return if head.len() < text.len() { format!("{}…", head) } else { head };
+    };
+    let end = start + query.len();
+    let before: Vec<char> = text[..start].chars().rev().take(SNIPPET_RADIUS + 1).collect();
+    let after: Vec<char> = text[end..].chars().take(SNIPPET_RADIUS + 1).collect();
+
+    let mut snippet = String::new();
+    if before.len() > SNIPPET_RADIUS {
+        snippet.push('…');
+    }
+    snippet.extend(before.iter().take(SNIPPET_RADIUS).rev());
+    snippet.push('[');
+    snippet.push_str(&text[start..end]);
+    snippet.push(']');
+    snippet.extend(after.iter().take(SNIPPET_RADIUS));
+    if after.len() > SNIPPET_RADIUS {
+        snippet.push('…');
+    }
+    snippet
+}
+
+impl ArchiveRepository {
+    pub fn new(conn: Arc<Mutex<Option<Connection>>>) -> Self {
+        Self { conn }
+    }
+
+    /// Store the archive of an item, replacing an earlier one, and record the
+    /// assets it uses
+    pub async fn save(&self, archive: &Archive) -> DomainResult<Archive> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let images = serde_json::to_string(&archive.images).map_err(|e| DomainError::Internal(e.to_string()))?;
+        let now = Timestamp::now();
+        let tx = conn.unchecked_transaction().map_err(|e| DomainError::Internal(e.to_string()))?;
+        tx.execute(
+            "INSERT INTO archives (item_id, url, title, html_hash, text, images, created_at, updated_at, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL)
+             ON CONFLICT(item_id) DO UPDATE SET url = ?2, title = ?3, html_hash = ?4, text = ?5, images = ?6,
+                created_at = ?7, updated_at = ?8, deleted_at = NULL",
+            params![archive.item_id, archive.url, archive.title, archive.html_hash, archive.text, images, archive.created_at, now],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM asset_refs WHERE owner_type = ? AND owner_id = ?",
+            params![ASSET_OWNER_ARCHIVE, archive.item_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        for hash in archive.asset_hashes() {
+            tx.execute(
+                "INSERT OR IGNORE INTO asset_refs (asset_hash, owner_type, owner_id) VALUES (?, ?, ?)",
+                params![hash, ASSET_OWNER_ARCHIVE, archive.item_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        conn.query_row(
+            "SELECT item_id, url, title, html_hash, text, images, created_at FROM archives WHERE item_id = ?",
+            params![archive.item_id],
+            row_to_archive,
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// The archive of an item
+    pub async fn find(&self, item_id: u32) -> DomainResult<Option<Archive>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        conn.query_row(
+            "SELECT item_id, url, title, html_hash, text, images, created_at FROM archives WHERE item_id = ? AND deleted_at IS NULL",
+            params![item_id],
+            row_to_archive,
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// Archives of items that still exist, newest first
+    pub async fn list(&self) -> DomainResult<Vec<Archive>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT a.item_id, a.url, a.title, a.html_hash, a.text, a.images, a.created_at FROM archives a
+                 JOIN items i ON i.id = a.item_id AND i.deleted_at IS NULL
+                 WHERE a.deleted_at IS NULL ORDER BY a.created_at DESC"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let archives = stmt.query_map([], row_to_archive)
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(archives)
+    }
+
+    /// Archived pages whose title or text contains the query, newest first.
+    ///
+    /// Queries of three or more characters use the trigram index; shorter ones
+    /// scan the index with LIKE.
+    pub async fn search(&self, query: &str, limit: usize) -> DomainResult<Vec<ArchiveMatch>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let (condition, pattern) = if query.chars().count() >= 3 {
+            ("archives_fts MATCH ?1", fts_phrase(query))
+        } else {
+            ("(f.title LIKE ?1 ESCAPE '\\' OR f.text LIKE ?1 ESCAPE '\\')", like_pattern(query))
+        };
+        let sql = format!(
+            "SELECT a.item_id, a.title, a.url, a.text FROM archives_fts f
+             JOIN archives a ON a.item_id = f.rowid AND a.deleted_at IS NULL
+             JOIN items i ON i.id = a.item_id AND i.deleted_at IS NULL
+             WHERE {} ORDER BY a.created_at DESC LIMIT ?2",
+            condition
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| DomainError::Internal(e.to_string()))?;
+        let matches = stmt.query_map(params![pattern, limit as i64], |row| {
+                let text: String = row.get(3)?;
+                Ok(ArchiveMatch {
+                    item_id: row.get(0)?,
+                    title: row.get(1)?,
+                    url: row.get(2)?,
+                    snippet: snippet(&text, query),
+                })
+            })
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(matches)
+    }
+
+    /// Mark the archive of an item deleted (soft delete so it syncs) and drop
+    /// its asset references
+    pub async fn delete(&self, item_id: u32) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let now = Timestamp::now();
+        conn.execute(
+            "UPDATE archives SET deleted_at = ?1, updated_at = ?1 WHERE item_id = ?2 AND deleted_at IS NULL",
+            params![now, item_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM asset_refs WHERE owner_type = ? AND owner_id = ?",
+            params![ASSET_OWNER_ARCHIVE, item_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_marks_the_match() {
+        let text = format!("{} Rust ownership rules {}", "a".repeat(50), "b".repeat(50));
+        assert_eq!(
+            snippet(&text, "OWNERSHIP"),
+            format!("…{} Rust [ownership] rules {}…", "a".repeat(34), "b".repeat(33))
+        );
+        assert_eq!(snippet("短文本：所有权规则", "所有权"), "短文本：[所有权]规则");
+        assert_eq!(snippet("Only the title matched", "zzz"), "Only the title matched");
+    }
+}
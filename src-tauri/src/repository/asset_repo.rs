@@ -0,0 +1,175 @@
+//! Asset Repository
+//!
+//! Metadata of content-addressed assets (`assets` table) and the references
+//! to them from item and tag text (`asset_refs` table). The files themselves
+//! are managed by the asset commands.
+
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::domain::{Asset, AssetRef, DomainError, DomainResult, Timestamp};
+
+pub struct AssetRepository {
+    conn: Arc<Mutex<Option<Connection>>>,
+}
+
+fn row_to_asset(row: &Row) -> rusqlite::Result<Asset> {
+    Ok(Asset {
+        hash: row.get(0)?,
+        ext: row.get(1)?,
+        mime: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        original_name: row.get(4)?,
+        created_at: row.get::<_, Option<Timestamp>>(5)?.unwrap_or(Timestamp::from_millis(0)),
+    })
+}
+
+impl AssetRepository {
+    pub fn new(conn: Arc<Mutex<Option<Connection>>>) -> Self {
+        Self { conn }
+    }
+
+    /// Record an asset; an existing asset with the same hash is kept (and
+    /// undeleted), so the first original name wins
+    pub async fn upsert(&self, asset: &Asset) -> DomainResult<Asset> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let now = Timestamp::now();
+        conn.execute(
+            "INSERT INTO assets (hash, ext, mime, size, original_name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT(hash) DO UPDATE SET deleted_at = NULL, updated_at = ?6 WHERE assets.deleted_at IS NOT NULL",
+            params![asset.hash, asset.ext, asset.mime, asset.size as i64, asset.original_name, now],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        conn.query_row(
+            "SELECT hash, ext, mime, size, original_name, created_at FROM assets WHERE hash = ?",
+            params![asset.hash],
+            row_to_asset,
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// Find an asset by content hash
+    pub async fn find(&self, hash: &str) -> DomainResult<Option<Asset>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        conn.query_row(
+            "SELECT hash, ext, mime, size, original_name, created_at FROM assets WHERE hash = ? AND deleted_at IS NULL",
+            params![hash],
+            row_to_asset,
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// All assets, newest first
+    pub async fn list(&self) -> DomainResult<Vec<Asset>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT hash, ext, mime, size, original_name, created_at FROM assets WHERE deleted_at IS NULL ORDER BY created_at DESC"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let assets = stmt.query_map([], row_to_asset)
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(assets)
+    }
+
+    /// Replace the assets referenced by one owner (item or tag)
+    pub async fn set_refs(&self, owner_type: &str, owner_id: u32, hashes: &[String]) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let tx = conn.unchecked_transaction().map_err(|e| DomainError::Internal(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM asset_refs WHERE owner_type = ? AND owner_id = ?",
+            params![owner_type, owner_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        for hash in hashes {
+            tx.execute(
+                "INSERT OR IGNORE INTO asset_refs (asset_hash, owner_type, owner_id) VALUES (?, ?, ?)",
+                params![hash, owner_type, owner_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// Replace all references at once (after a full rescan of items and tags)
+    pub async fn replace_all_refs(&self, refs: &[AssetRef]) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let tx = conn.unchecked_transaction().map_err(|e| DomainError::Internal(e.to_string()))?;
+        tx.execute("DELETE FROM asset_refs", ())
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        for r in refs {
+            tx.execute(
+                "INSERT OR IGNORE INTO asset_refs (asset_hash, owner_type, owner_id) VALUES (?, ?, ?)",
+                params![r.asset_hash, r.owner_type, r.owner_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DomainError::Internal(e.to_string()))
+    }
+
+    /// Items and tags referencing an asset
+    pub async fn get_refs(&self, hash: &str) -> DomainResult<Vec<AssetRef>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT asset_hash, owner_type, owner_id FROM asset_refs WHERE asset_hash = ? ORDER BY owner_type, owner_id"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let refs = stmt.query_map(params![hash], |row| Ok(AssetRef {
+                asset_hash: row.get(0)?,
+                owner_type: row.get(1)?,
+                owner_id: row.get(2)?,
+            }))
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(refs)
+    }
+
+    /// Assets no item or tag refers to
+    pub async fn list_unreferenced(&self) -> DomainResult<Vec<Asset>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT hash, ext, mime, size, original_name, created_at FROM assets a
+                 WHERE deleted_at IS NULL AND NOT EXISTS (SELECT 1 FROM asset_refs r WHERE r.asset_hash = a.hash)"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let assets = stmt.query_map([], row_to_asset)
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(assets)
+    }
+
+    /// Mark an asset deleted (soft delete so it syncs)
+    pub async fn delete(&self, hash: &str) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let now = Timestamp::now();
+        conn.execute(
+            "UPDATE assets SET deleted_at = ?1, updated_at = ?1 WHERE hash = ?2",
+            params![now, hash],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+}
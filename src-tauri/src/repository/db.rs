@@ -3,7 +3,6 @@
 //! Manages SQLite database connection and migrations.
 
 use rusqlite::{Connection, params};
-use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 // Import shared sync types and functions from tauri-sync-db
@@ -15,15 +14,22 @@ pub use tauri_sync_db_backend::{
     validate_cloud_connection
 };
 
-/// Backup data structure for safe migration (tag-all specific)
-#[derive(Serialize, Deserialize)]
-pub struct BackupData {
-    pub items: Vec<serde_json::Value>,
-    pub tags: Vec<serde_json::Value>,
-    pub workspaces: Vec<serde_json::Value>,
-    pub item_tags: Vec<serde_json::Value>,
-    pub tag_tags: Vec<serde_json::Value>,
-}
+/// Tables synced with the cloud database; everything else (sync bookkeeping,
+/// search indexes, conflicts) stays on this device
+pub const SYNCED_TABLES: &[&str] = &[
+    "workspaces",
+    "workspace_dirs",
+    "tags",
+    "items",
+    "item_tags",
+    "tag_tags",
+    "tag_aliases",
+    "item_links",
+    "assets",
+    "archives",
+    "settings",
+    "window_state",
+];
 
 // DbState, SyncConfig, validate_cloud_connection are now provided by tauri-sync-db
 
@@ -74,7 +80,7 @@ pub async fn init_db(db_path: &PathBuf) -> Result<DbState, String> {
 
 
 /// Check if a column exists in a table
-fn column_exists(conn: &Connection, table: &str, column: &str) -> bool {
+pub(super) fn column_exists(conn: &Connection, table: &str, column: &str) -> bool {
     let query = format!("PRAGMA table_info({})", table);
     let mut stmt = match conn.prepare(&query) {
         Ok(s) => s,
@@ -217,6 +223,30 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
             .map_err(|e| format!("Failed to add deleted_at to tags: {}", e))?;
     }
 
+    // Level 11: Tag metadata (memo, icon, description, pinned)
+    if !column_exists(conn, "tags", "memo") {
+        conn.execute("ALTER TABLE tags ADD COLUMN memo TEXT", ())
+            .map_err(|e| format!("Failed to add memo to tags: {}", e))?;
+    }
+    if !column_exists(conn, "tags", "icon") {
+        conn.execute("ALTER TABLE tags ADD COLUMN icon TEXT", ())
+            .map_err(|e| format!("Failed to add icon to tags: {}", e))?;
+    }
+    if !column_exists(conn, "tags", "description") {
+        conn.execute("ALTER TABLE tags ADD COLUMN description TEXT", ())
+            .map_err(|e| format!("Failed to add description to tags: {}", e))?;
+    }
+    if !column_exists(conn, "tags", "pinned") {
+        conn.execute("ALTER TABLE tags ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0", ())
+            .map_err(|e| format!("Failed to add pinned to tags: {}", e))?;
+    }
+
+    // Level 22: Whether the colour was picked from the palette (see repository::tag::tag_color)
+    if !column_exists(conn, "tags", "color_auto") {
+        conn.execute("ALTER TABLE tags ADD COLUMN color_auto INTEGER NOT NULL DEFAULT 0", ())
+            .map_err(|e| format!("Failed to add color_auto to tags: {}", e))?;
+    }
+
     // Level 3: Item-Tag many-to-many relationship
     conn.execute(
         "CREATE TABLE IF NOT EXISTS item_tags (
@@ -242,6 +272,10 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
     if !column_exists(conn, "item_tags", "deleted_at") {
         conn.execute("ALTER TABLE item_tags ADD COLUMN deleted_at INTEGER DEFAULT NULL", ()).map_err(|e| e.to_string())?;
     }
+    // Level 14: How the tag was applied ('manual' or 'inline' #tag token)
+    if !column_exists(conn, "item_tags", "source") {
+        conn.execute("ALTER TABLE item_tags ADD COLUMN source TEXT NOT NULL DEFAULT 'manual'", ()).map_err(|e| e.to_string())?;
+    }
 
     // Level 3: Tag-Tag multi-parent relationship (tag can have multiple parent tags)
     conn.execute(
@@ -270,6 +304,93 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
         conn.execute("ALTER TABLE tag_tags ADD COLUMN deleted_at INTEGER DEFAULT NULL", ()).map_err(|e| e.to_string())?;
     }
 
+    // Level 10: Tag aliases (alternative names resolving to a canonical tag)
+    // alias is stored normalized (trimmed + lower-cased) so lookups are case-insensitive
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tag_aliases (
+            alias TEXT PRIMARY KEY,
+            tag_id INTEGER NOT NULL,
+            created_at INTEGER DEFAULT 0,
+            updated_at INTEGER DEFAULT 0,
+            deleted_at INTEGER DEFAULT NULL,
+            FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tag_aliases_tag ON tag_aliases(tag_id)", ()).map_err(|e| e.to_string())?;
+
+    // Level 12: Key/value app settings (synced; values are JSON)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            created_at INTEGER DEFAULT 0,
+            updated_at INTEGER DEFAULT 0,
+            deleted_at INTEGER DEFAULT NULL
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Level 13: Wiki links between items ([[Item title]] / [[#id]] in memos)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_links (
+            source_item_id INTEGER NOT NULL,
+            link_text TEXT NOT NULL,
+            target_item_id INTEGER NOT NULL,
+            created_at INTEGER DEFAULT 0,
+            updated_at INTEGER DEFAULT 0,
+            deleted_at INTEGER DEFAULT NULL,
+            PRIMARY KEY (source_item_id, link_text),
+            FOREIGN KEY(source_item_id) REFERENCES items(id) ON DELETE CASCADE,
+            FOREIGN KEY(target_item_id) REFERENCES items(id) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_item_links_target ON item_links(target_item_id)", ()).map_err(|e| e.to_string())?;
+    // Level 21: Links whose target doesn't exist yet (local only, never synced),
+    // resolved into item_links when items are created, renamed or synced
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pending_item_links (
+            source_item_id INTEGER NOT NULL,
+            link_text TEXT NOT NULL,
+            PRIMARY KEY (source_item_id, link_text),
+            FOREIGN KEY(source_item_id) REFERENCES items(id) ON DELETE CASCADE
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Level 15: Content-addressed assets (assets/{hash}.{ext}) and who references them
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS assets (
+            hash TEXT PRIMARY KEY,
+            ext TEXT NOT NULL,
+            mime TEXT NOT NULL,
+            size INTEGER NOT NULL DEFAULT 0,
+            original_name TEXT,
+            created_at INTEGER DEFAULT 0,
+            updated_at INTEGER DEFAULT 0,
+            deleted_at INTEGER DEFAULT NULL
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    // Derived from memo/summary text, so rebuilt locally rather than synced
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS asset_refs (
+            asset_hash TEXT NOT NULL,
+            owner_type TEXT NOT NULL,
+            owner_id INTEGER NOT NULL,
+            PRIMARY KEY (asset_hash, owner_type, owner_id)
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_asset_refs_owner ON asset_refs(owner_type, owner_id)", ()).map_err(|e| e.to_string())?;
+
     // Level 4: Window state persistence
     conn.execute(
         "CREATE TABLE IF NOT EXISTS window_state (
@@ -390,6 +511,116 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    // Level 16: Normalised bookmark URL (see domain::url_key), unique per workspace
+    if !column_exists(conn, "items", "url_key") {
+        conn.execute("ALTER TABLE items ADD COLUMN url_key TEXT", ())
+            .map_err(|e| format!("Failed to add url_key: {}", e))?;
+        // Bookmarks used to be plain documents in the web-bookmarks workspace
+        conn.execute(
+            "UPDATE items SET item_type = 'bookmark' WHERE workspace_id = 4 AND item_type = 'document' AND url IS NOT NULL AND url != ''",
+            (),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_items_workspace_url_key ON items(workspace_id, url_key)
+         WHERE url_key IS NOT NULL AND deleted_at IS NULL",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    // Also covers rows restored from a backup or pulled by sync
+    backfill_url_keys(conn)?;
+
+    // Level 18: Offline page archives (see domain::Archive)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS archives (
+            item_id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL,
+            title TEXT NOT NULL DEFAULT '',
+            html_hash TEXT NOT NULL,
+            text TEXT NOT NULL DEFAULT '',
+            images TEXT NOT NULL DEFAULT '[]',
+            created_at INTEGER DEFAULT 0,
+            updated_at INTEGER DEFAULT 0,
+            deleted_at INTEGER DEFAULT NULL
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    // Full-text index of live archives, keyed by item id. Kept up to date by
+    // triggers so rows written by sync are indexed too; trigram tokens make
+    // substring search work for Chinese text.
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS archives_fts USING fts5(title, text, tokenize = 'trigram');
+         CREATE TRIGGER IF NOT EXISTS archives_fts_insert AFTER INSERT ON archives BEGIN
+             DELETE FROM archives_fts WHERE rowid = new.item_id;
+             INSERT INTO archives_fts (rowid, title, text) SELECT new.item_id, new.title, new.text WHERE new.deleted_at IS NULL;
+         END;
+         CREATE TRIGGER IF NOT EXISTS archives_fts_update AFTER UPDATE ON archives BEGIN
+             DELETE FROM archives_fts WHERE rowid = old.item_id;
+             INSERT INTO archives_fts (rowid, title, text) SELECT new.item_id, new.title, new.text WHERE new.deleted_at IS NULL;
+         END;
+         CREATE TRIGGER IF NOT EXISTS archives_fts_delete AFTER DELETE ON archives BEGIN
+             DELETE FROM archives_fts WHERE rowid = old.item_id;
+         END;",
+    )
+    .map_err(|e| format!("Failed to create archive index: {}", e))?;
+
+    // Level 19: Link health of items with a URL (see domain::LinkCheck)
+    for (column, kind) in [("link_status", "INTEGER"), ("link_redirect", "TEXT"), ("link_error", "TEXT"), ("link_checked_at", "INTEGER")] {
+        if !column_exists(conn, "items", column) {
+            conn.execute(&format!("ALTER TABLE items ADD COLUMN {} {}", column, kind), ())
+                .map_err(|e| format!("Failed to add {}: {}", column, e))?;
+        }
+    }
+
+    // Level 20: Sync conflicts (local only, never synced; see domain::SyncConflict)
+    // sync_base holds each row as of the last sync, to tell which side changed it
+    // Rows used to be keyed by id alone: the base is rebuilt by the next sync,
+    // stored conflicts keep their id as a one-column key
+    let keyed_by_id = column_exists(conn, "sync_conflicts", "row_id");
+    if column_exists(conn, "sync_base", "row_id") {
+        conn.execute("DROP TABLE sync_base", ()).map_err(|e| e.to_string())?;
+    }
+    if keyed_by_id {
+        conn.execute("ALTER TABLE sync_conflicts RENAME TO sync_conflicts_by_id", ()).map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_base (
+            table_name TEXT NOT NULL,
+            row_key TEXT NOT NULL,
+            updated_at INTEGER,
+            row TEXT NOT NULL,
+            PRIMARY KEY (table_name, row_key)
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            row_key TEXT NOT NULL,
+            base TEXT,
+            local TEXT NOT NULL,
+            remote TEXT NOT NULL,
+            kept TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            UNIQUE (table_name, row_key)
+        )",
+        (),
+    )
+    .map_err(|e| e.to_string())?;
+    if keyed_by_id {
+        conn.execute(
+            "INSERT INTO sync_conflicts (id, table_name, row_key, base, local, remote, kept, created_at)
+             SELECT id, table_name, json_array(row_id), base, local, remote, kept, created_at FROM sync_conflicts_by_id",
+            (),
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("DROP TABLE sync_conflicts_by_id", ()).map_err(|e| e.to_string())?;
+    }
+
     // Level 8: Sync Status table (required by generic sync backend)
     conn.execute(
         "CREATE TABLE IF NOT EXISTS sync_status (
@@ -402,216 +633,31 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(())
-}
+    // Level 17: Second-based, text and missing timestamps (older clipper, column defaults)
+    super::timestamp::repair_timestamps(conn)?;
 
-// ============================================================================
-// Data Backup and Restore
-// ============================================================================
-
-
-/// Backup all data from current database
-pub fn backup_local_data(conn: &Connection) -> Result<BackupData, String> {
-    // Backup items
-    // Explicitly select columns to ensure order and completeness
-    let mut items_stmt = conn.prepare("SELECT id, text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, workspace_id, url, summary, created_at, updated_at, content_hash, quick_hash, last_known_path, is_dir FROM items").map_err(|e| e.to_string())?;
-    let mut items_rows = items_stmt.query([]).map_err(|e| e.to_string())?;
-    let mut items = Vec::new();
-    while let Ok(Some(row)) = items_rows.next() {
-        let id: i64 = row.get(0).map_err(|e| e.to_string())?;
-        let text: String = row.get(1).map_err(|e| e.to_string())?;
-        let completed: i64 = row.get(2).map_err(|e| e.to_string())?;
-        let item_type: String = row.get(3).map_err(|e| e.to_string())?;
-        let memo: Option<String> = row.get(4).ok();
-        let target_count: Option<i64> = row.get(5).ok();
-        let current_count: i64 = row.get(6).map_err(|e| e.to_string())?;
-        let parent_id: Option<i64> = row.get(7).ok();
-        let position: i64 = row.get(8).map_err(|e| e.to_string())?;
-        let collapsed: i64 = row.get(9).map_err(|e| e.to_string())?;
-        let workspace_id: i64 = row.get(10).map_err(|e| e.to_string())?;
-        let url: Option<String> = row.get(11).ok();
-        let summary: Option<String> = row.get(12).ok();
-        let created_at: Option<i64> = row.get(13).ok();
-        let updated_at: Option<i64> = row.get(14).ok();
-        let content_hash: Option<String> = row.get(15).ok();
-        let quick_hash: Option<String> = row.get(16).ok();
-        let last_known_path: Option<String> = row.get(17).ok();
-        let is_dir: i64 = row.get::<_, i64>(18).unwrap_or(0);
-        
-        items.push(serde_json::json!({
-            "id": id,
-            "text": text,
-            "completed": completed,
-            "item_type": item_type,
-            "memo": memo,
-            "target_count": target_count,
-            "current_count": current_count,
-            "parent_id": parent_id,
-            "position": position,
-            "collapsed": collapsed,
-            "workspace_id": workspace_id,
-            "url": url,
-            "summary": summary,
-            "created_at": created_at,
-            "updated_at": updated_at,
-            "content_hash": content_hash,
-            "quick_hash": quick_hash,
-            "last_known_path": last_known_path,
-            "is_dir": is_dir
-        }));
-    }
-    
-    // Backup tags
-    let mut tags_stmt = conn.prepare("SELECT id, name, color, position FROM tags").map_err(|e| e.to_string())?;
-    let mut tags_rows = tags_stmt.query([]).map_err(|e| e.to_string())?;
-    let mut tags = Vec::new();
-    while let Ok(Some(row)) = tags_rows.next() {
-        let id: i64 = row.get(0).map_err(|e| e.to_string())?;
-        let name: String = row.get(1).map_err(|e| e.to_string())?;
-        let color: Option<String> = row.get(2).ok();
-        let position: i64 = row.get(3).map_err(|e| e.to_string())?;
-        
-        tags.push(serde_json::json!({
-            "id": id,
-            "name": name,
-            "color": color,
-            "position": position
-        }));
-    }
-    
-    // Backup workspaces
-    let mut ws_stmt = conn.prepare("SELECT id, name, updated_at FROM workspaces").map_err(|e| e.to_string())?;
-    let mut ws_rows = ws_stmt.query([]).map_err(|e| e.to_string())?;
-    let mut workspaces = Vec::new();
-    while let Ok(Some(row)) = ws_rows.next() {
-        let id: i64 = row.get(0).map_err(|e| e.to_string())?;
-        let name: String = row.get(1).map_err(|e| e.to_string())?;
-        let updated_at: Option<i64> = row.get(2).ok();
-        
-        workspaces.push(serde_json::json!({
-            "id": id,
-            "name": name,
-            "updated_at": updated_at
-        }));
-    }
-
-    // Backup item_tags
-    let mut item_tags_stmt = conn.prepare("SELECT item_id, tag_id FROM item_tags").map_err(|e| e.to_string())?;
-    let mut item_tags_rows = item_tags_stmt.query([]).map_err(|e| e.to_string())?;
-    let mut item_tags = Vec::new();
-    while let Ok(Some(row)) = item_tags_rows.next() {
-        let item_id: i64 = row.get(0).map_err(|e| e.to_string())?;
-        let tag_id: i64 = row.get(1).map_err(|e| e.to_string())?;
-        
-        item_tags.push(serde_json::json!({
-            "item_id": item_id,
-            "tag_id": tag_id
-        }));
-    }
-
-    // Backup tag_tags
-    let mut tag_tags_stmt = conn.prepare("SELECT child_tag_id, parent_tag_id, position FROM tag_tags").map_err(|e| e.to_string())?;
-    let mut tag_tags_rows = tag_tags_stmt.query([]).map_err(|e| e.to_string())?;
-    let mut tag_tags = Vec::new();
-    while let Ok(Some(row)) = tag_tags_rows.next() {
-        let child_tag_id: i64 = row.get(0).map_err(|e| e.to_string())?;
-        let parent_tag_id: i64 = row.get(1).map_err(|e| e.to_string())?;
-        let position: i64 = row.get(2).map_err(|e| e.to_string())?;
-        
-        tag_tags.push(serde_json::json!({
-            "child_tag_id": child_tag_id,
-            "parent_tag_id": parent_tag_id,
-            "position": position
-        }));
-    }
-    
-    eprintln!("Backup complete: {} items, {} tags, {} workspaces, {} item_tags, {} tag_tags", 
-              items.len(), tags.len(), workspaces.len(), item_tags.len(), tag_tags.len());
-    
-    Ok(BackupData { items, tags, workspaces, item_tags, tag_tags })
+    Ok(())
 }
 
-/// Restore data to database
-pub fn restore_data(conn: &Connection, backup: BackupData) -> Result<(), String> {
-    eprintln!("Restoring data: {} items, {} tags, {} workspaces, {} item_tags, {} tag_tags",
-              backup.items.len(), backup.tags.len(), backup.workspaces.len(), backup.item_tags.len(), backup.tag_tags.len());
-    
-    // Restore workspaces first
-    for ws in backup.workspaces {
-        let id = ws["id"].as_i64().unwrap();
-        let name = ws["name"].as_str().unwrap();
-        let updated_at = ws["updated_at"].as_i64().unwrap_or(0);
-        conn.execute(
-            "INSERT OR REPLACE INTO workspaces (id, name, updated_at) VALUES (?, ?, ?)",
-            params![id, name, updated_at]
-        ).map_err(|e| e.to_string())?;
-    }
-    
-    // Restore tags
-    for tag in backup.tags {
-        let id = tag["id"].as_i64().unwrap();
-        let name = tag["name"].as_str().unwrap();
-        let color = tag["color"].as_str(); // Option<String>
-        let position = tag["position"].as_i64().unwrap_or(0);
-        
-        conn.execute(
-            "INSERT OR REPLACE INTO tags (id, name, color, position) VALUES (?, ?, ?, ?)",
-            params![id, name, color, position]
-        ).map_err(|e| e.to_string())?;
-    }
-    
-    // Restore items
-    for item in backup.items {
-        let id = item["id"].as_i64().unwrap();
-        let text = item["text"].as_str().unwrap();
-        let completed = item["completed"].as_i64().unwrap_or(0);
-        let item_type = item["item_type"].as_str().unwrap();
-        let memo = item["memo"].as_str();
-        let target_count = item["target_count"].as_i64();
-        let current_count = item["current_count"].as_i64().unwrap_or(0);
-        let parent_id = item["parent_id"].as_i64();
-        let position = item["position"].as_i64().unwrap_or(0);
-        let collapsed = item["collapsed"].as_i64().unwrap_or(0);
-        let workspace_id = item["workspace_id"].as_i64().unwrap_or(1);
-        let url = item["url"].as_str();
-        let summary = item["summary"].as_str();
-        let created_at = item["created_at"].as_i64();
-        let updated_at = item["updated_at"].as_i64();
-        let content_hash = item["content_hash"].as_str();
-        let quick_hash = item["quick_hash"].as_str();
-        let last_known_path = item["last_known_path"].as_str();
-        let is_dir = item["is_dir"].as_i64().unwrap_or(0);
-        
-        conn.execute(
-            "INSERT OR REPLACE INTO items (id, text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, workspace_id, url, summary, created_at, updated_at, content_hash, quick_hash, last_known_path, is_dir) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            params![id, text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, workspace_id, url, summary, created_at, updated_at, content_hash, quick_hash, last_known_path, is_dir]
-        ).map_err(|e| e.to_string())?;
-    }
-
-    // Restore item_tags
-    for it in backup.item_tags {
-        let item_id = it["item_id"].as_i64().unwrap();
-        let tag_id = it["tag_id"].as_i64().unwrap();
-        
-        conn.execute(
-            "INSERT OR REPLACE INTO item_tags (item_id, tag_id) VALUES (?, ?)",
-            params![item_id, tag_id]
-        ).map_err(|e| e.to_string())?;
-    }
-
-    // Restore tag_tags
-    for tt in backup.tag_tags {
-        let child_tag_id = tt["child_tag_id"].as_i64().unwrap();
-        let parent_tag_id = tt["parent_tag_id"].as_i64().unwrap();
-        let position = tt["position"].as_i64().unwrap_or(0);
-        
-        conn.execute(
-            "INSERT OR REPLACE INTO tag_tags (child_tag_id, parent_tag_id, position) VALUES (?, ?, ?)",
-            params![child_tag_id, parent_tag_id, position]
-        ).map_err(|e| e.to_string())?;
+/// Fill in `url_key` for live items that have a URL but no key yet.
+///
+/// Rows whose key is already taken in their workspace (duplicates saved before
+/// keys existed) are left without one rather than failing the migration.
+pub(crate) fn backfill_url_keys(conn: &Connection) -> Result<(), String> {
+    let pending: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare("SELECT id, url FROM items WHERE url IS NOT NULL AND url_key IS NULL AND deleted_at IS NULL ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    for (id, url) in pending {
+        let Some(key) = crate::domain::url_key(&url) else { continue };
+        match conn.execute("UPDATE items SET url_key = ? WHERE id = ?", params![key, id]) {
+            Ok(_) => {}
+            Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::ConstraintViolation => {}
+            Err(e) => return Err(e.to_string()),
+        }
     }
-    
-    eprintln!("Data restore complete");
     Ok(())
 }
@@ -5,7 +5,7 @@
 use async_trait::async_trait;
 use rusqlite::params;
 
-use crate::domain::{Item, DomainError, DomainResult};
+use crate::domain::{Item, DomainError, DomainResult, Timestamp};
 
 /// Trait for item hierarchy operations
 #[async_trait]
@@ -73,7 +73,7 @@ impl ItemHierarchyOperations for super::item_repo::ItemRepository {
              // Move the item
              conn.execute(
                  "UPDATE items SET parent_id = ?, position = ?, updated_at = ? WHERE id = ?",
-                 params![new_parent_id, position, chrono::Utc::now().timestamp_millis(), id],
+                 params![new_parent_id, position, Timestamp::now(), id],
              )
              .map_err(|e| DomainError::Internal(e.to_string()))?;
         }
@@ -136,7 +136,7 @@ impl ItemHierarchyOperations for super::item_repo::ItemRepository {
         let new_state = !current;
         conn.execute(
             "UPDATE items SET collapsed = ?, updated_at = ? WHERE id = ?",
-            params![new_state, chrono::Utc::now().timestamp_millis(), id],
+            params![new_state, Timestamp::now(), id],
         ).map_err(|e| DomainError::Internal(e.to_string()))?;
         
         Ok(new_state)
@@ -0,0 +1,101 @@
+//! Item Link Health Operations
+//!
+//! Stores link checks (see `domain::LinkCheck`) in the item row. Recording a
+//! check leaves `updated_at` alone: the result is not an edit, and must not
+//! win over a change made to the item on another device.
+
+use async_trait::async_trait;
+use rusqlite::{params, Row};
+use rusqlite::types::Value;
+
+use crate::domain::{BrokenLink, DomainError, DomainResult, LinkCheck, Timestamp};
+
+/// Trait for link health bookkeeping
+#[async_trait]
+pub trait ItemLinkHealthOperations {
+    /// (id, url) of items with a web URL that were never checked or last
+    /// checked before `checked_before`; never-checked first, then oldest
+    async fn links_to_check(&self, workspace_id: Option<u32>, checked_before: Option<Timestamp>) -> DomainResult<Vec<(u32, String)>>;
+
+    /// Store the result of checking an item's URL
+    async fn record_link_check(&self, item_id: u32, check: &LinkCheck) -> DomainResult<()>;
+
+    /// Items whose last check found the link broken, most recently checked first
+    async fn list_broken_links(&self, workspace_id: Option<u32>) -> DomainResult<Vec<BrokenLink>>;
+}
+
+fn row_to_broken_link(row: &Row) -> rusqlite::Result<BrokenLink> {
+    Ok(BrokenLink {
+        item_id: row.get(0)?,
+        text: row.get(1)?,
+        url: row.get(2)?,
+        workspace_id: row.get(3)?,
+        check: LinkCheck {
+            status: row.get(4)?,
+            redirect_to: row.get(5)?,
+            error: row.get(6)?,
+            checked_at: row.get(7)?,
+        },
+    })
+}
+
+#[async_trait]
+impl ItemLinkHealthOperations for super::item_repo::ItemRepository {
+    async fn links_to_check(&self, workspace_id: Option<u32>, checked_before: Option<Timestamp>) -> DomainResult<Vec<(u32, String)>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut sql = "SELECT id, url FROM items
+             WHERE deleted_at IS NULL AND (url LIKE 'http://%' OR url LIKE 'https://%')".to_string();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(workspace_id) = workspace_id {
+            values.push(Value::Integer(workspace_id as i64));
+            sql.push_str(&format!(" AND workspace_id = ?{}", values.len()));
+        }
+        match checked_before {
+            Some(cutoff) => {
+                values.push(Value::Integer(cutoff.millis()));
+                sql.push_str(&format!(" AND (link_checked_at IS NULL OR link_checked_at < ?{})", values.len()));
+            }
+            None => sql.push_str(" AND link_checked_at IS NULL"),
+        }
+        sql.push_str(" ORDER BY link_checked_at IS NOT NULL, link_checked_at, id");
+
+        let mut stmt = conn.prepare(&sql).map_err(|e| DomainError::Internal(e.to_string()))?;
+        let links = stmt.query_map(rusqlite::params_from_iter(values), |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(links)
+    }
+
+    async fn record_link_check(&self, item_id: u32, check: &LinkCheck) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        conn.execute(
+            "UPDATE items SET link_status = ?1, link_redirect = ?2, link_error = ?3, link_checked_at = ?4 WHERE id = ?5",
+            params![check.status, check.redirect_to, check.error, check.checked_at, item_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_broken_links(&self, workspace_id: Option<u32>) -> DomainResult<Vec<BrokenLink>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT id, text, url, workspace_id, link_status, link_redirect, link_error, link_checked_at FROM items
+                 WHERE deleted_at IS NULL AND url IS NOT NULL AND link_checked_at IS NOT NULL
+                   AND (?1 IS NULL OR workspace_id = ?1)
+                 ORDER BY link_checked_at DESC, id"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let links = stmt.query_map(params![workspace_id], row_to_broken_link)
+            .map_err(|e| DomainError::Internal(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(links.into_iter().filter(|link| link.check.is_broken()).collect())
+    }
+}
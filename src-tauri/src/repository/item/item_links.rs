@@ -0,0 +1,214 @@
+//! Item Link Operations
+//!
+//! Maintains the item_links table from `[[...]]` links in item memos and
+//! answers backlink / outgoing link queries. Links whose target doesn't exist
+//! yet wait in pending_item_links until an item they resolve to appears.
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::domain::{ItemLink, WikiLinkTarget, DomainError, DomainResult, extract_wiki_links, Timestamp};
+
+/// Trait for wiki link operations between items
+#[async_trait]
+pub trait ItemLinkOperations {
+    /// Re-extract links from a memo and store them for the source item
+    async fn update_links(&self, source_item_id: u32, memo: Option<&str>) -> DomainResult<Vec<ItemLink>>;
+
+    /// Items whose memos link to this item
+    async fn get_backlinks(&self, item_id: u32) -> DomainResult<Vec<ItemLink>>;
+
+    /// Items this item's memo links to
+    async fn get_outgoing_links(&self, item_id: u32) -> DomainResult<Vec<ItemLink>>;
+
+    /// Resolve a link written in the source item's memo to an item id
+    async fn resolve_link(&self, source_item_id: u32, link_text: &str) -> DomainResult<Option<u32>>;
+
+    /// Store pending links whose target now exists (after items were created,
+    /// renamed or synced); returns the number of links resolved
+    async fn resolve_pending_links(&self) -> DomainResult<usize>;
+}
+
+/// Resolve a link: stored mapping first (survives renames), then by id / title
+fn resolve_with_conn(conn: &Connection, source_item_id: u32, link_text: &str) -> DomainResult<Option<u32>> {
+    let link_text = link_text.trim();
+
+    let stored: Option<u32> = conn.query_row(
+            "SELECT l.target_item_id FROM item_links l
+             JOIN items i ON i.id = l.target_item_id AND i.deleted_at IS NULL
+             WHERE l.source_item_id = ? AND l.link_text = ? AND l.deleted_at IS NULL",
+            params![source_item_id, link_text],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    if stored.is_some() {
+        return Ok(stored);
+    }
+
+    match WikiLinkTarget::parse(link_text) {
+        Some(WikiLinkTarget::Id(id)) => conn.query_row(
+                "SELECT id FROM items WHERE id = ? AND deleted_at IS NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| DomainError::Internal(e.to_string())),
+        // Same workspace as the source wins, then the oldest item
+        Some(WikiLinkTarget::Title(title)) => conn.query_row(
+                "SELECT id FROM items
+                 WHERE lower(trim(text)) = lower(?1) AND deleted_at IS NULL AND id != ?2
+                 ORDER BY workspace_id != (SELECT workspace_id FROM items WHERE id = ?2), id
+                 LIMIT 1",
+                params![title, source_item_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| DomainError::Internal(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Insert or revive the link from `source_item_id` written as `link_text`
+fn store_link(conn: &Connection, source_item_id: u32, link_text: &str, target_item_id: u32, now: Timestamp) -> DomainResult<()> {
+    conn.execute(
+        "INSERT INTO item_links (source_item_id, link_text, target_item_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(source_item_id, link_text) DO UPDATE SET
+            target_item_id = excluded.target_item_id, updated_at = excluded.updated_at, deleted_at = NULL",
+        params![source_item_id, link_text, target_item_id, now],
+    )
+    .map_err(|e| DomainError::Internal(e.to_string()))?;
+    Ok(())
+}
+
+/// Query links with item titles joined; `filter` is the WHERE condition on `l`
+fn query_links(conn: &Connection, filter: &str, item_id: u32) -> DomainResult<Vec<ItemLink>> {
+    let sql = format!(
+        "SELECT l.source_item_id, l.target_item_id, l.link_text, s.text, t.text
+         FROM item_links l
+         JOIN items s ON s.id = l.source_item_id AND s.deleted_at IS NULL
+         JOIN items t ON t.id = l.target_item_id AND t.deleted_at IS NULL
+         WHERE l.deleted_at IS NULL AND {}
+         ORDER BY s.text, t.text",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql)
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    let mut rows = stmt.query(params![item_id])
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+    let mut links = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        links.push(ItemLink {
+            source_item_id: row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?,
+            target_item_id: row.get(1).map_err(|e| DomainError::Internal(e.to_string()))?,
+            link_text: row.get(2).map_err(|e| DomainError::Internal(e.to_string()))?,
+            source_text: row.get(3).unwrap_or_default(),
+            target_text: row.get(4).unwrap_or_default(),
+        });
+    }
+    Ok(links)
+}
+
+#[async_trait]
+impl ItemLinkOperations for super::item_repo::ItemRepository {
+    async fn update_links(&self, source_item_id: u32, memo: Option<&str>) -> DomainResult<Vec<ItemLink>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let link_texts = memo.map(extract_wiki_links).unwrap_or_default();
+
+        // Resolve before writing so stored mappings of unchanged links are reused
+        let mut resolved = Vec::new();
+        let mut pending = Vec::new();
+        for text in &link_texts {
+            match resolve_with_conn(conn, source_item_id, text)? {
+                Some(target) => resolved.push((text.clone(), target)),
+                None => pending.push(text),
+            }
+        }
+
+        let now = Timestamp::now();
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        // Tombstone everything, then revive/insert current links
+        tx.execute(
+            "UPDATE item_links SET deleted_at = ?1, updated_at = ?1 WHERE source_item_id = ?2 AND deleted_at IS NULL",
+            params![now, source_item_id],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        for (text, target) in &resolved {
+            store_link(&tx, source_item_id, text, *target, now)?;
+        }
+        tx.execute("DELETE FROM pending_item_links WHERE source_item_id = ?", params![source_item_id])
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        for text in pending {
+            tx.execute(
+                "INSERT OR IGNORE INTO pending_item_links (source_item_id, link_text) VALUES (?, ?)",
+                params![source_item_id, text],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        query_links(conn, "l.source_item_id = ?", source_item_id)
+    }
+
+    async fn get_backlinks(&self, item_id: u32) -> DomainResult<Vec<ItemLink>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        query_links(conn, "l.target_item_id = ?", item_id)
+    }
+
+    async fn get_outgoing_links(&self, item_id: u32) -> DomainResult<Vec<ItemLink>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        query_links(conn, "l.source_item_id = ?", item_id)
+    }
+
+    async fn resolve_link(&self, source_item_id: u32, link_text: &str) -> DomainResult<Option<u32>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        resolve_with_conn(conn, source_item_id, link_text)
+    }
+
+    async fn resolve_pending_links(&self) -> DomainResult<usize> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let pending: Vec<(u32, String)> = {
+            let mut stmt = conn.prepare(
+                    "SELECT p.source_item_id, p.link_text FROM pending_item_links p
+                     JOIN items s ON s.id = p.source_item_id AND s.deleted_at IS NULL",
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            rows.collect::<Result<_, _>>().map_err(|e| DomainError::Internal(e.to_string()))?
+        };
+
+        let now = Timestamp::now();
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let mut resolved = 0;
+        for (source_item_id, text) in pending {
+            let Some(target) = resolve_with_conn(&tx, source_item_id, &text)? else {
+                continue;
+            };
+            store_link(&tx, source_item_id, &text, target, now)?;
+            tx.execute(
+                "DELETE FROM pending_item_links WHERE source_item_id = ? AND link_text = ?",
+                params![source_item_id, text],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+            resolved += 1;
+        }
+        tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+        Ok(resolved)
+    }
+}
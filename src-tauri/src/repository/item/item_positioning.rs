@@ -5,7 +5,7 @@
 use async_trait::async_trait;
 use rusqlite::params;
 
-use crate::domain::{DomainError, DomainResult};
+use crate::domain::{DomainError, DomainResult, Timestamp};
 
 /// Trait for item positioning operations
 #[async_trait]
@@ -71,7 +71,7 @@ impl ItemPositioningOperations for super::item_repo::ItemRepository {
         for (new_pos, id) in ids.iter().enumerate() {
             conn.execute(
                 "UPDATE items SET position = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
-                params![new_pos as i32, chrono::Utc::now().timestamp_millis(), *id],
+                params![new_pos as i32, Timestamp::now(), *id],
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
         }
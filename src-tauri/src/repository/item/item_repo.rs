@@ -12,7 +12,7 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::str::FromStr;
 
-use crate::domain::{Item, ItemType, DomainError, DomainResult};
+use crate::domain::{url_key, Item, ItemType, DomainError, DomainResult, Timestamp};
 use super::super::traits::Repository;
 
 /// SQLite implementation of Item repository
@@ -132,10 +132,10 @@ impl Repository<Item> for ItemRepository {
         let url = entity.url.clone();
         let summary = entity.summary.clone();
         let is_dir = if entity.is_dir { 1 } else { 0 };
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = Timestamp::now();
         
         conn.execute(
-            "UPDATE items SET text = ?, completed = ?, item_type = ?, memo = ?, target_count = ?, current_count = ?, parent_id = ?, position = ?, collapsed = ?, url = ?, summary = ?, content_hash = ?, quick_hash = ?, last_known_path = ?, is_dir = ?, updated_at = ? WHERE id = ?",
+            "UPDATE items SET text = ?, completed = ?, item_type = ?, memo = ?, target_count = ?, current_count = ?, parent_id = ?, position = ?, collapsed = ?, url = ?, url_key = ?, summary = ?, content_hash = ?, quick_hash = ?, last_known_path = ?, is_dir = ?, updated_at = ? WHERE id = ?",
             params![
                 text,
                 completed,
@@ -147,6 +147,7 @@ impl Repository<Item> for ItemRepository {
                 entity.position,
                 collapsed,
                 url,
+                entity.url.as_deref().and_then(url_key),
                 summary,
                 entity.content_hash.clone(),
                 entity.quick_hash.clone(),
@@ -156,7 +157,7 @@ impl Repository<Item> for ItemRepository {
                 entity.id
             ],
         )
-        .map_err(|e| DomainError::Internal(e.to_string()))?;
+        .map_err(item_write_error)?;
 
         let mut updated_item = entity.clone();
         updated_item.updated_at = Some(now);
@@ -167,7 +168,7 @@ impl Repository<Item> for ItemRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = Timestamp::now();
         
         // Soft delete: set deleted_at for all descendants first
         // Using recursive CTE to get all descendant IDs
@@ -196,6 +197,17 @@ impl Repository<Item> for ItemRepository {
     }
 }
 
+/// Map an item INSERT/UPDATE error; the only constraint on items is the
+/// per-workspace unique `url_key`, so a violation means a duplicate URL
+pub(super) fn item_write_error(e: rusqlite::Error) -> DomainError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            DomainError::Conflict("An item with this URL already exists in the workspace".to_string())
+        }
+        e => DomainError::Internal(e.to_string()),
+    }
+}
+
 /// Convert a database row to Item
 pub(super) fn row_to_item(row: &rusqlite::Row) -> DomainResult<Item> {
     Ok(Item {
@@ -211,8 +223,8 @@ pub(super) fn row_to_item(row: &rusqlite::Row) -> DomainResult<Item> {
         collapsed: row.get::<_, i32>(9).unwrap_or(0) != 0,
         url: row.get::<_, Option<String>>(10).unwrap_or(None),
         summary: row.get::<_, Option<String>>(11).unwrap_or(None),
-        created_at: row.get::<_, Option<i64>>(12).unwrap_or(None),
-        updated_at: row.get::<_, Option<i64>>(13).unwrap_or(None),
+        created_at: row.get::<_, Option<Timestamp>>(12).unwrap_or(None),
+        updated_at: row.get::<_, Option<Timestamp>>(13).unwrap_or(None),
         content_hash: row.get::<_, Option<String>>(14).unwrap_or(None),
         quick_hash: row.get::<_, Option<String>>(15).unwrap_or(None),
         last_known_path: row.get::<_, Option<String>>(16).unwrap_or(None),
@@ -246,9 +258,11 @@ mod tests {
                 position INTEGER DEFAULT 0,
                 collapsed INTEGER DEFAULT 0,
                 url TEXT,
+                url_key TEXT,
                 summary TEXT,
                 created_at INTEGER,
                 updated_at INTEGER,
+                deleted_at INTEGER,
                 content_hash TEXT,
                 quick_hash TEXT,
                 last_known_path TEXT,
@@ -271,18 +285,18 @@ mod tests {
         // We use repository create, which delegates to create_with_workspace(1)
         let created = repo.create(&item).await.unwrap();
         
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = Timestamp::now().millis();
         
         // created_at should be set
         assert!(created.created_at.is_some());
-        let created_at = created.created_at.unwrap();
+        let created_at = created.created_at.unwrap().millis();
         
         // Check created_at is valid (within last 5 seconds)
         assert!(created_at > now - 5000 && created_at <= now + 100);
         
         // updated_at should match created_at initially
         assert!(created.updated_at.is_some());
-        assert_eq!(created.updated_at.unwrap(), created_at);
+        assert_eq!(created.updated_at.unwrap().millis(), created_at);
         
         // Sleep slightly to guarantee different timestamp (needs async sleep or std sleep if single threaded)
         tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
@@ -294,7 +308,7 @@ mod tests {
         // Update uses the generic update, which sets updated_at
         let updated = repo.update(&to_update).await.unwrap();
         
-        let updated_ts = updated.updated_at.unwrap();
+        let updated_ts = updated.updated_at.unwrap().millis();
         assert!(updated_ts > created_at, "Updated time {} should be > created time {}", updated_ts, created_at);
         assert_eq!(updated.text, "Updated Name");
     }
@@ -0,0 +1,121 @@
+//! Item Search Operations
+//!
+//! Text search across workspaces (including archived pages) and URL lookup,
+//! used by the local API. Tags are given by id, or by name or alias (also as
+//! `#tag` tokens in the query) and resolved to their canonical tag.
+
+use async_trait::async_trait;
+use rusqlite::types::Value;
+
+use crate::domain::{extract_inline_tags, url_key, Item, DomainError, DomainResult};
+
+/// Filters for an item search; all given filters must match
+#[derive(Debug, Clone, Default)]
+pub struct ItemSearch {
+    /// Substring of the text, URL, summary, memo or archived page (case-insensitive
+    /// for ASCII); `#tag` tokens in it are taken as `tag_names`
+    pub query: String,
+    /// Tags the item must carry (all of them)
+    pub tag_ids: Vec<u32>,
+    /// Tags the item must carry, by name or alias; an unknown one matches nothing
+    pub tag_names: Vec<String>,
+    /// Only items with a URL
+    pub with_url: bool,
+    pub limit: usize,
+}
+
+/// Trait for searching items
+#[async_trait]
+pub trait ItemSearchOperations {
+    /// Items matching the search, newest first
+    async fn search(&self, search: &ItemSearch) -> DomainResult<Vec<Item>>;
+
+    /// The most recently created item whose URL normalises to the same key
+    /// (see `domain::url_key`), optionally only within one workspace
+    async fn find_by_url(&self, url: &str, workspace_id: Option<u32>) -> DomainResult<Option<Item>>;
+}
+
+/// Split `#tag` tokens (the leaf of `#parent/child`) off a query; returns the
+/// remaining text and the tag names
+fn split_query(query: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+    for token in query.split_whitespace() {
+        let paths = if token.starts_with('#') { extract_inline_tags(token) } else { Vec::new() };
+        match paths.first().and_then(|path| path.last()) {
+            Some(leaf) if paths.len() == 1 => tags.push(leaf.clone()),
+            _ => words.push(token),
+        }
+    }
+    (words.join(" "), tags)
+}
+
+/// Escape LIKE wildcards so the query matches literally
+fn like_pattern(query: &str) -> String {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+#[async_trait]
+impl ItemSearchOperations for super::item_repo::ItemRepository {
+    async fn search(&self, search: &ItemSearch) -> DomainResult<Vec<Item>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut sql = "SELECT id, text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, url, summary, CAST(created_at AS INTEGER) as created_at, CAST(updated_at AS INTEGER) as updated_at, content_hash, quick_hash, last_known_path, is_dir FROM items WHERE deleted_at IS NULL".to_string();
+        let mut values: Vec<Value> = Vec::new();
+
+        let (query, query_tags) = split_query(&search.query);
+        let mut tag_ids = search.tag_ids.clone();
+        for name in search.tag_names.iter().chain(&query_tags) {
+            match crate::repository::tag::resolve_tag_with_conn(conn, name)? {
+                Some(tag) => tag_ids.push(tag.id),
+                None => return Ok(Vec::new()),
+            }
+        }
+
+        if !query.is_empty() {
+            sql.push_str(" AND (text LIKE ?1 ESCAPE '\\' OR url LIKE ?1 ESCAPE '\\' OR summary LIKE ?1 ESCAPE '\\' OR memo LIKE ?1 ESCAPE '\\'
+                OR id IN (SELECT rowid FROM archives_fts WHERE text LIKE ?1 ESCAPE '\\'))");
+            values.push(Value::Text(like_pattern(&query)));
+        }
+        if search.with_url {
+            sql.push_str(" AND url IS NOT NULL AND url != ''");
+        }
+        for tag_id in &tag_ids {
+            values.push(Value::Integer(*tag_id as i64));
+            sql.push_str(&format!(" AND EXISTS (SELECT 1 FROM item_tags WHERE item_tags.item_id = items.id AND item_tags.tag_id = ?{} AND item_tags.deleted_at IS NULL)", values.len()));
+        }
+        values.push(Value::Integer(search.limit as i64));
+        sql.push_str(&format!(" ORDER BY created_at DESC, id DESC LIMIT ?{}", values.len()));
+
+        let mut stmt = conn.prepare(&sql)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(values))
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut items = Vec::new();
+        while let Ok(Some(row)) = rows.next() {
+            items.push(super::item_repo::row_to_item(row)?);
+        }
+        Ok(items)
+    }
+
+    async fn find_by_url(&self, url: &str, workspace_id: Option<u32>) -> DomainResult<Option<Item>> {
+        let Some(key) = url_key(url) else {
+            return Ok(None);
+        };
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare("SELECT id, text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, url, summary, CAST(created_at AS INTEGER) as created_at, CAST(updated_at AS INTEGER) as updated_at, content_hash, quick_hash, last_known_path, is_dir FROM items WHERE url_key = ?1 AND (?2 IS NULL OR workspace_id = ?2) AND deleted_at IS NULL ORDER BY created_at DESC, id DESC LIMIT 1")
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let mut rows = stmt.query(rusqlite::params![key, workspace_id])
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        match rows.next() {
+            Ok(Some(row)) => Ok(Some(super::item_repo::row_to_item(row)?)),
+            _ => Ok(None),
+        }
+    }
+}
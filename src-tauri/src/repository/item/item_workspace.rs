@@ -5,7 +5,8 @@
 use async_trait::async_trait;
 use rusqlite::params;
 
-use crate::domain::{Item, DomainError, DomainResult};
+use crate::domain::{url_key, Item, DomainError, DomainResult, Timestamp};
+use super::item_repo::item_write_error;
 
 /// Trait for workspace-specific item operations
 #[async_trait]
@@ -13,7 +14,7 @@ pub trait ItemWorkspaceOperations {
     /// List items by workspace
     async fn list_by_workspace(&self, workspace_id: u32) -> DomainResult<Vec<Item>>;
     
-    /// Create item with specific workspace_id
+    /// Create item with specific workspace_id (`created_at` is kept if set)
     async fn create_with_workspace(&self, entity: &Item, workspace_id: u32) -> DomainResult<Item>;
     
     /// Reset all completed items in a workspace back to incomplete
@@ -72,10 +73,12 @@ impl ItemWorkspaceOperations for super::item_repo::ItemRepository {
              let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
              
              let is_dir = if entity.is_dir { 1 } else { 0 };
-             let now = chrono::Utc::now().timestamp_millis();
+             let now = Timestamp::now();
+             // Items captured earlier (e.g. queued by the clipper) keep their time
+             let created_at = entity.created_at.unwrap_or(now);
         
              conn.execute(
-                "INSERT INTO items (text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, workspace_id, url, summary, content_hash, quick_hash, last_known_path, is_dir, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO items (text, completed, item_type, memo, target_count, current_count, parent_id, position, collapsed, workspace_id, url, url_key, summary, content_hash, quick_hash, last_known_path, is_dir, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params![
                     entity.text.clone(),
                     if entity.completed { 1 } else { 0 },
@@ -88,23 +91,24 @@ impl ItemWorkspaceOperations for super::item_repo::ItemRepository {
                     if entity.collapsed { 1 } else { 0 },
                     workspace_id,
                     entity.url.clone(),
+                    entity.url.as_deref().and_then(url_key),
                     entity.summary.clone(),
                     entity.content_hash.clone(),
                     entity.quick_hash.clone(),
                     entity.last_known_path.clone(),
                     is_dir,
-                    now,
+                    created_at,
                     now
                 ],
             )
-            .map_err(|e| DomainError::Internal(e.to_string()))?;
+            .map_err(item_write_error)?;
             
             // Return item with ID and timestamps
             let id = conn.last_insert_rowid() as u32;
             let mut item = entity.clone();
             item.id = id;
             item.position = position;
-            item.created_at = Some(now);
+            item.created_at = Some(created_at);
             item.updated_at = Some(now);
             Ok(item)
         }
@@ -117,7 +121,7 @@ impl ItemWorkspaceOperations for super::item_repo::ItemRepository {
         // Reset completed flag to false for all completed items in the workspace
         conn.execute(
             "UPDATE items SET completed = 0, updated_at = ? WHERE workspace_id = ? AND completed = 1 AND deleted_at IS NULL",
-            params![chrono::Utc::now().timestamp_millis(), workspace_id],
+            params![Timestamp::now(), workspace_id],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
 
@@ -5,11 +5,17 @@
 //! - item_hierarchy: Hierarchy operations (children, descendants, move)
 //! - item_positioning: Position management
 //! - item_workspace: Workspace-specific operations
+//! - item_links: Wiki links ([[...]]) between items and backlinks
+//! - item_search: Text search and URL lookup
+//! - item_link_health: Results of checking item URLs
 
 mod item_repo;
 mod item_hierarchy;
 mod item_positioning;
 mod item_workspace;
+mod item_links;
+mod item_search;
+mod item_link_health;
 
 pub use item_repo::ItemRepository;
 
@@ -17,3 +23,6 @@ pub use item_repo::ItemRepository;
 pub use item_hierarchy::ItemHierarchyOperations;
 pub use item_workspace::ItemWorkspaceOperations;
 pub use item_positioning::ItemPositioningOperations;
+pub use item_links::ItemLinkOperations;
+pub use item_search::{ItemSearch, ItemSearchOperations};
+pub use item_link_health::ItemLinkHealthOperations;
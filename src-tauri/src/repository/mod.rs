@@ -8,6 +8,11 @@ pub mod item;
 pub mod tag;
 pub mod window_state_repo;
 pub mod workspace_repo;
+pub mod settings_repo;
+pub mod asset_repo;
+pub mod archive_repo;
+pub mod sync_conflict_repo;
+pub mod timestamp;
 
 #[cfg(test)]
 mod tests;
@@ -16,7 +21,11 @@ pub use item::ItemRepository;
 pub use tag::TagRepository;
 pub use window_state_repo::{WindowStateRepository, WindowState};
 pub use workspace_repo::WorkspaceRepository;
+pub use settings_repo::SettingsRepository;
+pub use asset_repo::AssetRepository;
+pub use archive_repo::ArchiveRepository;
+pub use sync_conflict_repo::{key_sql, ChangedRow, SyncConflictRepository};
 pub use traits::{Repository, HierarchyRepository};
 
 // Re-export database types and functions (including shared crate functions)
-pub use db::{init_db, SyncConfig, BackupData, DbState, configure_sync, get_sync_config};
+pub use db::{init_db, SyncConfig, DbState, configure_sync, get_sync_config, SYNCED_TABLES};
@@ -0,0 +1,71 @@
+//! Settings Repository
+//!
+//! Key/value application settings stored as JSON in the `settings` table.
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::domain::{DomainError, DomainResult, Timestamp};
+
+/// Setting key: tag colour palette (`Vec<String>` of hex colours)
+pub const TAG_PALETTE_KEY: &str = "tag_palette";
+
+pub struct SettingsRepository {
+    conn: Arc<Mutex<Option<Connection>>>,
+}
+
+impl SettingsRepository {
+    pub fn new(conn: Arc<Mutex<Option<Connection>>>) -> Self {
+        Self { conn }
+    }
+
+    /// Read a setting, `None` if unset or unreadable
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> DomainResult<Option<T>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let raw: Option<String> = conn.query_row(
+                "SELECT value FROM settings WHERE key = ? AND deleted_at IS NULL",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(raw.and_then(|v| serde_json::from_str(&v).ok()))
+    }
+
+    /// Write a setting
+    pub async fn set<T: Serialize + Sync>(&self, key: &str, value: &T) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let json = serde_json::to_string(value).map_err(|e| DomainError::Internal(e.to_string()))?;
+        let now = Timestamp::now();
+        conn.execute(
+            "INSERT INTO settings (key, value, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at, deleted_at = NULL",
+            params![key, json, now],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Remove a setting (soft delete so it syncs)
+    pub async fn remove(&self, key: &str) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let now = Timestamp::now();
+        conn.execute(
+            "UPDATE settings SET deleted_at = ?1, updated_at = ?1 WHERE key = ?2",
+            params![now, key],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+}
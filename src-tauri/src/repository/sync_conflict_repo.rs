@@ -0,0 +1,374 @@
+//! Sync Conflict Repository
+//!
+//! Per-row base versions (`sync_base`) and stored conflicts
+//! (`sync_conflicts`), both local to this device. Rows of `CONFLICT_TABLES`
+//! are read and written generically as `SyncRow`s, so a column added later
+//! is covered without changes here. A row is found by its key (see
+//! `key_sql`), stored as `row_key`.
+
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::domain::{
+    ConflictResolution, ConflictSide, ConflictTable, DomainError, DomainResult, SyncConflict, SyncRow, Timestamp,
+    CONFLICT_TABLES,
+};
+
+/// A row changed on this device since the last sync
+#[derive(Debug, Clone)]
+pub struct ChangedRow {
+    pub table: &'static ConflictTable,
+    pub row_key: String,
+    /// The row as of the last sync; None if created since
+    pub base: Option<SyncRow>,
+    pub local: SyncRow,
+}
+
+pub struct SyncConflictRepository {
+    conn: Arc<Mutex<Option<Connection>>>,
+}
+
+fn internal(e: impl ToString) -> DomainError {
+    DomainError::Internal(e.to_string())
+}
+
+fn value_to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        // Conflict tables have no blob columns
+        ValueRef::Blob(_) => Value::Null,
+    }
+}
+
+fn json_to_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n.as_i64().map(SqlValue::Integer).unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// The leading columns of a row, named by `names`, as a `SyncRow`
+fn read_sync_row(row: &Row, names: &[String]) -> rusqlite::Result<SyncRow> {
+    let mut sync_row = SyncRow::new();
+    for (i, name) in names.iter().enumerate() {
+        sync_row.insert(name.clone(), value_to_json(row.get_ref(i)?));
+    }
+    Ok(sync_row)
+}
+
+fn parse_row(json: &str) -> DomainResult<SyncRow> {
+    serde_json::from_str(json).map_err(|e| DomainError::Internal(format!("Corrupt sync row: {}", e)))
+}
+
+fn to_json(row: &SyncRow) -> DomainResult<String> {
+    serde_json::to_string(row).map_err(internal)
+}
+
+fn conflict_table(table: &str) -> DomainResult<&'static ConflictTable> {
+    CONFLICT_TABLES.iter().find(|t| t.name == table)
+        .ok_or_else(|| DomainError::InvalidInput(format!("Not a conflict table: {}", table)))
+}
+
+/// SQL for the key of a row of `table` aliased `t`: its key columns as a JSON
+/// array. The same expression runs on the remote database.
+pub fn key_sql(table: &ConflictTable) -> String {
+    let columns: Vec<String> = table.key.iter().map(|column| format!("t.\"{}\"", column)).collect();
+    format!("json_array({})", columns.join(", "))
+}
+
+/// `WHERE` clause matching the row with this key, numbering parameters from 1
+fn key_filter(table: &ConflictTable, row_key: &str) -> DomainResult<(String, Vec<SqlValue>)> {
+    let values: Vec<Value> = serde_json::from_str(row_key)
+        .map_err(|e| DomainError::Internal(format!("Corrupt row key {}: {}", row_key, e)))?;
+    if values.len() != table.key.len() {
+        return Err(DomainError::Internal(format!("Row key {} does not fit {}", row_key, table.name)));
+    }
+    let conditions: Vec<String> = table.key.iter().enumerate()
+        .map(|(i, column)| format!("\"{}\" = ?{}", column, i + 1))
+        .collect();
+    Ok((conditions.join(" AND "), values.iter().map(json_to_value).collect()))
+}
+
+fn table_columns(conn: &Connection, table: &str) -> DomainResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).map_err(internal)?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))
+        .map_err(internal)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(internal)?;
+    Ok(columns)
+}
+
+/// Current version of a row
+fn read_row(conn: &Connection, table: &ConflictTable, row_key: &str) -> DomainResult<Option<SyncRow>> {
+    let (filter, key) = key_filter(table, row_key)?;
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE {}", table.name, filter)).map_err(internal)?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    stmt.query_row(params_from_iter(key), |row| read_sync_row(row, &names)).optional().map_err(internal)
+}
+
+/// Overwrite a row with `version` (columns the table lacks are skipped),
+/// stamped now so the choice wins the next sync
+fn write_row(conn: &Connection, table: &ConflictTable, row_key: &str, version: &SyncRow) -> DomainResult<()> {
+    let columns = table_columns(conn, table.name)?;
+    let (filter, mut values) = key_filter(table, row_key)?;
+    let mut assignments = Vec::new();
+    for (column, value) in version {
+        if table.key.contains(&column.as_str()) || column == "updated_at" || !columns.contains(column) {
+            continue;
+        }
+        values.push(json_to_value(value));
+        assignments.push(format!("\"{}\" = ?{}", column, values.len()));
+    }
+    values.push(SqlValue::Integer(Timestamp::now().millis()));
+    assignments.push(format!("updated_at = ?{}", values.len()));
+    let sql = format!("UPDATE {} SET {} WHERE {}", table.name, assignments.join(", "), filter);
+    let updated = conn.execute(&sql, params_from_iter(values)).map_err(internal)?;
+    if updated == 0 {
+        return Err(DomainError::NotFound(format!("{} row {} not found", table.name, row_key)));
+    }
+    Ok(())
+}
+
+/// A `sync_conflicts` row before its JSON is parsed
+struct StoredConflict {
+    id: u32,
+    table_name: String,
+    row_key: String,
+    base: Option<String>,
+    local: String,
+    remote: String,
+    kept: String,
+    created_at: Timestamp,
+}
+
+fn row_to_stored(row: &Row) -> rusqlite::Result<StoredConflict> {
+    Ok(StoredConflict {
+        id: row.get(0)?,
+        table_name: row.get(1)?,
+        row_key: row.get(2)?,
+        base: row.get(3)?,
+        local: row.get(4)?,
+        remote: row.get(5)?,
+        kept: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+impl StoredConflict {
+    fn parse(self) -> DomainResult<SyncConflict> {
+        Ok(SyncConflict {
+            id: self.id,
+            base: self.base.as_deref().map(parse_row).transpose()?,
+            local: parse_row(&self.local)?,
+            remote: parse_row(&self.remote)?,
+            kept: ConflictSide::parse(&self.kept)
+                .ok_or_else(|| DomainError::Internal(format!("Unknown conflict side: {}", self.kept)))?,
+            table_name: self.table_name,
+            row_key: self.row_key,
+            created_at: self.created_at,
+        })
+    }
+}
+
+const CONFLICT_COLUMNS: &str = "id, table_name, row_key, base, local, remote, kept, created_at";
+
+impl SyncConflictRepository {
+    pub fn new(conn: Arc<Mutex<Option<Connection>>>) -> Self {
+        Self { conn }
+    }
+
+    /// Rows whose `updated_at` moved since the last sync. Tables without any
+    /// base yet (never synced with conflict tracking) are skipped.
+    pub async fn changed_since_sync(&self) -> DomainResult<Vec<ChangedRow>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut changed = Vec::new();
+        for table in CONFLICT_TABLES {
+            let tracked: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM sync_base WHERE table_name = ?1)", params![table.name], |row| row.get(0))
+                .map_err(internal)?;
+            if !tracked {
+                continue;
+            }
+            let mut stmt = conn.prepare(&format!(
+                    "SELECT t.*, {key} AS row_key, b.row FROM {name} t
+                     LEFT JOIN sync_base b ON b.table_name = ?1 AND b.row_key = {key}
+                     WHERE b.row_key IS NULL OR t.updated_at IS NOT b.updated_at",
+                    key = key_sql(table),
+                    name = table.name,
+                ))
+                .map_err(internal)?;
+            let mut names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+            names.truncate(names.len() - 2);
+            let rows = stmt.query_map(params![table.name], |row| {
+                    Ok((
+                        read_sync_row(row, &names)?,
+                        row.get::<_, String>(names.len())?,
+                        row.get::<_, Option<String>>(names.len() + 1)?,
+                    ))
+                })
+                .map_err(internal)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(internal)?;
+            for (local, row_key, base) in rows {
+                let base = base.as_deref().map(parse_row).transpose()?;
+                changed.push(ChangedRow { table, row_key, base, local });
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Current version of a row
+    pub async fn read_row(&self, table: &str, row_key: &str) -> DomainResult<Option<SyncRow>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+        read_row(conn, conflict_table(table)?, row_key)
+    }
+
+    /// Store a conflict, replacing an unresolved earlier one for the same row
+    pub async fn record(
+        &self,
+        changed: &ChangedRow,
+        remote: &SyncRow,
+        kept: ConflictSide,
+    ) -> DomainResult<SyncConflict> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let base = changed.base.as_ref().map(to_json).transpose()?;
+        let id: u32 = conn.query_row(
+                "INSERT INTO sync_conflicts (table_name, row_key, base, local, remote, kept, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(table_name, row_key) DO UPDATE SET
+                    base = excluded.base, local = excluded.local, remote = excluded.remote,
+                    kept = excluded.kept, created_at = excluded.created_at
+                 RETURNING id",
+                params![changed.table.name, changed.row_key, base, to_json(&changed.local)?, to_json(remote)?, kept.as_str(), Timestamp::now()],
+                |row| row.get(0),
+            )
+            .map_err(internal)?;
+        conn.query_row(&format!("SELECT {} FROM sync_conflicts WHERE id = ?1", CONFLICT_COLUMNS), params![id], row_to_stored)
+            .map_err(internal)?
+            .parse()
+    }
+
+    /// Take the rows as synced as the base of the next sync; returns how many
+    /// bases changed. Rows written at or after `synced_before` were edited
+    /// here during the sync and keep their old base, so the next sync still
+    /// sees them as changed.
+    pub async fn rebase(&self, synced_before: Timestamp) -> DomainResult<usize> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let tx = conn.unchecked_transaction().map_err(internal)?;
+        let mut rebased = 0;
+        for table in CONFLICT_TABLES {
+            let key = key_sql(table);
+            let rows = {
+                let mut stmt = tx.prepare(&format!(
+                        "SELECT t.*, {key} AS row_key FROM {name} t
+                         LEFT JOIN sync_base b ON b.table_name = ?1 AND b.row_key = {key}
+                         WHERE (b.row_key IS NULL OR t.updated_at IS NOT b.updated_at)
+                           AND COALESCE(t.updated_at, 0) < ?2",
+                        name = table.name,
+                    ))
+                    .map_err(internal)?;
+                let mut names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+                names.pop();
+                let rows = stmt.query_map(params![table.name, synced_before], |row| {
+                        Ok((read_sync_row(row, &names)?, row.get::<_, String>(names.len())?))
+                    })
+                    .map_err(internal)?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(internal)?;
+                rows
+            };
+            for (row, row_key) in &rows {
+                tx.execute(
+                    "INSERT OR REPLACE INTO sync_base (table_name, row_key, updated_at, row) VALUES (?1, ?2, ?3, ?4)",
+                    params![table.name, row_key, json_to_value(row.get("updated_at").unwrap_or(&Value::Null)), to_json(row)?],
+                )
+                .map_err(internal)?;
+            }
+            rebased += rows.len();
+            rebased += tx.execute(
+                    &format!("DELETE FROM sync_base WHERE table_name = ?1 AND row_key NOT IN (SELECT {} FROM {} t)", key, table.name),
+                    params![table.name],
+                )
+                .map_err(internal)?;
+        }
+        tx.commit().map_err(internal)?;
+        Ok(rebased)
+    }
+
+    /// Unresolved conflicts, newest first
+    pub async fn list(&self) -> DomainResult<Vec<SyncConflict>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM sync_conflicts ORDER BY created_at DESC, id DESC", CONFLICT_COLUMNS))
+            .map_err(internal)?;
+        let rows = stmt.query_map([], row_to_stored)
+            .map_err(internal)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(internal)?;
+        rows.into_iter().map(StoredConflict::parse).collect()
+    }
+
+    pub async fn find(&self, id: u32) -> DomainResult<Option<SyncConflict>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        conn.query_row(&format!("SELECT {} FROM sync_conflicts WHERE id = ?1", CONFLICT_COLUMNS), params![id], row_to_stored)
+            .optional()
+            .map_err(internal)?
+            .map(StoredConflict::parse)
+            .transpose()
+    }
+
+    /// Settle a conflict: write the chosen version into its row and forget the
+    /// conflict. With `Merged`, the row keeps the synced version except for the
+    /// memo, which is `memo` if given or else the three-way merge of both.
+    /// Returns the row as written.
+    pub async fn resolve(&self, id: u32, resolution: ConflictResolution, memo: Option<String>) -> DomainResult<SyncRow> {
+        let conflict = self.find(id).await?
+            .ok_or_else(|| DomainError::NotFound(format!("Conflict {} not found", id)))?;
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+        let table = conflict_table(&conflict.table_name)?;
+
+        let version = match resolution {
+            ConflictResolution::KeepLocal => conflict.local.clone(),
+            ConflictResolution::KeepRemote => conflict.remote.clone(),
+            ConflictResolution::Merged => {
+                let memo = match memo {
+                    Some(memo) => memo,
+                    None => conflict.merged_memo()
+                        .ok_or_else(|| DomainError::InvalidInput("Neither version has a memo to merge".to_string()))?
+                        .text,
+                };
+                let mut version = read_row(conn, table, &conflict.row_key)?
+                    .ok_or_else(|| DomainError::NotFound(format!("{} row {} not found", table.name, conflict.row_key)))?;
+                version.insert("memo".to_string(), Value::from(memo));
+                version
+            }
+        };
+
+        let tx = conn.unchecked_transaction().map_err(internal)?;
+        write_row(&tx, table, &conflict.row_key, &version)?;
+        tx.execute("DELETE FROM sync_conflicts WHERE id = ?1", params![id]).map_err(internal)?;
+        tx.commit().map_err(internal)?;
+
+        read_row(conn, table, &conflict.row_key)?
+            .ok_or_else(|| DomainError::NotFound(format!("{} row {} not found", table.name, conflict.row_key)))
+    }
+}
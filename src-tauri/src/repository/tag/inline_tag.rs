@@ -0,0 +1,172 @@
+//! Inline Tag Operations
+//!
+//! Applies `#tag` / `#parent/child` tokens from item text and memos: missing
+//! tags and `tag_tags` edges are created, leaf tags are attached with source
+//! `inline`, and inline tags whose token disappeared are removed again.
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeSet;
+
+use crate::domain::{Tag, DomainError, DomainResult, extract_inline_tags, TAG_SOURCE_INLINE, Timestamp};
+
+/// Trait for inline tag operations
+#[async_trait]
+pub trait InlineTagOperations {
+    /// Sync inline tags of an item with the tokens in its text and memo.
+    ///
+    /// Returns the tags currently applied through tokens.
+    async fn sync_inline_tags(&self, item_id: u32, text: &str, memo: Option<&str>) -> DomainResult<Vec<Tag>>;
+}
+
+/// Find (by name or alias) or create a tag with the palette colour new tags
+/// get (see `tag_color::palette_color`; dropped again by `ensure_edge` under a
+/// coloured parent); returns (id, created)
+fn ensure_tag(conn: &Connection, name: &str, now: Timestamp) -> DomainResult<(u32, bool)> {
+    if let Some(tag) = super::tag_alias::resolve_with_conn(conn, name)? {
+        return Ok((tag.id, false));
+    }
+
+    // Names are unique: revive a tombstoned tag of the same name instead of failing
+    let color = super::tag_color::palette_color(conn)?;
+    conn.execute(
+        "INSERT INTO tags (name, color, color_auto, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(name) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at",
+        params![name, color, color.is_some(), now],
+    )
+    .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+    let id = conn.query_row("SELECT id FROM tags WHERE name = ?", params![name], |row| row.get(0))
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    Ok((id, true))
+}
+
+/// Ensure child -> parent edge; skipped if it would create a cycle. Returns true if added.
+fn ensure_edge(conn: &Connection, child_tag_id: u32, parent_tag_id: u32, now: Timestamp) -> DomainResult<bool> {
+    if child_tag_id == parent_tag_id {
+        return Ok(false);
+    }
+    let exists: Option<i32> = conn.query_row(
+            "SELECT 1 FROM tag_tags WHERE child_tag_id = ? AND parent_tag_id = ? AND deleted_at IS NULL",
+            params![child_tag_id, parent_tag_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    if exists.is_some() || super::tag_hierarchy::would_create_cycle(conn, child_tag_id, parent_tag_id)? {
+        return Ok(false);
+    }
+
+    conn.execute(
+        "INSERT INTO tag_tags (child_tag_id, parent_tag_id, position, created_at, updated_at)
+         VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM tag_tags WHERE parent_tag_id = ?2 AND deleted_at IS NULL), ?3, ?3)
+         ON CONFLICT(child_tag_id, parent_tag_id) DO UPDATE SET deleted_at = NULL, position = excluded.position, updated_at = excluded.updated_at",
+        params![child_tag_id, parent_tag_id, now],
+    )
+    .map_err(|e| DomainError::Internal(e.to_string()))?;
+    super::tag_color::drop_auto_color(conn, child_tag_id, parent_tag_id, now)?;
+    Ok(true)
+}
+
+#[async_trait]
+impl InlineTagOperations for super::tag_repo::TagRepository {
+    async fn sync_inline_tags(&self, item_id: u32, text: &str, memo: Option<&str>) -> DomainResult<Vec<Tag>> {
+        let guard = self.conn.lock().await;
+
+        let (tags, hierarchy_changed) = {
+            let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+            let mut paths = extract_inline_tags(text);
+            for path in extract_inline_tags(memo.unwrap_or("")) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+
+            let now = Timestamp::now();
+            let tx = conn.unchecked_transaction()
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 1. Create tags along each path and attach the leaf
+            let mut hierarchy_changed = false;
+            let mut leaf_ids = BTreeSet::new();
+            for path in &paths {
+                let mut parent: Option<u32> = None;
+                for segment in path {
+                    let (id, created) = ensure_tag(&tx, segment, now)?;
+                    hierarchy_changed |= created;
+                    if let Some(parent_id) = parent {
+                        hierarchy_changed |= ensure_edge(&tx, id, parent_id, now)?;
+                    }
+                    parent = Some(id);
+                }
+                if let Some(leaf) = parent {
+                    leaf_ids.insert(leaf);
+                }
+            }
+
+            // A live manual row stays manual; new or tombstoned rows become inline
+            for tag_id in &leaf_ids {
+                tx.execute(
+                    "INSERT INTO item_tags (item_id, tag_id, created_at, updated_at, source) VALUES (?1, ?2, ?3, ?3, ?4)
+                     ON CONFLICT(item_id, tag_id) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at, source = excluded.source
+                     WHERE item_tags.deleted_at IS NOT NULL",
+                    params![item_id, tag_id, now, TAG_SOURCE_INLINE],
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            }
+
+            // 2. Remove inline tags whose token is gone
+            let stale: Vec<u32> = {
+                let mut stmt = tx.prepare("SELECT tag_id FROM item_tags WHERE item_id = ? AND source = ? AND deleted_at IS NULL")
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                let mut rows = stmt.query(params![item_id, TAG_SOURCE_INLINE])
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                let mut stale = Vec::new();
+                while let Ok(Some(row)) = rows.next() {
+                    let tag_id: u32 = row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?;
+                    if !leaf_ids.contains(&tag_id) {
+                        stale.push(tag_id);
+                    }
+                }
+                stale
+            };
+            for tag_id in stale {
+                tx.execute(
+                    "UPDATE item_tags SET deleted_at = ?1, updated_at = ?1 WHERE item_id = ?2 AND tag_id = ?3",
+                    params![now, item_id, tag_id],
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            }
+
+            tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 3. Inline tags now applied
+            let mut stmt = conn.prepare(
+                    "SELECT t.id, t.name, t.color, t.position, t.memo, t.icon, t.description, t.pinned FROM tags t
+                     JOIN item_tags it ON it.tag_id = t.id
+                     WHERE it.item_id = ? AND it.source = ? AND it.deleted_at IS NULL AND t.deleted_at IS NULL
+                     ORDER BY t.name"
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let mut rows = stmt.query(params![item_id, TAG_SOURCE_INLINE])
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let mut tags = Vec::new();
+            while let Ok(Some(row)) = rows.next() {
+                tags.push(super::tag_repo::row_to_tag(row)?);
+            }
+            super::tag_color::fill_effective_colors(conn, &mut tags)?;
+
+            (tags, hierarchy_changed)
+        };
+
+        // Drop guard and reindex root tags if tags or edges were created
+        drop(guard);
+        if hierarchy_changed {
+            use super::tag_positioning::TagPositioningOperations;
+            self.reindex_root_tags().await?;
+        }
+
+        Ok(tags)
+    }
+}
@@ -5,12 +5,12 @@
 use async_trait::async_trait;
 use rusqlite::params;
 
-use crate::domain::{Tag, DomainError, DomainResult};
+use crate::domain::{Tag, DomainError, DomainResult, TAG_SOURCE_MANUAL, Timestamp};
 
 /// Trait for item-tag relationship operations
 #[async_trait]
 pub trait ItemTagOperations {
-    /// Add a tag to an item
+    /// Add a tag to an item (marks an inline-applied tag as manual, so it outlives its token)
     async fn add_tag_to_item(&self, item_id: u32, tag_id: u32) -> DomainResult<()>;
     
     /// Remove a tag from an item
@@ -30,8 +30,10 @@ impl ItemTagOperations for super::tag_repo::TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         conn.execute(
-            "INSERT OR IGNORE INTO item_tags (item_id, tag_id, updated_at) VALUES (?, ?, ?)",
-            params![item_id, tag_id, chrono::Utc::now().timestamp_millis()],
+            "INSERT INTO item_tags (item_id, tag_id, created_at, updated_at, source) VALUES (?1, ?2, ?3, ?3, ?4)
+             ON CONFLICT(item_id, tag_id) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at, source = excluded.source
+             WHERE item_tags.deleted_at IS NOT NULL OR item_tags.source != excluded.source",
+            params![item_id, tag_id, Timestamp::now(), TAG_SOURCE_MANUAL],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
 
@@ -58,9 +60,9 @@ impl ItemTagOperations for super::tag_repo::TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         let mut stmt = conn.prepare(
-                "SELECT t.id, t.name, t.color FROM tags t
+                "SELECT t.id, t.name, t.color, t.position, t.memo, t.icon, t.description, t.pinned FROM tags t
                  JOIN item_tags it ON t.id = it.tag_id
-                 WHERE it.item_id = ?"
+                 WHERE it.item_id = ? AND it.deleted_at IS NULL AND t.deleted_at IS NULL"
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
         
@@ -85,6 +87,7 @@ impl ItemTagOperations for super::tag_repo::TagRepository {
             a_pinyin.cmp(&b_pinyin)
         });
         
+        super::tag_color::fill_effective_colors(conn, &mut tags)?;
         Ok(tags)
     }
 
@@ -92,7 +95,7 @@ impl ItemTagOperations for super::tag_repo::TagRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let mut stmt = conn.prepare("SELECT item_id FROM item_tags WHERE tag_id = ?")
+        let mut stmt = conn.prepare("SELECT item_id FROM item_tags WHERE tag_id = ? AND deleted_at IS NULL")
             .map_err(|e| DomainError::Internal(e.to_string()))?;
             
         let mut rows = stmt.query(params![tag_id])
@@ -5,11 +5,19 @@
 //! - item_tag: Item-Tag relationship operations
 //! - tag_hierarchy: Tag-Tag relationship operations (parent-child)
 //! - tag_positioning: Position management operations
+//! - tag_alias: Tag aliases and merging
+//! - tag_stats: Usage statistics
+//! - tag_color: Effective (inherited) colours and palette assignment
+//! - inline_tag: `#tag` tokens in item text/memos
 
 mod tag_repo;
 mod item_tag;
 mod tag_hierarchy;
 mod tag_positioning;
+mod tag_alias;
+mod tag_stats;
+mod tag_color;
+mod inline_tag;
 
 pub use tag_repo::TagRepository;
 
@@ -17,3 +25,10 @@ pub use tag_repo::TagRepository;
 pub use item_tag::ItemTagOperations;
 pub use tag_hierarchy::TagHierarchyOperations;
 pub use tag_positioning::TagPositioningOperations;
+pub use tag_alias::TagAliasOperations;
+pub use tag_stats::TagStatsOperations;
+pub use tag_color::TagColorOperations;
+pub use inline_tag::InlineTagOperations;
+
+// Name/alias lookup for other repositories working on an open connection
+pub(crate) use tag_alias::resolve_with_conn as resolve_tag_with_conn;
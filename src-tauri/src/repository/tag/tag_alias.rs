@@ -0,0 +1,291 @@
+//! Tag Alias Operations
+//!
+//! Operations for tag aliases (tag_aliases table) and merging duplicate tags.
+//! An alias is an alternative name (`js`, `JS`) that resolves to one canonical tag.
+
+use async_trait::async_trait;
+use rusqlite::{params, OptionalExtension};
+
+use crate::domain::{Tag, TagAlias, DomainError, DomainResult, normalize_tag_name, Timestamp};
+use super::tag_hierarchy::would_create_cycle;
+
+/// Trait for tag alias and merge operations
+#[async_trait]
+pub trait TagAliasOperations {
+    /// Resolve a name to its canonical tag (by tag name or alias, case-insensitive)
+    async fn resolve_tag_name(&self, name: &str) -> DomainResult<Option<Tag>>;
+
+    /// Add an alias for a tag
+    async fn add_alias(&self, tag_id: u32, alias: &str) -> DomainResult<TagAlias>;
+
+    /// Remove an alias
+    async fn remove_alias(&self, alias: &str) -> DomainResult<()>;
+
+    /// Get all aliases of a tag
+    async fn get_aliases(&self, tag_id: u32) -> DomainResult<Vec<TagAlias>>;
+
+    /// Get all aliases (for autocomplete)
+    async fn list_aliases(&self) -> DomainResult<Vec<TagAlias>>;
+
+    /// Merge source tag into target tag.
+    ///
+    /// Moves all item_tags and tag_tags edges to the target, repoints the source's
+    /// aliases, keeps the source name as an alias and tombstones the source tag.
+    /// Tags where one is an ancestor of the other are not merged: the combined
+    /// tag would be its own ancestor.
+    async fn merge_tags(&self, source_tag_id: u32, target_tag_id: u32) -> DomainResult<Tag>;
+}
+
+/// Find a live tag by exact name (case-insensitive) or alias
+pub(crate) fn resolve_with_conn(conn: &rusqlite::Connection, name: &str) -> DomainResult<Option<Tag>> {
+    let key = normalize_tag_name(name);
+    if key.is_empty() {
+        return Ok(None);
+    }
+
+    let by_name = conn.query_row(
+            "SELECT id, name, color, position, memo, icon, description, pinned FROM tags
+             WHERE lower(trim(name)) = ? AND deleted_at IS NULL
+             ORDER BY id LIMIT 1",
+            params![key],
+            |row| Ok(super::tag_repo::row_to_tag(row)),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    if let Some(tag) = by_name {
+        let mut tag = tag?;
+        super::tag_color::fill_effective_colors(conn, std::slice::from_mut(&mut tag))?;
+        return Ok(Some(tag));
+    }
+
+    let by_alias = conn.query_row(
+            "SELECT t.id, t.name, t.color, t.position, t.memo, t.icon, t.description, t.pinned FROM tags t
+             JOIN tag_aliases a ON a.tag_id = t.id
+             WHERE a.alias = ? AND a.deleted_at IS NULL AND t.deleted_at IS NULL",
+            params![key],
+            |row| Ok(super::tag_repo::row_to_tag(row)),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    let mut tag = by_alias.transpose()?;
+    if let Some(tag) = tag.as_mut() {
+        super::tag_color::fill_effective_colors(conn, std::slice::from_mut(tag))?;
+    }
+    Ok(tag)
+}
+
+#[async_trait]
+impl TagAliasOperations for super::tag_repo::TagRepository {
+    async fn resolve_tag_name(&self, name: &str) -> DomainResult<Option<Tag>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        resolve_with_conn(conn, name)
+    }
+
+    async fn add_alias(&self, tag_id: u32, alias: &str) -> DomainResult<TagAlias> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let key = normalize_tag_name(alias);
+        if key.is_empty() {
+            return Err(DomainError::InvalidInput("Alias cannot be empty".to_string()));
+        }
+
+        // An alias must not shadow a different tag's name or alias
+        if let Some(existing) = resolve_with_conn(conn, &key)? {
+            if existing.id != tag_id {
+                return Err(DomainError::Conflict(format!(
+                    "'{}' already refers to tag '{}'", alias.trim(), existing.name
+                )));
+            }
+        }
+
+        let now = Timestamp::now();
+        conn.execute(
+            "INSERT INTO tag_aliases (alias, tag_id, created_at, updated_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(alias) DO UPDATE SET tag_id = excluded.tag_id, updated_at = excluded.updated_at, deleted_at = NULL",
+            params![key, tag_id, now, now],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(TagAlias { alias: key, tag_id })
+    }
+
+    async fn remove_alias(&self, alias: &str) -> DomainResult<()> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let now = Timestamp::now();
+
+        // Soft delete so the removal syncs
+        conn.execute(
+            "UPDATE tag_aliases SET deleted_at = ?, updated_at = ? WHERE alias = ?",
+            params![now, now, normalize_tag_name(alias)],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_aliases(&self, tag_id: u32) -> DomainResult<Vec<TagAlias>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare("SELECT alias, tag_id FROM tag_aliases WHERE tag_id = ? AND deleted_at IS NULL ORDER BY alias")
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut rows = stmt.query(params![tag_id])
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut aliases = Vec::new();
+        while let Ok(Some(row)) = rows.next() {
+            aliases.push(row_to_alias(row)?);
+        }
+        Ok(aliases)
+    }
+
+    async fn list_aliases(&self) -> DomainResult<Vec<TagAlias>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stmt = conn.prepare(
+                "SELECT a.alias, a.tag_id FROM tag_aliases a
+                 JOIN tags t ON t.id = a.tag_id
+                 WHERE a.deleted_at IS NULL AND t.deleted_at IS NULL
+                 ORDER BY a.alias"
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut rows = stmt.query([])
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut aliases = Vec::new();
+        while let Ok(Some(row)) = rows.next() {
+            aliases.push(row_to_alias(row)?);
+        }
+        Ok(aliases)
+    }
+
+    async fn merge_tags(&self, source_tag_id: u32, target_tag_id: u32) -> DomainResult<Tag> {
+        if source_tag_id == target_tag_id {
+            return Err(DomainError::InvalidInput("Cannot merge a tag into itself".to_string()));
+        }
+
+        let guard = self.conn.lock().await;
+
+        let target = {
+            let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+            let find_live = |id: u32| -> DomainResult<Tag> {
+                conn.query_row(
+                        "SELECT id, name, color, position, memo, icon, description, pinned FROM tags WHERE id = ? AND deleted_at IS NULL",
+                        params![id],
+                        |row| Ok(super::tag_repo::row_to_tag(row)),
+                    )
+                    .optional()
+                    .map_err(|e| DomainError::Internal(e.to_string()))?
+                    .ok_or_else(|| DomainError::NotFound(format!("Tag {} not found", id)))?
+            };
+            let source = find_live(source_tag_id)?;
+            let mut target = find_live(target_tag_id)?;
+            super::tag_color::fill_effective_colors(conn, std::slice::from_mut(&mut target))?;
+
+            // An edge from either tag to the other would become a cycle through the merged tag
+            if would_create_cycle(conn, source_tag_id, target_tag_id)? || would_create_cycle(conn, target_tag_id, source_tag_id)? {
+                return Err(DomainError::Conflict(format!(
+                    "Cannot merge '{}' into '{}': one is an ancestor of the other", source.name, target.name
+                )));
+            }
+
+            let now = Timestamp::now();
+            let tx = conn.unchecked_transaction()
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 1. item_tags: re-point live edges to the target, then tombstone the originals
+            tx.execute(
+                "INSERT INTO item_tags (item_id, tag_id, created_at, updated_at)
+                 SELECT item_id, ?1, ?3, ?3 FROM item_tags WHERE tag_id = ?2 AND deleted_at IS NULL
+                 ON CONFLICT(item_id, tag_id) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at",
+                params![target_tag_id, source_tag_id, now],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+            tx.execute(
+                "UPDATE item_tags SET deleted_at = ?, updated_at = ? WHERE tag_id = ? AND deleted_at IS NULL",
+                params![now, now, source_tag_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 2. tag_tags where source is the child: target inherits source's parents
+            tx.execute(
+                "INSERT INTO tag_tags (child_tag_id, parent_tag_id, position, created_at, updated_at)
+                 SELECT ?1, parent_tag_id, position, ?3, ?3 FROM tag_tags
+                 WHERE child_tag_id = ?2 AND parent_tag_id != ?1 AND deleted_at IS NULL
+                 ON CONFLICT(child_tag_id, parent_tag_id) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at",
+                params![target_tag_id, source_tag_id, now],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 3. tag_tags where source is the parent: children move under target (appended)
+            tx.execute(
+                "INSERT INTO tag_tags (child_tag_id, parent_tag_id, position, created_at, updated_at)
+                 SELECT child_tag_id, ?1,
+                        (SELECT COALESCE(MAX(position), -1) + 1 FROM tag_tags WHERE parent_tag_id = ?1 AND deleted_at IS NULL) + position,
+                        ?3, ?3
+                 FROM tag_tags
+                 WHERE parent_tag_id = ?2 AND child_tag_id != ?1 AND deleted_at IS NULL
+                 ON CONFLICT(child_tag_id, parent_tag_id) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at",
+                params![target_tag_id, source_tag_id, now],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+            tx.execute(
+                "UPDATE tag_tags SET deleted_at = ?1, updated_at = ?1
+                 WHERE (child_tag_id = ?2 OR parent_tag_id = ?2) AND deleted_at IS NULL",
+                params![now, source_tag_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            // 4. Aliases: source's aliases and its own name now resolve to the target
+            tx.execute(
+                "UPDATE tag_aliases SET tag_id = ?, updated_at = ? WHERE tag_id = ?",
+                params![target_tag_id, now, source_tag_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let source_key = normalize_tag_name(&source.name);
+            if source_key != normalize_tag_name(&target.name) {
+                tx.execute(
+                    "INSERT INTO tag_aliases (alias, tag_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+                     ON CONFLICT(alias) DO UPDATE SET tag_id = excluded.tag_id, updated_at = excluded.updated_at, deleted_at = NULL",
+                    params![source_key, target_tag_id, now],
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            }
+
+            // 5. Tombstone the source tag so the merge syncs
+            tx.execute(
+                "UPDATE tags SET deleted_at = ?, updated_at = ? WHERE id = ?",
+                params![now, now, source_tag_id],
+            )
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+            tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+            target
+        };
+
+        // Drop guard and reindex root tags since the source left the root list
+        drop(guard);
+
+        use super::tag_positioning::TagPositioningOperations;
+        self.reindex_root_tags().await?;
+
+        Ok(target)
+    }
+}
+
+/// Convert a database row to TagAlias
+fn row_to_alias(row: &rusqlite::Row) -> DomainResult<TagAlias> {
+    Ok(TagAlias {
+        alias: row.get(0).map_err(|e: rusqlite::Error| DomainError::Internal(e.to_string()))?,
+        tag_id: row.get(1).map_err(|e: rusqlite::Error| DomainError::Internal(e.to_string()))?,
+    })
+}
@@ -0,0 +1,146 @@
+//! Tag Colour Operations
+//!
+//! Effective colour resolution (a tag without a colour inherits from its first
+//! parent by `tag_tags.position`, ties broken by the earliest-added edge) and
+//! automatic palette assignment. Only root tags keep a palette colour: it is
+//! marked `color_auto` and dropped once the tag becomes a child of a coloured
+//! tag, so children inherit.
+
+use async_trait::async_trait;
+use rusqlite::{params, OptionalExtension};
+
+use crate::domain::{Tag, DomainError, DomainResult, pick_palette_color, DEFAULT_TAG_PALETTE, Timestamp};
+use crate::repository::settings_repo::TAG_PALETTE_KEY;
+
+/// Maximum ancestor depth walked when resolving colours (guards against cycles)
+const MAX_COLOR_DEPTH: i32 = 64;
+
+/// Trait for tag colour operations
+#[async_trait]
+pub trait TagColorOperations {
+    /// Own colour, else the nearest coloured ancestor along first parents
+    async fn effective_color(&self, tag_id: u32) -> DomainResult<Option<String>>;
+
+    /// Palette colour used least by existing tags
+    async fn next_palette_color(&self, palette: &[String]) -> DomainResult<Option<String>>;
+
+    /// Create a root tag with the colour new tags get without an explicit one
+    /// (see `palette_color`); every path that creates tags by name uses this
+    async fn create_with_palette_color(&self, name: &str) -> DomainResult<Tag>;
+}
+
+/// Resolve the effective colour of one tag
+pub(super) fn resolve_effective_color(conn: &rusqlite::Connection, tag_id: u32) -> DomainResult<Option<String>> {
+    conn.query_row(
+            "WITH RECURSIVE chain(id, color, depth) AS (
+                 SELECT id, NULLIF(color, ''), 0 FROM tags WHERE id = ?1
+                 UNION ALL
+                 SELECT t.id, NULLIF(t.color, ''), c.depth + 1 FROM chain c
+                 JOIN tags t ON t.id = (
+                     SELECT tt.parent_tag_id FROM tag_tags tt
+                     JOIN tags p ON p.id = tt.parent_tag_id AND p.deleted_at IS NULL
+                     WHERE tt.child_tag_id = c.id AND tt.deleted_at IS NULL
+                     ORDER BY tt.position, tt.rowid
+                     LIMIT 1
+                 )
+                 WHERE c.color IS NULL AND c.depth < ?2
+             )
+             SELECT color FROM chain WHERE color IS NOT NULL ORDER BY depth LIMIT 1",
+            params![tag_id, MAX_COLOR_DEPTH],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))
+}
+
+/// Palette colour used least by live tags
+fn least_used_color(conn: &rusqlite::Connection, palette: &[String]) -> DomainResult<Option<String>> {
+    let mut stmt = conn.prepare("SELECT color FROM tags WHERE deleted_at IS NULL AND color IS NOT NULL AND color != ''")
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    let mut rows = stmt.query([])
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+    let mut used = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        used.push(row.get::<_, String>(0).map_err(|e| DomainError::Internal(e.to_string()))?);
+    }
+
+    Ok(pick_palette_color(palette, &used))
+}
+
+/// Colour of a new tag created without one: the least-used colour of the
+/// configured palette (`TAG_PALETTE_KEY`, default `DEFAULT_TAG_PALETTE`)
+pub(super) fn palette_color(conn: &rusqlite::Connection) -> DomainResult<Option<String>> {
+    let raw: Option<String> = conn.query_row(
+            "SELECT value FROM settings WHERE key = ? AND deleted_at IS NULL",
+            params![TAG_PALETTE_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+    let palette: Vec<String> = raw.and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_else(|| DEFAULT_TAG_PALETTE.iter().map(|c| c.to_string()).collect());
+    least_used_color(conn, &palette)
+}
+
+/// Drop the palette colour of a tag that became a child of `parent_tag_id`
+/// if the parent has a colour to inherit; explicit colours are kept
+pub(super) fn drop_auto_color(conn: &rusqlite::Connection, child_tag_id: u32, parent_tag_id: u32, now: Timestamp) -> DomainResult<()> {
+    if resolve_effective_color(conn, parent_tag_id)?.is_none() {
+        return Ok(());
+    }
+    conn.execute(
+        "UPDATE tags SET color = NULL, color_auto = 0, updated_at = ? WHERE id = ? AND color_auto = 1",
+        params![now, child_tag_id],
+    )
+    .map_err(|e| DomainError::Internal(e.to_string()))?;
+    Ok(())
+}
+
+/// Fill `effective_color` on tags read from the database
+pub(super) fn fill_effective_colors(conn: &rusqlite::Connection, tags: &mut [Tag]) -> DomainResult<()> {
+    for tag in tags.iter_mut() {
+        tag.effective_color = match tag.color.as_deref() {
+            Some(c) if !c.is_empty() => Some(c.to_string()),
+            _ => resolve_effective_color(conn, tag.id)?,
+        };
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl TagColorOperations for super::tag_repo::TagRepository {
+    async fn effective_color(&self, tag_id: u32) -> DomainResult<Option<String>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        resolve_effective_color(conn, tag_id)
+    }
+
+    async fn next_palette_color(&self, palette: &[String]) -> DomainResult<Option<String>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        least_used_color(conn, palette)
+    }
+
+    async fn create_with_palette_color(&self, name: &str) -> DomainResult<Tag> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let color = palette_color(conn)?;
+        conn.execute(
+            "INSERT INTO tags (name, color, color_auto, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![name, color, color.is_some(), Timestamp::now()],
+        )
+        .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let mut tag = match color {
+            Some(color) => Tag::with_color(0, name.to_string(), color),
+            None => Tag::new(0, name.to_string()),
+        };
+        tag.id = conn.last_insert_rowid() as u32;
+        fill_effective_colors(conn, std::slice::from_mut(&mut tag))?;
+        Ok(tag)
+    }
+}
@@ -3,14 +3,18 @@
 //! Operations for managing parent-child relationships between tags (tag_tags table).
 
 use async_trait::async_trait;
-use rusqlite::params;
+use rusqlite::{Connection, params};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::domain::{Tag, DomainError, DomainResult};
+use crate::domain::{Tag, TagEdge, TagGraphReport, DomainError, DomainResult, Timestamp};
 
 /// Trait for tag hierarchy operations
 #[async_trait]
 pub trait TagHierarchyOperations {
     /// Add a parent tag to a child tag
+    ///
+    /// Fails with `DomainError::Conflict` for self-parenting or when the parent is
+    /// already a descendant of the child (the edge would close a cycle).
     async fn add_parent_tag(&self, child_tag_id: u32, parent_tag_id: u32) -> DomainResult<()>;
     
     /// Remove a parent tag from a child tag
@@ -24,6 +28,75 @@ pub trait TagHierarchyOperations {
     
     /// Get root tags (tags that have no parent tags)
     async fn get_root_tags(&self) -> DomainResult<Vec<Tag>>;
+    
+    /// Check the tag graph for cycles, self edges and dangling edges.
+    /// With `repair`, offending edges are tombstoned so the fix syncs.
+    async fn check_tag_graph(&self, repair: bool) -> DomainResult<TagGraphReport>;
+}
+
+/// Check whether `child -> parent` would close a cycle (parent is child or one of its descendants)
+pub(super) fn would_create_cycle(conn: &Connection, child_tag_id: u32, parent_tag_id: u32) -> DomainResult<bool> {
+    if child_tag_id == parent_tag_id {
+        return Ok(true);
+    }
+    
+    // Walk up from the new parent; if we reach the child, the child is an ancestor of the parent.
+    // UNION (not UNION ALL) keeps the walk finite even if the graph already contains a cycle.
+    conn.query_row(
+        "WITH RECURSIVE ancestors(id) AS (
+            SELECT ?1
+            UNION
+            SELECT tt.parent_tag_id FROM tag_tags tt
+            JOIN ancestors a ON tt.child_tag_id = a.id
+            WHERE tt.deleted_at IS NULL
+        )
+        SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = ?2)",
+        params![parent_tag_id, child_tag_id],
+        |row| row.get::<_, bool>(0),
+    )
+    .map_err(|e| DomainError::Internal(e.to_string()))
+}
+
+/// Find one cycle in a child -> parents adjacency map (DFS, deterministic order)
+fn find_cycle(graph: &BTreeMap<u32, Vec<u32>>) -> Option<Vec<u32>> {
+    fn visit(
+        node: u32,
+        graph: &BTreeMap<u32, Vec<u32>>,
+        path: &mut Vec<u32>,
+        on_path: &mut HashSet<u32>,
+        done: &mut HashSet<u32>,
+    ) -> Option<Vec<u32>> {
+        path.push(node);
+        on_path.insert(node);
+        for &next in graph.get(&node).map(|v| v.as_slice()).unwrap_or(&[]) {
+            if on_path.contains(&next) {
+                let start = path.iter().position(|&n| n == next).unwrap_or(0);
+                return Some(path[start..].to_vec());
+            }
+            if !done.contains(&next) {
+                if let Some(cycle) = visit(next, graph, path, on_path, done) {
+                    return Some(cycle);
+                }
+            }
+        }
+        path.pop();
+        on_path.remove(&node);
+        done.insert(node);
+        None
+    }
+    
+    let mut done = HashSet::new();
+    for &start in graph.keys() {
+        if done.contains(&start) {
+            continue;
+        }
+        let mut path = Vec::new();
+        let mut on_path = HashSet::new();
+        if let Some(cycle) = visit(start, graph, &mut path, &mut on_path, &mut done) {
+            return Some(cycle);
+        }
+    }
+    None
 }
 
 #[async_trait]
@@ -35,8 +108,18 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         {
             let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
             
+            if child_tag_id == parent_tag_id {
+                return Err(DomainError::Conflict("A tag cannot be its own parent".to_string()));
+            }
+            if would_create_cycle(conn, child_tag_id, parent_tag_id)? {
+                return Err(DomainError::Conflict(format!(
+                    "Tag {} is a descendant of tag {}; adding it as parent would create a cycle",
+                    parent_tag_id, child_tag_id
+                )));
+            }
+            
             // Get next position under this parent
-            let mut stmt = conn.prepare("SELECT COALESCE(MAX(position), -1) + 1 FROM tag_tags WHERE parent_tag_id = ?")
+            let mut stmt = conn.prepare("SELECT COALESCE(MAX(position), -1) + 1 FROM tag_tags WHERE parent_tag_id = ? AND deleted_at IS NULL")
                 .map_err(|e| DomainError::Internal(e.to_string()))?;
             
             let mut rows = stmt.query(params![parent_tag_id])
@@ -48,11 +131,15 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
                 0
             };
             
+            let now = Timestamp::now();
             conn.execute(
-                "INSERT OR IGNORE INTO tag_tags (child_tag_id, parent_tag_id, position, updated_at) VALUES (?, ?, ?, ?)",
-                params![child_tag_id, parent_tag_id, position, chrono::Utc::now().timestamp_millis()],
+                "INSERT INTO tag_tags (child_tag_id, parent_tag_id, position, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(child_tag_id, parent_tag_id) DO UPDATE SET deleted_at = NULL, position = excluded.position, updated_at = excluded.updated_at
+                 WHERE tag_tags.deleted_at IS NOT NULL",
+                params![child_tag_id, parent_tag_id, position, now],
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
+            super::tag_color::drop_auto_color(conn, child_tag_id, parent_tag_id, now)?;
         }
         
         // Drop guard and reindex root tags since a tag was removed from root
@@ -93,9 +180,9 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         let mut stmt = conn.prepare(
-                "SELECT t.id, t.name, t.color FROM tags t
+                "SELECT t.id, t.name, t.color, t.position, t.memo, t.icon, t.description, t.pinned FROM tags t
                  JOIN tag_tags tt ON t.id = tt.parent_tag_id
-                 WHERE tt.child_tag_id = ?
+                 WHERE tt.child_tag_id = ? AND tt.deleted_at IS NULL AND t.deleted_at IS NULL
                  ORDER BY t.name"
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -107,6 +194,7 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         while let Ok(Some(row)) = rows.next() {
             tags.push(super::tag_repo::row_to_tag(&row)?);
         }
+        super::tag_color::fill_effective_colors(conn, &mut tags)?;
         Ok(tags)
     }
 
@@ -115,9 +203,9 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         let mut stmt = conn.prepare(
-                "SELECT t.id, t.name, t.color, tt.position FROM tags t
+                "SELECT t.id, t.name, t.color, tt.position, t.memo, t.icon, t.description, t.pinned FROM tags t
                  JOIN tag_tags tt ON t.id = tt.child_tag_id
-                 WHERE tt.parent_tag_id = ?
+                 WHERE tt.parent_tag_id = ? AND tt.deleted_at IS NULL AND t.deleted_at IS NULL
                  ORDER BY tt.position"
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -129,6 +217,7 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         while let Ok(Some(row)) = rows.next() {
             tags.push(super::tag_repo::row_to_tag(&row)?);
         }
+        super::tag_color::fill_effective_colors(conn, &mut tags)?;
         Ok(tags)
     }
 
@@ -137,9 +226,10 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         let mut stmt = conn.prepare(
-                "SELECT id, name, color, position FROM tags 
-                 WHERE id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags)
-                 ORDER BY position, name"
+                "SELECT id, name, color, position, memo, icon, description, pinned FROM tags 
+                 WHERE deleted_at IS NULL
+                   AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags WHERE deleted_at IS NULL)
+                 ORDER BY pinned DESC, position, name"
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
             
@@ -150,7 +240,108 @@ impl TagHierarchyOperations for super::tag_repo::TagRepository {
         while let Ok(Some(row)) = rows.next() {
             tags.push(super::tag_repo::row_to_tag(&row)?);
         }
+        super::tag_color::fill_effective_colors(conn, &mut tags)?;
         
         Ok(tags)
     }
+
+    async fn check_tag_graph(&self, repair: bool) -> DomainResult<TagGraphReport> {
+        let guard = self.conn.lock().await;
+        
+        let report = {
+            let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+            
+            let live_tags: HashSet<u32> = {
+                let mut stmt = conn.prepare("SELECT id FROM tags WHERE deleted_at IS NULL")
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                let mut rows = stmt.query([])
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                let mut ids = HashSet::new();
+                while let Ok(Some(row)) = rows.next() {
+                    ids.insert(row.get::<_, u32>(0).map_err(|e| DomainError::Internal(e.to_string()))?);
+                }
+                ids
+            };
+            
+            // Live edges with their last change time (newest edge is blamed for a cycle)
+            let mut edges: Vec<(TagEdge, i64)> = Vec::new();
+            {
+                let mut stmt = conn.prepare("SELECT child_tag_id, parent_tag_id, COALESCE(updated_at, 0) FROM tag_tags WHERE deleted_at IS NULL")
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                let mut rows = stmt.query([])
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                while let Ok(Some(row)) = rows.next() {
+                    let edge = TagEdge {
+                        child_tag_id: row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?,
+                        parent_tag_id: row.get(1).map_err(|e| DomainError::Internal(e.to_string()))?,
+                    };
+                    edges.push((edge, row.get(2).unwrap_or(0)));
+                }
+            }
+            
+            let mut report = TagGraphReport::default();
+            let mut updated_at: HashMap<(u32, u32), i64> = HashMap::new();
+            let mut graph: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+            
+            for (edge, ts) in edges {
+                if !live_tags.contains(&edge.child_tag_id) || !live_tags.contains(&edge.parent_tag_id) {
+                    report.dangling_edges.push(edge);
+                } else if edge.child_tag_id == edge.parent_tag_id {
+                    report.self_edges.push(edge);
+                } else {
+                    updated_at.insert((edge.child_tag_id, edge.parent_tag_id), ts);
+                    graph.entry(edge.child_tag_id).or_default().push(edge.parent_tag_id);
+                }
+            }
+            for parents in graph.values_mut() {
+                parents.sort();
+            }
+            
+            // Find cycles one at a time, breaking each at its newest edge so the next search terminates
+            let mut cycle_breaks = Vec::new();
+            while let Some(cycle) = find_cycle(&graph) {
+                let newest = (0..cycle.len())
+                    .map(|i| (cycle[i], cycle[(i + 1) % cycle.len()]))
+                    .max_by_key(|key| updated_at.get(key).copied().unwrap_or(0))
+                    .expect("cycle has at least one edge");
+                if let Some(parents) = graph.get_mut(&newest.0) {
+                    parents.retain(|&p| p != newest.1);
+                }
+                cycle_breaks.push(TagEdge { child_tag_id: newest.0, parent_tag_id: newest.1 });
+                report.cycles.push(cycle);
+            }
+            
+            if repair {
+                let now = Timestamp::now();
+                let to_remove: Vec<TagEdge> = report.dangling_edges.iter()
+                    .chain(report.self_edges.iter())
+                    .chain(cycle_breaks.iter())
+                    .copied()
+                    .collect();
+                
+                let tx = conn.unchecked_transaction()
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                for edge in &to_remove {
+                    tx.execute(
+                        "UPDATE tag_tags SET deleted_at = ?, updated_at = ? WHERE child_tag_id = ? AND parent_tag_id = ?",
+                        params![now, now, edge.child_tag_id, edge.parent_tag_id],
+                    )
+                    .map_err(|e| DomainError::Internal(e.to_string()))?;
+                }
+                tx.commit().map_err(|e| DomainError::Internal(e.to_string()))?;
+                report.removed_edges = to_remove;
+            }
+            
+            report
+        };
+        
+        // Removed edges may have turned tags back into root tags
+        drop(guard);
+        if !report.removed_edges.is_empty() {
+            use super::tag_positioning::TagPositioningOperations;
+            self.reindex_root_tags().await?;
+        }
+        
+        Ok(report)
+    }
 }
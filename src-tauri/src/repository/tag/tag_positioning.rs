@@ -5,7 +5,7 @@
 use async_trait::async_trait;
 use rusqlite::params;
 
-use crate::domain::{DomainError, DomainResult};
+use crate::domain::{DomainError, DomainResult, Timestamp};
 
 /// Trait for tag positioning operations
 #[async_trait]
@@ -56,14 +56,14 @@ impl TagPositioningOperations for super::tag_repo::TagRepository {
             if new_position < old_position {
                 // Moving up: shift tags in [new_position, old_position) down by +1
                 conn.execute(
-                    "UPDATE tags SET position = position + 1 WHERE position >= ? AND position < ? AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags)",
+                    "UPDATE tags SET position = position + 1 WHERE position >= ? AND position < ? AND deleted_at IS NULL AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags WHERE deleted_at IS NULL)",
                     params![new_position, old_position],
                 )
                 .map_err(|e| DomainError::Internal(e.to_string()))?;
             } else {
                 // Moving down: shift tags in (old_position, new_position] up by -1
                 conn.execute(
-                    "UPDATE tags SET position = position - 1 WHERE position > ? AND position <= ? AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags)",
+                    "UPDATE tags SET position = position - 1 WHERE position > ? AND position <= ? AND deleted_at IS NULL AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags WHERE deleted_at IS NULL)",
                     params![old_position, new_position],
                 )
                 .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -72,7 +72,7 @@ impl TagPositioningOperations for super::tag_repo::TagRepository {
             // Update the tag's position
             conn.execute(
                 "UPDATE tags SET position = ?, updated_at = ? WHERE id = ?",
-                params![new_position, chrono::Utc::now().timestamp_millis(), id],
+                params![new_position, Timestamp::now(), id],
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
         }
@@ -93,7 +93,8 @@ impl TagPositioningOperations for super::tag_repo::TagRepository {
         // Get all root tags ordered by current position
         let mut stmt = conn.prepare(
                 "SELECT id FROM tags 
-                 WHERE id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags)
+                 WHERE deleted_at IS NULL
+                   AND id NOT IN (SELECT DISTINCT child_tag_id FROM tag_tags WHERE deleted_at IS NULL)
                  ORDER BY position, id"
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -112,7 +113,7 @@ impl TagPositioningOperations for super::tag_repo::TagRepository {
         for (new_pos, id) in ids.iter().enumerate() {
             conn.execute(
                 "UPDATE tags SET position = ?, updated_at = ? WHERE id = ?",
-                params![new_pos as i32, chrono::Utc::now().timestamp_millis(), *id],
+                params![new_pos as i32, Timestamp::now(), *id],
             )
             .map_err(|e| DomainError::Internal(e.to_string()))?;
         }
@@ -134,7 +135,7 @@ impl TagPositioningOperations for super::tag_repo::TagRepository {
         // Update the child's position under this parent
         conn.execute(
             "UPDATE tag_tags SET position = ?, updated_at = ? WHERE child_tag_id = ? AND parent_tag_id = ?",
-            params![position, chrono::Utc::now().timestamp_millis(), child_tag_id, parent_tag_id],
+            params![position, Timestamp::now(), child_tag_id, parent_tag_id],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
 
@@ -11,7 +11,7 @@ use rusqlite::{Connection, params};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::domain::{Tag, DomainError, DomainResult};
+use crate::domain::{Tag, DomainError, DomainResult, Timestamp};
 use super::super::traits::Repository;
 
 /// SQLite implementation of Tag repository
@@ -32,8 +32,8 @@ impl Repository<Tag> for TagRepository {
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
         conn.execute(
-            "INSERT INTO tags (name, color, updated_at) VALUES (?, ?, ?)",
-            params![entity.name.clone(), entity.color.clone(), chrono::Utc::now().timestamp_millis()],
+            "INSERT INTO tags (name, color, memo, icon, description, pinned, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![entity.name.clone(), entity.color.clone(), entity.memo.clone(), entity.icon.clone(), entity.description.clone(), entity.pinned, Timestamp::now()],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
 
@@ -41,6 +41,7 @@ impl Repository<Tag> for TagRepository {
         
         let mut tag = entity.clone();
         tag.id = id;
+        super::tag_color::fill_effective_colors(conn, std::slice::from_mut(&mut tag))?;
         Ok(tag)
     }
 
@@ -48,14 +49,16 @@ impl Repository<Tag> for TagRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let mut stmt = conn.prepare("SELECT id, name, color, position FROM tags WHERE id = ? AND deleted_at IS NULL")
+        let mut stmt = conn.prepare("SELECT id, name, color, position, memo, icon, description, pinned FROM tags WHERE id = ? AND deleted_at IS NULL")
             .map_err(|e| DomainError::Internal(e.to_string()))?;
             
         let mut rows = stmt.query(params![id])
             .map_err(|e| DomainError::Internal(e.to_string()))?;
 
         if let Ok(Some(row)) = rows.next() {
-            Ok(Some(row_to_tag(&row)?))
+            let mut tag = row_to_tag(&row)?;
+            super::tag_color::fill_effective_colors(conn, std::slice::from_mut(&mut tag))?;
+            Ok(Some(tag))
         } else {
             Ok(None)
         }
@@ -65,7 +68,7 @@ impl Repository<Tag> for TagRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let mut stmt = conn.prepare("SELECT id, name, color, position FROM tags WHERE deleted_at IS NULL ORDER BY name")
+        let mut stmt = conn.prepare("SELECT id, name, color, position, memo, icon, description, pinned FROM tags WHERE deleted_at IS NULL ORDER BY name")
              .map_err(|e| DomainError::Internal(e.to_string()))?;
              
         let mut rows = stmt.query([])
@@ -75,6 +78,7 @@ impl Repository<Tag> for TagRepository {
         while let Ok(Some(row)) = rows.next() {
             tags.push(row_to_tag(&row)?);
         }
+        super::tag_color::fill_effective_colors(conn, &mut tags)?;
         Ok(tags)
     }
 
@@ -82,20 +86,24 @@ impl Repository<Tag> for TagRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
+        // A colour set by hand is no longer the automatic palette colour
         conn.execute(
-            "UPDATE tags SET name = ?, color = ?, updated_at = ? WHERE id = ?",
-            params![entity.name.clone(), entity.color.clone(), chrono::Utc::now().timestamp_millis(), entity.id],
+            "UPDATE tags SET name = ?1, color = ?2, color_auto = CASE WHEN color IS ?2 THEN color_auto ELSE 0 END,
+             memo = ?3, icon = ?4, description = ?5, pinned = ?6, updated_at = ?7 WHERE id = ?8",
+            params![entity.name.clone(), entity.color.clone(), entity.memo.clone(), entity.icon.clone(), entity.description.clone(), entity.pinned, Timestamp::now(), entity.id],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
 
-        Ok(entity.clone())
+        let mut tag = entity.clone();
+        super::tag_color::fill_effective_colors(conn, std::slice::from_mut(&mut tag))?;
+        Ok(tag)
     }
 
     async fn delete(&self, id: u32) -> DomainResult<()> {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = Timestamp::now();
         
         // Soft delete: set deleted_at instead of removing
         conn.execute(
@@ -115,5 +123,10 @@ pub(super) fn row_to_tag(row: &rusqlite::Row) -> DomainResult<Tag> {
         name: row.get(1).map_err(|e: rusqlite::Error| DomainError::Internal(e.to_string()))?,
         color: row.get::<_, Option<String>>(2).unwrap_or(None),
         position: row.get::<_, i32>(3).unwrap_or(0),
+        memo: row.get::<_, Option<String>>(4).unwrap_or(None),
+        icon: row.get::<_, Option<String>>(5).unwrap_or(None),
+        description: row.get::<_, Option<String>>(6).unwrap_or(None),
+        pinned: row.get::<_, bool>(7).unwrap_or(false),
+        effective_color: None,
     })
 }
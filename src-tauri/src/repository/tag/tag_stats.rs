@@ -0,0 +1,104 @@
+//! Tag Statistics Operations
+//!
+//! Usage counts per tag: direct items, items including descendant tags,
+//! last-applied time and per-workspace breakdown. Only live rows are counted.
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+
+use crate::domain::{TagStats, WorkspaceTagCount, DomainError, DomainResult, Timestamp};
+
+/// Trait for tag statistics
+#[async_trait]
+pub trait TagStatsOperations {
+    /// Usage statistics for every live tag, ordered by tag id
+    async fn tag_stats(&self) -> DomainResult<Vec<TagStats>>;
+}
+
+#[async_trait]
+impl TagStatsOperations for super::tag_repo::TagRepository {
+    async fn tag_stats(&self) -> DomainResult<Vec<TagStats>> {
+        let guard = self.conn.lock().await;
+        let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
+
+        let mut stats: BTreeMap<u32, TagStats> = BTreeMap::new();
+
+        // 1. Every live tag starts at zero
+        {
+            let mut stmt = conn.prepare("SELECT id FROM tags WHERE deleted_at IS NULL")
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let mut rows = stmt.query([])
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            while let Ok(Some(row)) = rows.next() {
+                let tag_id: u32 = row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?;
+                stats.insert(tag_id, TagStats {
+                    tag_id,
+                    direct_count: 0,
+                    total_count: 0,
+                    last_applied_at: None,
+                    workspaces: Vec::new(),
+                    unused: true,
+                });
+            }
+        }
+
+        // 2. Direct counts + last applied, per workspace
+        {
+            let mut stmt = conn.prepare(
+                    "SELECT it.tag_id, COALESCE(i.workspace_id, 1), COUNT(*), MAX(it.updated_at)
+                     FROM item_tags it
+                     JOIN items i ON i.id = it.item_id
+                     WHERE it.deleted_at IS NULL AND i.deleted_at IS NULL
+                     GROUP BY it.tag_id, COALESCE(i.workspace_id, 1)
+                     ORDER BY it.tag_id, COALESCE(i.workspace_id, 1)"
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let mut rows = stmt.query([])
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            while let Ok(Some(row)) = rows.next() {
+                let tag_id: u32 = row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?;
+                let workspace_id: u32 = row.get(1).map_err(|e| DomainError::Internal(e.to_string()))?;
+                let count: u32 = row.get(2).map_err(|e| DomainError::Internal(e.to_string()))?;
+                let last: Option<i64> = row.get(3).unwrap_or(None);
+                let last = last.and_then(Timestamp::normalize);
+
+                if let Some(entry) = stats.get_mut(&tag_id) {
+                    entry.direct_count += count;
+                    entry.last_applied_at = entry.last_applied_at.max(last);
+                    entry.workspaces.push(WorkspaceTagCount { workspace_id, count });
+                }
+            }
+        }
+
+        // 3. Distinct items including descendants (UNION keeps cyclic graphs finite)
+        {
+            let mut stmt = conn.prepare(
+                    "WITH RECURSIVE descendants(root_id, tag_id) AS (
+                         SELECT id, id FROM tags WHERE deleted_at IS NULL
+                         UNION
+                         SELECT d.root_id, tt.child_tag_id FROM descendants d
+                         JOIN tag_tags tt ON tt.parent_tag_id = d.tag_id AND tt.deleted_at IS NULL
+                         JOIN tags t ON t.id = tt.child_tag_id AND t.deleted_at IS NULL
+                     )
+                     SELECT d.root_id, COUNT(DISTINCT it.item_id)
+                     FROM descendants d
+                     JOIN item_tags it ON it.tag_id = d.tag_id AND it.deleted_at IS NULL
+                     JOIN items i ON i.id = it.item_id AND i.deleted_at IS NULL
+                     GROUP BY d.root_id"
+                )
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            let mut rows = stmt.query([])
+                .map_err(|e| DomainError::Internal(e.to_string()))?;
+            while let Ok(Some(row)) = rows.next() {
+                let tag_id: u32 = row.get(0).map_err(|e| DomainError::Internal(e.to_string()))?;
+                let total: u32 = row.get(1).map_err(|e| DomainError::Internal(e.to_string()))?;
+                if let Some(entry) = stats.get_mut(&tag_id) {
+                    entry.total_count = total;
+                    entry.unused = total == 0;
+                }
+            }
+        }
+
+        Ok(stats.into_values().collect())
+    }
+}
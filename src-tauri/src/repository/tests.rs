@@ -1,12 +1,13 @@
 //! Repository Integration Tests
 //!
-//! Tests for ItemRepository with in-memory SQLite database.
+//! Tests for ItemRepository and TagRepository with in-memory SQLite database.
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::{Item, ItemType};
-    use crate::repository::{Repository, HierarchyRepository, ItemRepository, init_db};
-    use crate::repository::item::ItemHierarchyOperations;
+    use crate::domain::{Item, ItemType, Tag, TagEdge, DomainError, Archive, Asset, ASSET_OWNER_ARCHIVE, ASSET_OWNER_ITEM, ASSET_OWNER_TAG, BOOKMARK_WORKSPACE_ID, DEFAULT_TAG_PALETTE, Timestamp};
+    use crate::repository::{Repository, HierarchyRepository, ItemRepository, TagRepository, AssetRepository, ArchiveRepository, init_db};
+    use crate::repository::item::{ItemHierarchyOperations, ItemLinkOperations, ItemSearch, ItemSearchOperations, ItemWorkspaceOperations};
+    use crate::repository::tag::{ItemTagOperations, TagHierarchyOperations, TagAliasOperations, TagStatsOperations, TagColorOperations, InlineTagOperations};
     use std::path::PathBuf;
 
     async fn setup_test_db() -> ItemRepository {
@@ -75,7 +76,7 @@ mod tests {
         // Wait to ensure updated_at changes
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
         
-        let created_ts = created.updated_at.unwrap_or(0);
+        let created_ts = created.updated_at.unwrap();
 
         created.text = "Updated".to_string();
         created.completed = true;
@@ -83,7 +84,7 @@ mod tests {
         let updated = repo.update(&created).await.expect("Update failed");
         assert_eq!(updated.text, "Updated");
         assert!(updated.completed);
-        assert!(updated.updated_at.unwrap_or(0) > created_ts, "Updated timestamp should increase");
+        assert!(updated.updated_at.unwrap() > created_ts, "Updated timestamp should increase");
     }
 
     #[tokio::test]
@@ -224,4 +225,568 @@ mod tests {
         let found = repo.find_by_id(child.id).await.expect("Find failed");
         assert!(found.is_none());
     }
+
+    // ========================
+    // Level 10: Tag Alias + Merge Tests
+    // ========================
+
+    async fn setup_tag_test_db() -> (ItemRepository, TagRepository) {
+        let db_path = PathBuf::from(":memory:");
+        let db_state = init_db(&db_path).await.expect("Failed to init test DB");
+        (ItemRepository::new(db_state.conn.clone()), TagRepository::new(db_state.conn.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_resolve_tag_by_alias() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let js = tags.create(&Tag::new(0, "JavaScript".to_string())).await.unwrap();
+        tags.add_alias(js.id, " JS ").await.expect("Add alias failed");
+
+        let by_name = tags.resolve_tag_name("javascript").await.unwrap().unwrap();
+        assert_eq!(by_name.id, js.id);
+        let by_alias = tags.resolve_tag_name("js").await.unwrap().unwrap();
+        assert_eq!(by_alias.id, js.id);
+        assert!(tags.resolve_tag_name("rust").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_alias_conflicts_with_other_tag() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let js = tags.create(&Tag::new(0, "js".to_string())).await.unwrap();
+        let ts = tags.create(&Tag::new(0, "ts".to_string())).await.unwrap();
+
+        assert!(tags.add_alias(ts.id, "JS").await.is_err());
+        assert!(tags.add_alias(js.id, "JS").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_merge_tags_moves_edges() {
+        let (items, tags) = setup_tag_test_db().await;
+
+        let source = tags.create(&Tag::new(0, "js".to_string())).await.unwrap();
+        let target = tags.create(&Tag::new(0, "JavaScript".to_string())).await.unwrap();
+        let parent = tags.create(&Tag::new(0, "lang".to_string())).await.unwrap();
+        let child = tags.create(&Tag::new(0, "react".to_string())).await.unwrap();
+        tags.add_parent_tag(source.id, parent.id).await.unwrap();
+        tags.add_parent_tag(child.id, source.id).await.unwrap();
+
+        let a = items.create(&Item::new(0, "A".to_string(), ItemType::Document)).await.unwrap();
+        let b = items.create(&Item::new(0, "B".to_string(), ItemType::Document)).await.unwrap();
+        tags.add_tag_to_item(a.id, source.id).await.unwrap();
+        tags.add_tag_to_item(b.id, source.id).await.unwrap();
+        tags.add_tag_to_item(b.id, target.id).await.unwrap();
+
+        let merged = tags.merge_tags(source.id, target.id).await.expect("Merge failed");
+        assert_eq!(merged.id, target.id);
+
+        // Items now carry the target only
+        let mut tagged = tags.get_items_with_tag(target.id).await.unwrap();
+        tagged.sort();
+        assert_eq!(tagged, vec![a.id, b.id]);
+        assert!(tags.get_items_with_tag(source.id).await.unwrap().is_empty());
+
+        // Hierarchy moved to the target
+        let parents: Vec<u32> = tags.get_parent_tags(target.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(parents, vec![parent.id]);
+        let children: Vec<u32> = tags.get_child_tags(target.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(children, vec![child.id]);
+
+        // Source is tombstoned and its name resolves to the target
+        assert!(tags.find_by_id(source.id).await.unwrap().is_none());
+        let resolved = tags.resolve_tag_name("js").await.unwrap().unwrap();
+        assert_eq!(resolved.id, target.id);
+    }
+
+    #[tokio::test]
+    async fn test_merge_tag_into_itself_fails() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let tag = tags.create(&Tag::new(0, "solo".to_string())).await.unwrap();
+        assert!(tags.merge_tags(tag.id, tag.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_merge_rejects_ancestor_and_descendant() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        // x is a child of s, t a child of x: merging s into t would put t under x under t
+        let s = tags.create(&Tag::new(0, "s".to_string())).await.unwrap();
+        let x = tags.create(&Tag::new(0, "x".to_string())).await.unwrap();
+        let t = tags.create(&Tag::new(0, "t".to_string())).await.unwrap();
+        tags.add_parent_tag(x.id, s.id).await.unwrap();
+        tags.add_parent_tag(t.id, x.id).await.unwrap();
+
+        assert!(matches!(tags.merge_tags(s.id, t.id).await, Err(DomainError::Conflict(_))));
+        assert!(matches!(tags.merge_tags(t.id, s.id).await, Err(DomainError::Conflict(_))));
+
+        // Nothing changed
+        assert!(tags.find_by_id(s.id).await.unwrap().is_some());
+        let parents: Vec<u32> = tags.get_parent_tags(t.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(parents, vec![x.id]);
+        assert!(tags.check_tag_graph(false).await.unwrap().cycles.is_empty());
+    }
+
+    // ========================
+    // Level 10: Tag DAG Validation Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_add_parent_rejects_self_and_cycles() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let a = tags.create(&Tag::new(0, "a".to_string())).await.unwrap();
+        let b = tags.create(&Tag::new(0, "b".to_string())).await.unwrap();
+        let c = tags.create(&Tag::new(0, "c".to_string())).await.unwrap();
+
+        assert!(matches!(tags.add_parent_tag(a.id, a.id).await, Err(DomainError::Conflict(_))));
+
+        // a -> b -> c (child -> parent)
+        tags.add_parent_tag(a.id, b.id).await.unwrap();
+        tags.add_parent_tag(b.id, c.id).await.unwrap();
+
+        // c under a would close the loop a -> b -> c -> a
+        assert!(matches!(tags.add_parent_tag(c.id, a.id).await, Err(DomainError::Conflict(_))));
+        assert!(tags.get_parent_tags(c.id).await.unwrap().is_empty());
+
+        // Multi-parent without a cycle is still fine
+        tags.add_parent_tag(a.id, c.id).await.expect("Diamond edge should be allowed");
+    }
+
+    #[tokio::test]
+    async fn test_check_tag_graph_repairs_cycles_and_dangling_edges() {
+        let db_state = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let tags = TagRepository::new(db_state.conn.clone());
+
+        let a = tags.create(&Tag::new(0, "a".to_string())).await.unwrap();
+        let b = tags.create(&Tag::new(0, "b".to_string())).await.unwrap();
+        let gone = tags.create(&Tag::new(0, "gone".to_string())).await.unwrap();
+        tags.delete(gone.id).await.unwrap();
+
+        // Write a corrupt graph directly, bypassing validation (e.g. from an old sync)
+        {
+            let guard = db_state.conn.lock().await;
+            let conn = guard.as_ref().unwrap();
+            conn.execute_batch(&format!(
+                "INSERT INTO tag_tags (child_tag_id, parent_tag_id, updated_at) VALUES ({a}, {b}, 1);
+                 INSERT INTO tag_tags (child_tag_id, parent_tag_id, updated_at) VALUES ({b}, {a}, 2);
+                 INSERT INTO tag_tags (child_tag_id, parent_tag_id, updated_at) VALUES ({a}, {a}, 3);
+                 INSERT INTO tag_tags (child_tag_id, parent_tag_id, updated_at) VALUES ({a}, {gone}, 4);",
+                a = a.id, b = b.id, gone = gone.id
+            )).unwrap();
+        }
+
+        let report = tags.check_tag_graph(false).await.unwrap();
+        assert_eq!(report.cycles.len(), 1);
+        assert_eq!(report.self_edges.len(), 1);
+        assert_eq!(report.dangling_edges.len(), 1);
+        assert!(report.removed_edges.is_empty());
+
+        let repaired = tags.check_tag_graph(true).await.unwrap();
+        assert_eq!(repaired.removed_edges.len(), 3);
+        // The newest edge of the cycle (b -> a) is the one removed
+        assert!(repaired.removed_edges.contains(&TagEdge { child_tag_id: b.id, parent_tag_id: a.id }));
+
+        assert!(tags.check_tag_graph(false).await.unwrap().is_healthy());
+        let parents: Vec<u32> = tags.get_parent_tags(a.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(parents, vec![b.id]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_metadata_roundtrip() {
+        let (_items, tags) = setup_tag_test_db().await;
+
+        let plain = tags.create(&Tag::new(0, "plain".to_string())).await.unwrap();
+        let mut rust = Tag::new(0, "rust".to_string());
+        rust.icon = Some("🦀".to_string());
+        rust.description = Some("Rust language notes".to_string());
+        let mut rust = tags.create(&rust).await.unwrap();
+
+        rust.memo = Some("# Rust\nEverything about Rust".to_string());
+        rust.pinned = true;
+        tags.update(&rust).await.unwrap();
+
+        let found = tags.find_by_id(rust.id).await.unwrap().unwrap();
+        assert_eq!(found.icon.as_deref(), Some("🦀"));
+        assert_eq!(found.description.as_deref(), Some("Rust language notes"));
+        assert_eq!(found.memo.as_deref(), Some("# Rust\nEverything about Rust"));
+        assert!(found.pinned);
+
+        // Pinned tags come first among root tags
+        let roots: Vec<u32> = tags.get_root_tags().await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(roots, vec![rust.id, plain.id]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_stats_counts_descendants_and_workspaces() {
+        let db_state = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let items = ItemRepository::new(db_state.conn.clone());
+        let tags = TagRepository::new(db_state.conn.clone());
+
+        let lang = tags.create(&Tag::new(0, "lang".to_string())).await.unwrap();
+        let rust = tags.create(&Tag::new(0, "rust".to_string())).await.unwrap();
+        let dead = tags.create(&Tag::new(0, "dead".to_string())).await.unwrap();
+        tags.add_parent_tag(rust.id, lang.id).await.unwrap();
+
+        let a = items.create(&Item::new(0, "a".to_string(), ItemType::Once)).await.unwrap();
+        let b = items.create(&Item::new(0, "b".to_string(), ItemType::Once)).await.unwrap();
+        tags.add_tag_to_item(a.id, rust.id).await.unwrap();
+        tags.add_tag_to_item(a.id, lang.id).await.unwrap();
+        tags.add_tag_to_item(b.id, rust.id).await.unwrap();
+        {
+            let guard = db_state.conn.lock().await;
+            guard.as_ref().unwrap()
+                .execute("UPDATE items SET workspace_id = 2 WHERE id = ?", [b.id])
+                .unwrap();
+        }
+
+        let stats = tags.tag_stats().await.expect("Stats failed");
+        let find = |id: u32| stats.iter().find(|s| s.tag_id == id).unwrap().clone();
+
+        let lang_stats = find(lang.id);
+        assert_eq!(lang_stats.direct_count, 1);
+        assert_eq!(lang_stats.total_count, 2); // a (direct + via rust) counted once, b via rust
+
+        let rust_stats = find(rust.id);
+        assert_eq!(rust_stats.direct_count, 2);
+        assert_eq!(rust_stats.total_count, 2);
+        assert!(rust_stats.last_applied_at.is_some());
+        let per_ws: Vec<(u32, u32)> = rust_stats.workspaces.iter().map(|w| (w.workspace_id, w.count)).collect();
+        assert_eq!(per_ws, vec![(1, 1), (2, 1)]);
+
+        let dead_stats = find(dead.id);
+        assert!(dead_stats.unused);
+        assert_eq!(dead_stats.total_count, 0);
+        assert!(dead_stats.last_applied_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_effective_color_inherits_from_first_parent() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let red = tags.create(&Tag::with_color(0, "red".to_string(), "#ff0000".to_string())).await.unwrap();
+        let blue = tags.create(&Tag::with_color(0, "blue".to_string(), "#0000ff".to_string())).await.unwrap();
+        let mid = tags.create(&Tag::new(0, "mid".to_string())).await.unwrap();
+        let leaf = tags.create(&Tag::new(0, "leaf".to_string())).await.unwrap();
+        tags.add_parent_tag(mid.id, red.id).await.unwrap();
+        tags.add_parent_tag(leaf.id, mid.id).await.unwrap();
+        tags.add_parent_tag(leaf.id, blue.id).await.unwrap();
+
+        // leaf's first parent is mid (position order), which inherits from red
+        assert_eq!(tags.effective_color(leaf.id).await.unwrap().as_deref(), Some("#ff0000"));
+        let found = tags.find_by_id(leaf.id).await.unwrap().unwrap();
+        assert!(found.color.is_none());
+        assert_eq!(found.effective_color.as_deref(), Some("#ff0000"));
+
+        // Own colour wins
+        let children = tags.get_child_tags(red.id).await.unwrap();
+        assert_eq!(children[0].effective_color.as_deref(), Some("#ff0000"));
+        assert_eq!(tags.effective_color(blue.id).await.unwrap().as_deref(), Some("#0000ff"));
+
+        // Palette prefers colours not used yet
+        let palette: Vec<String> = vec!["#ff0000".into(), "#00ff00".into()];
+        assert_eq!(tags.next_palette_color(&palette).await.unwrap().as_deref(), Some("#00ff00"));
+    }
+
+    #[tokio::test]
+    async fn test_child_tags_inherit_instead_of_palette_colour() {
+        let (_, tags) = setup_tag_test_db().await;
+
+        let parent = tags.create_with_palette_color("parent").await.unwrap();
+        assert_eq!(parent.color.as_deref(), Some(DEFAULT_TAG_PALETTE[0]));
+
+        // Created at the root, then nested: the palette colour gives way to the parent's
+        let child = tags.create_with_palette_color("child").await.unwrap();
+        assert_eq!(child.color.as_deref(), Some(DEFAULT_TAG_PALETTE[1]));
+        tags.add_parent_tag(child.id, parent.id).await.unwrap();
+        let child = tags.find_by_id(child.id).await.unwrap().unwrap();
+        assert!(child.color.is_none());
+        assert_eq!(child.effective_color.as_deref(), Some(DEFAULT_TAG_PALETTE[0]));
+
+        // A colour picked by hand is kept
+        let mut picked = tags.create_with_palette_color("picked").await.unwrap();
+        picked.color = Some("#123456".to_string());
+        tags.update(&picked).await.unwrap();
+        tags.add_parent_tag(picked.id, parent.id).await.unwrap();
+        let picked = tags.find_by_id(picked.id).await.unwrap().unwrap();
+        assert_eq!(picked.effective_color.as_deref(), Some("#123456"));
+
+        // Nothing to inherit under an uncoloured parent, so the palette colour stays
+        let plain = tags.create(&Tag::new(0, "plain".to_string())).await.unwrap();
+        let kept = tags.create_with_palette_color("kept").await.unwrap();
+        tags.add_parent_tag(kept.id, plain.id).await.unwrap();
+        assert_eq!(tags.find_by_id(kept.id).await.unwrap().unwrap().color, kept.color);
+    }
+
+    // ========================
+    // Level 13: Wiki Link Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_wiki_links_backlinks_and_rename() {
+        let repo = setup_test_db().await;
+
+        let plan = repo.create(&Item::new(0, "Project plan".to_string(), ItemType::Document)).await.unwrap();
+        let other = repo.create(&Item::new(0, "Other".to_string(), ItemType::Document)).await.unwrap();
+        let notes = repo.create(&Item::new(0, "Notes".to_string(), ItemType::Document)).await.unwrap();
+
+        let memo = format!("See [[project plan]] and [[#{}|other]], [[Missing]]", other.id);
+        let outgoing = repo.update_links(notes.id, Some(&memo)).await.expect("Update links failed");
+        let targets: Vec<u32> = outgoing.iter().map(|l| l.target_item_id).collect();
+        assert_eq!(targets.len(), 2);
+        assert!(targets.contains(&plan.id) && targets.contains(&other.id));
+
+        let backlinks = repo.get_backlinks(plan.id).await.unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].source_item_id, notes.id);
+        assert_eq!(backlinks[0].source_text, "Notes");
+
+        // Rename the target: the stored link still resolves to it
+        let mut renamed = repo.find_by_id(plan.id).await.unwrap().unwrap();
+        renamed.text = "Roadmap".to_string();
+        repo.update(&renamed).await.unwrap();
+        repo.update_links(notes.id, Some(&memo)).await.unwrap();
+        assert_eq!(repo.resolve_link(notes.id, "project plan").await.unwrap(), Some(plan.id));
+        assert_eq!(repo.get_backlinks(plan.id).await.unwrap()[0].target_text, "Roadmap");
+
+        // Removing the link from the memo drops the backlink
+        repo.update_links(notes.id, Some("no links")).await.unwrap();
+        assert!(repo.get_backlinks(plan.id).await.unwrap().is_empty());
+        assert!(repo.get_outgoing_links(notes.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wiki_links_resolve_once_target_exists() {
+        let repo = setup_test_db().await;
+
+        let notes = repo.create(&Item::new(0, "Notes".to_string(), ItemType::Document)).await.unwrap();
+        let memo = "Later: [[Meeting notes]] and [[Agenda]]";
+        assert!(repo.update_links(notes.id, Some(memo)).await.unwrap().is_empty());
+        assert_eq!(repo.resolve_pending_links().await.unwrap(), 0);
+
+        // A new item with the linked title picks up the link
+        let meeting = repo.create(&Item::new(0, "Meeting notes".to_string(), ItemType::Document)).await.unwrap();
+        assert_eq!(repo.resolve_pending_links().await.unwrap(), 1);
+        assert_eq!(repo.get_backlinks(meeting.id).await.unwrap()[0].source_item_id, notes.id);
+
+        // So does an item renamed to it
+        let mut agenda = repo.create(&Item::new(0, "Draft".to_string(), ItemType::Document)).await.unwrap();
+        agenda.text = "Agenda".to_string();
+        repo.update(&agenda).await.unwrap();
+        assert_eq!(repo.resolve_pending_links().await.unwrap(), 1);
+        assert_eq!(repo.get_outgoing_links(notes.id).await.unwrap().len(), 2);
+        assert_eq!(repo.resolve_pending_links().await.unwrap(), 0);
+
+        // Links removed from the memo no longer wait for a target
+        repo.update_links(notes.id, Some("[[Someday]]")).await.unwrap();
+        repo.update_links(notes.id, Some("nothing")).await.unwrap();
+        repo.create(&Item::new(0, "Someday".to_string(), ItemType::Document)).await.unwrap();
+        assert_eq!(repo.resolve_pending_links().await.unwrap(), 0);
+        assert!(repo.get_outgoing_links(notes.id).await.unwrap().is_empty());
+    }
+
+    // ========================
+    // Level 14: Inline #tag Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_inline_tags_apply_and_remove_only_inline() {
+        let (items, tags) = setup_tag_test_db().await;
+
+        let item = items.create(&Item::new(0, "Read book #lang/rust".to_string(), ItemType::Once)).await.unwrap();
+        let manual = tags.create(&Tag::new(0, "manual".to_string())).await.unwrap();
+        tags.add_tag_to_item(item.id, manual.id).await.unwrap();
+
+        let applied = tags.sync_inline_tags(item.id, &item.text, Some("also #manual")).await.expect("Sync failed");
+        let names: Vec<String> = applied.iter().map(|t| t.name.clone()).collect();
+        assert_eq!(names, vec!["rust".to_string()]); // #manual stays a manual tag
+
+        let rust = tags.resolve_tag_name("rust").await.unwrap().unwrap();
+        let lang = tags.resolve_tag_name("lang").await.unwrap().unwrap();
+        let parents: Vec<u32> = tags.get_parent_tags(rust.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(parents, vec![lang.id]);
+        // Inline-created tags get a palette colour like any new root tag; the child inherits
+        assert_eq!(lang.color.as_deref(), Some(DEFAULT_TAG_PALETTE[0]));
+        assert!(rust.color.is_none());
+        assert_eq!(rust.effective_color.as_deref(), Some(DEFAULT_TAG_PALETTE[0]));
+
+        let mut on_item: Vec<u32> = tags.get_tags_for_item(item.id).await.unwrap().iter().map(|t| t.id).collect();
+        on_item.sort();
+        assert_eq!(on_item, vec![manual.id, rust.id]);
+
+        // Tokens removed: inline tag goes, manual tag stays
+        tags.sync_inline_tags(item.id, "Read book", None).await.unwrap();
+        let on_item: Vec<u32> = tags.get_tags_for_item(item.id).await.unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(on_item, vec![manual.id]);
+        assert!(tags.find_by_id(rust.id).await.unwrap().is_some()); // tag itself is kept
+    }
+
+    // ========================
+    // Level 15: Asset Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_assets_dedupe_and_track_refs() {
+        let db_state = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let repo = AssetRepository::new(db_state.conn.clone());
+
+        let asset = |hash: &str, name: &str| Asset {
+            hash: hash.to_string(),
+            ext: "png".to_string(),
+            mime: "image/png".to_string(),
+            size: 3,
+            original_name: Some(name.to_string()),
+            created_at: Timestamp::from_millis(0),
+        };
+        let first = repo.upsert(&asset("aa", "first.png")).await.expect("Upsert failed");
+        let again = repo.upsert(&asset("aa", "second.png")).await.unwrap();
+        assert_eq!(again.original_name.as_deref(), Some("first.png")); // same content stored once
+        assert_eq!(first.file_name(), "aa.png");
+        repo.upsert(&asset("bb", "b.png")).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+
+        repo.set_refs(ASSET_OWNER_ITEM, 1, &["aa".to_string()]).await.unwrap();
+        repo.set_refs(ASSET_OWNER_TAG, 7, &["aa".to_string(), "bb".to_string()]).await.unwrap();
+        assert_eq!(repo.get_refs("aa").await.unwrap().len(), 2);
+        assert!(repo.list_unreferenced().await.unwrap().is_empty());
+
+        // Tag memo no longer mentions bb
+        repo.set_refs(ASSET_OWNER_TAG, 7, &["aa".to_string()]).await.unwrap();
+        let unreferenced: Vec<String> = repo.list_unreferenced().await.unwrap().into_iter().map(|a| a.hash).collect();
+        assert_eq!(unreferenced, vec!["bb".to_string()]);
+
+        repo.delete("bb").await.unwrap();
+        assert!(repo.find("bb").await.unwrap().is_none());
+        assert!(repo.list_unreferenced().await.unwrap().is_empty());
+
+        // A full rescan replaces everything
+        repo.replace_all_refs(&[]).await.unwrap();
+        assert_eq!(repo.list_unreferenced().await.unwrap().len(), 1);
+    }
+
+    // ========================
+    // Level 16: Search Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_search_by_text_url_and_tags() {
+        let (items, tags) = setup_tag_test_db().await;
+
+        let mut bookmark = Item::new(0, "Rust book".to_string(), ItemType::Daily);
+        bookmark.url = Some("https://doc.rust-lang.org/book/".to_string());
+        let bookmark = items.create(&bookmark).await.unwrap();
+        let note = items.create(&Item::new(0, "100% rust".to_string(), ItemType::Document)).await.unwrap();
+        let web = tags.create(&Tag::new(0, "web".to_string())).await.unwrap();
+        tags.add_tag_to_item(bookmark.id, web.id).await.unwrap();
+
+        let search = |query: &str| ItemSearch { query: query.to_string(), limit: 10, ..Default::default() };
+        let ids = |found: Vec<Item>| found.iter().map(|i| i.id).collect::<Vec<u32>>();
+
+        assert_eq!(ids(items.search(&search("RUST")).await.unwrap()), vec![note.id, bookmark.id]);
+        assert_eq!(ids(items.search(&search("rust-lang")).await.unwrap()), vec![bookmark.id]);
+        assert_eq!(ids(items.search(&search("0%")).await.unwrap()), vec![note.id]); // wildcards match literally
+        assert_eq!(ids(items.search(&ItemSearch { with_url: true, ..search("") }).await.unwrap()), vec![bookmark.id]);
+        assert_eq!(ids(items.search(&ItemSearch { tag_ids: vec![web.id], ..search("rust") }).await.unwrap()), vec![bookmark.id]);
+        assert_eq!(items.search(&ItemSearch { limit: 1, ..search("") }).await.unwrap().len(), 1);
+
+        // Tag names and aliases, as a filter or `#tag` in the query, resolve to the canonical tag
+        tags.add_alias(web.id, "www").await.unwrap();
+        let by_name = |name: &str| ItemSearch { tag_names: vec![name.to_string()], ..search("") };
+        assert_eq!(ids(items.search(&by_name("WWW")).await.unwrap()), vec![bookmark.id]);
+        assert_eq!(ids(items.search(&by_name("web")).await.unwrap()), vec![bookmark.id]);
+        assert!(items.search(&by_name("unknown")).await.unwrap().is_empty());
+        assert_eq!(ids(items.search(&search("rust #www")).await.unwrap()), vec![bookmark.id]);
+        assert_eq!(ids(items.search(&search("#dev/www book")).await.unwrap()), vec![bookmark.id]);
+
+        let found = items.find_by_url("https://doc.rust-lang.org/book/", None).await.unwrap();
+        assert_eq!(found.map(|i| i.id), Some(bookmark.id));
+        items.delete(bookmark.id).await.unwrap();
+        assert!(items.find_by_url("https://doc.rust-lang.org/book/", None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bookmark_url_dedup_per_workspace() {
+        let repo = setup_test_db().await;
+        let bookmark = |url: &str| {
+            let mut item = Item::new(0, "Rust book".to_string(), ItemType::Bookmark);
+            item.url = Some(url.to_string());
+            item
+        };
+
+        let saved = repo.create_with_workspace(&bookmark("https://doc.rust-lang.org/book/"), BOOKMARK_WORKSPACE_ID).await.unwrap();
+        let found = repo.find_by_url("https://DOC.rust-lang.org/book?utm_source=rss#ch01", Some(BOOKMARK_WORKSPACE_ID)).await.unwrap();
+        assert_eq!(found.map(|i| i.id), Some(saved.id));
+        assert!(repo.find_by_url("https://doc.rust-lang.org/book", Some(1)).await.unwrap().is_none());
+
+        // Same key in the same workspace is rejected; other workspaces are independent
+        let dup = repo.create_with_workspace(&bookmark("https://doc.rust-lang.org/book?utm_medium=x"), BOOKMARK_WORKSPACE_ID).await;
+        assert!(matches!(dup, Err(DomainError::Conflict(_))));
+        repo.create_with_workspace(&bookmark("https://doc.rust-lang.org/book"), 1).await.unwrap();
+
+        // Editing another bookmark onto a taken URL conflicts too
+        let other = repo.create_with_workspace(&bookmark("https://example.com/"), BOOKMARK_WORKSPACE_ID).await.unwrap();
+        let mut edited = other.clone();
+        edited.url = Some("https://doc.rust-lang.org/book/#top".to_string());
+        assert!(matches!(repo.update(&edited).await, Err(DomainError::Conflict(_))));
+
+        // Deleting frees the URL
+        repo.delete(saved.id).await.unwrap();
+        repo.update(&edited).await.unwrap();
+        assert_eq!(repo.find_by_url("https://doc.rust-lang.org/book", Some(BOOKMARK_WORKSPACE_ID)).await.unwrap().map(|i| i.id), Some(other.id));
+    }
+
+    // ========================
+    // Level 18: Archive Tests
+    // ========================
+
+    #[tokio::test]
+    async fn test_archive_save_search_and_delete() {
+        let db_state = init_db(&PathBuf::from(":memory:")).await.expect("Failed to init test DB");
+        let items = ItemRepository::new(db_state.conn.clone());
+        let archives = ArchiveRepository::new(db_state.conn.clone());
+        let assets = AssetRepository::new(db_state.conn.clone());
+
+        let mut bookmark = Item::new(0, "Rust book".to_string(), ItemType::Bookmark);
+        bookmark.url = Some("https://doc.rust-lang.org/book/".to_string());
+        let bookmark = items.create_with_workspace(&bookmark, BOOKMARK_WORKSPACE_ID).await.unwrap();
+        let archive = Archive {
+            item_id: bookmark.id,
+            url: "https://doc.rust-lang.org/book/".to_string(),
+            title: "The Rust Programming Language".to_string(),
+            html_hash: "aa".to_string(),
+            text: "Ownership is Rust's most unique feature.\n所有权是 Rust 最独特的功能".to_string(),
+            images: vec!["bb".to_string()],
+            created_at: Timestamp::from_millis(1_700_000_000_000),
+        };
+        assert_eq!(archives.save(&archive).await.unwrap(), archive);
+        assert_eq!(assets.get_refs("bb").await.unwrap()[0].owner_type, ASSET_OWNER_ARCHIVE);
+
+        let hits = |query: &'static str| {
+            let archives = &archives;
+            async move { archives.search(query, 10).await.unwrap().into_iter().map(|m| m.snippet).collect::<Vec<_>>() }
+        };
+        assert_eq!(hits("UNIQUE feature").await, vec!["Ownership is Rust's most [unique feature].\n所有权是 Rust 最独特的功能"]);
+        assert_eq!(hits("独特").await.len(), 1); // shorter than a trigram
+        assert_eq!(hits("programming").await.len(), 1); // title only
+        assert!(hits("\"borrow OR").await.is_empty());
+
+        // The local API search covers archived text
+        let found = items.search(&ItemSearch { query: "最独特".to_string(), limit: 10, ..Default::default() }).await.unwrap();
+        assert_eq!(found.iter().map(|i| i.id).collect::<Vec<_>>(), vec![bookmark.id]);
+
+        // Archiving again replaces the text in the index
+        archives.save(&Archive { text: "Borrowing".to_string(), images: vec![], ..archive.clone() }).await.unwrap();
+        assert!(hits("unique").await.is_empty());
+        assert_eq!(hits("borrowing").await.len(), 1);
+        assert!(assets.get_refs("bb").await.unwrap().is_empty());
+
+        // Archives of deleted items are left out, deleted archives are gone
+        items.delete(bookmark.id).await.unwrap();
+        assert!(hits("borrowing").await.is_empty());
+        assert!(archives.list().await.unwrap().is_empty());
+        archives.delete(bookmark.id).await.unwrap();
+        assert!(archives.find(bookmark.id).await.unwrap().is_none());
+        assert!(assets.get_refs("aa").await.unwrap().is_empty());
+    }
 }
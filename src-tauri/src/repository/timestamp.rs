@@ -0,0 +1,112 @@
+//! Timestamp Columns
+//!
+//! SQLite conversions for `domain::Timestamp`, and the repair of values
+//! written in the wrong unit (see `repair_timestamps`).
+
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::Connection;
+
+use super::db::column_exists;
+use crate::domain::{Timestamp, SECONDS_CUTOFF};
+
+impl ToSql for Timestamp {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.millis()))
+    }
+}
+
+impl FromSql for Timestamp {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        i64::column_result(value).map(Timestamp::from_millis)
+    }
+}
+
+/// Synced tables and their timestamp columns
+const TIMESTAMP_COLUMNS: &[(&str, &[&str])] = &[
+    ("items", &["created_at", "updated_at", "deleted_at"]),
+    ("tags", &["created_at", "updated_at", "deleted_at"]),
+    ("item_tags", &["created_at", "updated_at", "deleted_at"]),
+    ("tag_tags", &["created_at", "updated_at", "deleted_at"]),
+    ("tag_aliases", &["created_at", "updated_at", "deleted_at"]),
+    ("item_links", &["created_at", "updated_at", "deleted_at"]),
+    ("settings", &["created_at", "updated_at", "deleted_at"]),
+    ("assets", &["created_at", "updated_at", "deleted_at"]),
+    ("workspaces", &["created_at", "updated_at", "deleted_at"]),
+    ("workspace_dirs", &["created_at", "updated_at", "deleted_at"]),
+    ("archives", &["created_at", "updated_at", "deleted_at"]),
+    ("window_state", &["updated_at"]),
+];
+
+/// Bring every timestamp column to integer milliseconds; returns the number
+/// of values changed.
+///
+/// - text (numeric or ISO 8601) and real values become integers
+/// - second-based values (below `SECONDS_CUTOFF`) are scaled to milliseconds
+/// - a missing `created_at` takes `updated_at` and vice versa
+///
+/// Rows with no valid time at all keep 0, so they never win a
+/// last-writer-wins sync against a real change.
+pub fn repair_timestamps(conn: &Connection) -> Result<usize, String> {
+    let mut repaired = 0;
+    for (table, columns) in TIMESTAMP_COLUMNS {
+        let present: Vec<&str> = columns.iter().copied().filter(|c| column_exists(conn, table, c)).collect();
+        for column in &present {
+            let statements = [
+                // Unparseable text becomes 0 rather than NULL so deleted_at stays set
+                format!(
+                    "UPDATE {table} SET {column} = COALESCE(CASE WHEN {column} NOT GLOB '*[^0-9]*' THEN CAST({column} AS INTEGER)
+                     ELSE CAST(strftime('%s', {column}) AS INTEGER) * 1000 END, 0) WHERE typeof({column}) = 'text'"
+                ),
+                format!("UPDATE {table} SET {column} = CAST({column} AS INTEGER) WHERE typeof({column}) = 'real'"),
+                format!("UPDATE {table} SET {column} = {column} * 1000 WHERE {column} > 0 AND {column} < {SECONDS_CUTOFF}"),
+            ];
+            for sql in &statements {
+                repaired += conn.execute(sql, []).map_err(|e| format!("Failed to repair {}.{}: {}", table, column, e))?;
+            }
+        }
+        if present.contains(&"created_at") && present.contains(&"updated_at") {
+            for (missing, source) in [("created_at", "updated_at"), ("updated_at", "created_at")] {
+                let sql = format!("UPDATE {table} SET {missing} = {source} WHERE ({missing} IS NULL OR {missing} <= 0) AND {source} > 0");
+                repaired += conn.execute(&sql, []).map_err(|e| format!("Failed to repair {}.{}: {}", table, missing, e))?;
+            }
+        }
+    }
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_timestamps() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, created_at, updated_at, deleted_at);
+             INSERT INTO items VALUES
+                (1, 1700000000, 1700000000, NULL),
+                (2, 1700000000123, 0, NULL),
+                (3, 0, 0, NULL),
+                (4, '1700000000', '2024-01-02T03:04:05Z', 1700000500),
+                (5, 1700000000123.0, NULL, 'garbage');",
+        )
+        .unwrap();
+
+        assert!(repair_timestamps(&conn).unwrap() > 0);
+        type Row = (i64, Option<i64>, Option<i64>, Option<i64>);
+        let rows: Vec<Row> = conn
+            .prepare("SELECT id, created_at, updated_at, deleted_at FROM items ORDER BY id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, vec![
+            (1, Some(1_700_000_000_000), Some(1_700_000_000_000), None),
+            (2, Some(1_700_000_000_123), Some(1_700_000_000_123), None),
+            (3, Some(0), Some(0), None),
+            (4, Some(1_700_000_000_000), Some(1_704_164_645_000), Some(1_700_000_500_000)),
+            (5, Some(1_700_000_000_123), Some(1_700_000_000_123), Some(0)),
+        ]);
+
+        // Nothing left to do the second time
+        assert_eq!(repair_timestamps(&conn).unwrap(), 0);
+    }
+}
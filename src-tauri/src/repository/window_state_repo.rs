@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::domain::Timestamp;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowState {
     pub width: f64,
@@ -42,7 +44,7 @@ impl WindowStateRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or("Database connection not initialized")?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         conn.execute(
             "INSERT OR REPLACE INTO window_state (id, width, height, x, y, pinned, updated_at) VALUES (1, ?, ?, ?, ?, ?, ?)",
             params![state.width, state.height, state.x, state.y, if state.pinned { 1 } else { 0 }, now],
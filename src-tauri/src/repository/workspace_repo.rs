@@ -6,7 +6,7 @@ use rusqlite::{Connection, params};
 use tokio::sync::Mutex;
 use std::sync::Arc;
 
-use crate::domain::{Workspace, DomainResult, DomainError};
+use crate::domain::{Workspace, DomainResult, DomainError, Timestamp};
 
 /// Fixed workspace IDs (1=todos, 2=files, 3=others, 4=web-bookmarks)
 /// These workspaces cannot be deleted or renamed
@@ -46,9 +46,9 @@ impl WorkspaceRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         conn.execute(
-            "INSERT INTO workspaces (name, updated_at) VALUES (?, ?)",
+            "INSERT INTO workspaces (name, created_at, updated_at) VALUES (?1, ?2, ?2)",
             params![name, now],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -66,7 +66,7 @@ impl WorkspaceRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         
         // Soft delete all items in this workspace
         conn.execute(
@@ -94,7 +94,7 @@ impl WorkspaceRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         conn.execute(
             "UPDATE workspaces SET name = ?, updated_at = ? WHERE id = ?",
             params![name, now, id],
@@ -162,9 +162,9 @@ impl WorkspaceRepository {
              return Ok(dir);
         }
 
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         conn.execute(
-            "INSERT INTO workspace_dirs (workspace_id, path, collapsed, updated_at) VALUES (?, ?, 1, ?)",
+            "INSERT INTO workspace_dirs (workspace_id, path, collapsed, created_at, updated_at) VALUES (?1, ?2, 1, ?3, ?3)",
             params![workspace_id, clean_path, now],
         )
         .map_err(|e| DomainError::Internal(e.to_string()))?;
@@ -178,7 +178,7 @@ impl WorkspaceRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         
         // Soft delete
         conn.execute(
@@ -195,7 +195,7 @@ impl WorkspaceRepository {
         let guard = self.conn.lock().await;
         let conn = guard.as_ref().ok_or(DomainError::Internal("Database not initialized".to_string()))?;
         
-        let now = chrono::Local::now().timestamp_millis();
+        let now = Timestamp::now();
         let val = if collapsed { 1 } else { 0 };
         
         conn.execute(
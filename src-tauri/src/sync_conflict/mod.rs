@@ -0,0 +1,332 @@
+//! Sync Conflict Layer
+//!
+//! The sync backend is last-writer-wins per row. Around it, rows changed on
+//! this device since the last sync (`sync_base`) are compared with their
+//! remote versions fetched just before syncing: when the remote one changed
+//! too and differs, both versions are stored as a `SyncConflict` after the
+//! sync, whichever of them won.
+//!
+//! `prepare` runs before the backend sync and `finish` after it. Remote rows
+//! are read through `RemoteRows`; the app uses `TursoRemote`, tests a fake.
+
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri_plugin_http::reqwest;
+use tokio::sync::Mutex;
+
+use crate::domain::{same_content, ConflictSide, ConflictTable, DomainError, DomainResult, SyncConflict, SyncRow, Timestamp, CONFLICT_TABLES};
+use crate::repository::{key_sql, ChangedRow, SyncConflictRepository};
+
+/// Event carrying the number of new conflicts after a sync that found some
+pub const SYNC_CONFLICTS_EVENT: &str = "sync-conflicts-found";
+
+/// Rows requested per remote query
+const FETCH_CHUNK: usize = 200;
+
+/// Reads rows from the remote database
+#[async_trait]
+pub trait RemoteRows: Send + Sync {
+    /// The rows of `table` with these keys (missing ones are left out)
+    async fn fetch(&self, table: &ConflictTable, keys: &[&str]) -> DomainResult<Vec<SyncRow>>;
+}
+
+/// Reads rows from Turso over its HTTP pipeline API
+pub struct TursoRemote {
+    client: reqwest::Client,
+    endpoint: String,
+    token: String,
+}
+
+impl TursoRemote {
+    /// `url` as configured for sync (`libsql://` or `https://`)
+    pub fn new(client: reqwest::Client, url: &str, token: &str) -> Self {
+        let base = match url.strip_prefix("libsql://") {
+            Some(host) => format!("https://{}", host),
+            None => url.to_string(),
+        };
+        Self { client, endpoint: format!("{}/v2/pipeline", base.trim_end_matches('/')), token: token.to_string() }
+    }
+}
+
+/// A Hrana value as JSON (integers arrive as strings)
+fn hrana_to_json(value: &Value) -> Value {
+    match value.get("type").and_then(Value::as_str) {
+        Some("integer") => value.get("value").and_then(Value::as_str)
+            .and_then(|v| v.parse::<i64>().ok())
+            .map_or(Value::Null, Value::from),
+        Some("float") | Some("text") => value.get("value").cloned().unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+#[async_trait]
+impl RemoteRows for TursoRemote {
+    async fn fetch(&self, table: &ConflictTable, keys: &[&str]) -> DomainResult<Vec<SyncRow>> {
+        let placeholders = vec!["?"; keys.len()].join(", ");
+        let args: Vec<Value> = keys.iter().map(|key| json!({ "type": "text", "value": key })).collect();
+        let sql = format!("SELECT t.* FROM {} t WHERE {} IN ({})", table.name, key_sql(table), placeholders);
+        let body = json!({
+            "requests": [
+                { "type": "execute", "stmt": { "sql": sql, "args": args } },
+                { "type": "close" }
+            ]
+        });
+        let http_err = |e: reqwest::Error| DomainError::Internal(format!("Remote query failed: {}", e));
+        let response: Value = self.client.post(&self.endpoint)
+            .bearer_auth(&self.token)
+            .json(&body)
+            .send().await.map_err(http_err)?
+            .error_for_status().map_err(http_err)?
+            .json().await.map_err(http_err)?;
+
+        let first = &response["results"][0];
+        if first["type"] != "ok" {
+            return Err(DomainError::Internal(format!("Remote query failed: {}", first["error"])));
+        }
+        let result = &first["response"]["result"];
+        let columns: Vec<String> = result["cols"].as_array().into_iter().flatten()
+            .map(|col| col["name"].as_str().unwrap_or_default().to_string())
+            .collect();
+        let rows = result["rows"].as_array().into_iter().flatten()
+            .map(|row| {
+                columns.iter().cloned()
+                    .zip(row.as_array().into_iter().flatten().map(hrana_to_json))
+                    .collect::<SyncRow>()
+            })
+            .collect();
+        Ok(rows)
+    }
+}
+
+/// Rows changed on both sides, found before syncing
+pub struct PendingSync {
+    edited_both: Vec<(ChangedRow, SyncRow)>,
+    /// Rows written from here on were edited during the sync
+    started: Timestamp,
+}
+
+/// Find rows changed here and remotely since the last sync. Call right
+/// before the backend sync, and pass the result to `finish` after it.
+pub async fn prepare(conn: Arc<Mutex<Option<Connection>>>, remote: &dyn RemoteRows) -> DomainResult<PendingSync> {
+    let started = Timestamp::now();
+    let changed = SyncConflictRepository::new(conn).changed_since_sync().await?;
+
+    let mut edited_both = Vec::new();
+    for table in CONFLICT_TABLES {
+        let rows: Vec<&ChangedRow> = changed.iter().filter(|row| row.table.name == table.name).collect();
+        for chunk in rows.chunks(FETCH_CHUNK) {
+            let keys: Vec<&str> = chunk.iter().map(|row| row.row_key.as_str()).collect();
+            let remote_rows = remote.fetch(table, &keys).await?;
+            for row in chunk {
+                let Some(remote_row) = remote_rows.iter().find(|r| table.same_row(r, &row.local)) else {
+                    continue;
+                };
+                // Unchanged remotely since the last sync (a row created here
+                // since has no base: the same key on the remote is someone else's)
+                let remote_changed = row.base.as_ref()
+                    .is_none_or(|base| base.get("updated_at") != remote_row.get("updated_at"));
+                if remote_changed && !same_content(&row.local, remote_row) {
+                    edited_both.push(((*row).clone(), remote_row.clone()));
+                }
+            }
+        }
+    }
+    Ok(PendingSync { edited_both, started })
+}
+
+/// After the backend sync: store the conflicts found by `prepare` with the
+/// version that won, and take the synced rows as the base of the next sync
+/// (rows edited since `prepare` stay changed). Returns the new conflicts.
+pub async fn finish(conn: Arc<Mutex<Option<Connection>>>, pending: PendingSync) -> DomainResult<Vec<SyncConflict>> {
+    let repo = SyncConflictRepository::new(conn);
+    let mut conflicts = Vec::new();
+    for (changed, remote) in &pending.edited_both {
+        let synced = repo.read_row(changed.table.name, &changed.row_key).await?;
+        let kept = match synced {
+            Some(synced) if same_content(&changed.local, &synced) => ConflictSide::Local,
+            _ => ConflictSide::Remote,
+        };
+        conflicts.push(repo.record(changed, remote, kept).await?);
+    }
+    repo.rebase(pending.started).await?;
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ConflictResolution, Item, ItemType};
+    use crate::repository::{init_db, ItemRepository, Repository, SettingsRepository};
+    use std::path::PathBuf;
+    use std::sync::Mutex as StdMutex;
+
+    /// Remote rows kept in memory
+    #[derive(Default)]
+    struct FakeRemote(StdMutex<Vec<SyncRow>>);
+
+    #[async_trait]
+    impl RemoteRows for FakeRemote {
+        async fn fetch(&self, table: &ConflictTable, keys: &[&str]) -> DomainResult<Vec<SyncRow>> {
+            Ok(self.0.lock().unwrap().iter()
+                .filter(|row| {
+                    let key: Vec<Value> = table.key.iter().map(|column| row[*column].clone()).collect();
+                    keys.contains(&serde_json::to_string(&key).unwrap().as_str())
+                })
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// What the backend sync does for one row: the later `updated_at` wins
+    async fn sync_row(items: &ItemRepository, remote: &FakeRemote, id: u32) {
+        let mut local = items.find_by_id(id).await.unwrap().unwrap();
+        let remote_row = remote.0.lock().unwrap()[0].clone();
+        if remote_row["updated_at"].as_i64() > local.updated_at.map(|t| t.millis()) {
+            local.memo = remote_row["memo"].as_str().map(String::from);
+            items.update(&local).await.unwrap();
+        }
+    }
+
+    /// Sync with nothing to compare against yet, once the rows written so far
+    /// are older than its start (later ones count as edited during the sync)
+    async fn first_sync(conn: &Arc<Mutex<Option<Connection>>>, remote: &FakeRemote) {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let found = finish(conn.clone(), prepare(conn.clone(), remote).await.unwrap()).await.unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memo_edited_on_both_sides() {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let conflicts = SyncConflictRepository::new(db.conn.clone());
+        let mut item = Item::new(0, "Plan".to_string(), ItemType::Document);
+        item.memo = Some("intro\nbody\n".to_string());
+        let item = items.create(&item).await.unwrap();
+
+        let remote = FakeRemote::default();
+        first_sync(&db.conn, &remote).await;
+        let base = conflicts.read_row("items", &format!("[{}]", item.id)).await.unwrap().unwrap();
+
+        // Edited here...
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let mut local = item.clone();
+        local.memo = Some("intro\nbody\nlocal ending\n".to_string());
+        items.update(&local).await.unwrap();
+        // ...and later on another device
+        let mut remote_row = base.clone();
+        remote_row.insert("memo".to_string(), json!("new intro\nbody\n"));
+        remote_row.insert("updated_at".to_string(), json!(i64::MAX / 2));
+        *remote.0.lock().unwrap() = vec![remote_row];
+
+        let pending = prepare(db.conn.clone(), &remote).await.unwrap();
+        sync_row(&items, &remote, item.id).await;
+        let found = finish(db.conn.clone(), pending).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kept, ConflictSide::Remote);
+        assert_eq!(found[0].lost()["memo"], json!("intro\nbody\nlocal ending\n"));
+        assert_eq!(found[0].base.as_ref().unwrap()["memo"], json!("intro\nbody\n"));
+
+        // Nothing changed since: the next sync finds no new conflict
+        let pending = prepare(db.conn.clone(), &remote).await.unwrap();
+        assert!(finish(db.conn.clone(), pending).await.unwrap().is_empty());
+        assert_eq!(conflicts.list().await.unwrap().len(), 1);
+
+        let merged = conflicts.resolve(found[0].id, ConflictResolution::Merged, None).await.unwrap();
+        assert_eq!(merged["memo"], json!("new intro\nbody\nlocal ending\n"));
+        assert!(conflicts.list().await.unwrap().is_empty());
+        let saved = items.find_by_id(item.id).await.unwrap().unwrap();
+        assert_eq!(saved.memo.as_deref(), Some("new intro\nbody\nlocal ending\n"));
+        assert_eq!(saved.text, "Plan");
+    }
+
+    #[tokio::test]
+    async fn test_same_edit_and_one_sided_edit_are_not_conflicts() {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let conflicts = SyncConflictRepository::new(db.conn.clone());
+        let item = items.create(&Item::new(0, "Note".to_string(), ItemType::Document)).await.unwrap();
+        let remote = FakeRemote::default();
+        first_sync(&db.conn, &remote).await;
+        let base = conflicts.read_row("items", &format!("[{}]", item.id)).await.unwrap().unwrap();
+
+        // Edited here only: the remote still has the base version
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let mut local = item.clone();
+        local.memo = Some("same".to_string());
+        items.update(&local).await.unwrap();
+        *remote.0.lock().unwrap() = vec![base.clone()];
+        assert!(prepare(db.conn.clone(), &remote).await.unwrap().edited_both.is_empty());
+
+        // The same edit on both sides
+        let mut remote_row = base;
+        remote_row.insert("memo".to_string(), json!("same"));
+        remote_row.insert("updated_at".to_string(), json!(1));
+        *remote.0.lock().unwrap() = vec![remote_row];
+        assert!(prepare(db.conn.clone(), &remote).await.unwrap().edited_both.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_edit_during_sync_is_compared_next_time() {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let conflicts = SyncConflictRepository::new(db.conn.clone());
+        let item = items.create(&Item::new(0, "Draft".to_string(), ItemType::Document)).await.unwrap();
+        let remote = FakeRemote::default();
+        first_sync(&db.conn, &remote).await;
+        assert!(conflicts.changed_since_sync().await.unwrap().is_empty());
+
+        // Edited while the backend sync runs: not taken as the base
+        let pending = prepare(db.conn.clone(), &remote).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let mut local = item.clone();
+        local.memo = Some("written mid-sync".to_string());
+        items.update(&local).await.unwrap();
+        finish(db.conn.clone(), pending).await.unwrap();
+
+        let changed = conflicts.changed_since_sync().await.unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].row_key, format!("[{}]", item.id));
+        assert_eq!(changed[0].base.as_ref().unwrap()["memo"], Value::Null);
+        assert_eq!(changed[0].local["memo"], json!("written mid-sync"));
+    }
+
+    #[tokio::test]
+    async fn test_setting_edited_on_both_sides() {
+        let db = init_db(&PathBuf::from(":memory:")).await.unwrap();
+        let settings = SettingsRepository::new(db.conn.clone());
+        let conflicts = SyncConflictRepository::new(db.conn.clone());
+        settings.set("tag_palette", &vec!["#111111"]).await.unwrap();
+        let remote = FakeRemote::default();
+        first_sync(&db.conn, &remote).await;
+        let key = r#"["tag_palette"]"#;
+        let base = conflicts.read_row("settings", key).await.unwrap().unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        settings.set("tag_palette", &vec!["#222222"]).await.unwrap();
+        let mut remote_row = base;
+        remote_row.insert("value".to_string(), json!(r##"["#333333"]"##));
+        remote_row.insert("updated_at".to_string(), json!(1));
+        *remote.0.lock().unwrap() = vec![remote_row];
+
+        let found = finish(db.conn.clone(), prepare(db.conn.clone(), &remote).await.unwrap()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].table_name.as_str(), found[0].row_key.as_str(), found[0].row_id()), ("settings", key, None));
+        assert_eq!(found[0].kept, ConflictSide::Local);
+
+        conflicts.resolve(found[0].id, ConflictResolution::KeepRemote, None).await.unwrap();
+        let palette: Option<Vec<String>> = settings.get("tag_palette").await.unwrap();
+        assert_eq!(palette, Some(vec!["#333333".to_string()]));
+    }
+
+    #[test]
+    fn test_turso_endpoint_and_values() {
+        let remote = TursoRemote::new(reqwest::Client::new(), "libsql://notes-me.turso.io/", "t");
+        assert_eq!(remote.endpoint, "https://notes-me.turso.io/v2/pipeline");
+        assert_eq!(hrana_to_json(&json!({ "type": "integer", "value": "42" })), json!(42));
+        assert_eq!(hrana_to_json(&json!({ "type": "text", "value": "memo" })), json!("memo"));
+        assert_eq!(hrana_to_json(&json!({ "type": "null" })), Value::Null);
+    }
+}
@@ -0,0 +1,469 @@
+//! Sync Scheduler
+//!
+//! Runs cloud sync in the background: a few seconds after local writes
+//! (debounced, so a burst of edits is pushed once), periodically to pull
+//! other devices' changes, and when the window gains focus. Failed syncs are
+//! retried with exponential backoff. Every state change is reported as a
+//! `SchedulerStatus` (sent to the frontend as `sync-status` events).
+//!
+//! What a sync does and how local writes are noticed is up to the
+//! `SyncTarget`; the app syncs the database, tests use a stand-in server.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+
+use crate::domain::Timestamp;
+
+/// Event carrying a `SchedulerStatus` whenever it changes
+pub const SYNC_STATUS_EVENT: &str = "sync-status";
+
+/// What the scheduler syncs
+#[async_trait]
+pub trait SyncTarget: Send + Sync {
+    /// A count that grows with every write (polled)
+    async fn change_count(&self) -> u64;
+
+    /// Push local changes and pull remote ones; returns how much of the
+    /// change count the sync's own writes account for. The rest of what the
+    /// count grew by meanwhile were local writes, pushed after a debounce.
+    async fn sync(&self) -> Result<u64, String>;
+}
+
+/// Timing of background syncs
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Quiet time after the last local write before pushing
+    pub debounce: Duration,
+    /// Time between syncs while nothing happens locally
+    pub pull_interval: Duration,
+    /// How often the change count is polled
+    pub poll_interval: Duration,
+    /// Focusing the window syncs only if the last sync is older than this
+    pub focus_min_gap: Duration,
+    /// First retry delay; doubled on every further failure
+    pub backoff_base: Duration,
+    pub backoff_max: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_secs(3),
+            pull_interval: Duration::from_secs(5 * 60),
+            poll_interval: Duration::from_secs(1),
+            focus_min_gap: Duration::from_secs(30),
+            backoff_base: Duration::from_secs(2),
+            backoff_max: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// What the scheduler is doing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum SyncState {
+    Idle,
+    Syncing,
+    /// The last sync failed; retried at `retry_at`
+    Error { message: String },
+}
+
+/// Current state with the times around it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchedulerStatus {
+    #[serde(flatten)]
+    pub state: SyncState,
+    pub last_synced_at: Option<Timestamp>,
+    pub retry_at: Option<Timestamp>,
+}
+
+impl Default for SchedulerStatus {
+    fn default() -> Self {
+        Self { state: SyncState::Idle, last_synced_at: None, retry_at: None }
+    }
+}
+
+/// Called with every new status
+pub type StatusNotify = Arc<dyn Fn(&SchedulerStatus) + Send + Sync>;
+
+enum Trigger {
+    WindowFocused,
+    /// Synced outside the scheduler (manual sync)
+    Synced,
+}
+
+/// Handle to the background task; dropping it stops the task
+pub struct SyncScheduler {
+    triggers: mpsc::UnboundedSender<Trigger>,
+    status: Arc<Mutex<SchedulerStatus>>,
+    task: JoinHandle<()>,
+}
+
+impl SyncScheduler {
+    /// Start the background task (on the current tokio runtime). The first
+    /// sync runs right away.
+    pub fn start(target: Arc<dyn SyncTarget>, config: SchedulerConfig, notify: StatusNotify) -> Self {
+        let (triggers, receiver) = mpsc::unbounded_channel();
+        let status = Arc::new(Mutex::new(SchedulerStatus::default()));
+        let runner = Runner { target, config, notify, status: status.clone(), failures: 0 };
+        let task = tokio::spawn(runner.run(receiver));
+        Self { triggers, status, task }
+    }
+
+    pub fn status(&self) -> SchedulerStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn window_focused(&self) {
+        let _ = self.triggers.send(Trigger::WindowFocused);
+    }
+
+    /// Report a sync done elsewhere, so the next pull is a full interval away
+    pub fn synced(&self) {
+        let _ = self.triggers.send(Trigger::Synced);
+    }
+}
+
+impl Drop for SyncScheduler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct Runner {
+    target: Arc<dyn SyncTarget>,
+    config: SchedulerConfig,
+    notify: StatusNotify,
+    status: Arc<Mutex<SchedulerStatus>>,
+    failures: u32,
+}
+
+impl Runner {
+    async fn run(mut self, mut triggers: mpsc::UnboundedReceiver<Trigger>) {
+        let mut seen_changes = self.target.change_count().await;
+        let mut next_sync = Instant::now();
+        let mut next_poll = Instant::now() + self.config.poll_interval;
+        let mut last_synced: Option<Instant> = None;
+        // Earliest next attempt while failing
+        let mut retry_at: Option<Instant> = None;
+
+        loop {
+            let due = retry_at.map_or(next_sync, |retry| next_sync.max(retry));
+            tokio::select! {
+                trigger = triggers.recv() => match trigger {
+                    None => return,
+                    Some(Trigger::WindowFocused) => {
+                        if last_synced.is_none_or(|at| at.elapsed() >= self.config.focus_min_gap) {
+                            next_sync = next_sync.min(Instant::now());
+                        }
+                    }
+                    Some(Trigger::Synced) => {
+                        seen_changes = self.target.change_count().await;
+                        last_synced = Some(Instant::now());
+                        next_sync = Instant::now() + self.config.pull_interval;
+                        retry_at = None;
+                        self.failures = 0;
+                        self.set_status(SyncState::Idle, Some(Timestamp::now()), None);
+                    }
+                },
+                _ = sleep_until(next_poll.min(due)) => {
+                    let now = Instant::now();
+                    if now >= next_poll {
+                        next_poll = now + self.config.poll_interval;
+                        let changes = self.target.change_count().await;
+                        if changes != seen_changes {
+                            seen_changes = changes;
+                            // Every write restarts the quiet period
+                            next_sync = now + self.config.debounce;
+                        }
+                    }
+                    if now < retry_at.map_or(next_sync, |retry| next_sync.max(retry)) {
+                        continue;
+                    }
+                    let before = self.target.change_count().await;
+                    let synced = self.sync().await;
+                    seen_changes = self.target.change_count().await;
+                    match synced {
+                        Ok(own_writes) => {
+                            retry_at = None;
+                            last_synced = Some(Instant::now());
+                            // Writes made by the sync itself are not local changes;
+                            // any others during the sync are pushed after a debounce
+                            let wait = if seen_changes.saturating_sub(before) > own_writes {
+                                self.config.debounce
+                            } else {
+                                self.config.pull_interval
+                            };
+                            next_sync = Instant::now() + wait;
+                        }
+                        Err(retry) => {
+                            retry_at = Some(retry);
+                            next_sync = retry;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run one sync; returns the sync's own writes, or when to retry if it failed
+    async fn sync(&mut self) -> Result<u64, Instant> {
+        let last_synced_at = self.current().last_synced_at;
+        self.set_status(SyncState::Syncing, last_synced_at, None);
+        match self.target.sync().await {
+            Ok(own_writes) => {
+                self.failures = 0;
+                self.set_status(SyncState::Idle, Some(Timestamp::now()), None);
+                Ok(own_writes)
+            }
+            Err(message) => {
+                self.failures += 1;
+                let delay = backoff(&self.config, self.failures);
+                let retry_at = Timestamp::from_millis(Timestamp::now().millis() + delay.as_millis() as i64);
+                self.set_status(SyncState::Error { message }, last_synced_at, Some(retry_at));
+                Err(Instant::now() + delay)
+            }
+        }
+    }
+
+    fn current(&self) -> SchedulerStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set_status(&self, state: SyncState, last_synced_at: Option<Timestamp>, retry_at: Option<Timestamp>) {
+        let status = SchedulerStatus { state, last_synced_at, retry_at };
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = status.clone();
+        (self.notify)(&status);
+    }
+}
+
+/// Delay before retry number `failures` (1-based)
+fn backoff(config: &SchedulerConfig, failures: u32) -> Duration {
+    config.backoff_base.saturating_mul(1 << failures.saturating_sub(1).min(16)).min(config.backoff_max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use tauri_plugin_http::reqwest;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Stand-in sync server: answers 503 to the first `failures` requests,
+    /// then 200; counts requests
+    async fn serve(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sync", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let status = if n < failures { "503 Service Unavailable" } else { "200 OK" };
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+
+    /// Syncs by POSTing to the stand-in server; local writes are simulated
+    /// by bumping `changes`
+    struct HttpTarget {
+        client: reqwest::Client,
+        url: String,
+        changes: AtomicU64,
+    }
+
+    #[async_trait]
+    impl SyncTarget for HttpTarget {
+        async fn change_count(&self) -> u64 {
+            self.changes.load(Ordering::SeqCst)
+        }
+
+        async fn sync(&self) -> Result<u64, String> {
+            let response = self.client.post(&self.url).send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("HTTP {}", response.status()));
+            }
+            Ok(0)
+        }
+    }
+
+    fn config() -> SchedulerConfig {
+        SchedulerConfig {
+            debounce: Duration::from_millis(150),
+            pull_interval: Duration::from_secs(3600),
+            poll_interval: Duration::from_millis(10),
+            focus_min_gap: Duration::from_millis(200),
+            backoff_base: Duration::from_millis(50),
+            backoff_max: Duration::from_secs(1),
+        }
+    }
+
+    fn start(url: String) -> (SyncScheduler, Arc<HttpTarget>, Arc<Mutex<Vec<SchedulerStatus>>>) {
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let target = Arc::new(HttpTarget { client, url, changes: AtomicU64::new(0) });
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let seen = statuses.clone();
+        let notify: StatusNotify = Arc::new(move |status| seen.lock().unwrap().push(status.clone()));
+        (SyncScheduler::start(target.clone(), config(), notify), target, statuses)
+    }
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        for _ in 0..300 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out");
+    }
+
+    #[tokio::test]
+    async fn test_retries_with_backoff() {
+        let (url, requests) = serve(2).await;
+        let started = Instant::now();
+        let (scheduler, _target, statuses) = start(url);
+        wait_for(|| requests.load(Ordering::SeqCst) == 3 && scheduler.status().state == SyncState::Idle).await;
+        // 50ms after the first failure, 100ms after the second
+        assert!(started.elapsed() >= Duration::from_millis(150));
+
+        let states: Vec<_> = statuses.lock().unwrap().iter().map(|s| s.state.clone()).collect();
+        let error = SyncState::Error { message: "HTTP 503 Service Unavailable".to_string() };
+        assert_eq!(states, [SyncState::Syncing, error.clone(), SyncState::Syncing, error, SyncState::Syncing, SyncState::Idle]);
+        let statuses = statuses.lock().unwrap();
+        assert!(statuses[1].retry_at.is_some() && statuses[1].last_synced_at.is_none());
+        assert!(statuses[5].retry_at.is_none() && statuses[5].last_synced_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_pushes_once_after_a_burst_of_writes() {
+        let (url, requests) = serve(0).await;
+        let (scheduler, target, _statuses) = start(url);
+        wait_for(|| requests.load(Ordering::SeqCst) == 1 && scheduler.status().state == SyncState::Idle).await;
+
+        for _ in 0..3 {
+            target.changes.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        // Still within the quiet period of the last write
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        wait_for(|| requests.load(Ordering::SeqCst) == 2 && scheduler.status().state == SyncState::Idle).await;
+
+        // Focus right after a sync does not sync again; a while later it does
+        scheduler.window_focused();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        scheduler.window_focused();
+        wait_for(|| requests.load(Ordering::SeqCst) == 3).await;
+    }
+
+    /// Watches a real database the way the app does (`total_changes`); its
+    /// syncs write bookkeeping rows like the real one
+    struct DbTarget {
+        db: crate::repository::DbState,
+        syncs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SyncTarget for DbTarget {
+        async fn change_count(&self) -> u64 {
+            let guard = self.db.conn.lock().await;
+            guard.as_ref().map_or(0, |conn| conn.total_changes())
+        }
+
+        async fn sync(&self) -> Result<u64, String> {
+            let n = self.syncs.fetch_add(1, Ordering::SeqCst) + 1;
+            let guard = self.db.conn.lock().await;
+            let conn = guard.as_ref().ok_or("Database not initialized")?;
+            let before = conn.total_changes();
+            conn.execute(
+                "INSERT OR REPLACE INTO sync_status (table_name, last_sync_time, sync_count) VALUES ('items', datetime('now'), ?1)",
+                [n as i64],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(conn.total_changes() - before)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_write_triggers_one_sync() {
+        use crate::domain::{Item, ItemType};
+        use crate::repository::{init_db, ItemRepository, Repository};
+
+        let db = init_db(&std::path::PathBuf::from(":memory:")).await.unwrap();
+        let items = ItemRepository::new(db.conn.clone());
+        let target = Arc::new(DbTarget { db, syncs: AtomicUsize::new(0) });
+        let notify: StatusNotify = Arc::new(|_| {});
+        let scheduler = SyncScheduler::start(target.clone(), config(), notify);
+        wait_for(|| target.syncs.load(Ordering::SeqCst) == 1 && scheduler.status().state == SyncState::Idle).await;
+
+        items.create(&Item::new(0, "Written locally".to_string(), ItemType::Daily)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Still within the quiet period
+        assert_eq!(target.syncs.load(Ordering::SeqCst), 1);
+        wait_for(|| target.syncs.load(Ordering::SeqCst) == 2 && scheduler.status().state == SyncState::Idle).await;
+
+        // The sync's own writes are not taken for local changes
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(target.syncs.load(Ordering::SeqCst), 2);
+    }
+
+    /// Slow sync that writes once itself; local writes are simulated by
+    /// bumping `changes`
+    struct SlowTarget {
+        changes: AtomicU64,
+        syncs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SyncTarget for SlowTarget {
+        async fn change_count(&self) -> u64 {
+            self.changes.load(Ordering::SeqCst)
+        }
+
+        async fn sync(&self) -> Result<u64, String> {
+            self.syncs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            self.changes.fetch_add(1, Ordering::SeqCst);
+            Ok(1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_during_sync_is_pushed_after_debounce() {
+        let target = Arc::new(SlowTarget { changes: AtomicU64::new(0), syncs: AtomicUsize::new(0) });
+        let notify: StatusNotify = Arc::new(|_| {});
+        let scheduler = SyncScheduler::start(target.clone(), config(), notify);
+        wait_for(|| target.syncs.load(Ordering::SeqCst) == 1 && scheduler.status().state == SyncState::Idle).await;
+        // Only the sync's own write: nothing to push
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(target.syncs.load(Ordering::SeqCst), 1);
+
+        // A local write lands in the middle of the next sync
+        scheduler.window_focused();
+        wait_for(|| scheduler.status().state == SyncState::Syncing).await;
+        target.changes.fetch_add(1, Ordering::SeqCst);
+        let synced = Instant::now();
+        wait_for(|| target.syncs.load(Ordering::SeqCst) == 2 && scheduler.status().state == SyncState::Idle).await;
+        wait_for(|| target.syncs.load(Ordering::SeqCst) == 3).await;
+        // Pushed a debounce after the sync, not a pull interval later
+        assert!(synced.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let config = config();
+        let delays: Vec<_> = (1..=7).map(|n| backoff(&config, n).as_millis()).collect();
+        assert_eq!(delays, [50, 100, 200, 400, 800, 1000, 1000]);
+    }
+}
@@ -10,9 +10,15 @@ use crate::models::{Item, Tag, Workspace, FileViewItem};
 use crate::commands;
 use crate::context::AppContext;
 use crate::store::{AppState, AppStateStoreFields};
-use crate::components::{NewItemForm, TagColumn, TagEditor, ItemTreeView, EditTarget, WorkspaceTabBar, MemoEditorColumn, TitleBar, SyncModal, FilesWorkspace, TagDndContext};
+use crate::components::{NewItemForm, TagColumn, TagEditor, ItemTreeView, EditTarget, WorkspaceTabBar, MemoEditorColumn, TitleBar, SyncModal, FilesWorkspace, TagDndContext, SyncConflicts};
 use crate::mobile::MobileApp;
 
+/// `sync-status` event
+#[derive(serde::Deserialize)]
+struct SyncStatusEvent {
+    payload: commands::AppSyncStatus,
+}
+
 /// Filter mode for tag-based item filtering
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum FilterMode {
@@ -105,6 +111,41 @@ pub fn App() -> impl IntoView {
         });
     });
     
+    // A move onto cloud sync keeps the old database until the user decides
+    let review_cloud_migration = move || async move {
+        if !commands::has_pending_cloud_migration().await.unwrap_or(false) {
+            return;
+        }
+        let Some(window) = web_sys::window() else { return; };
+        let keep = window.confirm_with_message("本地数据已迁移到云同步数据库。\n确定: 保留迁移结果并删除迁移前的快照\n取消: 恢复迁移前的本地数据库 (回到本地模式)").unwrap_or(true);
+        let result = if keep {
+            commands::confirm_cloud_migration().await
+        } else {
+            commands::restore_cloud_migration().await
+        };
+        match result {
+            Ok(()) if !keep => {
+                set_sync_url.set(String::new());
+                set_sync_token.set(String::new());
+                set_sync_status.set("idle".to_string());
+                set_sync_msg.set("已恢复迁移前的本地数据库".to_string());
+                set_reload_trigger.update(|n| *n += 1);
+            }
+            Ok(()) => {}
+            Err(e) => {
+                let _ = window.alert_with_message(&format!("操作失败: {}", e));
+            }
+        }
+    };
+    spawn_local(async move {
+        // Restoring swaps the database file: wait until it is open
+        if commands::get_sync_status().await.is_ok() {
+            review_cloud_migration().await;
+        } else {
+            let _ = commands::listen_safe("db-initialized", move |_| spawn_local(review_cloud_migration())).await;
+        }
+    });
+
     // Startup sync: Load config and auto-sync if configured
     Effect::new(move |_| {
         spawn_local(async move {
@@ -123,7 +164,7 @@ pub fn App() -> impl IntoView {
                     set_sync_url.set(cfg.url.clone());
                     set_sync_token.set(cfg.token.clone());
                     
-                    // If configured, perform initial sync
+                    // If configured, the backend starts syncing right away
                     if !cfg.url.is_empty() && !cfg.token.is_empty() {
                         // Check if cloud sync is actually enabled (might have fallen back to local)
                         let is_enabled = commands::is_cloud_sync_enabled().await;
@@ -135,34 +176,19 @@ pub fn App() -> impl IntoView {
                             return;
                         }
 
-                        set_sync_status.set("syncing".to_string());
-                        
-
-                        
-                        // Perform sync
-                        match commands::sync_cloud_db().await {
-                            Ok(_) => {
-                                set_sync_status.set("success".to_string());
-                                set_reload_trigger.update(|n| *n += 1);
-                                // Update status msg with time
-                                if let Ok(status) = commands::get_sync_status().await {
-                                     if let Some(time) = status.last_sync_time {
-                                         set_sync_msg.set(format!("同步完成 ({})", time));
-                                     } else {
-                                         set_sync_msg.set("初始同步完成".to_string());
-                                     }
-                                } else {
-                                    set_sync_msg.set("初始同步完成".to_string());
+                        // The backend syncs in the background from here on
+                        // (reported through `sync-status` events); pick up
+                        // a sync that started before this listened
+                        if let Ok(status) = commands::get_sync_status().await {
+                            match status.state.as_str() {
+                                "syncing" => set_sync_status.set("syncing".to_string()),
+                                "error" => {
+                                    set_sync_status.set("error".to_string());
+                                    set_sync_msg.set(format!("同步失败: {}，稍后自动重试", status.message.unwrap_or_default()));
                                 }
-                            }
-                            Err(e) => {
-                                set_sync_status.set("error".to_string());
-                                set_sync_msg.set(format!("同步失败: {}", e));
+                                _ => {}
                             }
                         }
-                        
-                        
-                        // Sync done
                     } else {
                         // No config, do nothing
                     }
@@ -174,6 +200,36 @@ pub fn App() -> impl IntoView {
         });
     });
 
+    // Background sync progress
+    spawn_local(async move {
+        let _ = commands::listen_safe("sync-status", move |event| {
+            let Ok(event) = serde_wasm_bindgen::from_value::<SyncStatusEvent>(event) else { return; };
+            match event.payload.state.as_str() {
+                "syncing" => {
+                    set_sync_status.set("syncing".to_string());
+                    set_sync_msg.set("正在同步...".to_string());
+                }
+                "error" => {
+                    set_sync_status.set("error".to_string());
+                    set_sync_msg.set(format!("同步失败: {}，稍后自动重试", event.payload.message.unwrap_or_default()));
+                }
+                _ => {
+                    // Show what the sync pulled in
+                    if sync_status.get_untracked() == "syncing" {
+                        set_reload_trigger.update(|n| *n += 1);
+                    }
+                    set_sync_status.set("success".to_string());
+                    spawn_local(async move {
+                        match commands::get_sync_status().await.ok().and_then(|status| status.last_sync_time) {
+                            Some(time) => set_sync_msg.set(format!("同步完成 ({})", time)),
+                            None => set_sync_msg.set("同步完成".to_string()),
+                        }
+                    });
+                }
+            }
+        }).await;
+    });
+
     // Provide context to all children
     provide_context(AppContext::new((reload_trigger, set_reload_trigger), (adding_under, set_adding_under), current_workspace));
 
@@ -200,6 +256,26 @@ pub fn App() -> impl IntoView {
         });
     });
 
+    // Code highlighting stylesheet (served by the backend)
+    crate::markdown::load_highlight_css();
+    // Synced memos refer to assets by hash; find them in this device's app data
+    crate::markdown::load_assets_dir();
+
+    // The browser clipper writes through the local API: refresh right away
+    for event in ["items-changed", "tags-changed"] {
+        spawn_local(async move {
+            let _ = commands::listen_safe(event, move |_| {
+                set_reload_trigger.update(|n| *n += 1);
+                spawn_local(crate::store::store_reload_tags(store));
+            }).await;
+        });
+    }
+
+    // Keep inline #tag chip colours in sync with loaded tags
+    Effect::new(move |_| {
+        crate::markdown::set_tag_colors(&store.tags().get());
+    });
+
     // Load items when workspace or trigger changes
     Effect::new(move |_| {
         let _ = reload_trigger.get();
@@ -333,6 +409,8 @@ pub fn App() -> impl IntoView {
                 on_sync_click=Callback::new(toggle_sync_modal)
                 on_sync_right_click=Callback::new(perform_manual_sync)
             />
+            // Rows edited here and on another device since the last sync
+            <SyncConflicts />
             
             <div class="app-layout">
                 // Left: Tag Column
@@ -351,6 +429,7 @@ pub fn App() -> impl IntoView {
                         workspaces=workspaces
                         current_workspace=current_workspace
                         set_current_workspace=set_current_workspace
+                        selected_tags=selected_tags
                     />
                     
 
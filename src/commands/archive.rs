@@ -0,0 +1,41 @@
+//! Archive Commands
+//!
+//! Frontend bindings for offline reader copies of bookmarked pages.
+
+use serde::Serialize;
+use super::invoke;
+use crate::models::ArchiveView;
+
+#[derive(Serialize)]
+struct ArchiveUrlArgs<'a> {
+    #[serde(rename = "itemId")]
+    item_id: u32,
+    html: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ArchiveItemIdArgs {
+    #[serde(rename = "itemId")]
+    item_id: u32,
+}
+
+/// Archive the page of a bookmark (fetched by the backend unless `html` is given)
+pub async fn archive_url(item_id: u32, html: Option<&str>) -> Result<(), String> {
+    let js_args = serde_wasm_bindgen::to_value(&ArchiveUrlArgs { item_id, html }).map_err(|e| e.to_string())?;
+    let _ = invoke("archive_url", js_args).await;
+    Ok(())
+}
+
+/// The archive of an item with its reader HTML, if it has one
+pub async fn get_archive(item_id: u32) -> Result<Option<ArchiveView>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ArchiveItemIdArgs { item_id }).map_err(|e| e.to_string())?;
+    let result = invoke("get_archive", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Remove the archive of an item
+pub async fn delete_archive(item_id: u32) -> Result<(), String> {
+    let js_args = serde_wasm_bindgen::to_value(&ArchiveItemIdArgs { item_id }).map_err(|e| e.to_string())?;
+    let _ = invoke("delete_archive", js_args).await;
+    Ok(())
+}
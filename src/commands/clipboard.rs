@@ -1,9 +1,10 @@
 //! Clipboard Commands
 //!
-//! Frontend wrappers for clipboard operations.
+//! Frontend wrappers for clipboard and asset operations.
 
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
+use crate::models::SavedAsset;
 
 #[wasm_bindgen]
 extern "C" {
@@ -12,22 +13,39 @@ extern "C" {
 }
 
 #[derive(Serialize)]
-struct SaveClipboardImageArgs {
-    data: String,
+struct SaveAssetDataArgs<'a> {
+    data: &'a str,
+    #[serde(rename = "fileName")]
+    file_name: Option<&'a str>,
 }
 
-/// Save clipboard image data and return the file path
-pub async fn save_clipboard_image(data: &str) -> Result<String, String> {
-    let args = SaveClipboardImageArgs {
-        data: data.to_string(),
-    };
-    
-    let args_js = serde_wasm_bindgen::to_value(&args)
+#[derive(Serialize)]
+struct ImportAssetFileArgs<'a> {
+    path: &'a str,
+}
+
+/// Save pasted file data (base64 data URL) to the asset store
+pub async fn save_asset_data(data: &str, file_name: Option<&str>) -> Result<SavedAsset, String> {
+    let args_js = serde_wasm_bindgen::to_value(&SaveAssetDataArgs { data, file_name })
         .map_err(|e| format!("Failed to serialize args: {}", e))?;
-    
-    let result = invoke("save_clipboard_image", args_js).await;
-    
-    serde_wasm_bindgen::from_value::<String>(result)
+    let result = invoke("save_asset_data", args_js).await;
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Failed to parse result: {}", e))
+}
+
+/// Copy a dropped file into the asset store
+pub async fn import_asset_file(path: &str) -> Result<SavedAsset, String> {
+    let args_js = serde_wasm_bindgen::to_value(&ImportAssetFileArgs { path })
+        .map_err(|e| format!("Failed to serialize args: {}", e))?;
+    let result = invoke("import_asset_file", args_js).await;
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Failed to parse result: {}", e))
+}
+
+/// This device's asset directory (resolves `assets/{hash}.{ext}` references)
+pub async fn get_assets_dir() -> Result<String, String> {
+    let result = invoke("get_assets_dir", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result)
         .map_err(|e| format!("Failed to parse result: {}", e))
 }
 
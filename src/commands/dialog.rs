@@ -5,3 +5,20 @@ pub async fn pick_folder() -> Result<Option<String>, String> {
     let result = invoke("pick_folder", JsValue::NULL).await;
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
+
+#[derive(serde::Serialize)]
+struct PickSaveFileArgs<'a> {
+    #[serde(rename = "fileName")]
+    file_name: &'a str,
+}
+
+pub async fn pick_save_file(file_name: &str) -> Result<Option<String>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&PickSaveFileArgs { file_name }).map_err(|e| e.to_string())?;
+    let result = invoke("pick_save_file", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+pub async fn pick_file() -> Result<Option<String>, String> {
+    let result = invoke("pick_file", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
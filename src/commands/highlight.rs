@@ -0,0 +1,25 @@
+//! Highlight Commands
+//!
+//! Frontend bindings for backend code highlighting (syntect is not in the WASM bundle).
+
+use wasm_bindgen::prelude::*;
+use serde::Serialize;
+use super::invoke;
+
+#[derive(Serialize)]
+struct HighlightCodeArgs<'a> {
+    code: &'a str,
+    lang: &'a str,
+}
+
+pub async fn highlight_code(code: &str, lang: &str) -> Result<String, String> {
+    let js_args = serde_wasm_bindgen::to_value(&HighlightCodeArgs { code, lang }).map_err(|e| e.to_string())?;
+    let result = invoke("highlight_code", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Stylesheet for highlighted code (default light/dark themes)
+pub async fn highlight_css() -> Result<String, String> {
+    let result = invoke("highlight_css", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
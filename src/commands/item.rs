@@ -4,7 +4,7 @@
 
 use wasm_bindgen::prelude::*;
 use serde::Serialize;
-use crate::models::Item;
+use crate::models::{Item, ItemLink};
 use super::invoke;
 
 // ========================
@@ -20,6 +20,14 @@ pub struct CreateItemArgs<'a> {
     pub parent_id: Option<u32>,
     #[serde(rename = "workspaceId")]
     pub workspace_id: Option<u32>,
+    pub url: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct FindByUrlArgs<'a> {
+    url: &'a str,
+    #[serde(rename = "workspaceId")]
+    workspace_id: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -62,6 +70,13 @@ pub async fn create_item(args: &CreateItemArgs<'_>) -> Result<Item, String> {
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+/// The item saved under the same normalised URL, if any
+pub async fn find_item_by_url(url: &str, workspace_id: Option<u32>) -> Result<Option<Item>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&FindByUrlArgs { url, workspace_id }).map_err(|e| e.to_string())?;
+    let result = invoke("find_item_by_url", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 pub async fn toggle_item(id: u32) -> Result<Item, String> {
     let js_args = serde_wasm_bindgen::to_value(&IdArgs { id }).map_err(|e| e.to_string())?;
     let result = invoke("toggle_item", js_args).await;
@@ -214,3 +229,42 @@ pub async fn reset_all_items(workspace_id: u32) -> Result<u32, String> {
     let result = invoke("reset_all_items", js_args).await;
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
+
+// ========================
+// Wiki Links
+// ========================
+
+#[derive(Serialize)]
+struct ItemIdArgs {
+    #[serde(rename = "itemId")]
+    item_id: u32,
+}
+
+#[derive(Serialize)]
+struct ResolveItemLinkArgs<'a> {
+    #[serde(rename = "sourceItemId")]
+    source_item_id: u32,
+    #[serde(rename = "linkText")]
+    link_text: &'a str,
+}
+
+/// Items whose memos link to this item
+pub async fn get_backlinks(item_id: u32) -> Result<Vec<ItemLink>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ItemIdArgs { item_id }).map_err(|e| e.to_string())?;
+    let result = invoke("get_backlinks", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Items linked from this item's memo
+pub async fn get_outgoing_links(item_id: u32) -> Result<Vec<ItemLink>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ItemIdArgs { item_id }).map_err(|e| e.to_string())?;
+    let result = invoke("get_outgoing_links", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Resolve a [[...]] link from a memo to its target item
+pub async fn resolve_item_link(source_item_id: u32, link_text: &str) -> Result<Option<Item>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ResolveItemLinkArgs { source_item_id, link_text }).map_err(|e| e.to_string())?;
+    let result = invoke("resolve_item_link", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
@@ -10,6 +10,8 @@ mod sync;
 mod files;
 mod dialog;
 mod clipboard;
+mod highlight;
+mod archive;
 
 use wasm_bindgen::prelude::*;
 
@@ -48,3 +50,5 @@ pub use sync::*;
 pub use files::*;
 pub use dialog::*;
 pub use clipboard::*;
+pub use highlight::*;
+pub use archive::*;
@@ -6,6 +6,7 @@ use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use js_sys::Promise;
 use wasm_bindgen_futures::JsFuture;
+use crate::models::SyncConflict;
 
 #[wasm_bindgen]
 extern "C" {
@@ -20,8 +21,29 @@ pub struct SyncConfig {
     pub token: String,
 }
 
-/// Configure cloud synchronization
-pub async fn configure_cloud_sync(url: String, token: String) -> Result<(), String> {
+/// One synced table compared before and after moving onto cloud sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableCheck {
+    pub table: String,
+    pub source_rows: u64,
+    pub staged_rows: u64,
+    pub source_hash: String,
+    pub staged_hash: String,
+    pub missing: u64,
+}
+
+/// Outcome of `configure_cloud_sync`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Pre-migration database, kept until confirmed or restored
+    pub snapshot: String,
+    pub tables: Vec<TableCheck>,
+    pub synced: bool,
+    pub sync_error: Option<String>,
+}
+
+/// Configure cloud synchronization (moves local data onto the cloud database)
+pub async fn configure_cloud_sync(url: String, token: String) -> Result<MigrationReport, String> {
     #[derive(Serialize)]
     struct Args {
         url: String,
@@ -39,6 +61,36 @@ pub async fn configure_cloud_sync(url: String, token: String) -> Result<(), Stri
         .map_err(|e| format!("Response error: {}", e))
 }
 
+/// Keep the cloud database and drop the pre-migration snapshot
+pub async fn confirm_cloud_migration() -> Result<(), String> {
+    let promise = invoke("confirm_cloud_migration", JsValue::NULL);
+    let result = JsFuture::from(promise).await
+        .map_err(|e| e.as_string().unwrap_or_else(|| format!("{:?}", e)))?;
+    
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Response error: {}", e))
+}
+
+/// Go back to the database from before cloud sync was configured
+pub async fn restore_cloud_migration() -> Result<(), String> {
+    let promise = invoke("restore_cloud_migration", JsValue::NULL);
+    let result = JsFuture::from(promise).await
+        .map_err(|e| e.as_string().unwrap_or_else(|| format!("{:?}", e)))?;
+    
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Response error: {}", e))
+}
+
+/// Whether a pre-migration snapshot waits for confirmation
+pub async fn has_pending_cloud_migration() -> Result<bool, String> {
+    let promise = invoke("has_pending_cloud_migration", JsValue::NULL);
+    let result = JsFuture::from(promise).await
+        .map_err(|e| e.as_string().unwrap_or_else(|| format!("{:?}", e)))?;
+    
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Response error: {}", e))
+}
+
 /// Get current sync configuration
 pub async fn get_cloud_sync_config() -> Result<Option<SyncConfig>, String> {
     let promise = invoke("get_cloud_sync_config", JsValue::NULL);
@@ -88,11 +140,17 @@ pub async fn is_cloud_sync_enabled() -> bool {
     }
 }
 
-/// Sync status structure
+/// Sync status structure (also the payload of `sync-status` events, which
+/// carry only the background sync fields)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct AppSyncStatus {
     pub last_sync_time: Option<String>,
     pub sync_count: i32,
+    /// Background sync: "idle", "syncing" or "error"
+    pub state: String,
+    /// Why the last background sync failed
+    pub message: Option<String>,
 }
 
 /// Get current sync status
@@ -105,3 +163,33 @@ pub async fn get_sync_status() -> Result<AppSyncStatus, String> {
         .map_err(|e| format!("Response error: {}", e))
 }
 
+/// Rows edited here and on another device between syncs
+pub async fn list_conflicts() -> Result<Vec<SyncConflict>, String> {
+    let promise = invoke("list_conflicts", JsValue::NULL);
+    let result = JsFuture::from(promise).await
+        .map_err(|e| e.as_string().unwrap_or_else(|| format!("{:?}", e)))?;
+    
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Response error: {}", e))
+}
+
+/// Settle a conflict: `resolution` is "keep_local", "keep_remote" or "merged"
+/// (with the merged memo as edited)
+pub async fn resolve_conflict(id: u32, resolution: &str, memo: Option<String>) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct Args<'a> {
+        id: u32,
+        resolution: &'a str,
+        memo: Option<String>,
+    }
+    
+    let args = serde_wasm_bindgen::to_value(&Args { id, resolution, memo })
+        .map_err(|e| format!("Serialization error: {}", e))?;
+    
+    let promise = invoke("resolve_conflict", args);
+    let result = JsFuture::from(promise).await
+        .map_err(|e| e.as_string().unwrap_or_else(|| format!("{:?}", e)))?;
+    
+    serde_wasm_bindgen::from_value(result)
+        .map_err(|e| format!("Response error: {}", e))
+}
@@ -4,7 +4,7 @@
 
 use wasm_bindgen::prelude::*;
 use serde::Serialize;
-use crate::models::Tag;
+use crate::models::{Tag, TagAlias, TagGraphReport, TagStats};
 use super::invoke;
 
 // ========================
@@ -105,6 +105,39 @@ pub async fn update_tag(id: u32, name: Option<&str>, color: Option<&str>) -> Res
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+pub async fn get_tag(id: u32) -> Result<Option<Tag>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&IdArgs { id }).map_err(|e| e.to_string())?;
+    let result = invoke("get_tag", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct UpdateTagMetaArgs<'a> {
+    id: u32,
+    icon: Option<&'a str>,
+    description: Option<&'a str>,
+    pinned: Option<bool>,
+}
+
+/// Update icon/description/pinned. `None` keeps the current value, `Some("")` clears it.
+pub async fn update_tag_meta(id: u32, icon: Option<&str>, description: Option<&str>, pinned: Option<bool>) -> Result<Tag, String> {
+    let js_args = serde_wasm_bindgen::to_value(&UpdateTagMetaArgs { id, icon, description, pinned }).map_err(|e| e.to_string())?;
+    let result = invoke("update_tag", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct UpdateTagMemoArgs<'a> {
+    id: u32,
+    memo: Option<&'a str>,
+}
+
+pub async fn update_tag_memo(id: u32, memo: Option<&str>) -> Result<Tag, String> {
+    let js_args = serde_wasm_bindgen::to_value(&UpdateTagMemoArgs { id, memo }).map_err(|e| e.to_string())?;
+    let result = invoke("update_tag_memo", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 // ========================
 // Item-Tag Relationship Commands
 // ========================
@@ -148,6 +181,41 @@ pub async fn get_tag_parents(tag_id: u32) -> Result<Vec<Tag>, String> {
     serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
 }
 
+#[derive(Serialize)]
+struct CheckTagGraphArgs {
+    repair: bool,
+}
+
+/// Check the tag DAG for cycles/dangling edges (repair = tombstone offending edges)
+pub async fn check_tag_graph(repair: bool) -> Result<TagGraphReport, String> {
+    let js_args = serde_wasm_bindgen::to_value(&CheckTagGraphArgs { repair }).map_err(|e| e.to_string())?;
+    let result = invoke("check_tag_graph", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+pub async fn get_tag_palette() -> Result<Vec<String>, String> {
+    let result = invoke("get_tag_palette", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct TagPaletteArgs<'a> {
+    palette: &'a [String],
+}
+
+/// Set the automatic tag colour palette (empty disables auto colours)
+pub async fn set_tag_palette(palette: &[String]) -> Result<(), String> {
+    let js_args = serde_wasm_bindgen::to_value(&TagPaletteArgs { palette }).map_err(|e| e.to_string())?;
+    let _ = invoke("set_tag_palette", js_args).await;
+    Ok(())
+}
+
+/// Usage statistics for all tags
+pub async fn tag_stats() -> Result<Vec<TagStats>, String> {
+    let result = invoke("tag_stats", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
 pub async fn add_tag_parent(child_tag_id: u32, parent_tag_id: u32) -> Result<(), String> {
     let js_args = serde_wasm_bindgen::to_value(&TagTagArgs { child_tag_id, parent_tag_id }).map_err(|e| e.to_string())?;
     let _ = invoke("add_tag_parent", js_args).await;
@@ -175,3 +243,54 @@ pub async fn move_child_tag(child_tag_id: u32, parent_tag_id: u32, position: i32
     let _ = invoke("move_child_tag", js_args).await;
     Ok(())
 }
+
+// ========================
+// Tag Alias + Merge Commands
+// ========================
+
+#[derive(Serialize)]
+struct TagAliasArgs<'a> {
+    #[serde(rename = "tagId")]
+    tag_id: u32,
+    alias: &'a str,
+}
+
+#[derive(Serialize)]
+struct AliasArgs<'a> {
+    alias: &'a str,
+}
+
+#[derive(Serialize)]
+struct MergeTagsArgs {
+    source: u32,
+    target: u32,
+}
+
+pub async fn list_tag_aliases() -> Result<Vec<TagAlias>, String> {
+    let result = invoke("list_tag_aliases", JsValue::NULL).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+pub async fn get_tag_aliases(tag_id: u32) -> Result<Vec<TagAlias>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&TagIdArgs { tag_id }).map_err(|e| e.to_string())?;
+    let result = invoke("get_tag_aliases", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+pub async fn add_tag_alias(tag_id: u32, alias: &str) -> Result<TagAlias, String> {
+    let js_args = serde_wasm_bindgen::to_value(&TagAliasArgs { tag_id, alias }).map_err(|e| e.to_string())?;
+    let result = invoke("add_tag_alias", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+pub async fn remove_tag_alias(alias: &str) -> Result<(), String> {
+    let js_args = serde_wasm_bindgen::to_value(&AliasArgs { alias }).map_err(|e| e.to_string())?;
+    let _ = invoke("remove_tag_alias", js_args).await;
+    Ok(())
+}
+
+pub async fn merge_tags(source: u32, target: u32) -> Result<Tag, String> {
+    let js_args = serde_wasm_bindgen::to_value(&MergeTagsArgs { source, target }).map_err(|e| e.to_string())?;
+    let result = invoke("merge_tags", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
@@ -4,7 +4,7 @@
 
 use wasm_bindgen::prelude::*;
 use serde::Serialize;
-use crate::models::Workspace;
+use crate::models::{BookmarkExportReport, BookmarkImportReport, BrokenLink, SiteExportReport, Workspace};
 use super::invoke;
 
 // ========================
@@ -130,3 +130,77 @@ pub async fn toggle_workspace_dir_collapsed(id: u32, collapsed: bool) -> Result<
         serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
     }
 }
+
+#[derive(Serialize)]
+struct ExportSiteArgs<'a> {
+    #[serde(rename = "workspaceId")]
+    workspace_id: u32,
+    dir: &'a str,
+}
+
+/// Export a workspace as a static HTML site into `dir`
+pub async fn export_site(workspace_id: u32, dir: &str) -> Result<SiteExportReport, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ExportSiteArgs { workspace_id, dir }).map_err(|e| e.to_string())?;
+    let result = invoke("export_site", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct ExportBookmarksArgs<'a> {
+    #[serde(rename = "workspaceId")]
+    workspace_id: u32,
+    #[serde(rename = "tagFilter")]
+    tag_filter: Option<u32>,
+    format: &'a str,
+    path: &'a str,
+}
+
+/// Export the bookmarks of a workspace to `path`; `format` is "html", "opml" or "csv"
+pub async fn export_bookmarks(workspace_id: u32, tag_filter: Option<u32>, format: &str, path: &str) -> Result<BookmarkExportReport, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ExportBookmarksArgs { workspace_id, tag_filter, format, path }).map_err(|e| e.to_string())?;
+    let result = invoke("export_bookmarks", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct ImportBookmarksArgs<'a> {
+    path: &'a str,
+    folders: &'a str,
+}
+
+/// Import browser bookmarks into the web-bookmarks workspace; `folders` is
+/// "items" (nested items) or "tags"
+pub async fn import_bookmarks(path: &str, folders: &str) -> Result<BookmarkImportReport, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ImportBookmarksArgs { path, folders }).map_err(|e| e.to_string())?;
+    let result = invoke("import_bookmarks", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct StartLinkCheckArgs {
+    #[serde(rename = "workspaceId")]
+    workspace_id: Option<u32>,
+    #[serde(rename = "recheckAfterDays")]
+    recheck_after_days: Option<u32>,
+}
+
+/// Start checking bookmark links in the background; progress arrives as
+/// `link-check-progress` events
+pub async fn start_link_check(workspace_id: Option<u32>, recheck_after_days: Option<u32>) -> Result<(), String> {
+    let js_args = serde_wasm_bindgen::to_value(&StartLinkCheckArgs { workspace_id, recheck_after_days }).map_err(|e| e.to_string())?;
+    let _ = invoke("start_link_check", js_args).await;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ListBrokenLinksArgs {
+    #[serde(rename = "workspaceId")]
+    workspace_id: Option<u32>,
+}
+
+/// Items whose last link check failed
+pub async fn list_broken_links(workspace_id: Option<u32>) -> Result<Vec<BrokenLink>, String> {
+    let js_args = serde_wasm_bindgen::to_value(&ListBrokenLinksArgs { workspace_id }).map_err(|e| e.to_string())?;
+    let result = invoke("list_broken_links", js_args).await;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
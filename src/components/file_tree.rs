@@ -302,7 +302,7 @@ fn FileTreeRow(
                                                     view! {
                                                         <span 
                                                             class="file-tag-chip"
-                                                            style=format!("background-color: {}", tag.color.unwrap_or_else(|| "#eee".into()))
+                                                            style=format!("background-color: {}", tag.display_color().unwrap_or_else(|| "#eee".into()))
                                                         >
                                                             {tag.name}
                                                         </span>
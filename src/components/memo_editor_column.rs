@@ -8,9 +8,12 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{ClipboardEvent, DataTransferItem, DataTransferItemList, File, FileReader};
 
-use crate::commands;
+use crate::commands::{self, CreateItemArgs};
 use crate::components::EditTarget;
-use crate::markdown::{parse_markdown, trigger_math_render};
+use crate::models::{ArchiveView, ItemLink};
+use crate::context::AppContext;
+use crate::store::{use_app_store, store_reload_tags, AppStateStoreFields};
+use crate::markdown::{memo_tasks, parse_markdown, remove_task_lines, sanitize_html, toggle_task, trigger_code_highlight, trigger_diagram_render, trigger_math_render};
 
 /// Memo editor column with side-by-side edit and preview
 #[component]
@@ -18,28 +21,57 @@ pub fn MemoEditorColumn(
     editing_target: ReadSignal<Option<EditTarget>>,
     set_editing_target: WriteSignal<Option<EditTarget>>,
 ) -> impl IntoView {
+    let store = use_app_store();
+    let current_workspace = use_context::<AppContext>().expect("AppContext should be provided").current_workspace;
     let (memo_content, set_memo_content) = signal(String::new());
-    let (last_target_id, set_last_target_id) = signal::<Option<u32>>(None);
+    // (is_tag, id) - item and tag ids live in different tables and may collide
+    let (last_target_id, set_last_target_id) = signal::<Option<(bool, u32)>>(None);
+    // Items linking to the current item via [[...]]
+    let (backlinks, set_backlinks) = signal(Vec::<ItemLink>::new());
+    // Bookmark URL of the current item and its offline archive
+    let (item_url, set_item_url) = signal::<Option<String>>(None);
+    let (archive, set_archive) = signal::<Option<ArchiveView>>(None);
+    let (show_archive, set_show_archive) = signal(false);
+    let (is_archiving, set_is_archiving) = signal(false);
     
     // Load memo when target changes
     Effect::new(move |_| {
         if let Some(target) = editing_target.get() {
             let current_id = match &target {
-                EditTarget::Item(id, _) => Some(*id),
+                EditTarget::Item(id, _) => Some((false, *id)),
+                EditTarget::Tag(id, _) => Some((true, *id)),
                 _ => None,
             };
             
             // Only reload if target changed
             if current_id != last_target_id.get() {
                 set_last_target_id.set(current_id);
+                set_item_url.set(None);
+                set_archive.set(None);
+                set_show_archive.set(false);
                 
-                if let EditTarget::Item(id, _) = &target {
-                    let id = *id;
-                    spawn_local(async move {
-                        if let Ok(Some(item)) = commands::get_item(id).await {
-                            set_memo_content.set(item.memo.unwrap_or_default());
-                        }
-                    });
+                match &target {
+                    EditTarget::Item(id, _) => {
+                        let id = *id;
+                        spawn_local(async move {
+                            if let Ok(Some(item)) = commands::get_item(id).await {
+                                set_memo_content.set(item.memo.unwrap_or_default());
+                                set_item_url.set(item.url);
+                            }
+                            set_backlinks.set(commands::get_backlinks(id).await.unwrap_or_default());
+                            set_archive.set(commands::get_archive(id).await.ok().flatten());
+                        });
+                    }
+                    EditTarget::Tag(id, _) => {
+                        let id = *id;
+                        set_backlinks.set(Vec::new());
+                        spawn_local(async move {
+                            if let Ok(Some(tag)) = commands::get_tag(id).await {
+                                set_memo_content.set(tag.memo.unwrap_or_default());
+                            }
+                        });
+                    }
+                    EditTarget::MultiItems(_) => {}
                 }
             }
         } else {
@@ -50,27 +82,35 @@ pub fn MemoEditorColumn(
     // Save memo on blur
     let save_memo = move || {
         if let Some(target) = editing_target.get() {
-            if let EditTarget::Item(id, _) = target {
-                let content = memo_content.get();
-                let memo = if content.is_empty() { None } else { Some(content) };
-                spawn_local(async move {
-                    let _ = commands::update_item_memo(id, memo.as_deref()).await;
-                });
+            let content = memo_content.get();
+            let memo = if content.is_empty() { None } else { Some(content) };
+            match target {
+                EditTarget::Item(id, _) => {
+                    spawn_local(async move {
+                        if commands::update_item_memo(id, memo.as_deref()).await.is_ok() {
+                            // #tag tokens may have added or removed tags
+                            store_reload_tags(store).await;
+                        }
+                    });
+                }
+                EditTarget::Tag(id, _) => {
+                    spawn_local(async move {
+                        let _ = commands::update_tag_memo(id, memo.as_deref()).await;
+                    });
+                }
+                EditTarget::MultiItems(_) => {}
             }
         }
     };
     
-    // Handle paste event for images
+    // Handle paste event for files (images, PDFs, audio, video): store as asset, insert a reference
     let handle_paste = move |ev: ClipboardEvent| {
         if let Some(data_transfer) = ev.clipboard_data() {
             let items = data_transfer.items();
             
             for i in 0..items.length() {
                 if let Some(item) = items.get(i) {
-                    let item_type = item.type_();
-                    
-                    // Check if it's an image
-                    if item_type.starts_with("image/") {
+                    if item.kind() == "file" {
                         ev.prevent_default();
                         
                         // Get the textarea element for later use
@@ -79,6 +119,8 @@ pub fn MemoEditorColumn(
                         
                         if let Ok(Some(file)) = item.get_as_file() {
                             let set_memo = set_memo_content.clone();
+                            // Clipboard screenshots come as "image.png"; keep real names only
+                            let file_name = Some(file.name()).filter(|n| !n.is_empty() && n != "image.png");
                             
                             // Read file as base64
                             let reader = FileReader::new().unwrap();
@@ -88,43 +130,18 @@ pub fn MemoEditorColumn(
                                 if let Ok(result) = reader_clone.result() {
                                     if let Some(data_url) = result.as_string() {
                                         let textarea_for_async = textarea_opt.clone();
-                                        // Call backend to save image
+                                        // Call backend to save the asset
                                         spawn_local(async move {
-                                            match commands::save_clipboard_image(&data_url).await {
-                                                Ok(path) => {
-                                                    let img_markdown = format!("![]({})", path);
-                                                    
-                                                    // Use execCommand to insert text at cursor with native undo support
+                                            match commands::save_asset_data(&data_url, file_name.as_deref()).await {
+                                                Ok(asset) => {
                                                     if let Some(textarea) = textarea_for_async {
-                                                        // Focus the textarea
-                                                        let _ = textarea.focus();
-                                                        
-                                                        // Call document.execCommand via js_sys
-                                                        if let Some(window) = web_sys::window() {
-                                                            if let Some(document) = window.document() {
-                                                                // Use js_sys to call execCommand dynamically
-                                                                let exec_command = js_sys::Reflect::get(
-                                                                    &document,
-                                                                    &wasm_bindgen::JsValue::from_str("execCommand")
-                                                                );
-                                                                if let Ok(func) = exec_command {
-                                                                    if let Some(func) = func.dyn_ref::<js_sys::Function>() {
-                                                                        let _ = func.call3(
-                                                                            &document,
-                                                                            &wasm_bindgen::JsValue::from_str("insertText"),
-                                                                            &wasm_bindgen::JsValue::FALSE,
-                                                                            &wasm_bindgen::JsValue::from_str(&img_markdown)
-                                                                        );
-                                                                    }
-                                                                }
-                                                                // Update our signal to match textarea value
-                                                                set_memo.set(textarea.value());
-                                                            }
-                                                        }
+                                                        insert_at_cursor(&textarea, &asset.markdown());
+                                                        // Update our signal to match textarea value
+                                                        set_memo.set(textarea.value());
                                                     }
                                                 }
                                                 Err(e) => {
-                                                    web_sys::console::error_1(&format!("Failed to save image: {}", e).into());
+                                                    web_sys::console::error_1(&format!("Failed to save asset: {}", e).into());
                                                 }
                                             }
                                         });
@@ -144,6 +161,35 @@ pub fn MemoEditorColumn(
         }
     };
     
+    // Files dropped onto the memo textarea (delivered by Tauri as paths) are imported as assets
+    spawn_local(async move {
+        let _ = commands::listen_safe("tauri://drag-drop", move |event| {
+            let Ok(event) = serde_wasm_bindgen::from_value::<DragDropEvent>(event) else { return; };
+            // The listener outlives the component; ignore drops once it is gone
+            if !matches!(editing_target.try_get_untracked().flatten(), Some(EditTarget::Item(..) | EditTarget::Tag(..))) {
+                return;
+            }
+            let Some(textarea) = textarea_at(event.payload.position.x, event.payload.position.y) else { return; };
+            
+            spawn_local(async move {
+                let mut inserted = false;
+                for path in &event.payload.paths {
+                    match commands::import_asset_file(path).await {
+                        Ok(asset) => {
+                            insert_at_cursor(&textarea, &format!("{}\n", asset.markdown()));
+                            inserted = true;
+                        }
+                        Err(e) => web_sys::console::error_1(&format!("Failed to import {}: {}", path, e).into()),
+                    }
+                }
+                if inserted && set_memo_content.try_set(textarea.value()).is_none() {
+                    // Dropping doesn't focus the textarea, so there is no blur to save on
+                    save_memo();
+                }
+            });
+        }).await;
+    });
+    
     // Get title
     let title = move || {
         match editing_target.get() {
@@ -154,13 +200,120 @@ pub fn MemoEditorColumn(
         }
     };
     
+    // Follow [[...]] links in the preview: resolve against the current item and open the target.
+    // Task checkboxes toggle their line in the memo source and save it.
+    let handle_preview_click = move |ev: web_sys::MouseEvent| {
+        let Some(el) = ev.target().and_then(|t| t.dyn_into::<web_sys::Element>().ok()) else { return; };
+        if let Some(index) = el.get_attribute("data-task-index") {
+            let index = index.parse::<usize>().ok();
+            if let Some(updated) = index.and_then(|i| toggle_task(&memo_content.get_untracked(), i)) {
+                set_memo_content.set(updated);
+                save_memo();
+            }
+            return;
+        }
+        let Ok(Some(link)) = el.closest("a.wiki-link") else { return; };
+        ev.prevent_default();
+        let Some(link_text) = link.get_attribute("data-wiki-link") else { return; };
+        if let Some(EditTarget::Item(source_id, _)) = editing_target.get_untracked() {
+            spawn_local(async move {
+                match commands::resolve_item_link(source_id, &link_text).await {
+                    Ok(Some(item)) => set_editing_target.set(Some(EditTarget::Item(item.id, item.text))),
+                    _ => web_sys::console::warn_1(&format!("Unresolved link: [[{}]]", link_text).into()),
+                }
+            });
+        }
+    };
+    
     // Rendered HTML for preview
     let rendered_html = move || {
         let html = parse_markdown(&memo_content.get());
         trigger_math_render("#memo-preview"); // Scope to this element
+        trigger_diagram_render("#memo-preview");
+        trigger_code_highlight("#memo-preview");
         html
     };
     
+    // Promote the memo's checklist to child items of the edited item (checked tasks stay completed)
+    let promote_tasks = move |_| {
+        let Some(EditTarget::Item(item_id, _)) = editing_target.get_untracked() else { return; };
+        let source = memo_content.get_untracked();
+        let tasks = memo_tasks(&source);
+        if tasks.is_empty() {
+            return;
+        }
+        let workspace = current_workspace.get_untracked();
+        spawn_local(async move {
+            let mut promoted = Vec::new();
+            for task in tasks.into_iter().filter(|t| !t.text.is_empty()) {
+                let args = CreateItemArgs {
+                    text: &task.text,
+                    item_type: None,
+                    parent_id: Some(item_id),
+                    workspace_id: Some(workspace),
+                    url: None,
+                };
+                match commands::create_item(&args).await {
+                    Ok(child) => {
+                        if task.checked {
+                            let _ = commands::toggle_item(child.id).await;
+                        }
+                        promoted.push(task);
+                    }
+                    Err(e) => {
+                        web_sys::console::error_1(&format!("Failed to promote task: {}", e).into());
+                        break;
+                    }
+                }
+            }
+            if promoted.is_empty() {
+                return;
+            }
+            
+            let remaining = remove_task_lines(&source, &promoted);
+            set_memo_content.set(remaining.clone());
+            let memo = if remaining.trim().is_empty() { None } else { Some(remaining) };
+            let _ = commands::update_item_memo(item_id, memo.as_deref()).await;
+            
+            if let Ok(loaded) = commands::list_items_by_workspace(workspace).await {
+                *store.items().write() = loaded;
+            }
+            store_reload_tags(store).await;
+        });
+    };
+    let has_tasks = move || {
+        matches!(editing_target.get(), Some(EditTarget::Item(..))) && !memo_tasks(&memo_content.get()).is_empty()
+    };
+    
+    // (Re-)archive the bookmarked page for offline reading, then show the archive
+    let archive_page = move |_| {
+        let Some(EditTarget::Item(item_id, _)) = editing_target.get_untracked() else { return; };
+        set_is_archiving.set(true);
+        spawn_local(async move {
+            match commands::archive_url(item_id, None).await {
+                Ok(()) => {
+                    set_archive.set(commands::get_archive(item_id).await.ok().flatten());
+                    set_show_archive.set(true);
+                }
+                Err(e) => web_sys::console::error_1(&format!("Failed to archive page: {}", e).into()),
+            }
+            set_is_archiving.set(false);
+        });
+    };
+    let delete_archive = move |_| {
+        let Some(EditTarget::Item(item_id, _)) = editing_target.get_untracked() else { return; };
+        spawn_local(async move {
+            if commands::delete_archive(item_id).await.is_ok() {
+                set_archive.set(None);
+                set_show_archive.set(false);
+            }
+        });
+    };
+    // Archived page with its images resolved to the local asset store
+    let archive_html = move || {
+        archive.get().and_then(|a| a.html).map(|html| sanitize_html(&html, true))
+    };
+    
     // Vim mode indicator
     let (vim_mode, set_vim_mode) = signal(String::from("insert"));
     
@@ -201,6 +354,36 @@ pub fn MemoEditorColumn(
 
                 <div class="memo-editor-header">
                     <span class="memo-editor-title">{title}</span>
+                    <Show when=has_tasks>
+                        <button
+                            class="promote-tasks-btn"
+                            title="将清单中的任务转为子项"
+                            on:click=promote_tasks
+                        >
+                            "转为子项"
+                        </button>
+                    </Show>
+                    <Show when=move || item_url.get().is_some()>
+                        <button
+                            class="archive-btn"
+                            title="保存网页的阅读版本以便离线查看"
+                            disabled=move || is_archiving.get()
+                            on:click=archive_page
+                        >
+                            {move || if is_archiving.get() {
+                                "存档中..."
+                            } else if archive.get().is_some() {
+                                "重新存档"
+                            } else {
+                                "存档网页"
+                            }}
+                        </button>
+                    </Show>
+                    <Show when=move || archive.get().is_some()>
+                        <button class="archive-btn" on:click=move |_| set_show_archive.update(|v| *v = !*v)>
+                            {move || if show_archive.get() { "返回预览" } else { "阅读存档" }}
+                        </button>
+                    </Show>
                     <button 
                         class="cleanup-btn" 
                         title="Clean unused local images"
@@ -243,11 +426,106 @@ pub fn MemoEditorColumn(
                     
                     // Right: Preview area
                     <div class="memo-preview-pane">
-                        <div class="pane-header">"预览"</div>
-                        <div id="memo-preview" class="memo-preview-content" inner_html=rendered_html></div>
+                        <Show
+                            when=move || show_archive.get() && archive.get().is_some()
+                            fallback=move || view! {
+                                <div class="pane-header">"预览"</div>
+                                <div id="memo-preview" class="memo-preview-content" inner_html=rendered_html on:click=handle_preview_click></div>
+                            }
+                        >
+                            <div class="pane-header archive-header">
+                                <span>"存档: "</span>
+                                <span class="archive-source" title=move || archive.get().map(|a| a.url).unwrap_or_default()>
+                                    {move || archive.get().map(|a| a.title).unwrap_or_default()}
+                                </span>
+                                <button class="archive-delete-btn" title="删除存档" on:click=delete_archive>"删除"</button>
+                            </div>
+                            {move || match archive_html() {
+                                Some(html) => view! {
+                                    <div class="memo-preview-content archive-view" inner_html=html></div>
+                                }.into_any(),
+                                None => view! {
+                                    <div class="memo-preview-content archive-view archive-missing">"存档文件尚未同步到本设备"</div>
+                                }.into_any(),
+                            }}
+                        </Show>
+                        <Show when=move || !backlinks.get().is_empty()>
+                            <div class="memo-backlinks">
+                                <div class="pane-header">"反向链接"</div>
+                                <For
+                                    each=move || backlinks.get()
+                                    key=|l| (l.source_item_id, l.link_text.clone())
+                                    children=move |l| {
+                                        let source_id = l.source_item_id;
+                                        let source_text = l.source_text.clone();
+                                        view! {
+                                            <a
+                                                class="wiki-link memo-backlink"
+                                                href="#"
+                                                on:click=move |ev| {
+                                                    ev.prevent_default();
+                                                    set_editing_target.set(Some(EditTarget::Item(source_id, source_text.clone())));
+                                                }
+                                            >
+                                                {l.source_text}
+                                            </a>
+                                        }
+                                    }
+                                />
+                            </div>
+                        </Show>
                     </div>
                 </div>
             </div>
         </Show>
     }
 }
+
+/// `tauri://drag-drop` event (position in physical pixels)
+#[derive(serde::Deserialize)]
+struct DragDropEvent {
+    payload: DragDropPayload,
+}
+
+#[derive(serde::Deserialize)]
+struct DragDropPayload {
+    paths: Vec<String>,
+    position: DropPosition,
+}
+
+#[derive(serde::Deserialize)]
+struct DropPosition {
+    x: f64,
+    y: f64,
+}
+
+/// The memo textarea, if the drop position lies on it
+fn textarea_at(x: f64, y: f64) -> Option<web_sys::HtmlTextAreaElement> {
+    let window = web_sys::window()?;
+    let scale = window.device_pixel_ratio();
+    let (x, y) = (x / scale, y / scale);
+    let textarea = window.document()?
+        .query_selector(".memo-textarea").ok()??
+        .dyn_into::<web_sys::HtmlTextAreaElement>().ok()?;
+    let rect = textarea.get_bounding_client_rect();
+    (x >= rect.left() && x <= rect.right() && y >= rect.top() && y <= rect.bottom()).then_some(textarea)
+}
+
+/// Insert text at the cursor via execCommand, so it joins the native undo stack
+fn insert_at_cursor(textarea: &web_sys::HtmlTextAreaElement, text: &str) {
+    let _ = textarea.focus();
+    
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else { return; };
+    // execCommand is deprecated in web-sys, call it dynamically
+    let exec_command = js_sys::Reflect::get(&document, &wasm_bindgen::JsValue::from_str("execCommand"));
+    if let Ok(func) = exec_command {
+        if let Some(func) = func.dyn_ref::<js_sys::Function>() {
+            let _ = func.call3(
+                &document,
+                &wasm_bindgen::JsValue::from_str("insertText"),
+                &wasm_bindgen::JsValue::FALSE,
+                &wasm_bindgen::JsValue::from_str(text),
+            );
+        }
+    }
+}
@@ -14,6 +14,7 @@ mod memo_editor_column;
 mod title_bar;
 mod delete_confirm_button;
 mod editor_target;
+mod sync_conflicts;
 
 pub use tree_item::TreeItem;
 pub use new_item_form::NewItemForm;
@@ -27,6 +28,7 @@ pub use memo_editor_column::MemoEditorColumn;
 pub use title_bar::TitleBar;
 pub use delete_confirm_button::DeleteConfirmButton;
 pub use editor_target::EditTarget;
+pub use sync_conflicts::SyncConflicts;
 
 // Import shared sync modal from frontend crate
 pub use tauri_sync_db_frontend::desktop::SyncModal;
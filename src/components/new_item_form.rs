@@ -9,7 +9,7 @@ use wasm_bindgen::JsCast;
 use crate::commands::{self, CreateItemArgs};
 use crate::context::AppContext;
 use crate::components::type_selector::ITEM_TYPES;
-use crate::store::{use_app_store, AppStateStoreFields};
+use crate::store::{use_app_store, store_reload_tags, AppStateStoreFields};
 
 /// Form for creating new items (root or child)
 #[component]
@@ -19,6 +19,7 @@ pub fn NewItemForm() -> impl IntoView {
     
     let (new_text, set_new_text) = signal(String::new());
     let (item_type, set_item_type) = signal(String::from("daily"));
+    let (notice, set_notice) = signal(None::<String>);
 
     let create_item = move |ev: web_sys::SubmitEvent| {
         ev.prevent_default();
@@ -27,13 +28,27 @@ pub fn NewItemForm() -> impl IntoView {
         let parent = ctx.adding_under.get();
         let workspace = ctx.current_workspace.get();
         let selected_type = item_type.get();
+        set_notice.set(None);
         
         spawn_local(async move {
+            // Bookmarks take the URL as their text; skip ones already saved here
+            let url = (selected_type == "bookmark").then(|| text.trim().to_string());
+            if let Some(url) = &url {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    set_notice.set(Some("书签需要以 http:// 或 https:// 开头的链接".to_string()));
+                    return;
+                }
+                if let Ok(Some(existing)) = commands::find_item_by_url(url, Some(workspace)).await {
+                    set_notice.set(Some(format!("已保存过: {}", existing.text)));
+                    return;
+                }
+            }
             let args = CreateItemArgs {
                 text: &text,
                 item_type: Some(&selected_type),
                 parent_id: parent,
                 workspace_id: Some(workspace),
+                url: url.as_deref(),
             };
             if commands::create_item(&args).await.is_ok() {
                 set_new_text.set(String::new());
@@ -42,6 +57,9 @@ pub fn NewItemForm() -> impl IntoView {
                 if let Ok(loaded) = commands::list_items_by_workspace(workspace).await {
                     *store.items().write() = loaded;
                 }
+                if text.contains('#') {
+                    store_reload_tags(store).await;
+                }
             }
         });
     };
@@ -85,6 +103,10 @@ pub fn NewItemForm() -> impl IntoView {
                 }).collect_view()}
             </div>
             
+            {move || notice.get().map(|text| view! {
+                <div class="new-item-notice">{text}</div>
+            })}
+            
             {move || ctx.adding_under.get().map(|pid| view! {
                 <button type="button" class="cancel-btn" on:click=move |_| ctx.set_adding_under(None)>
                     "Cancel (#" {pid} ")"
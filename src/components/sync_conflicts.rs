@@ -0,0 +1,116 @@
+//! Sync Conflicts Component
+//!
+//! Badge shown while rows edited on two devices wait for a decision, and a
+//! panel to keep one version or merge their memos.
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use crate::commands;
+use crate::context::AppContext;
+use crate::models::SyncConflict;
+
+/// Conflict badge and resolution panel
+#[component]
+pub fn SyncConflicts() -> impl IntoView {
+    let ctx = use_context::<AppContext>().expect("AppContext should be provided");
+    let (conflicts, set_conflicts) = signal(Vec::<SyncConflict>::new());
+    let (show_panel, set_show_panel) = signal(false);
+
+    let load = move || {
+        spawn_local(async move {
+            if let Ok(loaded) = commands::list_conflicts().await {
+                let _ = set_conflicts.try_set(loaded);
+            }
+        });
+    };
+    // Reload after every sync (syncs bump the reload trigger)
+    Effect::new(move |_| {
+        let _ = ctx.reload_trigger.get();
+        load();
+    });
+    spawn_local(async move {
+        let _ = commands::listen_safe("sync-conflicts-found", move |_| load()).await;
+    });
+
+    let resolve = move |id: u32, resolution: &'static str, memo: Option<String>| {
+        spawn_local(async move {
+            match commands::resolve_conflict(id, resolution, memo).await {
+                Ok(()) => {
+                    set_conflicts.update(|list| list.retain(|c| c.id != id));
+                    if conflicts.get_untracked().is_empty() {
+                        set_show_panel.set(false);
+                    }
+                    ctx.reload();
+                }
+                Err(e) => {
+                    if let Some(window) = web_sys::window() {
+                        let _ = window.alert_with_message(&format!("解决冲突失败: {}", e));
+                    }
+                }
+            }
+        });
+    };
+
+    view! {
+        <Show when=move || !conflicts.get().is_empty()>
+            <button class="sync-conflict-badge" title="同步冲突" on:click=move |_| set_show_panel.update(|v| *v = !*v)>
+                {move || format!("⚠ {} 个同步冲突", conflicts.get().len())}
+            </button>
+        </Show>
+        <Show when=move || show_panel.get()>
+            <div class="sync-conflict-panel">
+                <div class="sync-conflict-panel-header">
+                    <span>"同步冲突"</span>
+                    <button class="sync-conflict-close" on:click=move |_| set_show_panel.set(false)>"×"</button>
+                </div>
+                <For
+                    each=move || conflicts.get()
+                    key=|c| c.id
+                    children=move |conflict| {
+                        let id = conflict.id;
+                        let kept = if conflict.kept == "local" { "当前为本机版本" } else { "当前为远端版本" };
+                        let merged = conflict.merged_memo.clone();
+                        let (memo, set_memo) = signal(merged.as_ref().map(|m| m.text.clone()).unwrap_or_default());
+                        let unresolved = merged.as_ref().map(|m| m.conflicts).unwrap_or(0);
+                        let has_merge = merged.is_some();
+                        view! {
+                            <div class="sync-conflict">
+                                <div class="sync-conflict-title">
+                                    <strong>{conflict.title()}</strong>
+                                    <span class="sync-conflict-kept">{kept}</span>
+                                </div>
+                                <div class="sync-conflict-versions">
+                                    <div>
+                                        <div class="sync-conflict-label">"本机"</div>
+                                        <pre>{conflict.memo(true)}</pre>
+                                    </div>
+                                    <div>
+                                        <div class="sync-conflict-label">"远端"</div>
+                                        <pre>{conflict.memo(false)}</pre>
+                                    </div>
+                                </div>
+                                <Show when=move || has_merge>
+                                    <div class="sync-conflict-label">
+                                        {if unresolved > 0 { format!("合并结果 ({} 处需手动处理)", unresolved) } else { "合并结果".to_string() }}
+                                    </div>
+                                    <textarea
+                                        class="sync-conflict-merged"
+                                        prop:value=move || memo.get()
+                                        on:input=move |ev| set_memo.set(event_target_value(&ev))
+                                    />
+                                </Show>
+                                <div class="sync-conflict-actions">
+                                    <button on:click=move |_| resolve(id, "keep_local", None)>"保留本机"</button>
+                                    <button on:click=move |_| resolve(id, "keep_remote", None)>"保留远端"</button>
+                                    <Show when=move || has_merge>
+                                        <button on:click=move |_| resolve(id, "merged", Some(memo.get_untracked()))>"使用合并"</button>
+                                    </Show>
+                                </div>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+        </Show>
+    }
+}
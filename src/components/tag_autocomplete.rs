@@ -2,11 +2,12 @@
 //!
 //! Reusable tag input with fuzzy search and autocomplete suggestions.
 //! Supports semicolon-separated batch input for pasting multiple tags.
+//! Tag aliases also match, suggesting the canonical tag.
 
 use leptos::prelude::*;
 use wasm_bindgen::JsCast;
 
-use crate::models::Tag;
+use crate::models::{Tag, TagAlias};
 
 /// Simple fuzzy match: check if query chars appear in order in the target
 pub fn fuzzy_match(query: &str, target: &str) -> bool {
@@ -45,10 +46,12 @@ fn replace_current_segment(input: &str, new_segment: &str) -> String {
 /// Props:
 /// - all_tags: Signal containing all available tags for autocomplete
 /// - on_select: Callback when a tag name is submitted (called for EACH tag)
+/// - aliases: Optional tag aliases; a matching alias suggests its canonical tag
 #[component]
 pub fn TagAutocomplete(
     all_tags: ReadSignal<Vec<Tag>>,
     #[prop(into)] on_select: Callback<String>,
+    #[prop(optional)] aliases: Option<ReadSignal<Vec<TagAlias>>>,
 ) -> impl IntoView {
     let (input_value, set_input_value) = signal(String::new());
     let (selected_idx, set_selected_idx) = signal(0usize);
@@ -62,9 +65,13 @@ pub fn TagAutocomplete(
             return vec![];
         }
         
+        let alias_list = aliases.map(|a| a.get()).unwrap_or_default();
         all_tags.get()
             .into_iter()
-            .filter(|tag| fuzzy_match(current_segment, &tag.name))
+            .filter(|tag| {
+                fuzzy_match(current_segment, &tag.name)
+                    || alias_list.iter().any(|a| a.tag_id == tag.id && fuzzy_match(current_segment, &a.alias))
+            })
             .take(5)
             .collect::<Vec<_>>()
     };
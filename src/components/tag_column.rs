@@ -6,7 +6,9 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 use wasm_bindgen::JsCast;
 
-use crate::models::Tag;
+use std::collections::HashMap;
+
+use crate::models::{Tag, TagStats};
 use crate::commands::{self, CreateTagArgs};
 use crate::context::AppContext;
 use crate::components::DeleteConfirmButton;
@@ -130,11 +132,13 @@ fn TagTreeNode(
     editing_target: ReadSignal<Option<EditTarget>>,
     set_editing_target: WriteSignal<Option<EditTarget>>,
     set_memo_editing_target: WriteSignal<Option<EditTarget>>,
+    /// Usage stats by tag id (empty unless sorting by usage)
+    stats: ReadSignal<HashMap<u32, TagStats>>,
 ) -> impl IntoView {
     let id = tag.id;
     let position = tag.position;
     let name = tag.name.clone();
-    let color = tag.color.clone().unwrap_or_else(|| "#666".to_string());
+    let color = tag.display_color().unwrap_or_else(|| "#666".to_string());
     let indent = depth * 16;
     
     let ctx = use_context::<AppContext>().expect("AppContext should be provided");
@@ -211,21 +215,20 @@ fn TagTreeNode(
         }
         set_last_click_time.set(now);
         
-        // Close memo editor (Tags don't have memos)
-        set_memo_editing_target.set(None);
-        
-        // Toggle properties editor
+        // Toggle properties editor + memo editor
         let current = editing_target.get();
         let is_editing_this = matches!(&current, Some(EditTarget::Tag(tid, _)) if *tid == id);
         if is_editing_this {
             set_editing_target.set(None);
+            set_memo_editing_target.set(None);
             spawn_local(async {
                 let _ = commands::shrink_window(800, 700).await;
             });
         } else {
             set_editing_target.set(Some(EditTarget::Tag(id, name_for_menu.clone())));
+            set_memo_editing_target.set(Some(EditTarget::Tag(id, name_for_menu.clone())));
             spawn_local(async {
-                let _ = commands::resize_window(1100, 700).await;
+                let _ = commands::resize_window(1800, 700).await;
             });
         }
     };
@@ -235,8 +238,10 @@ fn TagTreeNode(
         if is_selected() { c.push_str(" selected"); }
         if is_dragging() { c.push_str(" dragging"); }
         if is_drop_target() { c.push_str(" drop-target"); }
+        if stats.get().get(&id).map_or(false, |s| s.unused) { c.push_str(" unused"); }
         c
     };
+    let usage_count = move || stats.get().get(&id).map(|s| s.total_count);
 
     view! {
         <div class="tag-tree-item">
@@ -266,7 +271,10 @@ fn TagTreeNode(
                 }}
                 
                 <span class="tag-color-dot" style=format!("background-color: {};", color)></span>
-                <span class="tag-tree-name">{format!("[{}] {}", position, tag.name)}</span>
+                {tag.icon.clone().map(|icon| view! { <span class="tag-icon">{icon}</span> })}
+                <span class="tag-tree-name" title=tag.description.clone().unwrap_or_default()>{format!("[{}] {}", position, tag.name)}</span>
+                {tag.pinned.then(|| view! { <span class="tag-pinned" title="已置顶">"📌"</span> })}
+                {move || usage_count().map(|count| view! { <span class="tag-usage-count" title="使用次数 (含子标签)">{count}</span> })}
                 
                 // Delete button with confirmation
                 <DeleteConfirmButton
@@ -288,7 +296,7 @@ fn TagTreeNode(
                             each=move || children.get()
                             key=|child| {
                                 // Include position and name in key so component re-renders when they change
-                                (child.id, child.position, child.name.clone())
+                                (child.id, child.position, child.name.clone(), child.icon.clone(), child.pinned, child.effective_color.clone())
                             }
                             children=move |child| {
                                 let child_pos = child.position;
@@ -305,6 +313,7 @@ fn TagTreeNode(
                                         editing_target=editing_target
                                         set_editing_target=set_editing_target
                                         set_memo_editing_target=set_memo_editing_target
+                                        stats=stats
                                     />
                                 }
                             }
@@ -330,6 +339,32 @@ pub fn TagColumn(
     let ctx = use_context::<AppContext>().expect("AppContext should be provided");
     let store = use_app_store();
     
+    // Sort root tags by usage (tag_stats), reloaded when tag relations change
+    let (sort_by_usage, set_sort_by_usage) = signal(false);
+    let (stats, set_stats) = signal(HashMap::<u32, TagStats>::new());
+    Effect::new(move |_| {
+        let _ = store.tags_relation_version().get();
+        let _ = ctx.reload_trigger.get();
+        if !sort_by_usage.get() {
+            set_stats.set(HashMap::new());
+            return;
+        }
+        spawn_local(async move {
+            if let Ok(list) = commands::tag_stats().await {
+                set_stats.set(list.into_iter().map(|s| (s.tag_id, s)).collect());
+            }
+        });
+    });
+    let sorted_root_tags = move || {
+        let mut tags = store.root_tags().get();
+        if sort_by_usage.get() {
+            let stats = stats.get();
+            let usage = |t: &Tag| stats.get(&t.id).map_or(0, |s| s.total_count);
+            tags.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(usage(b).cmp(&usage(a))));
+        }
+        tags
+    };
+    
     // Expect DnD context to be provided by parent (App)
     let tag_dnd = use_context::<TagDndContext>().expect("TagDndContext should be provided");
     let dnd = tag_dnd.dnd;
@@ -403,16 +438,25 @@ pub fn TagColumn(
 
     view! {
         <div class="tag-column">
-            <div class="tag-column-header">"Tags"</div>
+            <div class="tag-column-header">
+                "Tags"
+                <button
+                    class=move || if sort_by_usage.get() { "tag-sort-btn active" } else { "tag-sort-btn" }
+                    title="按使用次数排序"
+                    on:click=move |_| set_sort_by_usage.update(|v| *v = !*v)
+                >
+                    "#"
+                </button>
+            </div>
             
             <TagAddInput />
             
             <div class="tag-tree">
                 <For
-                    each=move || store.root_tags().get()
+                    each=sorted_root_tags
                     key=|tag| {
                         // Include position and name in key so component re-renders when they change
-                        (tag.id, tag.position, tag.name.clone())
+                        (tag.id, tag.position, tag.name.clone(), tag.icon.clone(), tag.pinned, tag.effective_color.clone())
                     }
                     children=move |tag| {
                         let position = tag.position;
@@ -429,6 +473,7 @@ pub fn TagColumn(
                                 editing_target=editing_target
                                 set_editing_target=set_editing_target
                                 set_memo_editing_target=set_memo_editing_target
+                                stats=stats
                             />
                         }
                     }
@@ -6,13 +6,13 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 use wasm_bindgen::JsCast;
 
-use crate::models::Tag;
+use crate::models::{Tag, TagAlias};
 use crate::commands::{self, CreateTagArgs};
 use crate::context::AppContext;
 use crate::components::EditTarget;
 use crate::components::type_selector::TypeSelector;
 use crate::components::tag_autocomplete::TagAutocomplete;
-use crate::store::{use_app_store, store_update_item, store_update_tag, AppStateStoreFields};
+use crate::store::{use_app_store, store_update_item, store_update_tag, store_reload_tags, AppStateStoreFields};
 
 /// Tag editor column (third column)
 #[component]
@@ -34,6 +34,15 @@ pub fn TagEditor(
     // Common tags across multi-selected items (intersection)
     let (common_tags, set_common_tags) = signal(Vec::<Tag>::new());
     
+    // Aliases: all (for autocomplete) and those of the tag being edited
+    let (all_aliases, set_all_aliases) = signal(Vec::<TagAlias>::new());
+    let (tag_aliases, set_tag_aliases) = signal(Vec::<TagAlias>::new());
+    let (alias_input, set_alias_input) = signal(String::new());
+    // Tag metadata (tags only)
+    let (tag_icon, set_tag_icon) = signal(String::new());
+    let (tag_description, set_tag_description) = signal(String::new());
+    let (tag_pinned, set_tag_pinned) = signal(false);
+    
     // Load all tags for autocomplete
     Effect::new(move |_| {
         let _ = ctx.reload_trigger.get();
@@ -41,6 +50,9 @@ pub fn TagEditor(
             if let Ok(tags) = commands::list_tags().await {
                 set_all_tags.set(tags);
             }
+            if let Ok(aliases) = commands::list_tag_aliases().await {
+                set_all_aliases.set(aliases);
+            }
         });
     });
     
@@ -93,6 +105,14 @@ pub fn TagEditor(
                             // Backend sorts by pinyin
                             set_current_tags.set(tags);
                         }
+                        if let Ok(aliases) = commands::get_tag_aliases(id).await {
+                            set_tag_aliases.set(aliases);
+                        }
+                        if let Ok(Some(tag)) = commands::get_tag(id).await {
+                            set_tag_icon.set(tag.icon.unwrap_or_default());
+                            set_tag_description.set(tag.description.unwrap_or_default());
+                            set_tag_pinned.set(tag.pinned);
+                        }
                     });
                 }
                 EditTarget::MultiItems(ids) => {
@@ -156,6 +176,11 @@ pub fn TagEditor(
                 EditTarget::Item(id, _) => {
                     if let Ok(updated) = commands::update_item(*id, Some(&name), None).await {
                         store_update_item(&store, updated);
+                        // #tag tokens in the name may have added or removed tags
+                        store_reload_tags(store).await;
+                        if let Ok(tags) = commands::get_item_tags(*id).await {
+                            set_current_tags.set(tags);
+                        }
                     }
                 }
                 EditTarget::Tag(id, _) => {
@@ -172,6 +197,26 @@ pub fn TagEditor(
         });
     };
     
+    // Save icon/description/pinned (tags only)
+    let save_tag_meta = move |pinned_changed: bool| {
+        let Some(EditTarget::Tag(id, _)) = editing_target.get() else { return; };
+        let icon = tag_icon.get();
+        let description = tag_description.get();
+        let pinned = tag_pinned.get();
+        spawn_local(async move {
+            if let Ok(updated) = commands::update_tag_meta(id, Some(&icon), Some(&description), Some(pinned)).await {
+                store_update_tag(&store, updated);
+                if pinned_changed {
+                    // Pinned tags sort first among root tags
+                    if let Ok(loaded) = commands::get_root_tags().await {
+                        *store.root_tags().write() = loaded;
+                    }
+                    *store.tags_relation_version().write() += 1;
+                }
+            }
+        });
+    };
+    
     // Save type (items only)
     let save_type = move |new_type: String| {
         let target = editing_target.get();
@@ -206,6 +251,8 @@ pub fn TagEditor(
                     color: None,
                 };
                 match commands::create_tag(&args).await {
+                    // Backend may resolve the name via an alias to an existing tag
+                    Ok(new_tag) if all.iter().any(|t| t.id == new_tag.id) => new_tag,
                     Ok(new_tag) => {
                         // Also add to store.root_tags for TagColumn
                         store.root_tags().write().push(new_tag.clone());
@@ -294,6 +341,61 @@ pub fn TagEditor(
         });
     };
     
+    // Add alias to the tag being edited
+    let add_alias = move || {
+        let alias = alias_input.get().trim().to_string();
+        if alias.is_empty() { return; }
+        if let Some(EditTarget::Tag(id, _)) = editing_target.get() {
+            spawn_local(async move {
+                match commands::add_tag_alias(id, &alias).await {
+                    Ok(added) => {
+                        set_alias_input.set(String::new());
+                        set_tag_aliases.update(|list| {
+                            if !list.iter().any(|a| a.alias == added.alias) {
+                                list.push(added.clone());
+                            }
+                        });
+                        set_all_aliases.update(|list| list.push(added));
+                    }
+                    Err(e) => {
+                        web_sys::console::error_1(&format!("Failed to add alias: {}", e).into());
+                    }
+                }
+            });
+        }
+    };
+    
+    // Remove alias
+    let remove_alias = move |alias: String| {
+        spawn_local(async move {
+            let _ = commands::remove_tag_alias(&alias).await;
+            set_tag_aliases.update(|list| list.retain(|a| a.alias != alias));
+            set_all_aliases.update(|list| list.retain(|a| a.alias != alias));
+        });
+    };
+    
+    // Merge the tag being edited into another tag (chosen by name)
+    let merge_into = move |target_name: String| {
+        let Some(EditTarget::Tag(source_id, _)) = editing_target.get() else { return; };
+        let key = target_name.trim().to_lowercase();
+        let alias_tag_id = all_aliases.get().into_iter().find(|a| a.alias == key).map(|a| a.tag_id);
+        let target = all_tags.get().into_iter()
+            .find(|t| t.name.to_lowercase() == key || Some(t.id) == alias_tag_id);
+        let Some(target) = target else { return; };
+        if target.id == source_id { return; }
+        
+        spawn_local(async move {
+            if commands::merge_tags(source_id, target.id).await.is_ok() {
+                if let Ok(loaded) = commands::get_root_tags().await {
+                    *store.root_tags().write() = loaded;
+                }
+                *store.tags_relation_version().write() += 1;
+                set_editing_target.set(Some(EditTarget::Tag(target.id, target.name.clone())));
+                ctx.reload();
+            }
+        });
+    };
+    
     // Click on suggestion
     let on_suggestion_click = move |tag: Tag| {
         add_tag_by_name(tag.name);
@@ -317,6 +419,7 @@ pub fn TagEditor(
                             <TagAutocomplete 
                                 all_tags=all_tags
                                 on_select=add_tag_by_name.clone()
+                                aliases=all_aliases
                             />
                         </div>
                         
@@ -329,7 +432,7 @@ pub fn TagEditor(
                                     key=|tag| tag.id
                                     children=move |tag| {
                                         let tag_id = tag.id;
-                                        let color = tag.color.clone().unwrap_or_else(|| "#666".to_string());
+                                        let color = tag.display_color().unwrap_or_else(|| "#666".to_string());
                                         view! {
                                             <div class="current-tag-item">
                                                 <span class="tag-color-dot" style=format!("background-color: {};", color)></span>
@@ -455,6 +558,7 @@ pub fn TagEditor(
                             <TagAutocomplete 
                                 all_tags=all_tags
                                 on_select=add_tag_by_name.clone()
+                                aliases=all_aliases
                             />
                         </div>
                         
@@ -467,7 +571,7 @@ pub fn TagEditor(
                                     key=|tag| tag.id
                                     children=move |tag| {
                                         let tag_id = tag.id;
-                                        let color = tag.color.clone().unwrap_or_else(|| "#666".to_string());
+                                        let color = tag.display_color().unwrap_or_else(|| "#666".to_string());
                                         view! {
                                             <div class="current-tag-item">
                                                 <span class="tag-color-dot" style=format!("background-color: {};", color)></span>
@@ -479,6 +583,96 @@ pub fn TagEditor(
                                 />
                             </div>
                         </div>
+                        
+                        // Metadata + aliases + merge (only for tags)
+                        {move || if !is_item {
+                            view! {
+                                <div class="editor-section">
+                                    <label class="editor-label">"图标 / 描述"</label>
+                                    <input
+                                        type="text"
+                                        class="tag-icon-input"
+                                        placeholder="图标或 emoji"
+                                        prop:value=move || tag_icon.get()
+                                        on:input=move |ev| {
+                                            let target = ev.target().unwrap();
+                                            let input = target.dyn_ref::<web_sys::HtmlInputElement>().unwrap();
+                                            set_tag_icon.set(input.value());
+                                        }
+                                        on:blur=move |_| save_tag_meta(false)
+                                    />
+                                    <input
+                                        type="text"
+                                        class="tag-description-input"
+                                        placeholder="一句话描述..."
+                                        prop:value=move || tag_description.get()
+                                        on:input=move |ev| {
+                                            let target = ev.target().unwrap();
+                                            let input = target.dyn_ref::<web_sys::HtmlInputElement>().unwrap();
+                                            set_tag_description.set(input.value());
+                                        }
+                                        on:blur=move |_| save_tag_meta(false)
+                                    />
+                                    <label class="tag-pinned-toggle">
+                                        <input
+                                            type="checkbox"
+                                            prop:checked=move || tag_pinned.get()
+                                            on:change=move |ev| {
+                                                let target = ev.target().unwrap();
+                                                let input = target.dyn_ref::<web_sys::HtmlInputElement>().unwrap();
+                                                set_tag_pinned.set(input.checked());
+                                                save_tag_meta(true);
+                                            }
+                                        />
+                                        "置顶"
+                                    </label>
+                                </div>
+                                <div class="editor-section">
+                                    <label class="editor-label">"别名"</label>
+                                    <form class="tag-editor-form" on:submit=move |ev: web_sys::SubmitEvent| {
+                                        ev.prevent_default();
+                                        add_alias();
+                                    }>
+                                        <input
+                                            type="text"
+                                            placeholder="添加别名..."
+                                            prop:value=move || alias_input.get()
+                                            on:input=move |ev| {
+                                                let target = ev.target().unwrap();
+                                                let input = target.dyn_ref::<web_sys::HtmlInputElement>().unwrap();
+                                                set_alias_input.set(input.value());
+                                            }
+                                        />
+                                        <button type="submit">"+"</button>
+                                    </form>
+                                    <div class="current-tags-list">
+                                        <For
+                                            each=move || tag_aliases.get()
+                                            key=|a| a.alias.clone()
+                                            children=move |a| {
+                                                let alias = a.alias.clone();
+                                                view! {
+                                                    <div class="current-tag-item">
+                                                        <span class="current-tag-name">{a.alias}</span>
+                                                        <button class="remove-tag-btn" on:click=move |_| remove_alias(alias.clone())>"×"</button>
+                                                    </div>
+                                                }
+                                            }
+                                        />
+                                    </div>
+                                </div>
+                                <div class="editor-section">
+                                    <label class="editor-label">"合并到标签"</label>
+                                    <TagAutocomplete 
+                                        all_tags=all_tags
+                                        on_select=merge_into
+                                        aliases=all_aliases
+                                    />
+                                </div>
+                            }.into_any()
+                        } else {
+                            view! { <div></div> }.into_any()
+                        }}
                     </div>
                 }.into_any()
             }
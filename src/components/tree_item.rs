@@ -194,11 +194,12 @@ pub fn TreeItem(
         "once" => "✓",
         "countdown" => "⏳",
         "document" => "📑",
+        "bookmark" => "🔖",
         _ => "📌",
     };
     
     // Check if should show checkbox
-    let show_checkbox = item_type != "document";
+    let show_checkbox = item_type != "document" && item_type != "bookmark";
     let is_countdown = item_type == "countdown";
     let is_once = item_type == "once";
 
@@ -350,7 +351,7 @@ pub fn TreeItem(
                     each=move || item_tags.get()
                     key=|tag| tag.id
                     children=move |tag| {
-                        let color = tag.color.clone().unwrap_or_else(|| "#666".to_string());
+                        let color = tag.display_color().unwrap_or_else(|| "#666".to_string());
                         view! {
                             <span
                                 class="item-tag-chip"
@@ -10,6 +10,7 @@ pub const ITEM_TYPES: &[(&str, &str)] = &[
     ("once", "一次性"),
     ("countdown", "倒数"),
     ("document", "文档"),
+    ("bookmark", "书签"),
 ];
 
 /// Type selector buttons for items
@@ -4,12 +4,22 @@
 
 use leptos::prelude::*;
 use leptos::task::spawn_local;
-use crate::models::Workspace;
+use crate::models::{BrokenLink, LinkCheckProgress, Workspace};
 use crate::commands;
-use crate::store::{use_app_store, AppStateStoreFields};
+use crate::store::{use_app_store, store_reload_tags, AppStateStoreFields};
 
 /// Fixed workspace IDs (1-4) cannot be edited or deleted
 const FIXED_WORKSPACE_MAX_ID: u32 = 4;
+/// The fixed web-bookmarks workspace
+const BOOKMARK_WORKSPACE_ID: u32 = 4;
+/// Broken links listed in the report after a check
+const MAX_LISTED_BROKEN_LINKS: usize = 20;
+
+/// `link-check-progress` event
+#[derive(serde::Deserialize)]
+struct LinkCheckEvent {
+    payload: LinkCheckProgress,
+}
 
 /// Workspace Tab Bar component
 #[component]
@@ -17,6 +27,8 @@ pub fn WorkspaceTabBar(
     workspaces: Memo<Vec<Workspace>>,
     current_workspace: ReadSignal<u32>,
     set_current_workspace: WriteSignal<u32>,
+    /// Tags selected in the tag column; bookmark export is limited to the first
+    selected_tags: ReadSignal<Vec<u32>>,
 ) -> impl IntoView {
     let store = use_app_store();
     let (adding, set_adding) = signal(false);
@@ -40,6 +52,108 @@ pub fn WorkspaceTabBar(
         set_adding.set(false);
     };
     
+    // Export the current workspace as a static HTML site into a picked folder
+    let on_export = move |_| {
+        let workspace_id = current_workspace.get_untracked();
+        spawn_local(async move {
+            let Ok(Some(dir)) = commands::pick_folder().await else { return; };
+            let message = match commands::export_site(workspace_id, &dir).await {
+                Ok(report) if report.missing_assets.is_empty() => {
+                    format!("已导出 {} 个页面, {} 个附件到 {}", report.pages, report.assets, report.dir)
+                }
+                Ok(report) => format!(
+                    "已导出 {} 个页面, {} 个附件到 {}\n缺失附件:\n{}",
+                    report.pages, report.assets, report.dir, report.missing_assets.join("\n")
+                ),
+                Err(e) => format!("导出失败: {}", e),
+            };
+            if let Some(window) = web_sys::window() {
+                let _ = window.alert_with_message(&message);
+            }
+        });
+    };
+    
+    // Export the bookmarks of the current workspace (under the selected tag, if any)
+    let on_export_bookmarks = move |_| {
+        let workspace_id = current_workspace.get_untracked();
+        let tag_filter = selected_tags.get_untracked().first().copied();
+        spawn_local(async move {
+            let Some(window) = web_sys::window() else { return; };
+            let Ok(Some(format)) = window.prompt_with_message_and_default("导出格式 (html / opml / csv)", "html") else { return; };
+            let format = format.trim().to_lowercase();
+            if !["html", "opml", "csv"].contains(&format.as_str()) {
+                let _ = window.alert_with_message(&format!("不支持的格式: {}", format));
+                return;
+            }
+            let Ok(Some(path)) = commands::pick_save_file(&format!("bookmarks.{}", format)).await else { return; };
+            let message = match commands::export_bookmarks(workspace_id, tag_filter, &format, &path).await {
+                Ok(report) => format!("已导出 {} 个书签, {} 个文件夹到 {}", report.bookmarks, report.folders, report.path),
+                Err(e) => format!("导出失败: {}", e),
+            };
+            let _ = window.alert_with_message(&message);
+        });
+    };
+    
+    // Progress of the background link check (None when idle)
+    let (link_check, set_link_check) = signal::<Option<LinkCheckProgress>>(None);
+    spawn_local(async move {
+        let _ = commands::listen_safe("link-check-progress", move |event| {
+            let Ok(event) = serde_wasm_bindgen::from_value::<LinkCheckEvent>(event) else { return; };
+            if !event.payload.done {
+                let _ = set_link_check.try_set(Some(event.payload));
+                return;
+            }
+            let _ = set_link_check.try_set(None);
+            spawn_local(async move {
+                let Ok(broken) = commands::list_broken_links(Some(BOOKMARK_WORKSPACE_ID)).await else { return; };
+                if let Some(window) = web_sys::window() {
+                    let _ = window.alert_with_message(&broken_links_report(event.payload.checked, &broken));
+                }
+            });
+        }).await;
+    });
+    
+    // Check bookmark links not checked in the last week
+    let on_check_links = move |_| {
+        if link_check.get_untracked().is_some() { return; }
+        set_link_check.set(Some(LinkCheckProgress::default()));
+        spawn_local(async move {
+            let _ = commands::start_link_check(Some(BOOKMARK_WORKSPACE_ID), None).await;
+        });
+    };
+    let link_check_label = move || match link_check.get() {
+        Some(progress) if progress.total > 0 => format!("{}/{}", progress.checked, progress.total),
+        Some(_) => "…".to_string(),
+        None => "🔗".to_string(),
+    };
+    
+    // Import browser bookmarks (bookmark HTML, Chrome Bookmarks, Firefox places.sqlite)
+    let on_import_bookmarks = move |_| {
+        spawn_local(async move {
+            let Ok(Some(path)) = commands::pick_file().await else { return; };
+            let Some(window) = web_sys::window() else { return; };
+            let as_tags = window
+                .confirm_with_message("将书签文件夹导入为标签?\n确定: 标签  取消: 嵌套条目")
+                .unwrap_or(false);
+            let folders = if as_tags { "tags" } else { "items" };
+            let message = match commands::import_bookmarks(&path, folders).await {
+                Ok(report) => {
+                    // The button is only shown in the bookmarks workspace
+                    if let Ok(loaded) = commands::list_items_by_workspace(BOOKMARK_WORKSPACE_ID).await {
+                        *store.items().write() = loaded;
+                    }
+                    store_reload_tags(store).await;
+                    format!(
+                        "已导入 {} 个书签 ({} 个重复, {} 个非网页已跳过), {} 个文件夹, 新建 {} 个标签",
+                        report.imported, report.duplicates, report.skipped, report.folders, report.tags_created
+                    )
+                }
+                Err(e) => format!("导入失败: {}", e),
+            };
+            let _ = window.alert_with_message(&message);
+        });
+    };
+    
     view! {
         <div class="workspace-tab-bar">
             <For
@@ -157,7 +271,29 @@ pub fn WorkspaceTabBar(
                     </button>
                 }.into_any()
             }}
+            <button class="workspace-add-btn" title="导出为静态网站" on:click=on_export>"⇪"</button>
+            <Show when=move || current_workspace.get() == BOOKMARK_WORKSPACE_ID>
+                <button class="workspace-add-btn" title="导入浏览器书签" on:click=on_import_bookmarks>"⇩"</button>
+                <button class="workspace-add-btn" title="导出书签 (HTML / OPML / CSV)" on:click=on_export_bookmarks>"⇧"</button>
+                <button class="workspace-add-btn" title="检查失效链接" on:click=on_check_links>{link_check_label}</button>
+            </Show>
         </div>
     }
 }
 
+/// Alert text after a link check: how many links were checked and which are broken
+fn broken_links_report(checked: usize, broken: &[BrokenLink]) -> String {
+    let mut report = format!("已检查 {} 个链接, 共 {} 个失效链接", checked, broken.len());
+    for link in broken.iter().take(MAX_LISTED_BROKEN_LINKS) {
+        let reason = match (link.status, &link.error) {
+            (Some(status), _) => format!("HTTP {}", status),
+            (None, Some(error)) => error.clone(),
+            (None, None) => "无响应".to_string(),
+        };
+        report.push_str(&format!("\n{} ({}) - {}", link.text, reason, link.url));
+    }
+    if broken.len() > MAX_LISTED_BROKEN_LINKS {
+        report.push_str(&format!("\n…还有 {} 个", broken.len() - MAX_LISTED_BROKEN_LINKS));
+    }
+    report
+}
@@ -112,6 +112,7 @@ pub fn MobileApp() -> impl IntoView {
                 text: &content,
                 item_type: Some("note"),
                 parent_id: None,
+                url: None,
             };
 
             if let Ok(_) = commands::create_item(&args).await {
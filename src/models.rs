@@ -17,6 +17,18 @@ pub struct Item {
     pub parent_id: Option<u32>,
     pub position: i32,
     pub collapsed: bool,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Wiki link between two items (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemLink {
+    pub source_item_id: u32,
+    pub target_item_id: u32,
+    pub link_text: String,
+    pub source_text: String,
+    pub target_text: String,
 }
 
 /// Tag data structure (matches backend)
@@ -26,6 +38,65 @@ pub struct Tag {
     pub name: String,
     pub color: Option<String>,
     pub position: i32,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Own colour or inherited from the first parent (computed by backend)
+    #[serde(default)]
+    pub effective_color: Option<String>,
+}
+
+impl Tag {
+    /// Colour to render: effective (inherited) colour, falling back to the stored one
+    pub fn display_color(&self) -> Option<String> {
+        self.effective_color.clone().or_else(|| self.color.clone())
+    }
+}
+
+/// Tag alias (matches backend) - alternative name resolving to a canonical tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagAlias {
+    pub alias: String,
+    pub tag_id: u32,
+}
+
+/// Child -> parent tag edge (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagEdge {
+    pub child_tag_id: u32,
+    pub parent_tag_id: u32,
+}
+
+/// Tag graph check result (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagGraphReport {
+    pub cycles: Vec<Vec<u32>>,
+    pub dangling_edges: Vec<TagEdge>,
+    pub self_edges: Vec<TagEdge>,
+    pub removed_edges: Vec<TagEdge>,
+}
+
+/// Per-workspace tag count (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceTagCount {
+    pub workspace_id: u32,
+    pub count: u32,
+}
+
+/// Tag usage statistics (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagStats {
+    pub tag_id: u32,
+    pub direct_count: u32,
+    pub total_count: u32,
+    pub last_applied_at: Option<i64>,
+    pub workspaces: Vec<WorkspaceTagCount>,
+    pub unused: bool,
 }
 
 /// Workspace data structure (matches backend)
@@ -35,6 +106,152 @@ pub struct Workspace {
     pub name: String,
 }
 
+/// Result of exporting a workspace as a static site (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteExportReport {
+    pub dir: String,
+    pub pages: usize,
+    pub assets: usize,
+    pub missing_assets: Vec<String>,
+}
+
+/// Result of `export_bookmarks` (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookmarkExportReport {
+    pub path: String,
+    pub bookmarks: usize,
+    pub folders: usize,
+}
+
+/// Automatic three-way merge of two memos
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextMerge {
+    pub text: String,
+    /// Regions left between conflict markers
+    pub conflicts: usize,
+}
+
+/// A row edited on this device and another between syncs (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: u32,
+    /// One of the synced tables, e.g. "items", "tags" or "settings"
+    pub table_name: String,
+    /// Key columns of the row as a JSON array, e.g. `[12]` or `[3,4]`
+    pub row_key: String,
+    pub base: Option<serde_json::Map<String, serde_json::Value>>,
+    pub local: serde_json::Map<String, serde_json::Value>,
+    pub remote: serde_json::Map<String, serde_json::Value>,
+    /// "local" or "remote": the version now in the table
+    pub kept: String,
+    pub created_at: i64,
+    pub merged_memo: Option<TextMerge>,
+}
+
+impl SyncConflict {
+    /// Item text, tag or workspace name, folder path or setting key, from
+    /// whichever version has one; the table and row key otherwise
+    pub fn title(&self) -> String {
+        let field = match self.table_name.as_str() {
+            "items" => "text",
+            "tags" | "workspaces" => "name",
+            "workspace_dirs" => "path",
+            "settings" => "key",
+            _ => return format!("{} {}", self.table_name, self.row_key),
+        };
+        [&self.local, &self.remote].iter()
+            .find_map(|row| row.get(field).and_then(|v| v.as_str()))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Memo of the local or remote version
+    pub fn memo(&self, local: bool) -> String {
+        let row = if local { &self.local } else { &self.remote };
+        row.get("memo").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+    }
+}
+
+/// Progress of a background link check (`link-check-progress` event)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkCheckProgress {
+    pub total: usize,
+    pub checked: usize,
+    pub broken: usize,
+    pub done: bool,
+}
+
+/// A bookmark whose URL failed its last check (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub item_id: u32,
+    pub text: String,
+    pub url: String,
+    pub workspace_id: u32,
+    /// HTTP status; `None` when the site did not answer
+    pub status: Option<u16>,
+    pub redirect_to: Option<String>,
+    pub error: Option<String>,
+    pub checked_at: i64,
+}
+
+/// Result of `import_bookmarks` (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookmarkImportReport {
+    /// "netscape", "chrome" or "firefox"
+    pub format: String,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+    pub folders: usize,
+    pub tags_created: usize,
+}
+
+/// An asset stored in the app's content-addressed store (matches backend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedAsset {
+    pub path: String,
+    /// Device-independent `assets/{hash}.{ext}` reference for memos
+    pub reference: String,
+    pub hash: String,
+    pub ext: String,
+    pub mime: String,
+    pub size: u64,
+    pub original_name: Option<String>,
+    pub created_at: i64,
+}
+
+impl SavedAsset {
+    /// Markdown embedding the asset by reference: images, video and audio
+    /// inline, other files as a link
+    pub fn markdown(&self) -> String {
+        let dest = &self.reference;
+        let name = self.original_name.clone().unwrap_or_else(|| format!("{}.{}", self.hash, self.ext))
+            .replace(['[', ']'], "");
+        if ["image/", "video/", "audio/"].iter().any(|kind| self.mime.starts_with(kind)) {
+            format!("![]({})", dest)
+        } else {
+            format!("[{}]({})", name, dest)
+        }
+    }
+}
+
+/// Offline reader copy of a bookmarked page, with its HTML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveView {
+    pub item_id: u32,
+    pub url: String,
+    pub title: String,
+    pub html_hash: String,
+    pub text: String,
+    #[serde(default)]
+    pub images: Vec<String>,
+    pub created_at: i64,
+    /// Sanitised reader HTML with `assets/` images; `None` until the file is
+    /// synced to this device
+    pub html: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WorkspaceDir {
     pub id: u32,
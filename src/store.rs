@@ -74,6 +74,18 @@ pub fn store_update_tag(store: &AppStore, updated_tag: Tag) {
         .map(|tag| *tag = updated_tag);
 }
 
+/// Reload tags after a save that may have applied inline #tags
+/// (new tags, new tag_tags edges and item-tag rows are created by the backend)
+pub async fn store_reload_tags(store: AppStore) {
+    if let Ok(loaded) = crate::commands::list_tags().await {
+        *store.tags().write() = loaded;
+    }
+    if let Ok(loaded) = crate::commands::get_root_tags().await {
+        *store.root_tags().write() = loaded;
+    }
+    *store.tags_relation_version().write() += 1;
+}
+
 /// Remove a tag from the store by ID (from both tags and root_tags)
 pub fn store_remove_tag(store: &AppStore, tag_id: u32) {
     store.tags().write().retain(|tag| tag.id != tag_id);
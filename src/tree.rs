@@ -80,6 +80,7 @@ mod tests {
             parent_id,
             position,
             collapsed: false,
+            url: None,
         }
     }
 